@@ -11,25 +11,299 @@ settings = Settings
 accounts = Accounts
 post-placeholder = What's happening?
 post-button = Post
-character-count = { $count }/{ $limit }
+character-count = { $count } characters
 bluesky-truncate-warning = ⚠️ Bluesky posts will be truncated to { $limit } characters
 
-# Account settings
-bluesky-settings = Bluesky Settings
-mastodon-settings = Mastodon Settings  
-nostr-settings = Nostr Settings
+# Status messages
+posting = { $spinner } Posting...
+uploading-images = Uploading images: { $sent } of { $total }
+mining-pow = Mining proof of work for Nostr: { $attempts } of about { $expected } hashes tried
+post-success = Posted successfully!
+post-error = Failed to post: { $error }
+post-queued = Offline: the post is in the outbox and will be sent when the connection is back
+config-saved = Settings saved!
+
+# Compose
+post-to-all = Post to all
+post-to-all-partial = Post to all ({ $ticked } of { $total })
+counter = { $platform } { $count }
+counter-limit = { $platform } { $count }/{ $limit }
+counter-thread = { $platform } { $count }/{ $limit } · { $posts } posts
+word-count = { $count ->
+    [one] { $count } word
+   *[other] { $count } words
+}
+bluesky-block-warning = ⚠️ Bluesky posts are limited to { $limit } characters: shorten the text, post it as a thread, or untick Bluesky
+bluesky-skip-warning = ⚠️ Over { $limit } characters, so Bluesky will be skipped
+bluesky-thread-warning = Over { $limit } characters, so Bluesky gets it as a thread of replies
+telegram-truncate-warning = ⚠️ Telegram messages will be truncated to { $limit } characters
+farcaster-limit-warning = ⚠️ Casts are limited to { $limit } bytes, and accented letters and emoji take several each: shorten the text or untick Farcaster
+pixelfed-needs-image = ⚠️ Pixelfed posts need at least one image
+cancel = Cancel
+attach-image = 📎 Attach Image or Video
+paste-image = 📋 Paste Image
+emoji = 😀 Emoji
+emoji-search = Search emoji
+emoji-none-found = No emoji found
+shorten-links = 🔗 Shorten Links
+shortening-links = Shortening...
+loading = Loading...
+image-over-bluesky-limit = ⚠️ over Bluesky's 1 MB limit, will be recompressed
+video-bluesky-only = video, posted to Bluesky only
+video-left-out-warning = ⚠️ Only Bluesky takes video, so the other platforms get the text alone
+alt-text-placeholder = Describe this image (alt text)
+suggest-alt-text = Suggest
+suggesting-alt-text = Suggesting...
+missing-alt-text = { $count ->
+    [one] ⚠️ { $count } image without alt text
+   *[other] ⚠️ { $count } images without alt text
+}
+thread-mode = Post long text as a thread
+markdown = Markdown (sent as plain text to platforms that don't format it)
+dry-run = Dry run (log what would be posted without posting it)
+add-signature = Add signature
+post-language = Language
+content-warning = Content warning
+content-warning-placeholder = Content warning (Mastodon only)
+reply-toggle = Reply to a post
+quote-toggle = Quote it instead
+reference-url-placeholder = Link to the { $platform } post (blank to post normally there)
+wordpress-title-placeholder = Title (WordPress only, optional)
+lemmy-title-placeholder = Title (Lemmy, defaults to the first line)
+microblog-title-placeholder = Title (Micro.Blog only, optional)
+microblog-categories-placeholder = Categories, comma-separated
+nostr-article = Publish to Nostr as a long-form article
+nostr-article-title-placeholder = Article title (optional, first line otherwise)
+nostr-article-summary-placeholder = Article summary (optional)
+nostr-article-no-reply = Nostr articles can't be replies, so Nostr gets the article on its own
+devto-title-placeholder = Dev.to article title
+devto-tags-placeholder = Tags, comma-separated (up to 4)
+devto-needs-title = Dev.to needs an article title
+devto-no-schedule = Dev.to articles can't be scheduled: untick Dev.to to schedule the post
+show-preview = Show Preview
+hide-preview = Hide Preview
+preview-content-warning = CW: { $text }
+preview-title = Title: { $title }
+preview-too-long = Not posted: the text is too long
+retry-failed = Retry Failed
+schedule = Schedule
+schedule-placeholder = Schedule for (YYYY-MM-DD HH:MM)
+mastodon-server-schedule = Let Mastodon publish it, even if this computer is off (needs 5 minutes' notice)
+duplicate-warning = { $minutes ->
+    [0] ⚠️ You posted this less than a minute ago. Post it again?
+    [1] ⚠️ You posted this a minute ago. Post it again?
+   *[other] ⚠️ You posted this { $minutes } minutes ago. Post it again?
+}
+post-anyway = Post Anyway
+unsaved-settings-warning = Settings has unsaved changes, which this post won't use until they're saved
+save-and-post = Save and Post
+save-draft = Save Draft
+show-drafts = Show Drafts ({ $count })
+hide-drafts = Hide Drafts ({ $count })
+load = Load
+relay-rejected = Nostr relay { $relay } rejected the post: { $reason }
+view-post = View the post on { $platform }
+
+# Polls
+poll-toggle = Poll (Mastodon only)
+poll-needs-mastodon = Tick Mastodon to add a poll
+poll-unsupported = Your Mastodon instance doesn't support polls
+poll-no-images = Polls can't be posted with images
+poll-choice = Choice { $number }
+poll-add-choice = Add Choice
+poll-multiple = Allow multiple choices
+poll-ends-after = Ends after
+poll-too-few-choices = ⚠️ Fill in at least { $count } choices
+poll-5-minutes = 5 minutes
+poll-30-minutes = 30 minutes
+poll-1-hour = 1 hour
+poll-6-hours = 6 hours
+poll-1-day = 1 day
+poll-3-days = 3 days
+poll-7-days = 7 days
+
+# Settings
+platform-settings = { $platform } Settings
+search-settings = Search settings
+no-matching-settings = No settings match the search
 enable-account = Enable
-handle = Handle
-password = App Password
-instance-url = Instance URL
+invalid-url = Invalid URL format
+credentials-need-reentry = Stored credentials couldn't be decrypted on this machine — please re-enter them
+credentials-undecrypted = Stored credentials for { $platforms } couldn't be decrypted on this machine, so they're left out until re-entered in Settings
+checking-instance = Checking the instance...
+mastodon-instance-info = Connected to { $title } (version { $version })
+mastodon-instance-error = This instance couldn't be used: { $error }
+mastodon-instance-path-hint = Enter only the server address, like https://mastodon.social, not a profile or post link
+mastodon-limits-checked = Limits last checked { $time }
+mastodon-limits-unchecked = Limits not checked yet
+refresh-instance-limits = Refresh Limits
+mastodon-compatibility = Server software
+mastodon-compatibility-detect = Detect from the instance
+mastodon-compatibility-detected = Detect from the instance (found { $server })
+mastodon-compatibility-pleroma = Pleroma or Akkoma
+mastodon-compatibility-mastodon = Mastodon
+mastodon-default-visibility = Default visibility
+mastodon-visibility = Mastodon visibility
+mastodon-visibility-account-default = Account default
+mastodon-visibility-public = Public
+mastodon-visibility-unlisted = Unlisted
+mastodon-visibility-followers = Followers only
+mastodon-visibility-direct = Mentioned people only
+instance-url = Instance URL (e.g., { $example })
 access-token = Access Token
-private-key = Private Key (hex)
+username = Username
+remove = Remove
+bluesky-handle = Handle (e.g., user.bsky.social)
+invalid-handle = Invalid handle format
+password = App Password
+bluesky-service-url = Service URL (e.g., https://bsky.social)
+service-url-needs-https = Service URL must start with https://
+bluesky-over-limit = When a post is over { $limit } characters
+bluesky-thread = Split it into a thread
+bluesky-block-post = Don't post until it's shortened
+bluesky-skip = Skip Bluesky
+bluesky-truncate = Truncate it
+threads-user-id = User ID
+threads-images-note = Threads fetches images from a link, so attached images are left out unless uploaded to the Nostr media server. The uploads are signed with your Nostr key, so anyone who sees them can tell the Nostr and Threads accounts are the same person
+webhooks = Webhooks
+add-webhook = Add Webhook
+invalid-discord-webhook = Not a Discord webhook URL
+telegram-chat-id = Chat ID (e.g., @mychannel or -1001234567890)
+telegram-bot-token = Bot Token
+wordpress-site-url = Site URL (e.g., https://example.com)
+wordpress-app-password = Application Password
+wordpress-save-drafts = Save posts as drafts instead of publishing
+lemmy-username = Username or email
+lemmy-password = Password
+lemmy-community = Community (e.g., rust or rust@programming.dev)
+farcaster-signer-uuid = Neynar Signer UUID
+farcaster-api-key = Neynar API Key
+upload-images-to-nostr = Upload images to the Nostr media server
+farcaster-images-note = Casts embed images by link, so attached images are left out unless uploaded to the Nostr media server set up below. The uploads are signed with your Nostr key, so anyone who sees them can tell the Nostr and Farcaster accounts are the same person
+matrix-homeserver = Homeserver URL (e.g., https://matrix.org)
+matrix-room-id = Room ID (e.g., !abcdef:matrix.org)
+devto-site-url = Site URL (e.g., https://dev.to)
+devto-api-key = API Key
+devto-draft = Save articles as drafts
+devto-images-note = Articles are posted without the attached images
+private-key = Private Key (nsec1... or 64 hex characters)
+public-key = Public key: { $npub }
+signed-in-as = Signed in as
+account-lookup-error = Couldn't look up the account: { $error }
+nostr-media-host = Image upload server (e.g., https://nostr.build)
+nostr-blossom-server = Blossom server for images (optional, used instead of the server above)
+nostr-blossom-auth = Sign each upload with your key (most Blossom servers require this)
+nostr-pow-difficulty = Proof-of-work difficulty in bits (blank for none)
+nostr-pow-slow = Each extra bit doubles the work; notes at this difficulty may take minutes to mine
 relays = Relays
+show-relays = Show Relays
+hide-relays = Hide Relays
+checking = Checking...
+relay-latency = ● { $ms } ms
 add-relay = Add Relay
+check-relays = Check Relays
+publish-relay-list = Publish Relay List
+publishing-relay-list = Publishing relay list...
+relay-list-error = Failed to publish relay list: { $error }
+restore-default-relays = Restore Default Relays
+link-shortening = Link Shortening
+shorten-links-enabled = Shorten long links before posting
+shortener-address = Shortener address with {"{"}url{"}"} for the link
+shortener-needs-placeholder = The address needs a {"{"}url{"}"} placeholder
+shortener-api-key = API key (optional, sent as X-Api-Key)
+alt-text-suggestions = Alt Text Suggestions
+captioner-enabled = Suggest alt text with a captioning service
+captioner-address = Captioning service address (receives the image)
+captioner-token = Access token (optional)
+captioner-needs-https = The address must start with https://, unless the service runs on this computer
+text-cleanup = Text Cleanup
+text-cleanup-description = Applied to the text each platform receives; the editor keeps what you typed.
+trim-trailing-whitespace = Trim trailing whitespace
+collapse-blank-lines = Collapse blank lines
+signatures = Signatures
+signatures-description = Added after a blank line at the end of posts to each platform, and counted against its limit. Leave empty for none.
+signature-placeholder = Signature (e.g., — via yall-cosmic)
+security = Security
+credential-storage = Credential storage: { $backend }
+use-backend = Use { $backend }
+backend-machine-key = Machine key
+backend-master-password = Master password
+backend-keyring = System keyring
+strip-image-metadata = Remove location and camera details from images before uploading
+master-password = Master Password
+master-password-hint = Set a master password to require it at startup.
+set-master-password = Set Master Password
+change-master-password-hint = Change the master password; stored credentials are re-encrypted with the new one.
+current-password = Current Password
+new-password = New Password
+change-master-password = Change Master Password
+transfer-settings = Transfer Settings
+transfer-settings-hint = Move accounts to another machine. Credentials in the file are protected by the passphrase; importing replaces the current accounts.
+passphrase = Passphrase
+export-settings = Export Settings
+import-settings = Import Settings
 save-settings = Save Settings
 
-# Status messages
-posting = Posting...
-post-success = Posted successfully!
-post-error = Failed to post: { $error }
-config-saved = Settings saved!
+# Scheduled and history
+no-scheduled-posts = No scheduled posts.
+sending = Sending...
+scheduled-post-failed = ⚠️ Failed: { $error }
+retry = Retry
+outbox = Outbox
+outbox-waiting = Offline, trying again at { $time }
+outbox-pending = { $count ->
+    [one] { $count } post is waiting in the outbox
+   *[other] { $count } posts are waiting in the outbox
+}
+retry-now = Retry Now
+show = Show
+no-history = No posts sent yet.
+clear-history = Clear History
+duplicate = Duplicate
+edit-article = Edit Article
+
+# Unlocking
+unlock-title = Unlock Yall Cosmic
+unlock-hint = Enter your master password to decrypt your saved credentials.
+unlock = Unlock
+
+# Navigation
+scheduled = Scheduled ({ $count })
+history = History
+keep-text-leaving = ⚠️ Keep the text you've written before leaving Compose?
+keep-text-closing = ⚠️ Keep the text you've written before closing?
+save-as-draft = Save as Draft
+discard = Discard
+
+# Errors and status
+clipboard-no-image = The clipboard doesn't contain an image
+shorten-links-error = Failed to shorten links: { $error }
+suggest-alt-text-error = Failed to suggest alt text: { $error }
+too-many-images = At most { $count } images can be attached
+video-not-alone = A video has to be posted on its own, without images or another video
+invalid-schedule-time = Enter the time as YYYY-MM-DD HH:MM
+save-scheduled-post-error = Failed to save scheduled post
+mastodon-scheduled = Mastodon will publish the post at { $time }
+mastodon-schedule-fallback = Mastodon couldn't schedule the post ({ $error }), so it'll be sent from here instead
+save-draft-error = Failed to save draft
+save-settings-error = Failed to save settings
+posted-to = posted to { $platforms }
+relay-list-published = Relay list published to { $accepted } of { $tried } relays
+duplicate-relay = This relay is already in the list
+set-master-password-error = Failed to set master password
+change-master-password-error = Failed to change master password
+current-password-incorrect = Current master password is incorrect
+change-backend-error = Failed to change credential storage
+export-settings-error = Failed to export settings
+read-settings-error = Failed to read settings file
+wrong-passphrase = Wrong passphrase for this settings file
+invalid-settings-file = Not a valid settings file
+derive-key-error = Failed to derive key: { $error }
+incorrect-master-password = Incorrect master password
+media-files = Images and videos
+image-files = Image
+video-files = Video
+settings-files = Settings
+read-image-error = Failed to read image: { $error }
+decode-image-error = Failed to decode image: { $error }
+load-image-error = Failed to load image: { $error }
@@ -5,60 +5,317 @@
 pub enum Message {
     MicroBlogEnabledChanged(bool),
     MicroBlogTokenChanged(String),
+    ThreadsEnabledChanged(bool),
+    ThreadsUserIdChanged(String),
+    ThreadsTokenChanged(String),
+    ThreadsUploadImagesChanged(bool),
+    PixelfedEnabledChanged(bool),
+    PixelfedInstanceChanged(String),
+    PixelfedTokenChanged(String),
+    DiscordEnabledChanged(bool),
+    NewDiscordWebhookChanged(String),
+    AddDiscordWebhook,
+    RemoveDiscordWebhook(usize),
+    TelegramEnabledChanged(bool),
+    TelegramChatIdChanged(String),
+    TelegramTokenChanged(String),
+    WordPressEnabledChanged(bool),
+    WordPressSiteChanged(String),
+    WordPressUsernameChanged(String),
+    WordPressPasswordChanged(String),
+    WordPressDraftToggled(bool),
+    LemmyEnabledChanged(bool),
+    LemmyInstanceChanged(String),
+    LemmyUsernameChanged(String),
+    LemmyPasswordChanged(String),
+    LemmyCommunityChanged(String),
+    FarcasterEnabledChanged(bool),
+    FarcasterSignerChanged(String),
+    FarcasterApiKeyChanged(String),
+    FarcasterUploadImagesChanged(bool),
+    MatrixEnabledChanged(bool),
+    MatrixHomeserverChanged(String),
+    MatrixRoomChanged(String),
+    MatrixTokenChanged(String),
+    DevToEnabledChanged(bool),
+    DevToSiteChanged(String),
+    DevToApiKeyChanged(String),
+    DevToDraftToggled(bool),
+    UrlShortenerEnabledChanged(bool),
+    UrlShortenerEndpointChanged(String),
+    UrlShortenerApiKeyChanged(String),
+    CaptionerEnabledChanged(bool),
+    CaptionerEndpointChanged(String),
+    CaptionerTokenChanged(String),
+    TextCleanupChanged(Platform, TextCleanup),
+    SignatureChanged(Platform, String),
     SwitchView(ViewMode),
     UpdateConfig(Box<Config>),
     // Post composition
     PostEditorAction(text_editor::Action),
+    PostAnyway, // Send despite a recent post with the same text
+    DismissDuplicateWarning,
+    ToggleEmojiPicker,
+    EmojiSearchChanged(String),
+    InsertEmoji(&'static str),
     PostSubmit,
-    PostResult(Result<(), PostError>),
+    SubmitShortcut, // Ctrl+Enter in the editor
+    EscapePressed,
+    PostResult(PostReport),
+    RetryFailed, // Re-submit only to the platforms that failed last time
+    CancelPost, // Abort the in-flight post
+    SpinnerTick, // Advance the busy indicator
+    DismissStatus, // Clear a success or error message
+    OpenPostLink(String), // Show a created post in the browser
     PostToBlueskyToggled(bool),
     PostToMastodonToggled(bool),
     PostToMicroBlogToggled(bool),
     PostToNostrToggled(bool),
+    PostToThreadsToggled(bool),
+    PostToPixelfedToggled(bool),
+    PostToDiscordToggled(bool),
+    PostToTelegramToggled(bool),
+    PostToWordPressToggled(bool),
+    PostToLemmyToggled(bool),
+    PostToFarcasterToggled(bool),
+    PostToMatrixToggled(bool),
+    PostToDevToToggled(bool),
+    PostToAllToggled(bool),
+    WordPressTitleChanged(String),
+    LemmyTitleChanged(String),
+    MicroBlogTitleChanged(String),
+    MicroBlogCategoriesChanged(String),
+    DevToTitleChanged(String),
+    DevToTagsChanged(String),
+    NostrArticleToggled(bool),
+    NostrArticleTitleChanged(String),
+    NostrArticleSummaryChanged(String),
     AttachImage, // Open file picker
-    ImageSelected(Option<String>), // Some(path) or None to clear
+    PasteImage, // Attach the clipboard's image
+    ShortenLinks,
+    LinksShortened(String, Result<String, String>), // Text that was shortened, and the result
+    ImagesSelected(Vec<String>), // Paths picked in the file dialog
+    RemoveImage(usize), // Index into attached_images
+    ImageAltTextChanged(usize, String),
+    SuggestAltText(usize),
+    AltTextSuggested(String, Result<String, String>), // Image path, and the caption or error
+    ImagePreviewLoaded(String, Result<ImagePreview, String>), // Path, thumbnail and details
+    VideoChecked(String, Result<(), String>), // Path, and why Bluesky won't take the video
+    ContentWarningToggled(bool),
+    ReplyToggled(bool),
+    QuoteToggled(bool),
+    ReferenceUrlChanged(Platform, String),
+    ThreadModeToggled(bool),
+    MarkdownToggled(bool),
+    SignatureToggled(bool),
+    DryRunToggled(bool),
+    PostLanguageSelected(usize), // Index into POST_LANGUAGE_CODES
+    SpoilerTextChanged(String),
+    MastodonVisibilitySelected(usize), // Index into MastodonVisibility::ALL
+    PollToggled(bool),
+    PollOptionChanged(usize, String), // Index into poll_options
+    AddPollOption,
+    RemovePollOption(usize),
+    PollDurationSelected(usize), // Index into POLL_DURATION_SECS
+    PollMultipleToggled(bool),
+    ScheduleTimeChanged(String),
+    MastodonServerScheduleToggled(bool),
+    MastodonScheduleResult(PostReport),
+    SchedulePost,
+    SaveDraft,
+    LoadDraft(usize),
+    DeleteDraft(usize),
+    DuplicateToCompose(usize), // Index into config.history
+    EditNostrArticle(usize), // Index into config.history
+    ClearHistory,
+    TogglePreview,
+    ToggleDrafts,
+    CloseRequested, // Autosaves compose state on exit, asking first about unsaved text
+    SaveDraftAndLeave,
+    DiscardAndLeave,
+    CancelLeave,
+    WindowResized(cosmic::iced::Size),
+    MastodonInstanceFetched(u64, Result<MastodonInstance, String>), // Which check it answers, what the instance reported
+    AccountLoaded(Platform, Result<AccountPreview, String>),
+
+    // Scheduled posts
+    CheckScheduledPosts,
+    ScheduledPostResult(String, PostReport),
+    RetryScheduledPost(String),
+    RetryOutbox,
+    RemoveScheduledPost(String),
 
     // Settings
     BlueskyEnabledChanged(bool),
     BlueskyHandleChanged(String),
+    BlueskyServiceUrlChanged(String),
+    BlueskyTruncateBehaviorChanged(BlueskyTruncateBehavior),
     BlueskyPasswordChanged(String),
     MastodonEnabledChanged(bool),
     MastodonInstanceChanged(String),
     MastodonTokenChanged(String),
+    MastodonDefaultVisibilityChanged(MastodonVisibility),
+    MastodonCompatibilityChanged(MastodonCompatibility),
+    RefreshMastodonInstance,
     NostrEnabledChanged(bool),
     NostrPrivateKeyChanged(String),
+    NostrMediaHostChanged(String),
+    NostrBlossomServerChanged(String),
+    NostrBlossomAuthToggled(bool),
+    NostrPowDifficultyChanged(String),
     NewRelayChanged(String),
     AddRelay,
     RemoveRelay(usize),
+    MoveRelay { from: usize, to: usize }, // Indices into temp_nostr.relays
+    RestoreDefaultRelays,
+    PublishRelayList, // NIP-65 relay list from the relays being edited
+    RelayListPublished(Result<(usize, usize), String>), // Relays that accepted it, relays tried
+    CheckRelays,
+    RelayChecked(String, Result<Duration, String>), // Relay URL, round trip time
     SaveSettings,
+    SaveAndPost,
     ToggleRelays,
+    SettingsFilterChanged(String),
+    ToggleSettingsSection(SettingsSection),
+    SettingsSearchSubmitted,
+    NewMasterPasswordChanged(String),
+    SetMasterPassword,
+    CurrentMasterPasswordChanged(String),
+    ChangeMasterPassword,
+    UseCryptoBackend(CryptoBackend),
+    StripImageMetadataToggled(bool),
+    TransferPassphraseChanged(String),
+    ExportSettings,
+    ImportSettings,
+
+    // Unlock
+    MasterPasswordChanged(String),
+    UnlockSubmit,
 
 }
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::config::{Config, BlueskyConfig, MastodonConfig, NostrConfig};
+use crate::clipboard;
+use crate::config::{self, Config, BlueskyConfig, BlueskyTruncateBehavior, Draft, MastodonCompatibility, MastodonConfig, MastodonVisibility, NostrConfig, PixelfedConfig, SavedView, ScheduledPost, SettingsSection, SentPost, ThreadsConfig, DiscordWebhookConfig, TelegramConfig, LemmyConfig, FarcasterConfig, MatrixConfig, DevToConfig, UrlShortenerConfig, CaptionerConfig, TextCleanup, WordPressConfig, WordPressStatus};
 use rfd::FileDialog;
-use crate::crypto::CryptoManager;
-use crate::social::{self, PostError};
+use crate::crypto::{CryptoBackend, CryptoError, CryptoManager, EncryptedData};
+use crate::emoji;
+use crate::fl;
+use crate::i18n;
+use crate::social::{self, AccountInfo, ImageAttachment, MastodonInstance, PowProgress, UploadProgress, DevToOptions, LemmyOptions, MastodonOptions, MastodonPoll, MicroBlogOptions, NostrArticle, NostrOptions, Platform, PostOptions, PostReport, WordPressOptions, BLUESKY_CHARACTER_LIMIT, DEFAULT_MASTODON_CHARACTER_LIMIT, MAX_IMAGES, BLUESKY_MAX_IMAGE_BYTES, PIXELFED_CHARACTER_LIMIT, THREADS_CHARACTER_LIMIT, DISCORD_CHARACTER_LIMIT, TELEGRAM_MESSAGE_LIMIT, LEMMY_CHARACTER_LIMIT, FARCASTER_BYTE_LIMIT, MASTODON_MIN_SCHEDULE_LEAD};
 
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use cosmic::iced::keyboard::{self, key::Named, Key, Modifiers};
 use cosmic::iced::{Alignment, Length, Subscription};
 use cosmic::prelude::*;
 use cosmic::widget::{self, text_input, text_editor, checkbox, column, row, container, scrollable, divider, button};
 use cosmic::iced_core::text::Wrapping;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use futures_util::Stream;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
 use cosmic::{cosmic_theme, theme};
+use zeroize::Zeroizing;
+
+
 
 
+/// How often the scheduler checks for due posts
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+/// How long an outbox post waits before trying again while the network is still down
+const OUTBOX_RETRY_INTERVAL: chrono::TimeDelta = chrono::TimeDelta::minutes(2);
+/// How long a success message stays up; errors stay until dismissed
+const SUCCESS_STATUS_DURATION: Duration = Duration::from_secs(4);
+const SCHEDULE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+/// Mastodon poll durations offered in Compose
+const POLL_DURATION_SECS: &[u64] = &[300, 1800, 3600, 21600, 86400, 259200, 604800];
+/// Index into POLL_DURATION_SECS a new poll starts with
+const DEFAULT_POLL_DURATION: usize = 4;
+/// Mastodon's default limits on poll options
+const MIN_POLL_OPTIONS: usize = 2;
+const MAX_POLL_OPTIONS: usize = 4;
+/// The compose editor, focused on entering Compose
+static EDITOR_ID: LazyLock<widget::Id> = LazyLock::new(|| widget::Id::new("compose-editor"));
+/// The Settings search box, focused on entering Settings
+static SETTINGS_SEARCH_ID: LazyLock<widget::Id> = LazyLock::new(|| widget::Id::new("settings-search"));
+/// The master password prompt shown while locked
+static UNLOCK_ID: LazyLock<widget::Id> = LazyLock::new(|| widget::Id::new("unlock-password"));
+/// Highest Nostr proof-of-work difficulty accepted in Settings, already far past what a desktop mines quickly
+const MAX_POW_DIFFICULTY: u8 = 40;
+/// Difficulties above this get a warning that notes may take a while to mine
+const SLOW_POW_DIFFICULTY: u8 = 24;
+/// Languages posts can be tagged with, as ISO 639-1 codes and in their own names
+const POST_LANGUAGE_CODES: &[&str] = &["en", "de", "es", "fr", "it", "pt", "nl", "sv", "da", "nb", "fi", "pl", "cs", "uk", "ru", "tr", "ar", "hi", "ja", "ko", "zh"];
+const POST_LANGUAGE_NAMES: &[&str] = &[
+    "English", "Deutsch", "Español", "Français", "Italiano", "Português", "Nederlands", "Svenska", "Dansk", "Norsk bokmål", "Suomi", "Polski",
+    "Čeština", "Українська", "Русский", "Türkçe", "العربية", "हिन्दी", "日本語", "한국어", "中文",
+];
+/// Largest side of an attachment thumbnail, in pixels
+const THUMBNAIL_SIZE: u32 = 96;
+/// Avatars in settings are scaled to fit a square this many pixels wide.
+const AVATAR_SIZE: u32 = 64;
+/// Height the compose editor keeps when the window is too short to give it more
+const EDITOR_MIN_HEIGHT: f32 = 120.0;
+/// Frames of the busy indicator shown while posting
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
 
+/// An attached image's thumbnail and details, loaded in the background.
+#[derive(Debug, Clone)]
+pub struct ImagePreview {
+    thumbnail: widget::image::Handle,
+    width: u32,
+    height: u32,
+    bytes: usize,
+}
 
-const MAX_POST_LENGTH: usize = 500;
-const BLUESKY_LIMIT: usize = 300;
+/// The account saved credentials sign in to, with its avatar when one could be loaded.
+#[derive(Debug, Clone)]
+pub struct AccountPreview {
+    info: AccountInfo,
+    avatar: Option<widget::image::Handle>,
+}
 
 #[derive(Debug, Clone, Default)]
 pub enum ViewMode {
     #[default]
     Compose,
     Settings,
+    Scheduled,
+    History,
+    Unlock,
+}
+
+impl From<SavedView> for ViewMode {
+    fn from(view: SavedView) -> Self {
+        match view {
+            SavedView::Compose => ViewMode::Compose,
+            SavedView::Settings => ViewMode::Settings,
+            SavedView::Scheduled => ViewMode::Scheduled,
+            SavedView::History => ViewMode::History,
+        }
+    }
+}
+
+impl ViewMode {
+    /// The tab to reopen on launch; the unlock screen isn't one
+    fn saved(&self) -> Option<SavedView> {
+        match self {
+            ViewMode::Compose => Some(SavedView::Compose),
+            ViewMode::Settings => Some(SavedView::Settings),
+            ViewMode::Scheduled => Some(SavedView::Scheduled),
+            ViewMode::History => Some(SavedView::History),
+            ViewMode::Unlock => None,
+        }
+    }
+}
+
+/// Where the user was headed when asked what to do with unsaved text.
+#[derive(Debug, Clone)]
+pub enum PendingLeave {
+    View(ViewMode),
+    Close,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -67,6 +324,8 @@ pub enum PostingStatus {
     Idle,
     Posting,
     Success,
+    Scheduled(DateTime<Utc>), // Mastodon accepted the post to publish at this time
+    Queued,                   // Unreachable, so it went into the outbox
     Error(String),
 }
 
@@ -88,16 +347,104 @@ pub struct AppModel {
     post_to_mastodon: bool,
     post_to_microblog: bool,
     post_to_nostr: bool,
-    attached_image: Option<String>, // Path to selected image
+    post_to_threads: bool,
+    post_to_pixelfed: bool,
+    post_to_discord: bool,
+    post_to_telegram: bool,
+    post_to_wordpress: bool,
+    post_to_lemmy: bool,
+    post_to_farcaster: bool,
+    post_to_matrix: bool,
+    post_to_devto: bool, // Only counts as selected once there's a Dev.to title
+    attached_images: Vec<ImageAttachment>, // Selected images, at most MAX_IMAGES
+    image_previews: HashMap<String, Result<ImagePreview, String>>, // By path; missing while loading
+    spinner_frame: usize, // Index into SPINNER_FRAMES
+    failed_platforms: Vec<Platform>, // Platforms that errored on the last submit
+    rejected_relays: Vec<(String, String)>, // Nostr relays that rejected the last post, with reasons
+    post_warnings: Vec<String>, // Non-fatal problems from the last post
+    post_links: Vec<(Platform, String)>, // Where the last post can be seen
+    content_warning_enabled: bool,
+    reply_enabled: bool, // Reply to or quote the posts in reference_urls
+    quote: bool,
+    reference_urls: HashMap<Platform, String>, // Link to the post to reply to or quote, by platform
+    thread_mode: bool, // Split text over a platform's limit into a reply chain
+    markdown: bool, // Text is Markdown, sent as plain text where it isn't formatted
+    include_signature: bool, // Add the platforms' signatures to this post
+    dry_run: bool, // Log what would be posted instead of posting; not saved, so it can't be left on by accident
+    post_language: usize, // Index into POST_LANGUAGE_CODES
+    spoiler_text: String, // Mastodon content warning
+    mastodon_visibility: MastodonVisibility,
+    mastodon_visibility_labels: Vec<String>, // Translated names of MastodonVisibility::ALL, which the dropdown borrows
+    poll_enabled: bool,
+    poll_options: Vec<String>, // Between MIN_POLL_OPTIONS and MAX_POLL_OPTIONS
+    poll_duration: usize, // Index into POLL_DURATION_SECS
+    poll_duration_labels: Vec<String>, // Translated names of POLL_DURATION_SECS, which the dropdown borrows
+    poll_multiple: bool,
+    wordpress_title: String,
+    lemmy_title: String,
+    microblog_title: String,
+    microblog_categories: String, // Comma-separated
+    devto_title: String,
+    devto_tags: String, // Comma-separated
+    nostr_article: bool, // Publish to Nostr as a NIP-23 long-form article
+    nostr_article_title: String,
+    nostr_article_summary: String,
+    nostr_article_identifier: String, // The article's `d` tag: new for each one, kept while editing it
+    idempotency_key: String, // Per compose session, reused by retries so Mastodon dedupes them
+    schedule_time_input: String, // Local time, SCHEDULE_TIME_FORMAT
+    mastodon_server_schedule: bool, // Have Mastodon publish scheduled posts itself rather than queueing them here
+    pending_server_schedule: Option<ScheduledPost>, // Sent to Mastodon to schedule; queued here instead if it's refused
+    sending_scheduled: HashSet<String>, // IDs of scheduled posts currently being sent
+    show_drafts: bool,
+    show_preview: bool,
+    show_emoji_picker: bool,
+    emoji_search: String,
+    shortening_links: bool,
+    suggesting_alt_text: HashSet<String>, // Paths of images waiting on the captioning service
+    window_size: Option<(u32, u32)>, // Current size, saved on exit
+    duplicate_warning: Option<DateTime<Utc>>, // When the same text was last sent, while asking whether to post it again
+    pending_leave: Option<PendingLeave>, // Set while asking whether to keep unsaved text
     // Settings editing state
     temp_bluesky: BlueskyConfig,
     temp_mastodon: MastodonConfig,
     temp_nostr: NostrConfig,
     temp_microblog: crate::config::MicroBlogConfig,
+    temp_threads: ThreadsConfig,
+    temp_pixelfed: PixelfedConfig,
+    temp_discord: DiscordWebhookConfig,
+    new_discord_webhook: String,
+    temp_telegram: TelegramConfig,
+    temp_wordpress: WordPressConfig,
+    temp_lemmy: LemmyConfig,
+    temp_farcaster: FarcasterConfig,
+    temp_matrix: MatrixConfig,
+    temp_devto: DevToConfig,
+    temp_url_shortener: UrlShortenerConfig,
+    temp_captioner: CaptionerConfig,
+    temp_text_cleanup: HashMap<Platform, TextCleanup>,
+    temp_signatures: HashMap<Platform, String>,
     new_relay: String,
     show_relays: bool,
+    settings_filter: String,
+    mastodon_instance: Option<Result<MastodonInstance, String>>, // The saved instance's last check
+    checking_mastodon_instance: bool,
+    unsaved_settings: bool, // The Settings tab differs from the saved config; updated as it's edited
+    mastodon_instance_check: u64, // Counts checks, so only the latest one's answer is used
+    relay_checks: HashMap<String, Option<Result<Duration, String>>>, // Last check per relay; `None` while in progress
+    relay_list_status: Option<Result<String, String>>, // Outcome of publishing the relay list; `Ok("")` while in progress
+    accounts: HashMap<Platform, Result<AccountPreview, String>>, // Looked up after saving settings; missing while loading
+    new_master_password: String,
+    current_master_password: String, // Confirms a master password change
+    transfer_passphrase: String, // Protects credentials in exported settings files
     crypto_manager: CryptoManager,
-
+    http_client: reqwest::Client, // Shared by all requests so connections are reused
+    post_abort: Option<cosmic::iced::task::Handle>, // Aborts the in-flight post, while there is one
+    in_flight_post: Option<(String, Vec<ImageAttachment>, Option<NostrArticle>)>, // What's being sent, for the history
+    upload_progress: UploadProgress, // Image bytes sent by the post in progress
+    pow_progress: PowProgress, // Proof-of-work mining for the post in progress's Nostr notes
+    // Unlock state
+    master_password_input: String,
+    unlock_error: Option<String>,
 }
 
 /// Create a COSMIC application with system tray from the app model
@@ -127,61 +474,173 @@ impl cosmic::Application for AppModel {
         core: cosmic::Core,
         _flags: Self::Flags,
     ) -> (Self, Task<cosmic::Action<Self::Message>>) {
-        // Initialize crypto manager
-        let mut crypto_manager = CryptoManager::new();
-        if let Err(e) = crypto_manager.init_with_machine_key() {
-            eprintln!("Failed to initialize crypto manager: {}", e);
-        }
-
         // Load configuration
-        let mut config = cosmic_config::Config::new(Self::APP_ID, Config::VERSION)
-            .map(|context| match Config::get_entry(&context) {
-                Ok(config) => config,
-                Err((_errors, config)) => config,
-            })
-            .unwrap_or_default();
-
-        // Decrypt credentials
-        if let Err(e) = config.decrypt_credentials(&crypto_manager) {
-            eprintln!("Failed to decrypt credentials: {}", e);
-        }
+        let mut config = config::load(Self::APP_ID);
 
+        // With a master password the key is derived on unlock; otherwise use the machine key
+        let mut crypto_manager = CryptoManager::new();
+        let locked = config.has_master_password();
+        if !locked {
+            if config.crypto_backend == CryptoBackend::Keyring {
+                crypto_manager.init_with_keyring(Self::APP_ID);
+            } else if let Err(e) = crypto_manager.init_with_machine_key(config.kdf_params()) {
+                tracing::error!("Failed to initialize crypto manager: {}", e);
+            }
 
+            // Decrypt credentials
+            if let Err(failed) = config.decrypt_credentials(&crypto_manager) {
+                tracing::error!("Failed to decrypt credentials for {:?}", failed);
+            } else {
+                Self::upgrade_stored_credentials(&mut config, &crypto_manager);
+            }
+        }
 
-        // Initialize temp configs with decrypted values
-        let mut temp_bluesky = config.bluesky.clone();
-        let mut temp_mastodon = config.mastodon.clone();
-        let mut temp_nostr = config.nostr.clone();
-        let mut temp_microblog = config.microblog.clone();
+        let mastodon_visibility = config.mastodon.default_visibility;
 
-        // Copy decrypted values to temp configs
-        temp_bluesky.decrypted_password = config.bluesky.decrypted_password.clone();
-        temp_mastodon.decrypted_access_token = config.mastodon.decrypted_access_token.clone();
-        temp_microblog.decrypted_access_token = config.microblog.decrypted_access_token.clone();
-        temp_nostr.decrypted_private_key = config.nostr.decrypted_private_key.clone();
+        let http_client = social::build_http_client(config.request_timeout());
+        let show_relays = !config.relays_collapsed;
+        // Posts are tagged with the last language picked, or the system's until one is
+        let post_language = config
+            .post_language
+            .clone()
+            .or_else(i18n::system_language)
+            .and_then(|code| POST_LANGUAGE_CODES.iter().position(|known| *known == code))
+            .unwrap_or(0);
 
         // Construct the applet model with the runtime's core.
-        let app = AppModel {
+        let mut app = AppModel {
             core,
-            view_mode: ViewMode::Compose,
-            temp_bluesky,
-            temp_mastodon,
-            temp_nostr,
-            temp_microblog,
-            config: config.clone(),
+            view_mode: if locked { ViewMode::Unlock } else { config.last_view.into() },
+            temp_bluesky: BlueskyConfig::default(),
+            temp_mastodon: MastodonConfig::default(),
+            temp_nostr: NostrConfig::default(),
+            temp_microblog: crate::config::MicroBlogConfig::default(),
+            temp_threads: ThreadsConfig::default(),
+            temp_pixelfed: PixelfedConfig::default(),
+            temp_discord: DiscordWebhookConfig::default(),
+            new_discord_webhook: String::new(),
+            temp_telegram: TelegramConfig::default(),
+            temp_wordpress: WordPressConfig::default(),
+            temp_lemmy: LemmyConfig::default(),
+            temp_farcaster: FarcasterConfig::default(),
+            temp_matrix: MatrixConfig::default(),
+            temp_devto: DevToConfig::default(),
+            temp_url_shortener: UrlShortenerConfig::default(),
+            temp_captioner: CaptionerConfig::default(),
+            temp_text_cleanup: HashMap::new(),
+            temp_signatures: HashMap::new(),
+            config,
             post_editor_content: text_editor::Content::new(),
             posting_status: PostingStatus::Idle,
             new_relay: String::new(),
-            show_relays: true,
-            post_to_bluesky: config.bluesky.enabled,
-            post_to_mastodon: config.mastodon.enabled,
-            post_to_microblog: config.microblog.enabled,
-            post_to_nostr: config.nostr.enabled,
-            attached_image: None,
+            relay_list_status: None,
+            accounts: HashMap::new(),
+            show_relays,
+            settings_filter: String::new(),
+            mastodon_instance: None,
+            checking_mastodon_instance: false,
+            unsaved_settings: false,
+            mastodon_instance_check: 0,
+            relay_checks: HashMap::new(),
+            post_to_bluesky: false,
+            post_to_mastodon: false,
+            post_to_microblog: false,
+            post_to_nostr: false,
+            post_to_threads: false,
+            post_to_pixelfed: false,
+            post_to_discord: false,
+            post_to_telegram: false,
+            post_to_wordpress: false,
+            post_to_lemmy: false,
+            post_to_farcaster: false,
+            post_to_matrix: false,
+            post_to_devto: false,
+            attached_images: Vec::new(),
+            image_previews: HashMap::new(),
+            spinner_frame: 0,
+            failed_platforms: Vec::new(),
+            rejected_relays: Vec::new(),
+            post_warnings: Vec::new(),
+            post_links: Vec::new(),
+            content_warning_enabled: false,
+            reply_enabled: false,
+            quote: false,
+            reference_urls: HashMap::new(),
+            thread_mode: false,
+            markdown: false,
+            include_signature: true,
+            dry_run: false,
+            post_language,
+            spoiler_text: String::new(),
+            mastodon_visibility,
+            mastodon_visibility_labels: MastodonVisibility::ALL.iter().map(MastodonVisibility::label).collect(),
+            poll_enabled: false,
+            poll_options: vec![String::new(); MIN_POLL_OPTIONS],
+            poll_duration: DEFAULT_POLL_DURATION,
+            poll_duration_labels: vec![
+                fl!("poll-5-minutes"),
+                fl!("poll-30-minutes"),
+                fl!("poll-1-hour"),
+                fl!("poll-6-hours"),
+                fl!("poll-1-day"),
+                fl!("poll-3-days"),
+                fl!("poll-7-days"),
+            ],
+            poll_multiple: false,
+            wordpress_title: String::new(),
+            lemmy_title: String::new(),
+            microblog_title: String::new(),
+            microblog_categories: String::new(),
+            devto_title: String::new(),
+            devto_tags: String::new(),
+            nostr_article: false,
+            nostr_article_title: String::new(),
+            nostr_article_summary: String::new(),
+            nostr_article_identifier: uuid::Uuid::new_v4().to_string(),
+            idempotency_key: uuid::Uuid::new_v4().to_string(),
+            schedule_time_input: String::new(),
+            mastodon_server_schedule: true,
+            pending_server_schedule: None,
+            sending_scheduled: HashSet::new(),
+            show_drafts: false,
+            show_preview: false,
+            show_emoji_picker: false,
+            shortening_links: false,
+            suggesting_alt_text: HashSet::new(),
+            window_size: None,
+            duplicate_warning: None,
+            pending_leave: None,
+            emoji_search: String::new(),
+            new_master_password: String::new(),
+            current_master_password: String::new(),
+            transfer_passphrase: String::new(),
             crypto_manager,
+            http_client,
+            post_abort: None,
+            in_flight_post: None,
+            upload_progress: UploadProgress::default(),
+            pow_progress: PowProgress::default(),
+            master_password_input: String::new(),
+            unlock_error: None,
         };
+        app.load_editing_state();
+
+        // Restore whatever was in the editor when the app was last closed
+        if let Some(draft) = app.config.last_session.clone() {
+            app.load_draft(&draft);
+        }
+
+        // Send anything that came due while the app was closed
+        let mut startup = vec![app.fetch_mastodon_instance(), app.load_image_previews()];
+        if locked {
+            startup.push(focus(UNLOCK_ID.clone()));
+        } else {
+            startup.push(Task::done(cosmic::Action::App(Message::CheckScheduledPosts)));
+            startup.push(app.focus_view());
+        }
+        let startup = Task::batch(startup);
 
-        (app, Task::none())
+        (app, startup)
     }
 
     /// Main view for the application
@@ -189,14 +648,36 @@ impl cosmic::Application for AppModel {
         self.main_view()
     }
 
+    /// Ask about unsaved text before the window closes; the app exits once that's settled.
+    fn on_close_requested(&self, _id: cosmic::iced::window::Id) -> Option<Self::Message> {
+        Some(Message::CloseRequested)
+    }
+
     /// Register subscriptions for this application.
     fn subscription(&self) -> Subscription<Self::Message> {
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             // Watch for configuration changes.
             self.core()
                 .watch_config::<Config>(Self::APP_ID)
                 .map(|update| Message::UpdateConfig(Box::new(update.config))),
-        ])
+            // Periodically send scheduled posts that are due.
+            Subscription::run(scheduler_ticks),
+            // Track the size so the window reopens at it.
+            cosmic::iced::window::resize_events().map(|(_id, size)| Message::WindowResized(size)),
+            // Escape leaves Settings; the editor handles Ctrl+Enter itself.
+            keyboard::on_key_press(|key, _modifiers| match key {
+                Key::Named(Named::Escape) => Some(Message::EscapePressed),
+                _ => None,
+            }),
+        ];
+        // Clear a success message after a moment
+        if matches!(self.posting_status, PostingStatus::Success) {
+            subscriptions.push(Subscription::run(success_status_timeout));
+        }
+        if matches!(self.posting_status, PostingStatus::Posting) {
+            subscriptions.push(Subscription::run(spinner_ticks));
+        }
+        Subscription::batch(subscriptions)
     }
 
     /// Handle messages emitted by the applet and its widgets.
@@ -204,477 +685,3770 @@ impl cosmic::Application for AppModel {
         match message {
 
             Message::SwitchView(view_mode) => {
-                self.view_mode = view_mode;
-                Task::none()
+                if matches!(self.view_mode, ViewMode::Compose) && !matches!(view_mode, ViewMode::Compose) && self.has_unsaved_text() {
+                    self.pending_leave = Some(PendingLeave::View(view_mode));
+                    return Task::none();
+                }
+                self.set_view_mode(view_mode)
             }
             Message::UpdateConfig(config) => {
                 let mut config = *config;
                 // Decrypt credentials when config is reloaded
-                if let Err(e) = config.decrypt_credentials(&self.crypto_manager) {
-                    eprintln!("Failed to decrypt credentials in UpdateConfig: {}", e);
-                }
-                
-                eprintln!("UpdateConfig debug - config.nostr after decrypt:");
-                eprintln!("  enabled: {}", config.nostr.enabled);
-                eprintln!("  decrypted_private_key: '{}'", config.nostr.decrypted_private_key);
-                eprintln!("  decrypted_private_key.len(): {}", config.nostr.decrypted_private_key.len());
-                
+                if let Err(failed) = config.decrypt_credentials(&self.crypto_manager) {
+                    tracing::error!("Failed to decrypt credentials in UpdateConfig for {:?}", failed);
+                }
+                if config.request_timeout() != self.config.request_timeout() {
+                    self.http_client = social::build_http_client(config.request_timeout());
+                }
+
                 self.config = config;
                 Task::none()
             }
 
             Message::PostEditorAction(action) => {
+                if action.is_edit() {
+                    self.duplicate_warning = None;
+                }
                 self.post_editor_content.perform(action);
                 Task::none()
             }
-            Message::AttachImage => {
-                // Open native file picker dialog and set attached_image
-                let picked = FileDialog::new()
-                    .add_filter("Image", &["png", "jpg", "jpeg", "gif", "bmp", "webp"])
-                    .pick_file();
-                let path = picked.map(|p| p.to_string_lossy().to_string());
-                Task::done(cosmic::Action::App(Message::ImageSelected(path)))
+            Message::ToggleEmojiPicker => {
+                self.show_emoji_picker = !self.show_emoji_picker;
+                self.emoji_search.clear();
+                Task::none()
             }
-            Message::ImageSelected(path) => {
-                self.attached_image = path;
+            Message::EmojiSearchChanged(query) => {
+                self.emoji_search = query;
                 Task::none()
             }
-            Message::PostSubmit => {
-                let text = self.post_editor_content.text().to_string();
-                if text.trim().is_empty() || text.chars().count() > MAX_POST_LENGTH {
+            Message::InsertEmoji(emoji) => {
+                // Pasting inserts at the cursor, replacing any selection
+                self.post_editor_content
+                    .perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(emoji.to_string()))));
+                Task::none()
+            }
+            Message::AttachImage => {
+                // Open native file picker dialog and add to attached_images
+                let picked = FileDialog::new()
+                    .add_filter(fl!("media-files"), &["png", "jpg", "jpeg", "gif", "bmp", "webp", "mp4", "mov", "webm"])
+                    .add_filter(fl!("image-files"), &["png", "jpg", "jpeg", "gif", "bmp", "webp"])
+                    .add_filter(fl!("video-files"), &["mp4", "mov", "webm"])
+                    .pick_files()
+                    .unwrap_or_default();
+                let paths = picked.into_iter().map(|p| p.to_string_lossy().to_string()).collect();
+                Task::done(cosmic::Action::App(Message::ImagesSelected(paths)))
+            }
+            Message::PasteImage => {
+                if self.attached_images.len() >= MAX_IMAGES || self.poll_enabled || self.attached_images.iter().any(ImageAttachment::is_video) {
                     return Task::none();
                 }
-
-                self.posting_status = PostingStatus::Posting;
-                // Debug: Check self.config before creating copy
-                eprintln!("PostSubmit debug - self.config.nostr before copy:");
-                eprintln!("  enabled: {}", self.config.nostr.enabled);
-                eprintln!("  decrypted_private_key: '{}'", self.config.nostr.decrypted_private_key);
-                eprintln!("  decrypted_private_key.len(): {}", self.config.nostr.decrypted_private_key.len());
-
-                // Create config copy with decrypted values (clone doesn't work due to #[serde(skip)])
-                let mut config = self.config.clone();
-                config.bluesky.decrypted_password = self.config.bluesky.decrypted_password.clone();
-                config.mastodon.decrypted_access_token = self.config.mastodon.decrypted_access_token.clone();
-                config.microblog.decrypted_access_token = self.config.microblog.decrypted_access_token.clone();
-                config.nostr.decrypted_private_key = self.config.nostr.decrypted_private_key.clone();
-
-                // Debug: Check config after manual copy
-                eprintln!("PostSubmit debug - config.nostr after manual copy:");
-                eprintln!("  enabled: {}", config.nostr.enabled);
-                eprintln!("  decrypted_private_key: '{}'", config.nostr.decrypted_private_key);
-                eprintln!("  decrypted_private_key.len(): {}", config.nostr.decrypted_private_key.len());
-                let post_to_bluesky = self.post_to_bluesky;
-                let post_to_mastodon = self.post_to_mastodon;
-                let post_to_microblog = self.post_to_microblog;
-                let post_to_nostr = self.post_to_nostr;
-
-                let attached_image = self.attached_image.clone();
-                eprintln!("PostSubmit debug - attached_image: {:?}", attached_image);
-                eprintln!("PostSubmit debug - post_to_bluesky: {}", post_to_bluesky);
-                eprintln!("PostSubmit debug - post_to_mastodon: {}", post_to_mastodon);
-                eprintln!("PostSubmit debug - post_to_microblog: {}", post_to_microblog);
-                eprintln!("PostSubmit debug - post_to_nostr: {}", post_to_nostr);
-
+                match clipboard::paste_image() {
+                    Ok(Some(path)) => Task::done(cosmic::Action::App(Message::ImagesSelected(vec![path]))),
+                    Ok(None) => {
+                        self.posting_status = PostingStatus::Error(fl!("clipboard-no-image"));
+                        Task::none()
+                    }
+                    Err(e) => {
+                        tracing::warn!("Paste image: {}", e);
+                        self.posting_status = PostingStatus::Error(e);
+                        Task::none()
+                    }
+                }
+            }
+            Message::ShortenLinks => {
+                let text = self.post_editor_content.text();
+                self.shortening_links = true;
+                let client = self.http_client.clone();
+                let retry = self.config.retry_policy();
+                let shortener = self.config.url_shortener.clone();
                 Task::perform(
-                    {
-                        async move {
-                            let mut errors = Vec::new();
-                            let image_path = attached_image.as_deref();
-                            eprintln!("PostSubmit debug - image_path: {:?}", image_path);
-                            if post_to_bluesky {
-                                eprintln!("PostSubmit: Posting to Bluesky...");
-                                if let Err(e) = social::post_to_bluesky(&config.bluesky, &text, image_path).await {
-                                    eprintln!("PostSubmit: Bluesky error: {}", e);
-                                    errors.push(format!("Bluesky: {}", e));
-                                }
-                            }
-                            if post_to_mastodon {
-                                eprintln!("PostSubmit: Posting to Mastodon...");
-                                if let Err(e) = social::post_to_mastodon(&config.mastodon, &text, image_path).await {
-                                    eprintln!("PostSubmit: Mastodon error: {}", e);
-                                    errors.push(format!("Mastodon: {}", e));
-                                }
-                            }
-                            if post_to_microblog {
-                                eprintln!("PostSubmit: Posting to Micro.Blog...");
-                                if let Err(e) = social::post_to_microblog(&config.microblog, &text, image_path).await {
-                                    eprintln!("PostSubmit: Micro.Blog error: {}", e);
-                                    errors.push(format!("Micro.Blog: {}", e));
-                                }
-                            }
-                            if post_to_nostr {
-                                eprintln!("Attempting to post to Nostr...");
-                                if let Err(e) = social::post_to_nostr(&config.nostr, &text, image_path).await {
-                                    eprintln!("Nostr post failed: {}", e);
-                                    errors.push(format!("Nostr: {}", e));
-                                } else {
-                                    eprintln!("Nostr post succeeded!");
-                                }
-                            } else {
-                                eprintln!("Nostr posting is disabled (post_to_nostr = false)");
-                            }
-                            if errors.is_empty() {
-                                Ok(())
-                            } else {
-                                Err(PostError::Api(errors.join("; ")))
-                            }
-                        }
+                    async move {
+                        let result = social::shorten_links(&client, retry, &shortener, &text).await.map_err(|e| e.to_string());
+                        (text, result)
                     },
-                    |result| cosmic::Action::App(Message::PostResult(result)),
+                    |(text, result)| cosmic::Action::App(Message::LinksShortened(text, result)),
                 )
             }
-            Message::PostResult(result) => {
+            Message::LinksShortened(text, result) => {
+                self.shortening_links = false;
                 match result {
-                    Ok(()) => {
-                        self.posting_status = PostingStatus::Success;
-                        self.post_editor_content = text_editor::Content::new();
+                    // Leave the editor alone if it was changed while the links were being shortened
+                    Ok(shortened) if self.post_editor_content.text() == text => {
+                        self.post_editor_content = text_editor::Content::with_text(&shortened);
                     }
-                    Err(e) => {
-                        self.posting_status = PostingStatus::Error(e.to_string());
+                    Ok(_) => {}
+                    Err(e) => self.posting_status = PostingStatus::Error(fl!("shorten-links-error", error = e)),
+                }
+                Task::none()
+            }
+            Message::ImagesSelected(paths) => {
+                let mut checks = Vec::new();
+                for path in paths {
+                    if self.attached_images.len() >= MAX_IMAGES {
+                        self.posting_status = PostingStatus::Error(fl!("too-many-images", count = MAX_IMAGES));
+                        break;
+                    }
+                    if self.attached_images.iter().any(|image| image.path == path) {
+                        continue;
+                    }
+                    let attachment = ImageAttachment::new(path);
+                    // A video goes out on its own, without images or a second video
+                    if !self.attached_images.is_empty() && (attachment.is_video() || self.attached_images.iter().any(ImageAttachment::is_video)) {
+                        self.posting_status = PostingStatus::Error(fl!("video-not-alone"));
+                        break;
+                    }
+                    if attachment.is_video() {
+                        let path = attachment.path.clone();
+                        checks.push(Task::perform(check_video(path.clone()), move |result| cosmic::Action::App(Message::VideoChecked(path.clone(), result))));
+                    }
+                    self.attached_images.push(attachment);
+                }
+                checks.push(self.load_image_previews());
+                Task::batch(checks)
+            }
+            Message::VideoChecked(path, result) => {
+                if let Err(e) = result {
+                    if let Some(index) = self.attached_images.iter().position(|image| image.path == path) {
+                        let video = self.attached_images.remove(index);
+                        self.discard_pasted_images(&[video]);
+                        self.posting_status = PostingStatus::Error(e);
                     }
                 }
                 Task::none()
             }
-            Message::PostToBlueskyToggled(enabled) => {
-                self.post_to_bluesky = enabled;
+            Message::RemoveImage(index) => {
+                if index < self.attached_images.len() {
+                    let image = self.attached_images.remove(index);
+                    self.image_previews.remove(&image.path);
+                    self.discard_pasted_images(&[image]);
+                }
                 Task::none()
             }
-            Message::PostToMastodonToggled(enabled) => {
-                self.post_to_mastodon = enabled;
+            Message::ImagePreviewLoaded(path, preview) => {
+                if let Err(e) = &preview {
+                    tracing::warn!("Failed to load preview of {}: {}", path, e);
+                }
+                self.image_previews.insert(path, preview);
                 Task::none()
             }
-            Message::PostToMicroBlogToggled(enabled) => {
-                self.post_to_microblog = enabled;
+            Message::ImageAltTextChanged(index, alt_text) => {
+                if let Some(image) = self.attached_images.get_mut(index) {
+                    image.alt_text = alt_text;
+                }
                 Task::none()
             }
-            Message::PostToNostrToggled(enabled) => {
-                self.post_to_nostr = enabled;
+            Message::SuggestAltText(index) => {
+                let Some(image) = self.attached_images.get(index) else {
+                    return Task::none();
+                };
+                let path = image.path.clone();
+                self.suggesting_alt_text.insert(path.clone());
+                let client = self.http_client.clone();
+                let retry = self.config.retry_policy();
+                let captioner = self.config.captioner.clone();
+                let keep_metadata = self.config.keep_image_metadata;
+                Task::perform(
+                    async move {
+                        let result = social::suggest_alt_text(&client, retry, &captioner, &path, keep_metadata).await.map_err(|e| e.to_string());
+                        (path, result)
+                    },
+                    |(path, result)| cosmic::Action::App(Message::AltTextSuggested(path, result)),
+                )
+            }
+            Message::AltTextSuggested(path, result) => {
+                self.suggesting_alt_text.remove(&path);
+                match result {
+                    // The image may have been removed while the service was answering
+                    Ok(caption) => {
+                        if let Some(image) = self.attached_images.iter_mut().find(|image| image.path == path) {
+                            image.alt_text = caption;
+                        }
+                    }
+                    Err(e) => self.posting_status = PostingStatus::Error(fl!("suggest-alt-text-error", error = e)),
+                }
                 Task::none()
             }
-            Message::BlueskyEnabledChanged(enabled) => {
-                self.temp_bluesky.enabled = enabled;
+            Message::PostSubmit => {
+                // Ask before sending the same text again so soon
+                if let Some(post) = self.config.recent_duplicate(&self.post_editor_content.text(), Utc::now()) {
+                    self.duplicate_warning = Some(post.sent_at);
+                    return Task::none();
+                }
+                self.post_selected()
+            }
+            Message::PostAnyway => {
+                self.duplicate_warning = None;
+                if !self.can_post() {
+                    return Task::none();
+                }
+                self.post_selected()
+            }
+            Message::DismissDuplicateWarning => {
+                self.duplicate_warning = None;
                 Task::none()
             }
-            Message::BlueskyHandleChanged(handle) => {
-                self.temp_bluesky.handle = handle;
+            Message::DismissStatus => {
+                if !matches!(self.posting_status, PostingStatus::Posting) {
+                    self.posting_status = PostingStatus::Idle;
+                }
                 Task::none()
             }
-            Message::BlueskyPasswordChanged(password) => {
-                self.temp_bluesky.decrypted_password = password;
+            Message::OpenPostLink(url) => {
+                if let Err(e) = open::that_detached(&url) {
+                    tracing::error!("Failed to open {}: {}", url, e);
+                }
                 Task::none()
             }
-            Message::MastodonEnabledChanged(enabled) => {
-                self.temp_mastodon.enabled = enabled;
+            Message::SubmitShortcut => {
+                // Same conditions as the Post button being clickable
+                if self.can_post() {
+                    return Task::done(cosmic::Action::App(Message::PostSubmit));
+                }
                 Task::none()
             }
-            Message::MastodonInstanceChanged(instance) => {
-                self.temp_mastodon.instance_url = instance;
+            Message::EscapePressed => {
+                if matches!(self.view_mode, ViewMode::Settings) {
+                    return self.set_view_mode(ViewMode::Compose);
+                }
                 Task::none()
             }
-            Message::MastodonTokenChanged(token) => {
-                self.temp_mastodon.decrypted_access_token = token;
+            Message::SpinnerTick => {
+                self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
                 Task::none()
             }
-            Message::MicroBlogEnabledChanged(enabled) => {
-                self.temp_microblog.enabled = enabled;
+            Message::CancelPost => {
+                // Requests that already completed can't be taken back, but nothing else is sent
+                if let Some(handle) = self.post_abort.take() {
+                    handle.abort();
+                }
+                self.pow_progress.cancel();
+                self.in_flight_post = None;
+                self.posting_status = PostingStatus::Idle;
                 Task::none()
             }
-            Message::MicroBlogTokenChanged(token) => {
-                self.temp_microblog.decrypted_access_token = token;
+            Message::RetryFailed => {
+                let platforms = self.failed_platforms.clone();
+                self.submit_post(platforms)
+            }
+            Message::PostResult(report) => {
+                self.post_abort = None;
+                let mut sent_text = String::new();
+                let mut sent_images = Vec::new();
+                if let Some((text, images, nostr_article)) = self.in_flight_post.take() {
+                    // Updated in place rather than through a copy, which would duplicate the credentials
+                    self.config.record_sent_post(sent_post(self.idempotency_key.clone(), text.clone(), &images, nostr_article, &report));
+                    sent_text = text;
+                    if let Err(e) = Self::write_config(&self.config) {
+                        tracing::error!("Failed to update post history: {}", e);
+                    }
+                    sent_images = images;
+                }
+                for (platform, result) in &report.results {
+                    if let Err(e) = result {
+                        tracing::warn!("PostResult: {} error: {}", platform, e);
+                    }
+                }
+                self.failed_platforms = report.failed().iter().map(|(platform, _)| *platform).collect();
+                self.rejected_relays = report
+                    .rejected_relays()
+                    .into_iter()
+                    .map(|(relay, reason)| (relay.to_string(), reason.to_string()))
+                    .collect();
+                self.post_warnings = report
+                    .warnings
+                    .iter()
+                    .map(|(platform, warning)| format!("{}: {}", platform, warning))
+                    .collect();
+                self.post_links = report.links.clone();
+
+                // Something got through, so the network is back for anything waiting
+                let resumed = !report.dry_run && !report.succeeded().is_empty() && self.resume_outbox();
+                // Couldn't reach anything that failed: keep it in the outbox to send once the network is back.
+                // A post the outbox can't keep whole stays in compose to retry by hand.
+                let queued = !report.dry_run && report.only_network_failures() && !self.outbox_would_drop_options(&self.failed_platforms);
+                if queued {
+                    let errors: Vec<String> = report
+                        .failed()
+                        .iter()
+                        .map(|(platform, e)| format!("{}: {}", platform, e))
+                        .collect();
+                    let mut config = self.config.clone();
+                    config.scheduled_posts.push(ScheduledPost {
+                        id: self.idempotency_key.clone(),
+                        text: sent_text,
+                        images: sent_images.clone(),
+                        platforms: self.failed_platforms.clone(),
+                        scheduled_at: Utc::now() + OUTBOX_RETRY_INTERVAL,
+                        thread_mode: self.thread_mode,
+                        markdown: self.markdown,
+                        language: Some(POST_LANGUAGE_CODES[self.post_language].to_string()),
+                        last_error: Some(self.config.redact_credentials(&errors.join("; "))),
+                        offline: true,
+                        signature: self.include_signature,
+                        mastodon_visibility: self.mastodon_visibility,
+                        spoiler_text: self.content_warning_enabled.then(|| self.spoiler_text.clone()),
+                        poll: self.mastodon_poll(),
+                        reference_urls: self.reply_references(),
+                        quote: self.quote,
+                        nostr_article: self.nostr_article_options(),
+                        wordpress_title: self.wordpress_title_option(),
+                        lemmy_title: self.lemmy_title_option(),
+                        microblog_title: self.microblog_title_option(),
+                        microblog_categories: self.microblog_category_list(),
+                    });
+                    if let Err(e) = Self::write_config(&config) {
+                        tracing::error!("Failed to add the post to the outbox: {}", e);
+                    }
+                    self.config = config;
+                }
+
+                if report.all_succeeded() && report.dry_run {
+                    // Keep everything in place to adjust and try again
+                    self.posting_status = PostingStatus::Success;
+                } else if report.all_succeeded() || queued {
+                    self.posting_status = if queued { PostingStatus::Queued } else { PostingStatus::Success };
+                    self.post_editor_content = text_editor::Content::new();
+                    self.content_warning_enabled = false;
+                    self.spoiler_text.clear();
+                    self.mastodon_visibility = self.config.mastodon.default_visibility;
+                    self.reply_enabled = false;
+                    self.reference_urls.clear();
+                    self.quote = false;
+                    self.include_signature = true;
+                    self.reset_poll();
+                    self.wordpress_title.clear();
+                    self.lemmy_title.clear();
+                    self.microblog_title.clear();
+                    self.microblog_categories.clear();
+                    self.nostr_article_title.clear();
+                    self.nostr_article_summary.clear();
+                    self.nostr_article_identifier = uuid::Uuid::new_v4().to_string();
+                    self.devto_title.clear();
+                    self.devto_tags.clear();
+                    // Pasted images only exist for this post
+                    self.attached_images.retain(|image| !clipboard::is_pasted_image(&image.path));
+                    self.discard_pasted_images(&sent_images);
+                    // Start a new compose session
+                    self.idempotency_key = uuid::Uuid::new_v4().to_string();
+                    self.failed_platforms.clear();
+                } else {
+                    let mut summary: Vec<String> = report
+                        .failed()
+                        .iter()
+                        .map(|(platform, e)| format!("{}: {}", platform, e))
+                        .collect();
+                    let succeeded = report.succeeded();
+                    if !succeeded.is_empty() {
+                        let names: Vec<&str> = succeeded.iter().map(|p| p.name()).collect();
+                        summary.push(fl!("posted-to", platforms = names.join(", ")));
+                    }
+                    self.posting_status = PostingStatus::Error(summary.join("; "));
+                }
+                if resumed {
+                    Task::done(cosmic::Action::App(Message::CheckScheduledPosts))
+                } else {
+                    Task::none()
+                }
+            }
+            Message::ContentWarningToggled(enabled) => {
+                self.content_warning_enabled = enabled;
                 Task::none()
             }
-            Message::NostrEnabledChanged(enabled) => {
-                self.temp_nostr.enabled = enabled;
+            Message::ReplyToggled(enabled) => {
+                self.reply_enabled = enabled;
                 Task::none()
             }
-            Message::NostrPrivateKeyChanged(key) => {
-                self.temp_nostr.decrypted_private_key = key;
+            Message::QuoteToggled(quote) => {
+                self.quote = quote;
                 Task::none()
             }
-            Message::NewRelayChanged(relay) => {
-                self.new_relay = relay;
+            Message::ReferenceUrlChanged(platform, url) => {
+                self.reference_urls.insert(platform, url);
                 Task::none()
             }
-            Message::AddRelay => {
-                if Self::validate_relay_url(&self.new_relay) && !self.temp_nostr.relays.contains(&self.new_relay) {
-                    self.temp_nostr.relays.push(self.new_relay.clone());
-                    self.new_relay.clear();
+            Message::ThreadModeToggled(enabled) => {
+                self.thread_mode = enabled;
+                Task::none()
+            }
+            Message::SignatureToggled(enabled) => {
+                self.include_signature = enabled;
+                Task::none()
+            }
+            Message::MarkdownToggled(enabled) => {
+                self.markdown = enabled;
+                Task::none()
+            }
+            Message::DryRunToggled(enabled) => {
+                self.dry_run = enabled;
+                Task::none()
+            }
+            Message::PostLanguageSelected(index) => {
+                self.post_language = index;
+                let mut config = self.config.clone();
+                config.post_language = Some(POST_LANGUAGE_CODES[index].to_string());
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to save the post language: {}", e);
                 }
+                self.config = config;
                 Task::none()
             }
-            Message::RemoveRelay(index) => {
-                if index < self.temp_nostr.relays.len() {
-                    self.temp_nostr.relays.remove(index);
+            Message::SpoilerTextChanged(text) => {
+                self.spoiler_text = text;
+                Task::none()
+            }
+            Message::MastodonVisibilitySelected(index) => {
+                if let Some(visibility) = MastodonVisibility::ALL.get(index) {
+                    self.mastodon_visibility = *visibility;
+                }
+                Task::none()
+            }
+            Message::PollToggled(enabled) => {
+                // Mastodon rejects statuses with both a poll and media
+                self.poll_enabled = enabled && self.attached_images.is_empty();
+                Task::none()
+            }
+            Message::PollOptionChanged(index, option) => {
+                if let Some(existing) = self.poll_options.get_mut(index) {
+                    *existing = option;
+                }
+                Task::none()
+            }
+            Message::AddPollOption => {
+                if self.poll_options.len() < self.max_poll_options() {
+                    self.poll_options.push(String::new());
+                }
+                Task::none()
+            }
+            Message::RemovePollOption(index) => {
+                if self.poll_options.len() > MIN_POLL_OPTIONS && index < self.poll_options.len() {
+                    self.poll_options.remove(index);
+                }
+                Task::none()
+            }
+            Message::PollDurationSelected(index) => {
+                if index < POLL_DURATION_SECS.len() {
+                    self.poll_duration = index;
                 }
                 Task::none()
             }
-            Message::SaveSettings => {
-                // Debug: Check temp_nostr values before saving
-                eprintln!("SaveSettings debug - temp_nostr:");
-                eprintln!("  enabled: {}", self.temp_nostr.enabled);
-                eprintln!("  decrypted_private_key: '{}'", self.temp_nostr.decrypted_private_key);
-                eprintln!("  decrypted_private_key.len(): {}", self.temp_nostr.decrypted_private_key.len());
-
-                // Update config with temp values
-                self.config.bluesky = self.temp_bluesky.clone();
-                self.config.mastodon = self.temp_mastodon.clone();
-                self.config.microblog = self.temp_microblog.clone();
-                self.config.nostr = self.temp_nostr.clone();
-
-                // Debug: Check main config after copying
-                eprintln!("SaveSettings debug - config.nostr after copy:");
-                eprintln!("  enabled: {}", self.config.nostr.enabled);
-                eprintln!("  decrypted_private_key: '{}'", self.config.nostr.decrypted_private_key);
-                eprintln!("  decrypted_private_key.len(): {}", self.config.nostr.decrypted_private_key.len());
-                
-                // Encrypt credentials before saving
-                if let Err(e) = self.config.encrypt_credentials(&self.crypto_manager) {
-                    eprintln!("Failed to encrypt credentials: {}", e);
-                    self.posting_status = PostingStatus::Error("Failed to save settings".to_string());
+            Message::PollMultipleToggled(multiple) => {
+                self.poll_multiple = multiple;
+                Task::none()
+            }
+            Message::ScheduleTimeChanged(time) => {
+                self.schedule_time_input = time;
+                Task::none()
+            }
+            Message::SchedulePost => {
+                let text = self.post_editor_content.text().to_string();
+                let mut platforms = self.selected_platforms();
+                if text.trim().is_empty() || platforms.is_empty() || self.exceeds_limit(&text, &platforms) {
+                    return Task::none();
+                }
+                // Scheduled posts don't keep the Dev.to title
+                if self.outbox_would_drop_options(&platforms) {
+                    self.posting_status = PostingStatus::Error(fl!("devto-no-schedule"));
                     return Task::none();
                 }
-                
-                // Update posting toggles based on new config
-                self.post_to_bluesky = self.config.bluesky.enabled;
-                self.post_to_mastodon = self.config.mastodon.enabled;
-                self.post_to_microblog = self.config.microblog.enabled;
-                self.post_to_nostr = self.config.nostr.enabled;
-
-                if let Ok(config_context) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
-                    if let Err(e) = self.config.write_entry(&config_context) {
-                        eprintln!("Failed to save config: {}", e);
-                        self.posting_status = PostingStatus::Error("Failed to save settings".to_string());
+                let Some(scheduled_at) = Self::parse_schedule_time(&self.schedule_time_input) else {
+                    self.posting_status = PostingStatus::Error(fl!("invalid-schedule-time"));
+                    return Task::none();
+                };
+                let post = ScheduledPost {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    text,
+                    images: self.attached_images.clone(),
+                    platforms: Vec::new(),
+                    scheduled_at,
+                    thread_mode: self.thread_mode,
+                    markdown: self.markdown,
+                    language: Some(POST_LANGUAGE_CODES[self.post_language].to_string()),
+                    last_error: None,
+                    offline: false,
+                    signature: self.include_signature,
+                    mastodon_visibility: self.mastodon_visibility,
+                    spoiler_text: self.content_warning_enabled.then(|| self.spoiler_text.clone()),
+                    poll: self.mastodon_poll(),
+                    reference_urls: self.reply_references(),
+                    quote: self.quote,
+                    nostr_article: self.nostr_article_options(),
+                    wordpress_title: self.wordpress_title_option(),
+                    lemmy_title: self.lemmy_title_option(),
+                    microblog_title: self.microblog_title_option(),
+                    microblog_categories: self.microblog_category_list(),
+                };
+
+                // Mastodon takes it off the local queue when it can publish the post itself. Its half
+                // is a post of its own, in case it comes back to the local queue: the two are sent and
+                // removed separately, and each gets its own copy of pasted images to clean up.
+                let server_post = if self.schedules_on_mastodon(&post, &platforms) {
+                    platforms.retain(|platform| *platform != Platform::Mastodon);
+                    let images = if platforms.is_empty() {
+                        Ok(post.images.clone())
                     } else {
-                        self.posting_status = PostingStatus::Success;
+                        post.images
+                            .iter()
+                            .map(|image| clipboard::copy_pasted_image(&image.path).map(|path| ImageAttachment { path, ..image.clone() }))
+                            .collect::<Result<Vec<_>, _>>()
+                    };
+                    match images {
+                        Ok(images) => Some(ScheduledPost {
+                            id: uuid::Uuid::new_v4().to_string(),
+                            images,
+                            platforms: vec![Platform::Mastodon],
+                            ..post.clone()
+                        }),
+                        Err(e) => {
+                            tracing::error!("Failed to schedule the post on Mastodon: {}", e);
+                            self.posting_status = PostingStatus::Error(fl!("save-scheduled-post-error"));
+                            return Task::none();
+                        }
                     }
                 } else {
-                    self.posting_status = PostingStatus::Error("Failed to save settings".to_string());
+                    None
+                };
+                if !platforms.is_empty() {
+                    let mut config = self.config.clone();
+                    config.scheduled_posts.push(ScheduledPost { platforms, ..post });
+                    if let Err(e) = Self::write_config(&config) {
+                        tracing::error!("Failed to save scheduled post: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("save-scheduled-post-error"));
+                        return Task::none();
+                    }
+                    self.config = config;
                 }
-                
-                // Decrypt again for runtime use
-                if let Err(e) = self.config.decrypt_credentials(&self.crypto_manager) {
-                    eprintln!("Failed to decrypt credentials after save: {}", e);
-                } else {
-                    eprintln!("SaveSettings debug - config.nostr after decrypt:");
-                    eprintln!("  enabled: {}", self.config.nostr.enabled);
-                    eprintln!("  decrypted_private_key: '{}'", self.config.nostr.decrypted_private_key);
-                    eprintln!("  decrypted_private_key.len(): {}", self.config.nostr.decrypted_private_key.len());
+
+                self.post_editor_content = text_editor::Content::new();
+                self.attached_images.clear();
+                self.schedule_time_input.clear();
+                let Some(server_post) = server_post else {
+                    self.posting_status = PostingStatus::Idle;
+                    return Task::none();
+                };
+
+                self.posting_status = PostingStatus::Posting;
+                let mut options = server_post.post_options();
+                options.mastodon.scheduled_at = Some(server_post.scheduled_at);
+                let task = Task::perform(
+                    social::post_to_platforms(self.http_client.clone(), self.posting_config(), vec![Platform::Mastodon], server_post.text.clone(), server_post.images.clone(), options),
+                    |report| cosmic::Action::App(Message::MastodonScheduleResult(report)),
+                );
+                self.pending_server_schedule = Some(server_post);
+                task
+            }
+            Message::MastodonServerScheduleToggled(enabled) => {
+                self.mastodon_server_schedule = enabled;
+                Task::none()
+            }
+            Message::MastodonScheduleResult(report) => {
+                let Some(post) = self.pending_server_schedule.take() else {
+                    return Task::none();
+                };
+                if let Some((_, time)) = report.scheduled.first() {
+                    self.posting_status = PostingStatus::Scheduled(*time);
+                    // Mastodon has its images now
+                    self.discard_pasted_images(&post.images);
+                    return Task::none();
+                }
+
+                // Send it from here at the time instead
+                let error = report.failed().first().map(|(_, e)| e.to_string()).unwrap_or_default();
+                tracing::warn!("Mastodon didn't schedule the post, queueing it locally: {}", error);
+                let mut config = self.config.clone();
+                config.scheduled_posts.push(post);
+                match Self::write_config(&config) {
+                    Ok(()) => {
+                        self.config = config;
+                        self.posting_status = PostingStatus::Error(fl!("mastodon-schedule-fallback", error = error));
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to save scheduled post: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("save-scheduled-post-error"));
+                    }
                 }
-                
                 Task::none()
             }
-            Message::ToggleRelays => {
-                self.show_relays = !self.show_relays;
+            Message::SaveDraft => {
+                let Some(draft) = self.current_draft() else {
+                    return Task::none();
+                };
+                let mut config = self.config.clone();
+                config.drafts.push(draft);
+                match Self::write_config(&config) {
+                    Ok(()) => {
+                        self.config = config;
+                        self.show_drafts = true;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to save draft: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("save-draft-error"));
+                    }
+                }
+                Task::none()
+            }
+            Message::LoadDraft(index) => {
+                if let Some(draft) = self.config.drafts.get(index).cloned() {
+                    self.load_draft(&draft);
+                }
+                self.load_image_previews()
+            }
+            Message::DeleteDraft(index) => {
+                if index < self.config.drafts.len() {
+                    let mut config = self.config.clone();
+                    config.drafts.remove(index);
+                    if let Err(e) = Self::write_config(&config) {
+                        tracing::error!("Failed to delete draft: {}", e);
+                    }
+                    self.config = config;
+                }
+                Task::none()
+            }
+            Message::DuplicateToCompose(index) => {
+                if let Some(post) = self.config.history.get(index).cloned() {
+                    self.post_editor_content = text_editor::Content::with_text(&post.text);
+                    // Only image names are kept, so the images can't come along
+                    self.attached_images.clear();
+                    for platform in Platform::ALL {
+                        self.set_platform_selected(platform, post.results.iter().any(|(p, _)| *p == platform));
+                    }
+                    // A copy is a new article, not an edit of the old one
+                    self.nostr_article_identifier = uuid::Uuid::new_v4().to_string();
+                    return self.set_view_mode(ViewMode::Compose);
+                }
+                Task::none()
+            }
+            Message::EditNostrArticle(index) => {
+                let Some(post) = self.config.history.get(index).cloned() else {
+                    return Task::none();
+                };
+                let Some(article) = &post.nostr_article else {
+                    return Task::none();
+                };
+                // Publishing under the same identifier replaces the article, so only Nostr gets it
+                self.post_editor_content = text_editor::Content::with_text(&post.text);
+                self.attached_images.clear();
+                for platform in Platform::ALL {
+                    self.set_platform_selected(platform, platform == Platform::Nostr);
+                }
+                self.load_nostr_article(article);
+                self.set_view_mode(ViewMode::Compose)
+            }
+            Message::ClearHistory => {
+                let mut config = self.config.clone();
+                config.history.clear();
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to clear post history: {}", e);
+                }
+                self.config = config;
+                Task::none()
+            }
+            Message::TogglePreview => {
+                self.show_preview = !self.show_preview;
+                Task::none()
+            }
+            Message::ToggleDrafts => {
+                self.show_drafts = !self.show_drafts;
+                Task::none()
+            }
+            Message::CloseRequested => {
+                if self.has_unsaved_text() && !matches!(self.view_mode, ViewMode::Unlock) {
+                    self.pending_leave = Some(PendingLeave::Close);
+                    return Task::none();
+                }
+                self.leave(PendingLeave::Close)
+            }
+            Message::SaveDraftAndLeave => {
+                let (Some(leave), Some(draft)) = (self.pending_leave.take(), self.current_draft()) else {
+                    return Task::none();
+                };
+                let mut config = self.config.clone();
+                config.drafts.push(draft);
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to save draft: {}", e);
+                    self.posting_status = PostingStatus::Error(fl!("save-draft-error"));
+                    return Task::none();
+                }
+                self.config = config;
+                self.clear_compose_text();
+                self.leave(leave)
+            }
+            Message::DiscardAndLeave => {
+                let Some(leave) = self.pending_leave.take() else {
+                    return Task::none();
+                };
+                self.clear_compose_text();
+                self.leave(leave)
+            }
+            Message::CancelLeave => {
+                self.pending_leave = None;
+                Task::none()
+            }
+            Message::WindowResized(size) => {
+                // Saved on exit rather than on every step of a drag
+                self.window_size = Some((size.width.round() as u32, size.height.round() as u32));
                 Task::none()
             }
+            Message::CheckScheduledPosts => {
+                // Credentials aren't available until unlocked
+                if matches!(self.view_mode, ViewMode::Unlock) {
+                    return Task::none();
+                }
 
+                let now = Utc::now();
+                let mut tasks = Vec::new();
+                // Pick up changes to the instance's limits in sessions that stay open for days; after a
+                // failed check, wait for the next save or a manual refresh
+                if self.config.mastodon.limits_stale(now) && !self.checking_mastodon_instance && !matches!(self.mastodon_instance, Some(Err(_))) {
+                    tasks.push(self.fetch_mastodon_instance());
+                }
 
+                let due: Vec<ScheduledPost> = self
+                    .config
+                    .scheduled_posts
+                    .iter()
+                    .filter(|post| post.is_due(now) && !self.sending_scheduled.contains(&post.id))
+                    .cloned()
+                    .collect();
+
+                let config = self.posting_config();
+                for post in due {
+                    self.sending_scheduled.insert(post.id.clone());
+                    let options = post.post_options();
+                    let id = post.id;
+                    tasks.push(Task::perform(
+                        social::post_to_platforms(self.http_client.clone(), config.clone(), post.platforms, post.text, post.images, options),
+                        move |report| cosmic::Action::App(Message::ScheduledPostResult(id.clone(), report)),
+                    ));
+                }
+                Task::batch(tasks)
+            }
+            Message::ScheduledPostResult(id, report) => {
+                self.sending_scheduled.remove(&id);
+
+                let mut config = self.config.clone();
+                // Something got through, so the network is back for anything waiting
+                let resumed = !report.succeeded().is_empty() && config.resume_outbox(Utc::now());
+                let mut sent_images = Vec::new();
+                if let Some(post) = config.scheduled_posts.iter().find(|post| post.id == id) {
+                    config.record_sent_post(sent_post(id.clone(), post.text.clone(), &post.images, post.nostr_article.clone(), &report));
+                    sent_images = post.images.clone();
+                }
+                if report.all_succeeded() {
+                    config.scheduled_posts.retain(|post| post.id != id);
+                } else if let Some(post) = config.scheduled_posts.iter_mut().find(|post| post.id == id) {
+                    // Keep it queued for the platforms that still need it
+                    let errors: Vec<String> = report
+                        .failed()
+                        .iter()
+                        .map(|(platform, e)| format!("{}: {}", platform, e))
+                        .collect();
+                    post.platforms = report.failed().iter().map(|(platform, _)| *platform).collect();
+                    post.last_error = Some(self.config.redact_credentials(&errors.join("; ")));
+                    // Still offline: try again in a while. Anything else needs a look before retrying.
+                    post.offline = report.only_network_failures();
+                    if post.offline {
+                        post.scheduled_at = Utc::now() + OUTBOX_RETRY_INTERVAL;
+                    }
+                }
+
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to update scheduled posts: {}", e);
+                }
+                self.config = config;
+                if report.all_succeeded() {
+                    self.discard_pasted_images(&sent_images);
+                }
+                if resumed {
+                    Task::done(cosmic::Action::App(Message::CheckScheduledPosts))
+                } else {
+                    Task::none()
+                }
+            }
+            Message::RetryScheduledPost(id) => {
+                let mut config = self.config.clone();
+                if let Some(post) = config.scheduled_posts.iter_mut().find(|post| post.id == id) {
+                    post.last_error = None;
+                    if post.offline {
+                        post.scheduled_at = Utc::now();
+                    }
+                }
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to update scheduled posts: {}", e);
+                }
+                self.config = config;
+                Task::done(cosmic::Action::App(Message::CheckScheduledPosts))
+            }
+            Message::RetryOutbox => {
+                self.resume_outbox();
+                Task::done(cosmic::Action::App(Message::CheckScheduledPosts))
+            }
+            Message::RemoveScheduledPost(id) => {
+                let mut config = self.config.clone();
+                let removed: Vec<ImageAttachment> = config
+                    .scheduled_posts
+                    .iter()
+                    .filter(|post| post.id == id)
+                    .flat_map(|post| post.images.clone())
+                    .collect();
+                config.scheduled_posts.retain(|post| post.id != id);
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to update scheduled posts: {}", e);
+                }
+                self.config = config;
+                self.discard_pasted_images(&removed);
+                Task::none()
+            }
+            Message::PostToBlueskyToggled(enabled) => {
+                self.post_to_bluesky = enabled;
+                Task::none()
+            }
+            Message::PostToMastodonToggled(enabled) => {
+                self.post_to_mastodon = enabled;
+                Task::none()
+            }
+            Message::PostToMicroBlogToggled(enabled) => {
+                self.post_to_microblog = enabled;
+                Task::none()
+            }
+            Message::PostToNostrToggled(enabled) => {
+                self.post_to_nostr = enabled;
+                Task::none()
+            }
+            Message::PostToThreadsToggled(enabled) => {
+                self.post_to_threads = enabled;
+                Task::none()
+            }
+            Message::PostToPixelfedToggled(enabled) => {
+                self.post_to_pixelfed = enabled;
+                Task::none()
+            }
+            Message::PostToDiscordToggled(enabled) => {
+                self.post_to_discord = enabled;
+                Task::none()
+            }
+            Message::PostToTelegramToggled(enabled) => {
+                self.post_to_telegram = enabled;
+                Task::none()
+            }
+            Message::PostToWordPressToggled(enabled) => {
+                self.post_to_wordpress = enabled;
+                Task::none()
+            }
+            Message::PostToLemmyToggled(enabled) => {
+                self.post_to_lemmy = enabled;
+                Task::none()
+            }
+            Message::PostToFarcasterToggled(enabled) => {
+                self.post_to_farcaster = enabled;
+                Task::none()
+            }
+            Message::PostToMatrixToggled(enabled) => {
+                self.post_to_matrix = enabled;
+                Task::none()
+            }
+            Message::PostToDevToToggled(enabled) => {
+                self.post_to_devto = enabled;
+                Task::none()
+            }
+            Message::PostToAllToggled(enabled) => {
+                for platform in Platform::ALL {
+                    if self.platform_ready(platform) {
+                        self.set_platform_selected(platform, enabled);
+                    }
+                }
+                Task::none()
+            }
+            Message::WordPressTitleChanged(title) => {
+                self.wordpress_title = title;
+                Task::none()
+            }
+            Message::LemmyTitleChanged(title) => {
+                self.lemmy_title = title;
+                Task::none()
+            }
+            Message::MicroBlogTitleChanged(title) => {
+                self.microblog_title = title;
+                Task::none()
+            }
+            Message::MicroBlogCategoriesChanged(categories) => {
+                self.microblog_categories = categories;
+                Task::none()
+            }
+            Message::DevToTitleChanged(title) => {
+                self.devto_title = title;
+                Task::none()
+            }
+            Message::DevToTagsChanged(tags) => {
+                self.devto_tags = tags;
+                Task::none()
+            }
+            Message::NostrArticleToggled(enabled) => {
+                self.nostr_article = enabled;
+                Task::none()
+            }
+            Message::NostrArticleTitleChanged(title) => {
+                self.nostr_article_title = title;
+                Task::none()
+            }
+            Message::NostrArticleSummaryChanged(summary) => {
+                self.nostr_article_summary = summary;
+                Task::none()
+            }
+            Message::BlueskyEnabledChanged(enabled) => {
+                self.temp_bluesky.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::BlueskyHandleChanged(handle) => {
+                self.temp_bluesky.handle = handle;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::BlueskyServiceUrlChanged(url) => {
+                self.temp_bluesky.service_url = url;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::BlueskyTruncateBehaviorChanged(behavior) => {
+                self.temp_bluesky.truncate_behavior = behavior;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::BlueskyPasswordChanged(password) => {
+                self.temp_bluesky.decrypted_password = Zeroizing::new(password);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MastodonEnabledChanged(enabled) => {
+                self.temp_mastodon.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MastodonInstanceChanged(instance) => {
+                self.temp_mastodon.instance_url = instance;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MastodonTokenChanged(token) => {
+                self.temp_mastodon.decrypted_access_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MastodonDefaultVisibilityChanged(visibility) => {
+                self.temp_mastodon.default_visibility = visibility;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MastodonCompatibilityChanged(compatibility) => {
+                self.temp_mastodon.compatibility = compatibility;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MicroBlogEnabledChanged(enabled) => {
+                self.temp_microblog.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MicroBlogTokenChanged(token) => {
+                self.temp_microblog.decrypted_access_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::ThreadsEnabledChanged(enabled) => {
+                self.temp_threads.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::ThreadsUserIdChanged(user_id) => {
+                self.temp_threads.user_id = user_id;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::ThreadsTokenChanged(token) => {
+                self.temp_threads.decrypted_access_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::ThreadsUploadImagesChanged(enabled) => {
+                self.temp_threads.upload_images = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::PixelfedEnabledChanged(enabled) => {
+                self.temp_pixelfed.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::PixelfedInstanceChanged(instance) => {
+                self.temp_pixelfed.instance_url = instance;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::PixelfedTokenChanged(token) => {
+                self.temp_pixelfed.decrypted_access_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::DiscordEnabledChanged(enabled) => {
+                self.temp_discord.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NewDiscordWebhookChanged(url) => {
+                self.new_discord_webhook = url;
+                Task::none()
+            }
+            Message::AddDiscordWebhook => {
+                let url = self.new_discord_webhook.trim().to_string();
+                if social::is_discord_webhook_url(&url) && !self.temp_discord.decrypted_webhook_urls.contains(&url) {
+                    self.temp_discord.decrypted_webhook_urls.push(url);
+                    self.new_discord_webhook.clear();
+                }
+                self.settings_edited();
+                Task::none()
+            }
+            Message::RemoveDiscordWebhook(index) => {
+                if index < self.temp_discord.decrypted_webhook_urls.len() {
+                    self.temp_discord.decrypted_webhook_urls.remove(index);
+                }
+                self.settings_edited();
+                Task::none()
+            }
+            Message::TelegramEnabledChanged(enabled) => {
+                self.temp_telegram.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::TelegramChatIdChanged(chat_id) => {
+                self.temp_telegram.chat_id = chat_id;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::TelegramTokenChanged(token) => {
+                self.temp_telegram.decrypted_bot_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::WordPressEnabledChanged(enabled) => {
+                self.temp_wordpress.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::WordPressSiteChanged(site_url) => {
+                self.temp_wordpress.site_url = site_url;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::WordPressUsernameChanged(username) => {
+                self.temp_wordpress.username = username;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::WordPressPasswordChanged(password) => {
+                self.temp_wordpress.decrypted_app_password = Zeroizing::new(password);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::WordPressDraftToggled(draft) => {
+                self.temp_wordpress.default_status = if draft { WordPressStatus::Draft } else { WordPressStatus::Publish };
+                self.settings_edited();
+                Task::none()
+            }
+            Message::LemmyEnabledChanged(enabled) => {
+                self.temp_lemmy.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::LemmyInstanceChanged(instance_url) => {
+                self.temp_lemmy.instance_url = instance_url;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::LemmyUsernameChanged(username) => {
+                self.temp_lemmy.username = username;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::LemmyPasswordChanged(password) => {
+                self.temp_lemmy.decrypted_password = Zeroizing::new(password);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::LemmyCommunityChanged(community) => {
+                self.temp_lemmy.community = community;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::FarcasterEnabledChanged(enabled) => {
+                self.temp_farcaster.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::FarcasterSignerChanged(signer_uuid) => {
+                self.temp_farcaster.signer_uuid = signer_uuid;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::FarcasterApiKeyChanged(api_key) => {
+                self.temp_farcaster.decrypted_api_key = Zeroizing::new(api_key);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::FarcasterUploadImagesChanged(enabled) => {
+                self.temp_farcaster.upload_images = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MatrixEnabledChanged(enabled) => {
+                self.temp_matrix.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MatrixHomeserverChanged(homeserver_url) => {
+                self.temp_matrix.homeserver_url = homeserver_url;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MatrixRoomChanged(room_id) => {
+                self.temp_matrix.room_id = room_id;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MatrixTokenChanged(token) => {
+                self.temp_matrix.decrypted_access_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::DevToEnabledChanged(enabled) => {
+                self.temp_devto.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::DevToSiteChanged(site_url) => {
+                self.temp_devto.site_url = site_url;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::DevToApiKeyChanged(api_key) => {
+                self.temp_devto.decrypted_api_key = Zeroizing::new(api_key);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::DevToDraftToggled(draft) => {
+                self.temp_devto.draft = draft;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::UrlShortenerEnabledChanged(enabled) => {
+                self.temp_url_shortener.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::UrlShortenerEndpointChanged(endpoint) => {
+                self.temp_url_shortener.endpoint = endpoint;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::UrlShortenerApiKeyChanged(api_key) => {
+                self.temp_url_shortener.decrypted_api_key = Zeroizing::new(api_key);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::CaptionerEnabledChanged(enabled) => {
+                self.temp_captioner.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::CaptionerEndpointChanged(endpoint) => {
+                self.temp_captioner.endpoint = endpoint;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::CaptionerTokenChanged(token) => {
+                self.temp_captioner.decrypted_token = Zeroizing::new(token);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::TextCleanupChanged(platform, cleanup) => {
+                self.temp_text_cleanup.insert(platform, cleanup);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::SignatureChanged(platform, signature) => {
+                self.temp_signatures.insert(platform, signature);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NostrEnabledChanged(enabled) => {
+                self.temp_nostr.enabled = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NostrPrivateKeyChanged(key) => {
+                self.temp_nostr.decrypted_private_key = Zeroizing::new(key);
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NostrMediaHostChanged(host) => {
+                self.temp_nostr.media_host = host;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NostrBlossomServerChanged(server) => {
+                self.temp_nostr.blossom_server_url = server;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NostrBlossomAuthToggled(enabled) => {
+                self.temp_nostr.blossom_auth = enabled;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NostrPowDifficultyChanged(difficulty) => {
+                // Anything that isn't a number of bits is ignored; clearing the field turns it off
+                if difficulty.trim().is_empty() {
+                    self.temp_nostr.pow_difficulty = None;
+                } else if let Ok(difficulty) = difficulty.trim().parse::<u8>() {
+                    self.temp_nostr.pow_difficulty = Some(difficulty.min(MAX_POW_DIFFICULTY));
+                }
+                self.settings_edited();
+                Task::none()
+            }
+            Message::NewRelayChanged(relay) => {
+                self.new_relay = relay;
+                Task::none()
+            }
+            Message::AddRelay => {
+                if let Ok(relay) = self.new_relay_url() {
+                    self.temp_nostr.relays.push(relay);
+                    self.new_relay.clear();
+                }
+                self.settings_edited();
+                Task::none()
+            }
+            Message::RemoveRelay(index) => {
+                if index < self.temp_nostr.relays.len() {
+                    self.temp_nostr.relays.remove(index);
+                }
+                self.settings_edited();
+                Task::none()
+            }
+            Message::MoveRelay { from, to } => {
+                let relays = &mut self.temp_nostr.relays;
+                if from < relays.len() && to < relays.len() {
+                    let relay = relays.remove(from);
+                    relays.insert(to, relay);
+                }
+                self.settings_edited();
+                Task::none()
+            }
+            Message::RestoreDefaultRelays => {
+                self.temp_nostr.relays = NostrConfig::default().relays;
+                self.settings_edited();
+                Task::none()
+            }
+            Message::SaveSettings => self.save_settings(),
+            Message::SaveAndPost => {
+                // Saving re-ticks every enabled platform, so keep the ones picked for this post
+                let platforms = self.selected_platforms();
+                let saved = self.save_settings();
+                if matches!(self.posting_status, PostingStatus::Error(_)) {
+                    return saved;
+                }
+                for platform in Platform::ALL {
+                    self.set_platform_selected(platform, platforms.contains(&platform));
+                }
+                Task::batch([saved, Task::done(cosmic::Action::App(Message::PostSubmit))])
+            }
+            Message::AccountLoaded(platform, result) => {
+                // Ignore lookups for an account that's been turned off since
+                if self.platform_ready(platform) {
+                    self.accounts.insert(platform, result);
+                }
+                Task::none()
+            }
+            Message::MastodonInstanceFetched(check, result) => {
+                // Ignore answers to checks that another check or a settings change replaced
+                if check != self.mastodon_instance_check {
+                    return Task::none();
+                }
+                self.checking_mastodon_instance = false;
+                match &result {
+                    Ok(instance) => {
+                        for mastodon in [&mut self.config.mastodon, &mut self.temp_mastodon] {
+                            // Keep what's cached for anything this instance doesn't report
+                            mastodon.max_characters = instance.max_characters.or(mastodon.max_characters);
+                            mastodon.max_media_attachments = instance.max_media_attachments.or(mastodon.max_media_attachments);
+                            mastodon.max_poll_options = instance.max_poll_options.or(mastodon.max_poll_options);
+                            mastodon.max_upload_size = instance.max_upload_size.or(mastodon.max_upload_size);
+                            mastodon.pleroma_detected = instance.pleroma;
+                            mastodon.limits_fetched_at = Some(Utc::now());
+                        }
+                        if let Err(e) = Self::write_config(&self.config) {
+                            tracing::warn!("Failed to cache Mastodon instance limits: {}", e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to look up the Mastodon instance: {}", e),
+                }
+                self.mastodon_instance = Some(result);
+                Task::none()
+            }
+            Message::RefreshMastodonInstance => self.fetch_mastodon_instance(),
+            Message::CheckRelays => {
+                self.set_show_relays(true);
+                let tasks: Vec<_> = self
+                    .temp_nostr
+                    .relays
+                    .iter()
+                    .map(|relay| {
+                        self.relay_checks.insert(relay.clone(), None);
+                        let client = self.http_client.clone();
+                        let relay = relay.clone();
+                        Task::perform(
+                            async move {
+                                let result = social::check_nostr_relay(&client, &relay).await.map_err(|e| e.to_string());
+                                (relay, result)
+                            },
+                            |(relay, result)| cosmic::Action::App(Message::RelayChecked(relay, result)),
+                        )
+                    })
+                    .collect();
+                Task::batch(tasks)
+            }
+            Message::RelayChecked(relay, result) => {
+                self.relay_checks.insert(relay, Some(result));
+                Task::none()
+            }
+            Message::PublishRelayList => {
+                self.relay_list_status = Some(Ok(String::new()));
+                let nostr = self.temp_nostr.clone();
+                let timeout = self.config.request_timeout();
+                Task::perform(
+                    async move {
+                        social::publish_nostr_relay_list(&nostr, timeout)
+                            .await
+                            .map(|results| (results.iter().filter(|(_, result)| result.is_ok()).count(), results.len()))
+                            .map_err(|e| e.to_string())
+                    },
+                    |result| cosmic::Action::App(Message::RelayListPublished(result)),
+                )
+            }
+            Message::RelayListPublished(result) => {
+                self.relay_list_status = Some(result.map(|(accepted, tried)| fl!("relay-list-published", accepted = accepted, tried = tried)));
+                Task::none()
+            }
+            Message::SettingsFilterChanged(filter) => {
+                self.settings_filter = filter;
+                Task::none()
+            }
+            Message::ToggleSettingsSection(section) => {
+                let expand = self.config.collapsed_settings.contains(&section);
+                self.set_settings_section_expanded(section, expand);
+                // Opening a section puts the cursor in its first field
+                if expand {
+                    return focus(settings_field_id(section));
+                }
+                Task::none()
+            }
+            Message::SettingsSearchSubmitted => {
+                // Enter in the search box goes to the first section it found
+                let filter = self.settings_filter.trim().to_lowercase();
+                match SettingsSection::ALL.into_iter().find(|section| section.title().to_lowercase().contains(&filter)) {
+                    Some(section) => {
+                        self.set_settings_section_expanded(section, true);
+                        focus(settings_field_id(section))
+                    }
+                    None => Task::none(),
+                }
+            }
+            Message::ToggleRelays => {
+                self.set_show_relays(!self.show_relays);
+                Task::none()
+            }
+            Message::NewMasterPasswordChanged(password) => {
+                self.new_master_password = password;
+                Task::none()
+            }
+            Message::SetMasterPassword => {
+                if self.config.has_master_password() || self.new_master_password.is_empty() {
+                    return Task::none();
+                }
+
+                let salt = CryptoManager::generate_salt();
+                let mut crypto_manager = CryptoManager::new();
+                let verifier = crypto_manager
+                    .init_with_password(&self.new_master_password, &salt, self.config.kdf_params())
+                    .and_then(|_| crypto_manager.create_verifier());
+                match verifier {
+                    Ok(verifier) => {
+                        let mut config = self.config.clone();
+                        config.master_password_salt = Some(salt);
+                        config.master_password_verifier = Some(verifier);
+                        self.switch_crypto_backend(crypto_manager, config);
+                        self.new_master_password.clear();
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to set master password: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("set-master-password-error"));
+                    }
+                }
+                Task::none()
+            }
+            Message::CurrentMasterPasswordChanged(password) => {
+                self.current_master_password = password;
+                Task::none()
+            }
+            Message::ChangeMasterPassword => {
+                if !self.config.has_master_password() || self.new_master_password.is_empty() {
+                    return Task::none();
+                }
+
+                // Config is only written once every credential is re-encrypted
+                match self.config.change_master_password(&self.current_master_password, &self.new_master_password) {
+                    Ok((config, crypto_manager)) => match Self::write_config_verifier_last(&config, self.config.master_password_verifier.clone()) {
+                        Ok(()) => {
+                            self.config = config;
+                            self.crypto_manager = crypto_manager;
+                            self.current_master_password.clear();
+                            self.new_master_password.clear();
+                            self.posting_status = PostingStatus::Success;
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to save config: {}", e);
+                            self.posting_status = PostingStatus::Error(fl!("change-master-password-error"));
+                        }
+                    },
+                    Err(CryptoError::DecryptionFailed) => {
+                        self.posting_status = PostingStatus::Error(fl!("current-password-incorrect"));
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to change master password: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("change-master-password-error"));
+                    }
+                }
+                Task::none()
+            }
+            Message::UseCryptoBackend(backend) => {
+                let mut crypto_manager = CryptoManager::new();
+                match backend {
+                    CryptoBackend::MachineKey => {
+                        if let Err(e) = crypto_manager.init_with_machine_key(self.config.kdf_params()) {
+                            tracing::error!("Failed to initialize crypto manager: {}", e);
+                            self.posting_status = PostingStatus::Error(fl!("change-backend-error"));
+                            return Task::none();
+                        }
+                    }
+                    CryptoBackend::Keyring => crypto_manager.init_with_keyring(Self::APP_ID),
+                    // A master password is set through SetMasterPassword
+                    CryptoBackend::Password => return Task::none(),
+                }
+
+                let mut config = self.config.clone();
+                config.master_password_salt = None;
+                config.master_password_verifier = None;
+                self.switch_crypto_backend(crypto_manager, config);
+                Task::none()
+            }
+            Message::StripImageMetadataToggled(strip) => {
+                let mut config = self.config.clone();
+                config.keep_image_metadata = !strip;
+                match Self::write_config(&config) {
+                    Ok(()) => self.config = config,
+                    Err(e) => {
+                        tracing::error!("Failed to save image metadata setting: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("save-settings-error"));
+                    }
+                }
+                Task::none()
+            }
+            Message::TransferPassphraseChanged(passphrase) => {
+                self.transfer_passphrase = passphrase;
+                Task::none()
+            }
+            Message::ExportSettings => {
+                if self.transfer_passphrase.is_empty() {
+                    return Task::none();
+                }
+                let Some(path) = FileDialog::new()
+                    .add_filter(fl!("settings-files"), &["json"])
+                    .set_file_name("yall-cosmic-settings.json")
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+
+                let written = self
+                    .config
+                    .export(&self.transfer_passphrase)
+                    .map_err(|e| e.to_string())
+                    .and_then(|contents| std::fs::write(&path, contents).map_err(|e| e.to_string()));
+                match written {
+                    Ok(()) => {
+                        self.transfer_passphrase.clear();
+                        self.posting_status = PostingStatus::Success;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to export settings: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("export-settings-error"));
+                    }
+                }
+                Task::none()
+            }
+            Message::ImportSettings => {
+                if self.transfer_passphrase.is_empty() {
+                    return Task::none();
+                }
+                let Some(path) = FileDialog::new()
+                    .add_filter(fl!("settings-files"), &["json"])
+                    .pick_file()
+                else {
+                    return Task::none();
+                };
+
+                let contents = match std::fs::read_to_string(&path) {
+                    Ok(contents) => contents,
+                    Err(e) => {
+                        tracing::error!("Failed to read settings file: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("read-settings-error"));
+                        return Task::none();
+                    }
+                };
+                let config = match self.config.import(&contents, &self.transfer_passphrase, &self.crypto_manager) {
+                    Ok(config) => config,
+                    Err(CryptoError::DecryptionFailed) => {
+                        self.posting_status = PostingStatus::Error(fl!("wrong-passphrase"));
+                        return Task::none();
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to import settings: {}", e);
+                        self.posting_status = PostingStatus::Error(fl!("invalid-settings-file"));
+                        return Task::none();
+                    }
+                };
+                if let Err(e) = Self::write_config(&config) {
+                    tracing::error!("Failed to save imported settings: {}", e);
+                    self.posting_status = PostingStatus::Error(fl!("save-settings-error"));
+                    return Task::none();
+                }
+
+                self.config = config;
+                self.transfer_passphrase.clear();
+                self.load_editing_state();
+                self.posting_status = PostingStatus::Success;
+                Task::none()
+            }
+            Message::MasterPasswordChanged(password) => {
+                self.master_password_input = password;
+                self.unlock_error = None;
+                Task::none()
+            }
+            Message::UnlockSubmit => {
+                let (Some(salt), Some(verifier)) = (
+                    self.config.master_password_salt.clone(),
+                    self.config.master_password_verifier.clone(),
+                ) else {
+                    return Task::none();
+                };
+
+                let mut crypto_manager = CryptoManager::new();
+                if let Err(e) = crypto_manager.init_with_password(&self.master_password_input, &salt, self.config.kdf_params()) {
+                    self.unlock_error = Some(fl!("derive-key-error", error = e.to_string()));
+                    return Task::none();
+                }
+                if !crypto_manager.verify(&verifier) {
+                    self.unlock_error = Some(fl!("incorrect-master-password"));
+                    return Task::none();
+                }
+                // The password is right, so whatever doesn't decrypt just needs entering again
+                match self.config.decrypt_credentials(&crypto_manager) {
+                    Ok(()) => Self::upgrade_stored_credentials(&mut self.config, &crypto_manager),
+                    Err(failed) => tracing::error!("Failed to decrypt credentials after unlock for {:?}", failed),
+                }
+
+                self.crypto_manager = crypto_manager;
+                self.master_password_input.clear();
+                self.unlock_error = None;
+                self.load_editing_state();
+                self.view_mode = self.config.last_view.into();
+                Task::batch([Task::done(cosmic::Action::App(Message::CheckScheduledPosts)), self.focus_view()])
+            }
+
+
+        }
+    }
+
+}
+
+impl AppModel {
+    /// Save the settings being edited, encrypting their credentials, and check the accounts again.
+    fn save_settings(&mut self) -> Task<cosmic::Action<Message>> {
+        let instance_changed = self.config.mastodon.instance_url != self.temp_mastodon.instance_url;
+        // A new default applies to the post being written too
+        if self.config.mastodon.default_visibility != self.temp_mastodon.default_visibility {
+            self.mastodon_visibility = self.temp_mastodon.default_visibility;
+        }
+
+        // Update config with temp values
+        let mut config = self.config.clone();
+        self.apply_editing_state(&mut config);
+        self.config = config;
+        self.unsaved_settings = false;
+
+        // Encrypt credentials before saving
+        if let Err(e) = self.config.encrypt_credentials(&self.crypto_manager) {
+            tracing::error!("Failed to encrypt credentials: {}", e);
+            self.posting_status = PostingStatus::Error(fl!("save-settings-error"));
+            return Task::none();
+        }
+        
+        // Update posting toggles based on new config
+        for platform in Platform::ALL {
+            self.set_platform_selected(platform, self.config.platform_enabled(platform));
+        }
+
+        let mut saved = false;
+        if let Ok(config_context) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+            if let Err(e) = self.config.write_entry(&config_context) {
+                tracing::error!("Failed to save config: {}", e);
+                self.posting_status = PostingStatus::Error(fl!("save-settings-error"));
+            } else {
+                self.posting_status = PostingStatus::Success;
+                saved = true;
+            }
+        } else {
+            self.posting_status = PostingStatus::Error(fl!("save-settings-error"));
+        }
+        
+        // Decrypt again for runtime use
+        if let Err(failed) = self.config.decrypt_credentials(&self.crypto_manager) {
+            tracing::error!("Failed to decrypt credentials after save for {:?}", failed);
+        }
+
+        let mut tasks = Vec::new();
+        if saved {
+            tasks.push(self.fetch_accounts());
+        }
+        if instance_changed {
+            for mastodon in [&mut self.config.mastodon, &mut self.temp_mastodon] {
+                mastodon.max_characters = None;
+                mastodon.max_media_attachments = None;
+                mastodon.max_poll_options = None;
+                mastodon.max_upload_size = None;
+                mastodon.pleroma_detected = false;
+                mastodon.limits_fetched_at = None;
+            }
+        }
+        // Check the instance on every save, so a mistyped address shows up here rather than when posting
+        tasks.push(self.fetch_mastodon_instance());
+        Task::batch(tasks)
+    }
+
+    /// Copy the settings being edited into `config`.
+    fn apply_editing_state(&self, config: &mut Config) {
+        config.bluesky = self.temp_bluesky.clone();
+        config.mastodon = self.temp_mastodon.clone();
+        config.microblog = self.temp_microblog.clone();
+        config.nostr = self.temp_nostr.clone();
+        config.threads = self.temp_threads.clone();
+        config.pixelfed = self.temp_pixelfed.clone();
+        config.discord = self.temp_discord.clone();
+        config.telegram = self.temp_telegram.clone();
+        config.wordpress = self.temp_wordpress.clone();
+        config.lemmy = self.temp_lemmy.clone();
+        config.farcaster = self.temp_farcaster.clone();
+        config.matrix = self.temp_matrix.clone();
+        config.devto = self.temp_devto.clone();
+        config.url_shortener = self.temp_url_shortener.clone();
+        config.captioner = self.temp_captioner.clone();
+        config.text_cleanup = self.temp_text_cleanup.clone();
+        config.signatures = self.temp_signatures.clone();
+    }
+
+    /// Whether the Settings tab has changes that haven't been saved yet, and so won't be used for posting.
+    fn has_unsaved_settings(&self) -> bool {
+        let mut edited = self.config.clone();
+        self.apply_editing_state(&mut edited);
+        !edited.same_settings(&self.config)
+    }
+
+    /// Recheck `unsaved_settings` after an edit in the Settings tab, so the view doesn't have to.
+    fn settings_edited(&mut self) {
+        self.unsaved_settings = self.has_unsaved_settings();
+    }
+
+    /// Copy the decrypted config into the settings editing state and posting toggles.
+    fn load_editing_state(&mut self) {
+        // Initialize temp configs with decrypted values
+        self.temp_bluesky = self.config.bluesky.clone();
+        self.temp_mastodon = self.config.mastodon.clone();
+        self.temp_nostr = self.config.nostr.clone();
+        self.temp_microblog = self.config.microblog.clone();
+        self.temp_threads = self.config.threads.clone();
+        self.temp_pixelfed = self.config.pixelfed.clone();
+        self.temp_discord = self.config.discord.clone();
+        self.temp_telegram = self.config.telegram.clone();
+        self.temp_wordpress = self.config.wordpress.clone();
+        self.temp_lemmy = self.config.lemmy.clone();
+        self.temp_farcaster = self.config.farcaster.clone();
+        self.temp_matrix = self.config.matrix.clone();
+        self.temp_devto = self.config.devto.clone();
+        self.temp_url_shortener = self.config.url_shortener.clone();
+        self.temp_captioner = self.config.captioner.clone();
+        self.temp_text_cleanup = self.config.text_cleanup.clone();
+        self.temp_signatures = self.config.signatures.clone();
+        self.unsaved_settings = false;
+
+        // Pre-tick the platforms used last time, as long as they're still enabled
+        for platform in Platform::ALL {
+            let remembered = self.config.last_platforms.as_ref().is_none_or(|last| last.contains(&platform));
+            self.set_platform_selected(platform, self.config.platform_enabled(platform) && remembered);
+        }
+    }
+
+    /// Re-encrypt the saved credentials with a new backend and persist the change.
+    fn switch_crypto_backend(&mut self, crypto_manager: CryptoManager, mut config: Config) {
+        let Some(backend) = crypto_manager.backend() else {
+            return;
+        };
+        config.crypto_backend = backend;
+        if let Err(e) = config.encrypt_credentials(&crypto_manager) {
+            tracing::error!("Failed to re-encrypt credentials: {}", e);
+            self.posting_status = PostingStatus::Error(fl!("change-backend-error"));
+            return;
+        }
+
+        if let Err(e) = Self::write_config_verifier_last(&config, self.config.master_password_verifier.clone()) {
+            tracing::error!("Failed to save config: {}", e);
+            self.posting_status = PostingStatus::Error(fl!("change-backend-error"));
+            return;
+        }
+
+        // Clean up secrets the previous backend kept outside the config
+        if self.crypto_manager.backend() != Some(backend) {
+            if let Err(e) = self.config.forget_credentials(&self.crypto_manager) {
+                tracing::warn!("Failed to remove old credentials: {}", e);
+            }
+        }
+
+        self.config = config;
+        self.crypto_manager = crypto_manager;
+        self.posting_status = PostingStatus::Success;
+    }
+
+    /// Copy of the config with decrypted credentials, shared by the posting tasks.
+    fn posting_config(&self) -> Arc<Config> {
+        Arc::new(self.config.clone())
+    }
+
+    /// Persist the config as-is; credentials must already be encrypted.
+    fn write_config(config: &Config) -> Result<(), String> {
+        let context = cosmic_config::Config::new(Self::APP_ID, Config::VERSION).map_err(|e| e.to_string())?;
+        config.write_entry(&context).map_err(|e| e.to_string())
+    }
+
+    /// `write_config` for a config whose master password verifier is replacing `previous_verifier`.
+    fn write_config_verifier_last(config: &Config, previous_verifier: Option<EncryptedData>) -> Result<(), String> {
+        let context = cosmic_config::Config::new(Self::APP_ID, Config::VERSION).map_err(|e| e.to_string())?;
+        config.write_verifier_last(&context, previous_verifier).map_err(|e| e.to_string())
+    }
+
+    /// Save credentials from before label binding, or derived with other Argon2 settings, in the
+    /// current format once they've been decrypted.
+    fn upgrade_stored_credentials(config: &mut Config, crypto: &CryptoManager) {
+        match config.upgrade_credentials(crypto) {
+            Ok(true) => {
+                if let Err(e) = Self::write_config(config) {
+                    tracing::error!("Failed to save re-encrypted credentials: {}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to re-encrypt credentials: {}", e),
+        }
+    }
+
+    /// Whether Mastodon's part of a scheduled post goes to the server to publish. That needs the
+    /// option on, enough notice, and text that fits one status, as a thread can't be scheduled.
+    fn schedules_on_mastodon(&self, post: &ScheduledPost, platforms: &[Platform]) -> bool {
+        let text = social::text_for_platform(Platform::Mastodon, &post.text, post.markdown, post.signature, &self.config);
+        self.mastodon_server_schedule
+            && platforms.contains(&Platform::Mastodon)
+            && post.scheduled_at - Utc::now() >= MASTODON_MIN_SCHEDULE_LEAD
+            && social::preview_posts(Platform::Mastodon, &text, post.thread_mode, &self.config).len() == 1
+    }
+
+    /// Parse a local `YYYY-MM-DD HH:MM` time into UTC.
+    fn parse_schedule_time(input: &str) -> Option<chrono::DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(input.trim(), SCHEDULE_TIME_FORMAT).ok()?;
+        Local
+            .from_local_datetime(&naive)
+            .single()
+            .map(|local| local.with_timezone(&Utc))
+    }
+
+    /// Snapshot of the compose state, or `None` if there's nothing worth keeping.
+    fn current_draft(&self) -> Option<Draft> {
+        let text = self.post_editor_content.text();
+        if text.trim().is_empty() && self.attached_images.is_empty() {
+            return None;
+        }
+        Some(Draft {
+            text: text.trim_end_matches('\n').to_string(),
+            images: self.attached_images.clone(),
+            platforms: self.selected_platforms(),
+            saved_at: Utc::now(),
+            nostr_article: self.nostr_article_options(),
+        })
+    }
+
+    /// Put a draft's content and platform selection into the compose view.
+    fn load_draft(&mut self, draft: &Draft) {
+        self.post_editor_content = text_editor::Content::with_text(&draft.text);
+        self.attached_images = draft.images.clone();
+        for platform in Platform::ALL {
+            self.set_platform_selected(platform, draft.platforms.contains(&platform));
+        }
+        match &draft.nostr_article {
+            Some(article) => self.load_nostr_article(article),
+            None => self.nostr_article_identifier = uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// Put an article's title, summary and identifier into the compose view, in article mode.
+    fn load_nostr_article(&mut self, article: &NostrArticle) {
+        self.nostr_article = true;
+        self.nostr_article_title = article.title.clone();
+        self.nostr_article_summary = article.summary.clone();
+        self.nostr_article_identifier = article.identifier.clone();
+    }
+
+    /// Load thumbnails for attached images that don't have one yet; videos go without.
+    fn load_image_previews(&self) -> Task<cosmic::Action<Message>> {
+        let tasks: Vec<_> = self
+            .attached_images
+            .iter()
+            .filter(|image| !image.is_video() && !self.image_previews.contains_key(&image.path))
+            .map(|image| {
+                let path = image.path.clone();
+                Task::perform(load_image_preview(path.clone()), move |preview| {
+                    cosmic::Action::App(Message::ImagePreviewLoaded(path.clone(), preview))
+                })
+            })
+            .collect();
+        Task::batch(tasks)
+    }
+
+    /// Look up who the Bluesky, Mastodon and Micro.blog credentials sign in as, replacing
+    /// earlier lookups. Nostr's public key is derived from the key field as it's typed.
+    fn fetch_accounts(&mut self) -> Task<cosmic::Action<Message>> {
+        let retry = self.config.retry_policy();
+        let mut tasks = Vec::new();
+        for platform in [Platform::Bluesky, Platform::Mastodon, Platform::MicroBlog] {
+            self.accounts.remove(&platform);
+            if !self.platform_ready(platform) {
+                continue;
+            }
+            let client = self.http_client.clone();
+            let on_loaded = move |result| cosmic::Action::App(Message::AccountLoaded(platform, result));
+            let task = match platform {
+                Platform::Bluesky => {
+                    let config = self.config.bluesky.clone();
+                    Task::perform(
+                        async move {
+                            let info = social::fetch_bluesky_account(&client, retry, &config).await;
+                            load_account_preview(client, info).await
+                        },
+                        on_loaded,
+                    )
+                }
+                Platform::Mastodon => {
+                    let config = self.config.mastodon.clone();
+                    Task::perform(
+                        async move {
+                            let info = social::fetch_mastodon_account(&client, retry, &config).await;
+                            load_account_preview(client, info).await
+                        },
+                        on_loaded,
+                    )
+                }
+                _ => {
+                    let config = self.config.microblog.clone();
+                    Task::perform(
+                        async move {
+                            let info = social::fetch_microblog_account(&client, retry, &config).await;
+                            load_account_preview(client, info).await
+                        },
+                        on_loaded,
+                    )
+                }
+            };
+            tasks.push(task);
+        }
+        Task::batch(tasks)
+    }
+
+    /// The looked-up account for a platform's settings section: avatar, display name and handle.
+    fn account_row(&self, platform: Platform) -> Option<Element<Message>> {
+        match self.accounts.get(&platform)? {
+            Ok(account) => {
+                let mut details = column();
+                if !account.info.display_name.is_empty() {
+                    details = details.push(widget::text(account.info.display_name.clone()));
+                }
+                details = details.push(widget::text(account.info.handle.clone()).size(12));
+                let mut account_row = row().spacing(8).align_y(Alignment::Center);
+                if let Some(avatar) = &account.avatar {
+                    account_row = account_row.push(
+                        widget::image(avatar.clone())
+                            .width(Length::Fixed(32.0))
+                            .height(Length::Fixed(32.0))
+                    );
+                }
+                Some(
+                    column()
+                        .push(widget::text(fl!("signed-in-as")).size(12))
+                        .push(account_row.push(details))
+                        .spacing(4)
+                        .into(),
+                )
+            }
+            Err(e) => Some(widget::text(fl!("account-lookup-error", error = e.clone())).size(12).into()),
+        }
+    }
+
+    /// Check the saved instance is a Mastodon server and look up its limits in the background.
+    fn fetch_mastodon_instance(&mut self) -> Task<cosmic::Action<Message>> {
+        self.mastodon_instance = None;
+        // Any check still running is for settings that no longer apply
+        self.mastodon_instance_check += 1;
+        self.checking_mastodon_instance = false;
+        let instance_url = self.config.mastodon.instance_url.clone();
+        if !self.config.mastodon.enabled || !Self::validate_url(&instance_url) {
+            return Task::none();
+        }
+        self.checking_mastodon_instance = true;
+        let check = self.mastodon_instance_check;
+        let client = self.http_client.clone();
+        Task::perform(
+            async move {
+                social::fetch_mastodon_instance(&client, &instance_url)
+                    .await
+                    .map_err(|e| e.to_string())
+            },
+            move |result| cosmic::Action::App(Message::MastodonInstanceFetched(check, result)),
+        )
+    }
+
+    /// Most choices a poll can have here and on the instance.
+    fn max_poll_options(&self) -> usize {
+        self.config.mastodon.max_poll_options.map_or(MAX_POLL_OPTIONS, |max| max.min(MAX_POLL_OPTIONS))
+    }
+
+    /// Character limit for a platform, or `None` if it has no practical limit.
+    fn character_limit(&self, platform: Platform) -> Option<usize> {
+        match platform {
+            Platform::Bluesky => Some(BLUESKY_CHARACTER_LIMIT),
+            Platform::Mastodon => Some(self.config.mastodon.max_characters.unwrap_or(DEFAULT_MASTODON_CHARACTER_LIMIT)),
+            Platform::Threads => Some(THREADS_CHARACTER_LIMIT),
+            Platform::Pixelfed => Some(PIXELFED_CHARACTER_LIMIT),
+            Platform::Discord => Some(DISCORD_CHARACTER_LIMIT),
+            Platform::Telegram => Some(TELEGRAM_MESSAGE_LIMIT),
+            Platform::Lemmy => Some(LEMMY_CHARACTER_LIMIT),
+            Platform::Farcaster => Some(FARCASTER_BYTE_LIMIT),
+            // Nostr and Matrix have no practical limit, and Micro.Blog, WordPress and Dev.to are blogs
+            Platform::MicroBlog | Platform::Nostr | Platform::WordPress | Platform::Matrix | Platform::DevTo => None,
+        }
+    }
+
+    /// Whether the text is too long for any of the platforms. Telegram truncates instead of
+    /// blocking, Bluesky only blocks if set to, and long text that becomes a thread is fine.
+    fn exceeds_limit(&self, text: &str, platforms: &[Platform]) -> bool {
+        let bluesky_blocks = self.config.bluesky.truncate_behavior == BlueskyTruncateBehavior::BlockPost;
+        platforms
+            .iter()
+            .filter(|platform| **platform != Platform::Telegram)
+            .filter(|platform| bluesky_blocks || **platform != Platform::Bluesky)
+            .filter(|platform| !self.splits_into_thread(**platform))
+            .any(|platform| self.character_limit(*platform).is_some_and(|limit| social::post_length(*platform, &social::text_for_platform(*platform, text, self.markdown, self.include_signature, &self.config)) > limit))
+    }
+
+    /// Whether text over `platform`'s limit goes out as a thread: in thread mode, or on
+    /// Bluesky when it's set to split long posts.
+    fn splits_into_thread(&self, platform: Platform) -> bool {
+        (self.thread_mode && platform.supports_threads())
+            || (platform == Platform::Bluesky && self.config.bluesky.truncate_behavior == BlueskyTruncateBehavior::Thread)
+    }
+
+    /// Platforms currently ticked in the compose view.
+    fn selected_platforms(&self) -> Vec<Platform> {
+        Platform::ALL.into_iter().filter(|platform| self.platform_selected(*platform)).collect()
+    }
+
+    /// Whether a platform's compose checkbox is ticked.
+    fn platform_selected(&self, platform: Platform) -> bool {
+        match platform {
+            Platform::Bluesky => self.post_to_bluesky,
+            Platform::Mastodon => self.post_to_mastodon,
+            Platform::MicroBlog => self.post_to_microblog,
+            Platform::Nostr => self.post_to_nostr,
+            Platform::Threads => self.post_to_threads,
+            Platform::Pixelfed => self.post_to_pixelfed,
+            Platform::Discord => self.post_to_discord,
+            Platform::Telegram => self.post_to_telegram,
+            Platform::WordPress => self.post_to_wordpress,
+            Platform::Lemmy => self.post_to_lemmy,
+            Platform::Farcaster => self.post_to_farcaster,
+            Platform::Matrix => self.post_to_matrix,
+            // Articles can't go out without a title
+            Platform::DevTo => self.post_to_devto && !self.devto_title.trim().is_empty(),
+        }
+    }
+
+    fn set_platform_selected(&mut self, platform: Platform, selected: bool) {
+        let flag = match platform {
+            Platform::Bluesky => &mut self.post_to_bluesky,
+            Platform::Mastodon => &mut self.post_to_mastodon,
+            Platform::MicroBlog => &mut self.post_to_microblog,
+            Platform::Nostr => &mut self.post_to_nostr,
+            Platform::Threads => &mut self.post_to_threads,
+            Platform::Pixelfed => &mut self.post_to_pixelfed,
+            Platform::Discord => &mut self.post_to_discord,
+            Platform::Telegram => &mut self.post_to_telegram,
+            Platform::WordPress => &mut self.post_to_wordpress,
+            Platform::Lemmy => &mut self.post_to_lemmy,
+            Platform::Farcaster => &mut self.post_to_farcaster,
+            Platform::Matrix => &mut self.post_to_matrix,
+            Platform::DevTo => &mut self.post_to_devto,
+        };
+        *flag = selected;
+    }
+
+    /// Whether a platform has everything it needs to post, judging by the settings being
+    /// edited. Only these platforms get a compose checkbox.
+    fn platform_ready(&self, platform: Platform) -> bool {
+        match platform {
+            Platform::Bluesky => self.temp_bluesky.enabled && !self.temp_bluesky.handle.is_empty() && !self.temp_bluesky.decrypted_password.is_empty() && Self::validate_url(&self.temp_bluesky.service_url),
+            Platform::Mastodon => self.temp_mastodon.enabled && !self.temp_mastodon.instance_url.is_empty() && !self.temp_mastodon.decrypted_access_token.is_empty(),
+            Platform::MicroBlog => self.temp_microblog.enabled && !self.temp_microblog.decrypted_access_token.is_empty(),
+            Platform::Nostr => self.temp_nostr.enabled && !self.temp_nostr.decrypted_private_key.is_empty() && !self.temp_nostr.relays.is_empty(),
+            Platform::Threads => self.temp_threads.enabled && !self.temp_threads.user_id.is_empty() && !self.temp_threads.decrypted_access_token.is_empty(),
+            Platform::Pixelfed => self.temp_pixelfed.enabled && !self.temp_pixelfed.instance_url.is_empty() && !self.temp_pixelfed.decrypted_access_token.is_empty(),
+            Platform::Discord => self.temp_discord.enabled && !self.temp_discord.decrypted_webhook_urls.is_empty(),
+            Platform::Telegram => self.temp_telegram.enabled && !self.temp_telegram.chat_id.is_empty() && !self.temp_telegram.decrypted_bot_token.is_empty(),
+            Platform::WordPress => self.temp_wordpress.enabled && !self.temp_wordpress.site_url.is_empty() && !self.temp_wordpress.username.is_empty() && !self.temp_wordpress.decrypted_app_password.is_empty(),
+            Platform::Lemmy => self.temp_lemmy.enabled && Self::validate_url(&self.temp_lemmy.instance_url) && !self.temp_lemmy.username.is_empty() && !self.temp_lemmy.decrypted_password.is_empty() && !self.temp_lemmy.community.trim().is_empty(),
+            Platform::Farcaster => self.temp_farcaster.enabled && !self.temp_farcaster.signer_uuid.trim().is_empty() && !self.temp_farcaster.decrypted_api_key.is_empty(),
+            Platform::Matrix => self.temp_matrix.enabled && Self::validate_url(&self.temp_matrix.homeserver_url) && !self.temp_matrix.room_id.trim().is_empty() && !self.temp_matrix.decrypted_access_token.is_empty(),
+            Platform::DevTo => self.temp_devto.enabled && Self::validate_url(&self.temp_devto.site_url) && !self.temp_devto.decrypted_api_key.is_empty(),
+        }
+    }
+
+    /// Whether the current text can be posted to the ticked platforms right now.
+    fn can_post(&self) -> bool {
+        let text = self.post_editor_content.text();
+        let platforms = self.selected_platforms();
+        !matches!(self.posting_status, PostingStatus::Posting)
+            && !text.trim().is_empty()
+            && !self.exceeds_limit(&text, &platforms)
+            && (!platforms.contains(&Platform::Mastodon) || self.poll_is_valid())
+    }
+
+    /// The poll to send with the next Mastodon post, if one is being built.
+    fn mastodon_poll(&self) -> Option<MastodonPoll> {
+        self.poll_enabled.then(|| MastodonPoll {
+            options: self.poll_options.clone(),
+            expires_in: POLL_DURATION_SECS[self.poll_duration],
+            multiple: self.poll_multiple,
+        })
+    }
+
+    /// Whether sending the post to `platforms` from the outbox would leave out something set for
+    /// it. The outbox keeps the WordPress, Lemmy and Micro.Blog titles but not Dev.to's title and
+    /// tags, and every Dev.to article has a title.
+    fn outbox_would_drop_options(&self, platforms: &[Platform]) -> bool {
+        platforms.contains(&Platform::DevTo)
+    }
+
+    /// The WordPress title being written, if there is one
+    fn wordpress_title_option(&self) -> Option<String> {
+        (!self.wordpress_title.trim().is_empty()).then(|| self.wordpress_title.clone())
+    }
+
+    /// The Lemmy title being written, if there is one
+    fn lemmy_title_option(&self) -> Option<String> {
+        (!self.lemmy_title.trim().is_empty()).then(|| self.lemmy_title.clone())
+    }
+
+    /// The Micro.Blog title being written, if there is one
+    fn microblog_title_option(&self) -> Option<String> {
+        (!self.microblog_title.trim().is_empty()).then(|| self.microblog_title.clone())
+    }
+
+    /// The Micro.Blog categories being written, as a list
+    fn microblog_category_list(&self) -> Vec<String> {
+        self.microblog_categories
+            .split(',')
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Links to the posts being replied to or quoted, by platform; none unless replying.
+    /// Nostr articles can't be replies, so Nostr has none while writing one.
+    fn reply_references(&self) -> HashMap<Platform, String> {
+        if !self.reply_enabled {
+            return HashMap::new();
+        }
+        let mut references = self.reference_urls.clone();
+        if self.nostr_article {
+            references.remove(&Platform::Nostr);
         }
+        references
     }
 
-}
+    /// The Nostr article being written, if the post goes to Nostr as one.
+    fn nostr_article_options(&self) -> Option<NostrArticle> {
+        self.nostr_article.then(|| NostrArticle {
+            title: self.nostr_article_title.clone(),
+            summary: self.nostr_article_summary.clone(),
+            identifier: self.nostr_article_identifier.clone(),
+        })
+    }
+
+    /// No poll, or one with enough filled-in options.
+    fn poll_is_valid(&self) -> bool {
+        !self.poll_enabled || self.poll_options.iter().filter(|option| !option.trim().is_empty()).count() >= MIN_POLL_OPTIONS
+    }
+
+    /// Make the outbox due now, saving it if there was anything in it. Returns whether there was.
+    fn resume_outbox(&mut self) -> bool {
+        let mut config = self.config.clone();
+        if !config.resume_outbox(Utc::now()) {
+            return false;
+        }
+        if let Err(e) = Self::write_config(&config) {
+            tracing::error!("Failed to update the outbox: {}", e);
+        }
+        self.config = config;
+        true
+    }
+
+    /// Delete the temp files of pasted images that nothing refers to any more.
+    fn discard_pasted_images(&self, images: &[ImageAttachment]) {
+        let in_use = |path: &str| {
+            self.attached_images.iter().any(|image| image.path == path)
+                || self.config.drafts.iter().chain(self.config.last_session.iter()).any(|draft| draft.images.iter().any(|image| image.path == path))
+                || self.config.scheduled_posts.iter().any(|post| post.images.iter().any(|image| image.path == path))
+        };
+        for image in images {
+            if !in_use(&image.path) {
+                clipboard::remove_pasted_image(&image.path);
+            }
+        }
+    }
+
+    /// Whether leaving Compose would lose text that hasn't been posted or saved.
+    fn has_unsaved_text(&self) -> bool {
+        !self.post_editor_content.text().trim().is_empty()
+    }
+
+    /// Empty the editor and its attachments once the user has kept or given up the text.
+    fn clear_compose_text(&mut self) {
+        self.post_editor_content = text_editor::Content::new();
+        self.duplicate_warning = None;
+        let images = std::mem::take(&mut self.attached_images);
+        self.discard_pasted_images(&images);
+    }
+
+    /// Carry on to where the user was going once unsaved text is dealt with.
+    fn leave(&mut self, leave: PendingLeave) -> Task<cosmic::Action<Message>> {
+        match leave {
+            PendingLeave::View(view_mode) => self.set_view_mode(view_mode),
+            PendingLeave::Close => {
+                self.save_session();
+                cosmic::iced::exit()
+            }
+        }
+    }
+
+    /// Remember the editor's content and the window size for next launch.
+    fn save_session(&mut self) {
+        let session = self.current_draft();
+        let window_size = self.window_size.or(self.config.window_size);
+        if session != self.config.last_session || window_size != self.config.window_size {
+            let mut config = self.config.clone();
+            config.last_session = session;
+            config.window_size = window_size;
+            if let Err(e) = Self::write_config(&config) {
+                tracing::error!("Failed to autosave session: {}", e);
+            }
+            self.config = config;
+        }
+    }
+
+    fn reset_poll(&mut self) {
+        self.poll_enabled = false;
+        self.poll_options = vec![String::new(); MIN_POLL_OPTIONS];
+        self.poll_duration = DEFAULT_POLL_DURATION;
+        self.poll_multiple = false;
+    }
+
+    /// Show another tab, remembering it for next launch.
+    fn set_view_mode(&mut self, view_mode: ViewMode) -> Task<cosmic::Action<Message>> {
+        if let Some(saved) = view_mode.saved().filter(|saved| *saved != self.config.last_view) {
+            let mut config = self.config.clone();
+            config.last_view = saved;
+            if let Err(e) = Self::write_config(&config) {
+                tracing::error!("Failed to save the open tab: {}", e);
+            }
+            self.config = config;
+        }
+        self.view_mode = view_mode;
+        self.focus_view()
+    }
+
+    /// Put the cursor where typing is most likely to start in the current view.
+    fn focus_view(&self) -> Task<cosmic::Action<Message>> {
+        match self.view_mode {
+            ViewMode::Compose => focus(EDITOR_ID.clone()),
+            ViewMode::Settings => focus(SETTINGS_SEARCH_ID.clone()),
+            _ => Task::none(),
+        }
+    }
+
+    /// Expand or collapse a Settings section, remembering it for next launch.
+    fn set_settings_section_expanded(&mut self, section: SettingsSection, expanded: bool) {
+        if self.config.collapsed_settings.contains(&section) != expanded {
+            return;
+        }
+        let mut config = self.config.clone();
+        if expanded {
+            config.collapsed_settings.retain(|collapsed| *collapsed != section);
+        } else {
+            config.collapsed_settings.push(section);
+        }
+        if let Err(e) = Self::write_config(&config) {
+            tracing::error!("Failed to save the collapsed settings: {}", e);
+        }
+        self.config = config;
+    }
+
+    /// Expand or collapse the relay list, remembering it for next launch.
+    fn set_show_relays(&mut self, show: bool) {
+        self.show_relays = show;
+        if self.config.relays_collapsed == show {
+            let mut config = self.config.clone();
+            config.relays_collapsed = !show;
+            if let Err(e) = Self::write_config(&config) {
+                tracing::error!("Failed to save the relay list state: {}", e);
+            }
+            self.config = config;
+        }
+    }
+
+    /// Post to the ticked platforms, remembering them for next time.
+    fn post_selected(&mut self) -> Task<cosmic::Action<Message>> {
+        let platforms = self.selected_platforms();
+        if self.config.last_platforms.as_ref() != Some(&platforms) {
+            let mut config = self.config.clone();
+            config.last_platforms = Some(platforms.clone());
+            if let Err(e) = Self::write_config(&config) {
+                tracing::error!("Failed to save platform selection: {}", e);
+            }
+            self.config = config;
+        }
+        self.submit_post(platforms)
+    }
+
+    /// Send the current editor text and attached image to the given platforms.
+    fn submit_post(&mut self, platforms: Vec<Platform>) -> Task<cosmic::Action<Message>> {
+        let text = self.post_editor_content.text().to_string();
+        if text.trim().is_empty() || self.exceeds_limit(&text, &platforms) || self.post_abort.is_some() {
+            return Task::none();
+        }
+
+        self.posting_status = PostingStatus::Posting;
+        let config = self.posting_config();
+        let attached_images = self.attached_images.clone();
+        tracing::debug!("Posting to {:?} with {} image(s)", platforms, attached_images.len());
+
+        let options = PostOptions {
+            mastodon: MastodonOptions {
+                spoiler_text: self.content_warning_enabled.then(|| self.spoiler_text.clone()),
+                visibility: self.mastodon_visibility,
+                poll: self.mastodon_poll(),
+                scheduled_at: None,
+                markdown: self.markdown,
+                ..Default::default()
+            },
+            wordpress: WordPressOptions {
+                title: self.wordpress_title_option(),
+            },
+            lemmy: LemmyOptions {
+                title: self.lemmy_title_option(),
+            },
+            microblog: MicroBlogOptions {
+                title: self.microblog_title_option(),
+                categories: self.microblog_category_list(),
+            },
+            nostr: NostrOptions {
+                article: self.nostr_article_options(),
+            },
+            devto: DevToOptions {
+                title: Some(self.devto_title.clone()),
+                tags: self.devto_tags.split(',').map(|tag| tag.to_string()).collect(),
+            },
+            markdown: self.markdown,
+            signature: self.include_signature,
+            thread_mode: self.thread_mode,
+            language: Some(POST_LANGUAGE_CODES[self.post_language].to_string()),
+            dry_run: self.dry_run,
+            upload_progress: UploadProgress::default(),
+            pow_progress: PowProgress::default(),
+            reference_urls: self.reply_references(),
+            quote: self.quote,
+            idempotency_key: Some(self.idempotency_key.clone()),
+        };
+        self.upload_progress = options.upload_progress.clone();
+        self.pow_progress = options.pow_progress.clone();
+
+        // Dry runs stay out of the history
+        if !self.dry_run {
+            self.in_flight_post = Some((text.clone(), attached_images.clone(), options.nostr.article.clone()));
+        }
+        let (task, handle) = Task::perform(
+            social::post_to_platforms(self.http_client.clone(), config, platforms, text, attached_images, options),
+            |report| cosmic::Action::App(Message::PostResult(report)),
+        )
+        .abortable();
+        self.post_abort = Some(handle);
+        task
+    }
+
+    /// Checkbox label for a platform, flagged when its last post attempt failed.
+    fn platform_label(&self, platform: Platform) -> String {
+        if self.failed_platforms.contains(&platform) {
+            format!("⚠️ {}", platform.name())
+        } else {
+            platform.name().to_string()
+        }
+    }
 
-impl AppModel {
     fn validate_url(url: &str) -> bool {
         url.starts_with("https://") && url.len() > 8
     }
 
-    fn validate_handle(handle: &str) -> bool {
-        !handle.is_empty() && handle.contains('.')
-    }
+    fn validate_handle(handle: &str) -> bool {
+        !handle.is_empty() && handle.contains('.')
+    }
+
+    fn validate_private_key(key: &str) -> bool {
+        social::parse_nostr_secret_key(key).is_ok()
+    }
+
+    /// A relay list can be published once there's a valid key and at least one relay, and no publish is running.
+    fn can_publish_relay_list(&self) -> bool {
+        !self.temp_nostr.relays.is_empty()
+            && Self::validate_private_key(&self.temp_nostr.decrypted_private_key)
+            && !matches!(&self.relay_list_status, Some(Ok(message)) if message.is_empty())
+    }
+
+    /// The relay typed into the Add Relay field, normalized, or why it can't be added.
+    fn new_relay_url(&self) -> Result<String, String> {
+        let relay = social::normalize_relay_url(&self.new_relay)?;
+        let duplicate = self
+            .temp_nostr
+            .relays
+            .iter()
+            .any(|existing| social::normalize_relay_url(existing).is_ok_and(|existing| existing == relay));
+        if duplicate {
+            return Err(fl!("duplicate-relay"));
+        }
+        Ok(relay)
+    }
+
+    /// Links to the post to reply to or quote on each selected platform that supports it, or
+    /// `None` when none of them do. Platforms left blank get a normal post.
+    fn reply_section(&self, selected_platforms: &[Platform]) -> Option<Element<Message>> {
+        let cosmic_theme::Spacing { space_xxs, space_s, .. } = theme::active().cosmic().spacing;
+        let platforms: Vec<Platform> = [Platform::Bluesky, Platform::Mastodon, Platform::Nostr]
+            .into_iter()
+            .filter(|platform| selected_platforms.contains(platform))
+            .collect();
+        if platforms.is_empty() {
+            return None;
+        }
+        let posting = matches!(self.posting_status, PostingStatus::Posting);
+
+        let mut section = column()
+            .push(
+                row()
+                    .push(checkbox(fl!("reply-toggle"), self.reply_enabled).on_toggle_maybe((!posting).then_some(Message::ReplyToggled)))
+                    .push(checkbox(fl!("quote-toggle"), self.quote).on_toggle_maybe((!posting && self.reply_enabled).then_some(Message::QuoteToggled)))
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            )
+            .spacing(space_xxs);
+        if !self.reply_enabled {
+            return Some(section.into());
+        }
+        for platform in platforms {
+            if platform == Platform::Nostr && self.nostr_article {
+                section = section.push(widget::text(fl!("nostr-article-no-reply")).size(12));
+                continue;
+            }
+            let url = self.reference_urls.get(&platform).map(String::as_str).unwrap_or_default();
+            section = section.push(
+                text_input(fl!("reference-url-placeholder", platform = platform.name()), url)
+                    .on_input(move |url| Message::ReferenceUrlChanged(platform, url))
+                    .width(Length::Fill)
+            );
+        }
+        Some(section.into())
+    }
+
+    /// The Mastodon poll builder, greyed out unless Mastodon is ticked and no images are attached.
+    fn poll_section(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, space_s, .. } = theme::active().cosmic().spacing;
+        let polls_supported = self.config.mastodon.max_poll_options != Some(0);
+        let available = self.post_to_mastodon && polls_supported && self.attached_images.is_empty() && !matches!(self.posting_status, PostingStatus::Posting);
+
+        let mut toggle = checkbox(fl!("poll-toggle"), self.poll_enabled);
+        if available {
+            toggle = toggle.on_toggle(Message::PollToggled);
+        }
+        let mut section = column().push(toggle).spacing(space_xxs);
+        if !self.post_to_mastodon {
+            section = section.push(widget::text(fl!("poll-needs-mastodon")).size(11));
+        } else if !polls_supported {
+            section = section.push(widget::text(fl!("poll-unsupported")).size(11));
+        } else if !self.attached_images.is_empty() {
+            section = section.push(widget::text(fl!("poll-no-images")).size(11));
+        }
+        if !self.poll_enabled {
+            return section.into();
+        }
+
+        for (index, option) in self.poll_options.iter().enumerate() {
+            let mut option_input = text_input(fl!("poll-choice", number = index + 1), option).width(Length::Fill);
+            if available {
+                option_input = option_input.on_input(move |option| Message::PollOptionChanged(index, option));
+            }
+            let remove_button = widget::button::text("✕")
+                .on_press_maybe((available && self.poll_options.len() > MIN_POLL_OPTIONS).then_some(Message::RemovePollOption(index)));
+            section = section.push(
+                row()
+                    .push(option_input)
+                    .push(remove_button)
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        let add_button = widget::button::standard(fl!("poll-add-choice"))
+            .on_press_maybe((available && self.poll_options.len() < self.max_poll_options()).then_some(Message::AddPollOption));
+        let duration = widget::dropdown(
+            &self.poll_duration_labels,
+            Some(self.poll_duration),
+            Message::PollDurationSelected,
+        );
+        let mut multiple = checkbox(fl!("poll-multiple"), self.poll_multiple);
+        if available {
+            multiple = multiple.on_toggle(Message::PollMultipleToggled);
+        }
+        section = section.push(
+            row()
+                .push(add_button)
+                .push(widget::text(fl!("poll-ends-after")).size(12))
+                .push(duration)
+                .push(multiple)
+                .align_y(Alignment::Center)
+                .spacing(space_s)
+        );
+        if !self.poll_is_valid() {
+            section = section.push(widget::text(fl!("poll-too-few-choices", count = MIN_POLL_OPTIONS)).size(11));
+        }
+        section.into()
+    }
+
+    /// Emoji buttons grouped by category, or the matches when searching.
+    fn emoji_picker(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, space_xs, .. } = theme::active().cosmic().spacing;
+        const PER_ROW: usize = 12;
+
+        let emoji_grid = |entries: &[emoji::Emoji]| {
+            let mut grid = column().spacing(space_xxs);
+            for chunk in entries.chunks(PER_ROW) {
+                let mut emoji_row = row().spacing(space_xxs);
+                for &(symbol, name) in chunk {
+                    emoji_row = emoji_row.push(
+                        widget::tooltip(
+                            widget::button::text(symbol).on_press(Message::InsertEmoji(symbol)),
+                            widget::text(name),
+                            widget::tooltip::Position::Top,
+                        )
+                    );
+                }
+                grid = grid.push(emoji_row);
+            }
+            grid
+        };
+
+        let mut picker = column()
+            .push(
+                text_input(fl!("emoji-search"), &self.emoji_search)
+                    .on_input(Message::EmojiSearchChanged)
+                    .width(Length::Fill)
+            )
+            .spacing(space_xs);
+        if self.emoji_search.trim().is_empty() {
+            for (category, entries) in emoji::CATEGORIES {
+                picker = picker
+                    .push(widget::text(*category).size(12))
+                    .push(emoji_grid(entries));
+            }
+        } else {
+            let found = emoji::search(&self.emoji_search);
+            if found.is_empty() {
+                picker = picker.push(widget::text(fl!("emoji-none-found")).size(12));
+            }
+            picker = picker.push(emoji_grid(&found));
+        }
+        container(scrollable(picker).height(Length::Fixed(180.0)))
+            .padding(space_xs)
+            .class(theme::Container::Card)
+            .into()
+    }
+
+    /// The posts each selected platform will get, updated as the text changes.
+    fn preview_section(&self, platforms: &[Platform]) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, space_xs, .. } = theme::active().cosmic().spacing;
+        let text = self.post_editor_content.text();
+
+        let mut preview = column().spacing(space_xs);
+        for platform in platforms {
+            let mut platform_preview = column()
+                .push(widget::text::heading(platform.name()))
+                .spacing(space_xxs);
+            if *platform == Platform::Mastodon && self.content_warning_enabled && !self.spoiler_text.trim().is_empty() {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-content-warning", text = self.spoiler_text.trim())).size(12));
+            }
+            if *platform == Platform::WordPress && !self.wordpress_title.trim().is_empty() {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-title", title = self.wordpress_title.trim())).size(12));
+            }
+            if *platform == Platform::Lemmy {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-title", title = social::lemmy_title(&text, Some(&self.lemmy_title)))).size(12));
+            }
+            if *platform == Platform::MicroBlog && !self.microblog_title.trim().is_empty() {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-title", title = self.microblog_title.trim())).size(12));
+            }
+            if *platform == Platform::DevTo {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-title", title = self.devto_title.trim())).size(12));
+            }
+            if *platform == Platform::Nostr && self.nostr_article {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-title", title = social::nostr_article_title(&text, &self.nostr_article_title))).size(12));
+            }
+            let platform_text = social::text_for_platform(*platform, &text, self.markdown, self.include_signature, &self.config);
+            let posts = social::preview_posts(*platform, &platform_text, self.thread_mode, &self.config);
+            if posts.is_empty() {
+                platform_preview = platform_preview.push(widget::text(fl!("preview-too-long")).size(12));
+            }
+            for post in posts {
+                platform_preview = platform_preview.push(
+                    container(widget::text(post).size(12).wrapping(Wrapping::Word))
+                        .padding(space_xs)
+                        .width(Length::Fill)
+                        .class(theme::Container::Card)
+                );
+            }
+            preview = preview.push(platform_preview);
+        }
+        preview.into()
+    }
+
+    fn compose_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, space_xs, space_s, .. } = theme::active().cosmic().spacing;
+
+        let posting = matches!(self.posting_status, PostingStatus::Posting);
+        let mut editor = text_editor(&self.post_editor_content)
+            .id(EDITOR_ID.clone())
+            .placeholder(fl!("post-placeholder"));
+        // Without an action handler the editor is read-only
+        if !posting {
+            editor = editor.on_action(Message::PostEditorAction);
+        }
+        let text_editor_widget = container(
+            editor
+                .key_binding(|key_press| {
+                    if is_submit_shortcut(&key_press.key, key_press.modifiers) {
+                        Some(text_editor::Binding::Custom(Message::SubmitShortcut))
+                    } else if is_paste_image_shortcut(&key_press.key, key_press.modifiers) {
+                        Some(text_editor::Binding::Custom(Message::PasteImage))
+                    } else {
+                        text_editor::Binding::from_key_press(key_press)
+                    }
+                })
+                .height(Length::Fill)
+                .min_height(EDITOR_MIN_HEIGHT)
+                .wrapping(Wrapping::Word)
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(space_s);
+
+        let post_text = self.post_editor_content.text();
+        let selected_platforms = self.selected_platforms();
+
+        // One counter per platform being posted to, measured the way that platform does, red when over its limit
+        let mut char_limit_text = row().spacing(space_s);
+        for platform in &selected_platforms {
+            let platform_text = social::text_for_platform(*platform, &post_text, self.markdown, self.include_signature, &self.config);
+            let char_count = social::post_length(*platform, &platform_text);
+            let counter = match self.character_limit(*platform) {
+                Some(limit) => {
+                    if self.splits_into_thread(*platform) && char_count > limit {
+                        let posts = social::split_into_thread(&platform_text, limit).len();
+                        char_limit_text = char_limit_text.push(
+                            widget::text(fl!("counter-thread", platform = platform.name(), count = char_count, limit = limit, posts = posts)).size(12)
+                        );
+                        continue;
+                    }
+                    let counter = widget::text(fl!("counter-limit", platform = platform.name(), count = char_count, limit = limit)).size(12);
+                    if char_count > limit {
+                        counter.class(theme::Text::Color(cosmic::iced::Color::from_rgb(0.85, 0.2, 0.2)))
+                    } else {
+                        counter
+                    }
+                }
+                None => widget::text(fl!("counter", platform = platform.name(), count = char_count)).size(12),
+            };
+            char_limit_text = char_limit_text.push(counter);
+        }
+        if selected_platforms.is_empty() {
+            char_limit_text = char_limit_text.push(widget::text(fl!("character-count", count = social::grapheme_count(&post_text))).size(12));
+        }
+        let words = social::word_count(&post_text);
+        char_limit_text = char_limit_text.push(widget::text(fl!("word-count", count = words)).size(12));
+
+        // Say what will happen to Bluesky if the text is over its limit
+        let bluesky_warning = if social::post_length(Platform::Bluesky, &social::text_for_platform(Platform::Bluesky, &post_text, self.markdown, self.include_signature, &self.config)) > BLUESKY_CHARACTER_LIMIT && !self.thread_mode && selected_platforms.contains(&Platform::Bluesky) {
+            let warning = match self.config.bluesky.truncate_behavior {
+                BlueskyTruncateBehavior::Thread => fl!("bluesky-thread-warning", limit = BLUESKY_CHARACTER_LIMIT),
+                BlueskyTruncateBehavior::BlockPost => fl!("bluesky-block-warning", limit = BLUESKY_CHARACTER_LIMIT),
+                BlueskyTruncateBehavior::SkipBluesky => fl!("bluesky-skip-warning", limit = BLUESKY_CHARACTER_LIMIT),
+                BlueskyTruncateBehavior::Truncate => fl!("bluesky-truncate-warning", limit = BLUESKY_CHARACTER_LIMIT),
+            };
+            Some(widget::text(warning).size(11))
+        } else {
+            None
+        };
+
+        let over_limit = self.exceeds_limit(self.post_editor_content.text().as_str(), &selected_platforms);
+        let post_button = if posting {
+            widget::button::destructive(fl!("cancel"))
+                .on_press(Message::CancelPost)
+        } else {
+            widget::button::suggested(fl!("post-button"))
+                .on_press_maybe(self.can_post().then_some(Message::PostSubmit))
+        };
+
+        let status_text = match &self.posting_status {
+            PostingStatus::Idle => None,
+            PostingStatus::Posting => Some(widget::text(fl!("posting", spinner = SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()])).size(12)),
+            PostingStatus::Success => Some(widget::text(fl!("post-success")).size(12)),
+            PostingStatus::Scheduled(time) => Some(
+                widget::text(fl!("mastodon-scheduled", time = time.with_timezone(&Local).format(SCHEDULE_TIME_FORMAT).to_string())).size(12)
+            ),
+            PostingStatus::Queued => Some(widget::text(fl!("post-queued")).size(12)),
+            PostingStatus::Error(err) => Some(widget::text(fl!("post-error", error = err.as_str())).size(12)),
+        };
+        let status_line = status_text.map(|text| {
+            let mut line = row()
+                .push(text.width(Length::Fill))
+                .align_y(Alignment::Center)
+                .spacing(space_s);
+            if !posting {
+                line = line.push(
+                    widget::button::standard("✕")
+                        .on_press(Message::DismissStatus)
+                );
+            }
+            line
+        });
+
+        let mut checkboxes = row().spacing(space_s);
+        let has_devto_title = !self.devto_title.trim().is_empty();
+
+        // One checkbox ticks or clears every configured platform at once
+        let ready_platforms: Vec<Platform> = Platform::ALL
+            .into_iter()
+            .filter(|platform| self.platform_ready(*platform) && (*platform != Platform::DevTo || has_devto_title))
+            .collect();
+        let ticked = ready_platforms.iter().filter(|platform| self.platform_selected(**platform)).count();
+        let post_to_all = (ready_platforms.len() > 1).then(|| {
+            let label = if ticked > 0 && ticked < ready_platforms.len() {
+                fl!("post-to-all-partial", ticked = ticked, total = ready_platforms.len())
+            } else {
+                fl!("post-to-all")
+            };
+            checkbox(label, ticked == ready_platforms.len()).on_toggle_maybe((!posting).then_some(Message::PostToAllToggled))
+        });
+
+        // Only show checkboxes for configured platforms
+        if self.platform_ready(Platform::Mastodon) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Mastodon), self.post_to_mastodon).on_toggle_maybe((!posting).then_some(Message::PostToMastodonToggled)));
+        }
+        if self.platform_ready(Platform::Bluesky) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Bluesky), self.post_to_bluesky).on_toggle_maybe((!posting).then_some(Message::PostToBlueskyToggled)));
+        }
+        if self.platform_ready(Platform::MicroBlog) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::MicroBlog), self.post_to_microblog).on_toggle_maybe((!posting).then_some(Message::PostToMicroBlogToggled)));
+        }
+        if self.platform_ready(Platform::Nostr) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Nostr), self.post_to_nostr).on_toggle_maybe((!posting).then_some(Message::PostToNostrToggled)));
+        }
+        if self.platform_ready(Platform::Threads) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Threads), self.post_to_threads).on_toggle_maybe((!posting).then_some(Message::PostToThreadsToggled)));
+        }
+        if self.platform_ready(Platform::Pixelfed) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Pixelfed), self.post_to_pixelfed).on_toggle_maybe((!posting).then_some(Message::PostToPixelfedToggled)));
+        }
+        if self.platform_ready(Platform::Discord) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Discord), self.post_to_discord).on_toggle_maybe((!posting).then_some(Message::PostToDiscordToggled)));
+        }
+        if self.platform_ready(Platform::Telegram) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Telegram), self.post_to_telegram).on_toggle_maybe((!posting).then_some(Message::PostToTelegramToggled)));
+        }
+        if self.platform_ready(Platform::WordPress) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::WordPress), self.post_to_wordpress).on_toggle_maybe((!posting).then_some(Message::PostToWordPressToggled)));
+        }
+        if self.platform_ready(Platform::Lemmy) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Lemmy), self.post_to_lemmy).on_toggle_maybe((!posting).then_some(Message::PostToLemmyToggled)));
+        }
+        if self.platform_ready(Platform::Farcaster) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Farcaster), self.post_to_farcaster).on_toggle_maybe((!posting).then_some(Message::PostToFarcasterToggled)));
+        }
+        if self.platform_ready(Platform::Matrix) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::Matrix), self.post_to_matrix).on_toggle_maybe((!posting).then_some(Message::PostToMatrixToggled)));
+        }
+        if self.platform_ready(Platform::DevTo) {
+            checkboxes = checkboxes.push(checkbox(self.platform_label(Platform::DevTo), self.platform_selected(Platform::DevTo)).on_toggle_maybe((!posting && has_devto_title).then_some(Message::PostToDevToToggled)));
+        }
+
+        // Image attachment section
+        let mut image_section = column().spacing(space_xs);
+        
+        // A Mastodon poll can't be sent with images, and a video can't be sent with anything else
+        let has_video = self.attached_images.iter().any(ImageAttachment::is_video);
+        let can_attach = self.attached_images.len() < MAX_IMAGES && !self.poll_enabled && !has_video && !posting;
+        let attach_button = widget::button::standard(fl!("attach-image"))
+            .on_press_maybe(can_attach.then_some(Message::AttachImage));
+        let paste_button = widget::button::standard(fl!("paste-image"))
+            .on_press_maybe(can_attach.then_some(Message::PasteImage));
+        let emoji_button = widget::button::standard(fl!("emoji"))
+            .on_press(Message::ToggleEmojiPicker);
+        let mut compose_buttons = row().push(attach_button).push(paste_button).push(emoji_button).spacing(space_s);
+        if self.config.url_shortener.enabled {
+            let can_shorten = !posting && !self.shortening_links && !social::links_to_shorten(&post_text, &self.config.url_shortener.endpoint).is_empty();
+            compose_buttons = compose_buttons.push(
+                widget::button::standard(if self.shortening_links { fl!("shortening-links") } else { fl!("shorten-links") })
+                    .on_press_maybe(can_shorten.then_some(Message::ShortenLinks))
+            );
+        }
+        image_section = image_section.push(compose_buttons);
+        if self.show_emoji_picker {
+            image_section = image_section.push(self.emoji_picker());
+        }
+        
+        for (index, image) in self.attached_images.iter().enumerate() {
+            let (thumbnail, details): (Element<Message>, String) = match self.image_previews.get(&image.path) {
+                _ if image.is_video() => (widget::text("🎬").width(Length::Fixed(48.0)).into(), fl!("video-bluesky-only")),
+                Some(Ok(preview)) => {
+                    let mut details = format!("{}×{} · {}", preview.width, preview.height, format_file_size(preview.bytes));
+                    if preview.bytes > BLUESKY_MAX_IMAGE_BYTES && selected_platforms.contains(&Platform::Bluesky) {
+                        details.push_str(&format!(" · {}", fl!("image-over-bluesky-limit")));
+                    }
+                    (
+                        widget::image(preview.thumbnail.clone())
+                            .width(Length::Fixed(48.0))
+                            .height(Length::Fixed(48.0))
+                            .into(),
+                        details,
+                    )
+                }
+                Some(Err(e)) => (widget::text("⚠️").width(Length::Fixed(48.0)).into(), e.clone()),
+                None => (widget::text("…").width(Length::Fixed(48.0)).into(), fl!("loading")),
+            };
+            let name = std::path::Path::new(&image.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| image.path.clone());
+            let alt_input = text_input(fl!("alt-text-placeholder"), &image.alt_text)
+                .on_input(move |alt_text| Message::ImageAltTextChanged(index, alt_text))
+                .width(Length::Fill);
+            let mut alt_row = row().push(alt_input).align_y(Alignment::Center).spacing(space_xxs);
+            if self.config.captioner.enabled && !image.is_video() {
+                let suggesting = self.suggesting_alt_text.contains(&image.path);
+                alt_row = alt_row.push(
+                    widget::button::standard(if suggesting { fl!("suggesting-alt-text") } else { fl!("suggest-alt-text") })
+                        .on_press_maybe((!posting && !suggesting).then_some(Message::SuggestAltText(index)))
+                );
+            }
+            let remove_button = widget::button::destructive("✕")
+                .on_press_maybe((!posting).then_some(Message::RemoveImage(index)));
+            image_section = image_section.push(
+                row()
+                    .push(thumbnail)
+                    .push(
+                        column()
+                            .push(widget::text(format!("{} ({})", name, details)).size(11))
+                            .push(alt_row)
+                            .spacing(space_xxs)
+                            .width(Length::Fill)
+                    )
+                    .push(remove_button)
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        // Missing alt text doesn't block posting, but screen readers get nothing to announce
+        let missing_alt = self.attached_images.iter().filter(|image| image.alt_text.trim().is_empty()).count();
+        if missing_alt > 0 {
+            image_section = image_section.push(
+                widget::text(fl!("missing-alt-text", count = missing_alt))
+                .size(12)
+            );
+        }
+
+        let mut content = column()
+            .push(text_editor_widget)
+            .push(image_section);
+        if let Some(post_to_all) = post_to_all {
+            content = content.push(post_to_all);
+        }
+        content = content
+            .push(checkboxes)
+            .push(
+                checkbox(fl!("thread-mode"), self.thread_mode)
+                    .on_toggle_maybe((!posting).then_some(Message::ThreadModeToggled))
+            )
+            .push(
+                checkbox(fl!("markdown"), self.markdown)
+                    .on_toggle_maybe((!posting).then_some(Message::MarkdownToggled))
+            )
+            .push(
+                checkbox(fl!("dry-run"), self.dry_run)
+                    .on_toggle_maybe((!posting).then_some(Message::DryRunToggled))
+            )
+            .spacing(space_s);
+
+        // Only offered when something ticked has a signature to leave off
+        if selected_platforms.iter().any(|platform| self.config.signature(*platform).is_some()) {
+            content = content.push(
+                checkbox(fl!("add-signature"), self.include_signature)
+                    .on_toggle_maybe((!posting).then_some(Message::SignatureToggled))
+            );
+        }
+
+        // Only Mastodon, Bluesky and Nostr tag posts with a language
+        if self.post_to_mastodon || self.post_to_bluesky || self.post_to_nostr {
+            content = content.push(
+                row()
+                    .push(widget::text(fl!("post-language")).size(12))
+                    .push(widget::dropdown(POST_LANGUAGE_NAMES, Some(self.post_language), Message::PostLanguageSelected))
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        // Content warnings and visibility are Mastodon features, so only offer them when posting there
+        if self.post_to_mastodon {
+            let visibility = MastodonVisibility::ALL.iter().position(|visibility| *visibility == self.mastodon_visibility);
+            content = content.push(
+                row()
+                    .push(widget::text(fl!("mastodon-visibility")).size(12))
+                    .push(widget::dropdown(&self.mastodon_visibility_labels, visibility, Message::MastodonVisibilitySelected))
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+            let mut cw_row = row()
+                .push(
+                    checkbox(fl!("content-warning"), self.content_warning_enabled)
+                        .on_toggle_maybe((!posting).then_some(Message::ContentWarningToggled))
+                )
+                .align_y(Alignment::Center)
+                .spacing(space_s);
+            if self.content_warning_enabled {
+                cw_row = cw_row.push(
+                    text_input(fl!("content-warning-placeholder"), &self.spoiler_text)
+                        .on_input(Message::SpoilerTextChanged)
+                        .width(Length::Fill)
+                );
+            }
+            content = content.push(cw_row);
+        }
+
+        if let Some(reply_section) = self.reply_section(&selected_platforms) {
+            content = content.push(reply_section);
+        }
+
+        content = content.push(self.poll_section());
+
+        // Titles are only used by WordPress
+        if selected_platforms.contains(&Platform::WordPress) {
+            content = content.push(
+                text_input(fl!("wordpress-title-placeholder"), &self.wordpress_title)
+                    .on_input(Message::WordPressTitleChanged)
+                    .width(Length::Fill)
+            );
+        }
+
+        // Lemmy posts always have a title, taken from the first line unless one is given
+        if selected_platforms.contains(&Platform::Lemmy) {
+            content = content.push(
+                text_input(fl!("lemmy-title-placeholder"), &self.lemmy_title)
+                    .on_input(Message::LemmyTitleChanged)
+                    .width(Length::Fill)
+            );
+        }
+
+        // A title turns a Micro.Blog post into a long-form entry
+        if selected_platforms.contains(&Platform::MicroBlog) {
+            content = content.push(
+                row()
+                    .push(
+                        text_input(fl!("microblog-title-placeholder"), &self.microblog_title)
+                            .on_input(Message::MicroBlogTitleChanged)
+                            .width(Length::Fill)
+                    )
+                    .push(
+                        text_input(fl!("microblog-categories-placeholder"), &self.microblog_categories)
+                            .on_input(Message::MicroBlogCategoriesChanged)
+                            .width(Length::Fill)
+                    )
+                    .spacing(space_s)
+            );
+        }
+
+        // Nostr can take the post as a long-form article with its own title and summary
+        if selected_platforms.contains(&Platform::Nostr) {
+            content = content.push(
+                checkbox(fl!("nostr-article"), self.nostr_article)
+                    .on_toggle_maybe((!posting).then_some(Message::NostrArticleToggled))
+            );
+            if self.nostr_article {
+                content = content.push(
+                    row()
+                        .push(
+                            text_input(fl!("nostr-article-title-placeholder"), &self.nostr_article_title)
+                                .on_input(Message::NostrArticleTitleChanged)
+                                .width(Length::Fill)
+                        )
+                        .push(
+                            text_input(fl!("nostr-article-summary-placeholder"), &self.nostr_article_summary)
+                                .on_input(Message::NostrArticleSummaryChanged)
+                                .width(Length::Fill)
+                        )
+                        .spacing(space_s)
+                );
+            }
+        }
+
+        // Dev.to articles need a title before the platform can be ticked
+        if self.platform_ready(Platform::DevTo) {
+            content = content.push(
+                row()
+                    .push(
+                        text_input(fl!("devto-title-placeholder"), &self.devto_title)
+                            .on_input(Message::DevToTitleChanged)
+                            .width(Length::Fill)
+                    )
+                    .push(
+                        text_input(fl!("devto-tags-placeholder"), &self.devto_tags)
+                            .on_input(Message::DevToTagsChanged)
+                            .width(Length::Fill)
+                    )
+                    .spacing(space_s)
+            );
+            if self.post_to_devto && !has_devto_title {
+                content = content.push(widget::text(fl!("devto-needs-title")).size(11));
+            }
+        }
 
-    fn validate_private_key(key: &str) -> bool {
-        key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit())
-    }
+        if let Some(warning) = bluesky_warning {
+            content = content.push(warning);
+        }
 
-    fn validate_relay_url(url: &str) -> bool {
-        url.starts_with("wss://") && url.len() > 6
-    }
-    fn compose_view(&self) -> Element<Message> {
-        let cosmic_theme::Spacing { space_s, .. } = theme::active().cosmic().spacing;
+        if selected_platforms.contains(&Platform::Pixelfed) && self.attached_images.iter().all(ImageAttachment::is_video) {
+            content = content.push(widget::text(fl!("pixelfed-needs-image")).size(11));
+        }
 
-        let text_editor_widget = container(
-            text_editor(&self.post_editor_content)
-                .placeholder("What's happening?")
-                .on_action(Message::PostEditorAction)
-                .height(200.0)
-                .width(500.0)
-                .wrapping(Wrapping::Word)
-        )
-        .padding(space_s);
+        if self.attached_images.iter().any(ImageAttachment::is_video) && selected_platforms.iter().any(|platform| *platform != Platform::Bluesky) {
+            content = content.push(widget::text(fl!("video-left-out-warning")).size(11));
+        }
 
-        let char_count = self.post_editor_content.text().chars().count();
-        let char_limit_text = widget::text(format!("{}/{}", char_count, MAX_POST_LENGTH))
-            .size(12);
+        if selected_platforms.contains(&Platform::Telegram) && social::post_length(Platform::Telegram, &social::text_for_platform(Platform::Telegram, &post_text, self.markdown, self.include_signature, &self.config)) > TELEGRAM_MESSAGE_LIMIT {
+            content = content.push(widget::text(fl!("telegram-truncate-warning", limit = TELEGRAM_MESSAGE_LIMIT)).size(11));
+        }
 
-        // Show Bluesky warning if over 300 characters and Bluesky is enabled
-        let bluesky_warning = if char_count > BLUESKY_LIMIT && self.post_to_bluesky && 
-            self.temp_bluesky.enabled && !self.temp_bluesky.handle.is_empty() && !self.temp_bluesky.decrypted_password.is_empty() {
-            Some(widget::text(format!("⚠️ Bluesky posts will be truncated to {} characters", BLUESKY_LIMIT))
-                .size(11))
-        } else {
-            None
-        };
+        // The counter is in bytes, so say why it runs ahead of the character count
+        if selected_platforms.contains(&Platform::Farcaster) && social::post_length(Platform::Farcaster, &social::text_for_platform(Platform::Farcaster, &post_text, self.markdown, self.include_signature, &self.config)) > FARCASTER_BYTE_LIMIT {
+            content = content.push(widget::text(fl!("farcaster-limit-warning", limit = FARCASTER_BYTE_LIMIT)).size(11));
+        }
 
-        let post_button = if self.post_editor_content.text().trim().is_empty() || char_count > MAX_POST_LENGTH {
-            widget::button::suggested("Post")
-        } else {
-            widget::button::suggested("Post")
-                .on_press(Message::PostSubmit)
-        };
+        // What each platform will receive, after truncation and thread splitting
+        if !selected_platforms.is_empty() && !self.post_editor_content.text().trim().is_empty() {
+            content = content.push(
+                widget::button::standard(if self.show_preview { fl!("hide-preview") } else { fl!("show-preview") })
+                    .on_press(Message::TogglePreview)
+            );
+            if self.show_preview {
+                content = content.push(self.preview_section(&selected_platforms));
+            }
+        }
 
-        let status_text = match &self.posting_status {
-            PostingStatus::Idle => None,
-            PostingStatus::Posting => Some(widget::text("Posting...").size(12)),
-            PostingStatus::Success => Some(widget::text("Posted successfully!").size(12)),
-            PostingStatus::Error(err) => Some(widget::text(format!("Failed to post: {}", err)).size(12)),
-        };
+        let mut action_row = row()
+            .push(char_limit_text)
+            .push(widget::horizontal_space())
+            .align_y(Alignment::Center)
+            .spacing(space_s);
 
-        let mut checkboxes = row().spacing(space_s);
+        if !self.failed_platforms.is_empty() && !posting {
+            action_row = action_row.push(
+                widget::button::standard(fl!("retry-failed"))
+                    .on_press(Message::RetryFailed)
+            );
+        }
 
-        // Only show checkboxes for configured platforms
-        if self.temp_mastodon.enabled && !self.temp_mastodon.instance_url.is_empty() && !self.temp_mastodon.decrypted_access_token.is_empty() {
-            checkboxes = checkboxes.push(checkbox("Mastodon", self.post_to_mastodon).on_toggle(Message::PostToMastodonToggled));
+        content = content.push(action_row.push(post_button));
+
+        let schedules_devto = self.selected_platforms().contains(&Platform::DevTo);
+        let can_schedule = !self.post_editor_content.text().trim().is_empty()
+            && !over_limit
+            && !posting
+            && !schedules_devto
+            && !self.schedule_time_input.trim().is_empty();
+        content = content.push(
+            row()
+                .push(
+                    text_input(fl!("schedule-placeholder"), &self.schedule_time_input)
+                        .on_input(Message::ScheduleTimeChanged)
+                        .width(Length::Fill)
+                )
+                .push(
+                    widget::button::standard(fl!("schedule"))
+                        .on_press_maybe(can_schedule.then_some(Message::SchedulePost))
+                )
+                .align_y(Alignment::Center)
+                .spacing(space_s)
+        );
+        if schedules_devto {
+            content = content.push(widget::text(fl!("devto-no-schedule")).size(12));
         }
-        if self.temp_bluesky.enabled && !self.temp_bluesky.handle.is_empty() && !self.temp_bluesky.decrypted_password.is_empty() {
-            checkboxes = checkboxes.push(checkbox("Bluesky", self.post_to_bluesky).on_toggle(Message::PostToBlueskyToggled));
+        if self.post_to_mastodon {
+            content = content.push(
+                checkbox(fl!("mastodon-server-schedule"), self.mastodon_server_schedule)
+                    .on_toggle_maybe((!posting).then_some(Message::MastodonServerScheduleToggled))
+            );
         }
-        if self.temp_microblog.enabled && !self.temp_microblog.decrypted_access_token.is_empty() {
-            checkboxes = checkboxes.push(checkbox("Micro.Blog", self.post_to_microblog).on_toggle(Message::PostToMicroBlogToggled));
+
+        // Posting uses the saved settings, so point out edits that haven't been saved yet
+        if !posting && self.unsaved_settings {
+            content = content.push(
+                row()
+                    .push(widget::text(fl!("unsaved-settings-warning")).size(12).width(Length::Fill))
+                    .push(
+                        widget::button::suggested(fl!("save-and-post"))
+                            .on_press_maybe(self.can_post().then_some(Message::SaveAndPost))
+                    )
+                    .push(
+                        widget::button::standard(fl!("show"))
+                            .on_press(Message::SwitchView(ViewMode::Settings))
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
         }
-        if self.temp_nostr.enabled && !self.temp_nostr.decrypted_private_key.is_empty() && !self.temp_nostr.relays.is_empty() {
-            checkboxes = checkboxes.push(checkbox("Nostr", self.post_to_nostr).on_toggle(Message::PostToNostrToggled));
+
+        if let Some(sent_at) = self.duplicate_warning {
+            let minutes = (Utc::now() - sent_at).num_minutes();
+            content = content.push(
+                row()
+                    .push(widget::text(fl!("duplicate-warning", minutes = minutes)).size(12).width(Length::Fill))
+                    .push(
+                        widget::button::suggested(fl!("post-anyway"))
+                            .on_press_maybe(self.can_post().then_some(Message::PostAnyway))
+                    )
+                    .push(
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::DismissDuplicateWarning)
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
         }
 
-        // Image attachment section
-        let mut image_section = row().spacing(space_s);
-        
-        let attach_button = widget::button::standard("📎 Attach Image")
-            .on_press(Message::AttachImage);
-        image_section = image_section.push(attach_button);
-        
-        if let Some(ref image_path) = self.attached_image {
-            let filename = std::path::Path::new(image_path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("image");
-            image_section = image_section.push(widget::text(format!("📷 {}", filename)).size(12));
-            
-            let clear_button = widget::button::destructive("✕")
-                .on_press(Message::ImageSelected(None));
-            image_section = image_section.push(clear_button);
+        if let Some(status) = status_line {
+            content = content.push(status);
         }
 
-        let mut content = column()
-            .push(text_editor_widget)
-            .push(image_section)
-            .push(checkboxes)
-            .spacing(space_s);
+        // Platforms that turned up unconfigured because their credentials didn't decrypt
+        let undecrypted: Vec<&str> = Platform::ALL
+            .into_iter()
+            .filter(|platform| self.config.platform_enabled(*platform) && self.config.decrypt_failed(SettingsSection::Platform(*platform)))
+            .map(|platform| platform.name())
+            .collect();
+        if !undecrypted.is_empty() {
+            content = content.push(
+                row()
+                    .push(widget::text(fl!("credentials-undecrypted", platforms = undecrypted.join(", "))).size(12).width(Length::Fill))
+                    .push(
+                        widget::button::standard(fl!("show"))
+                            .on_press(Message::SwitchView(ViewMode::Settings))
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
 
-        if let Some(warning) = bluesky_warning {
-            content = content.push(warning);
+        let outbox = self.config.scheduled_posts.iter().filter(|post| post.offline).count();
+        if outbox > 0 {
+            content = content.push(
+                row()
+                    .push(widget::text(fl!("outbox-pending", count = outbox)).size(12).width(Length::Fill))
+                    .push(
+                        widget::button::standard(fl!("retry-now"))
+                            .on_press(Message::RetryOutbox)
+                    )
+                    .push(
+                        widget::button::standard(fl!("show"))
+                            .on_press(Message::SwitchView(ViewMode::Scheduled))
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        // Redrawn by the spinner ticks while posting
+        if let Some((sent, total)) = self.upload_progress.bytes().filter(|(sent, total)| posting && sent < total) {
+            content = content.push(
+                column()
+                    .push(widget::progress_bar(0.0..=1.0, sent as f32 / total as f32))
+                    .push(
+                        widget::text(fl!("uploading-images", sent = format_file_size(sent as usize), total = format_file_size(total as usize)))
+                            .size(12)
+                    )
+                    .spacing(space_xxs)
+            );
+        }
+        if let Some(attempts) = self.pow_progress.attempts().filter(|_| posting) {
+            // A hand-edited config can ask for more bits than the field allows
+            let difficulty = self.config.nostr.pow_difficulty.unwrap_or_default();
+            let expected = 1u64.checked_shl(difficulty.into()).unwrap_or(u64::MAX);
+            content = content.push(
+                widget::text(fl!("mining-pow", attempts = attempts, expected = expected))
+                    .size(12)
+            );
         }
 
+        // Drafts
+        let draft_text = self.post_editor_content.text();
+        let can_save_draft = !draft_text.trim().is_empty() || !self.attached_images.is_empty();
         content = content.push(
             row()
-                .push(char_limit_text)
-                .push(widget::horizontal_space())
-                .push(post_button)
-                .align_y(Alignment::Center)
+                .push(
+                    widget::button::standard(fl!("save-draft"))
+                        .on_press_maybe(can_save_draft.then_some(Message::SaveDraft))
+                )
+                .push(
+                    widget::button::standard(if self.show_drafts {
+                        fl!("hide-drafts", count = self.config.drafts.len())
+                    } else {
+                        fl!("show-drafts", count = self.config.drafts.len())
+                    })
+                    .on_press(Message::ToggleDrafts)
+                )
                 .spacing(space_s)
         );
+        if self.show_drafts {
+            for (i, draft) in self.config.drafts.iter().enumerate() {
+                let preview: String = draft.text.chars().take(60).collect();
+                let saved = draft.saved_at.with_timezone(&Local).format(SCHEDULE_TIME_FORMAT);
+                content = content.push(
+                    row()
+                        .push(widget::text(format!("{} · {}", saved, preview)).size(12).width(Length::Fill))
+                        .push(
+                            widget::button::standard(fl!("load"))
+                                .on_press_maybe((!posting).then_some(Message::LoadDraft(i)))
+                        )
+                        .push(
+                            widget::button::destructive("✕")
+                                .on_press(Message::DeleteDraft(i))
+                        )
+                        .align_y(Alignment::Center)
+                        .spacing(space_s)
+                );
+            }
+        }
+
+        for (platform, link) in &self.post_links {
+            content = content.push(
+                widget::button::link(fl!("view-post", platform = platform.name()))
+                    .on_press(Message::OpenPostLink(link.clone()))
+            );
+        }
+
+        for warning in &self.post_warnings {
+            content = content.push(widget::text(format!("⚠️ {}", warning)).size(11));
+        }
+
+        for (relay, reason) in &self.rejected_relays {
+            content = content.push(widget::text(fl!("relay-rejected", relay = relay.as_str(), reason = reason.as_str())).size(11));
+        }
+
+        content.into()
+    }
+
+    fn settings_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xs, space_s, space_m, .. } = theme::active().cosmic().spacing;
+        let space_xs = (space_xs as f32 * 0.75) as u16;
+        let space_s = (space_s as f32 * 0.75) as u16;
+        let space_m = (space_m as f32 * 0.75) as u16;
+
+        let mut bluesky_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_bluesky.enabled)
+                    .on_toggle(Message::BlueskyEnabledChanged)
+            )
+            .push(
+                text_input(fl!("bluesky-handle"), &self.temp_bluesky.handle)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Bluesky)))
+                    .on_input(Message::BlueskyHandleChanged)
+                    .width(Length::Fill)
+            );
+
+        if self.temp_bluesky.enabled && !self.temp_bluesky.handle.is_empty() && !Self::validate_handle(&self.temp_bluesky.handle) {
+            bluesky_section = bluesky_section.push(widget::text(fl!("invalid-handle")).size(12));
+        }
+
+        bluesky_section = bluesky_section
+            .push(
+                text_input(fl!("password"), self.temp_bluesky.decrypted_password.as_str())
+                    .on_input(Message::BlueskyPasswordChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("bluesky-service-url"), &self.temp_bluesky.service_url)
+                    .on_input(Message::BlueskyServiceUrlChanged)
+                    .width(Length::Fill)
+            );
+
+        if !Self::validate_url(&self.temp_bluesky.service_url) {
+            bluesky_section = bluesky_section.push(widget::text(fl!("service-url-needs-https")).size(12));
+        }
+
+        bluesky_section = bluesky_section.push(widget::text(fl!("bluesky-over-limit", limit = BLUESKY_CHARACTER_LIMIT)).size(12));
+        for behavior in BlueskyTruncateBehavior::ALL {
+            bluesky_section = bluesky_section.push(
+                widget::radio(behavior.label(), behavior, Some(self.temp_bluesky.truncate_behavior), Message::BlueskyTruncateBehaviorChanged)
+            );
+        }
+
+        if let Some(account) = self.account_row(Platform::Bluesky) {
+            bluesky_section = bluesky_section.push(account);
+        }
+
+        bluesky_section = bluesky_section.spacing(space_xs);
+
+        let mut mastodon_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_mastodon.enabled)
+                    .on_toggle(Message::MastodonEnabledChanged)
+            )
+            .push(
+                text_input(fl!("instance-url", example = "https://mastodon.social"), &self.temp_mastodon.instance_url)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Mastodon)))
+                    .on_input(Message::MastodonInstanceChanged)
+                    .width(Length::Fill)
+            );
+
+        if self.temp_mastodon.enabled && !self.temp_mastodon.instance_url.is_empty() && !Self::validate_url(&self.temp_mastodon.instance_url) {
+            mastodon_section = mastodon_section.push(widget::text(fl!("invalid-url")).size(12));
+        } else if self.temp_mastodon.instance_url == self.config.mastodon.instance_url {
+            // What the saved instance said when it was checked
+            if self.checking_mastodon_instance {
+                mastodon_section = mastodon_section.push(widget::text(fl!("checking-instance")).size(12));
+            }
+            match &self.mastodon_instance {
+                Some(Ok(instance)) => {
+                    mastodon_section = mastodon_section.push(
+                        widget::text(fl!("mastodon-instance-info", title = instance.title.as_str(), version = instance.version.as_str())).size(12)
+                    );
+                }
+                Some(Err(e)) => {
+                    mastodon_section = mastodon_section.push(widget::text(fl!("mastodon-instance-error", error = e.as_str())).size(12));
+                    if url::Url::parse(&self.config.mastodon.instance_url).is_ok_and(|url| url.path().trim_matches('/') != "") {
+                        mastodon_section = mastodon_section.push(widget::text(fl!("mastodon-instance-path-hint")).size(12));
+                    }
+                }
+                None => {}
+            }
+            if self.config.mastodon.enabled && Self::validate_url(&self.config.mastodon.instance_url) {
+                let checked = match self.config.mastodon.limits_fetched_at {
+                    Some(time) => fl!("mastodon-limits-checked", time = time.with_timezone(&Local).format(SCHEDULE_TIME_FORMAT).to_string()),
+                    None => fl!("mastodon-limits-unchecked"),
+                };
+                mastodon_section = mastodon_section.push(
+                    row()
+                        .push(widget::text(checked).size(12).width(Length::Fill))
+                        .push(
+                            widget::button::standard(fl!("refresh-instance-limits"))
+                                .on_press_maybe((!self.checking_mastodon_instance).then_some(Message::RefreshMastodonInstance))
+                        )
+                        .align_y(Alignment::Center)
+                        .spacing(space_s)
+                );
+            }
+        }
+
+        mastodon_section = mastodon_section
+            .push(
+                text_input(fl!("access-token"), self.temp_mastodon.decrypted_access_token.as_str())
+                    .on_input(Message::MastodonTokenChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(widget::text(fl!("mastodon-compatibility")).size(12))
+            .spacing(space_xs);
+        for compatibility in MastodonCompatibility::ALL {
+            let label = if compatibility == MastodonCompatibility::Detect && self.temp_mastodon.instance_url == self.config.mastodon.instance_url {
+                let detected = if self.config.mastodon.pleroma_detected {
+                    MastodonCompatibility::Pleroma.label()
+                } else {
+                    MastodonCompatibility::Mastodon.label()
+                };
+                fl!("mastodon-compatibility-detected", server = detected)
+            } else {
+                compatibility.label()
+            };
+            mastodon_section = mastodon_section.push(
+                widget::radio(label, compatibility, Some(self.temp_mastodon.compatibility), Message::MastodonCompatibilityChanged)
+            );
+        }
+        mastodon_section = mastodon_section.push(widget::text(fl!("mastodon-default-visibility")).size(12));
+        for visibility in MastodonVisibility::ALL {
+            mastodon_section = mastodon_section.push(
+                widget::radio(visibility.label(), visibility, Some(self.temp_mastodon.default_visibility), Message::MastodonDefaultVisibilityChanged)
+            );
+        }
+        if let Some(account) = self.account_row(Platform::Mastodon) {
+            mastodon_section = mastodon_section.push(account);
+        }
+
+        let mut microblog_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_microblog.enabled)
+                    .on_toggle(Message::MicroBlogEnabledChanged)
+            )
+            .push(
+                text_input(fl!("access-token"), self.temp_microblog.decrypted_access_token.as_str())
+                    .id(settings_field_id(SettingsSection::Platform(Platform::MicroBlog)))
+                    .on_input(Message::MicroBlogTokenChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .spacing(space_xs);
+        if let Some(account) = self.account_row(Platform::MicroBlog) {
+            microblog_section = microblog_section.push(account);
+        }
+
+        let threads_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_threads.enabled)
+                    .on_toggle(Message::ThreadsEnabledChanged)
+            )
+            .push(
+                text_input(fl!("threads-user-id"), &self.temp_threads.user_id)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Threads)))
+                    .on_input(Message::ThreadsUserIdChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("access-token"), self.temp_threads.decrypted_access_token.as_str())
+                    .on_input(Message::ThreadsTokenChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                checkbox(fl!("upload-images-to-nostr"), self.temp_threads.upload_images)
+                    .on_toggle(Message::ThreadsUploadImagesChanged)
+            )
+            .push(widget::text(fl!("threads-images-note")).size(12))
+            .spacing(space_xs);
+
+        let mut pixelfed_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_pixelfed.enabled)
+                    .on_toggle(Message::PixelfedEnabledChanged)
+            )
+            .push(
+                text_input(fl!("instance-url", example = "https://pixelfed.social"), &self.temp_pixelfed.instance_url)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Pixelfed)))
+                    .on_input(Message::PixelfedInstanceChanged)
+                    .width(Length::Fill)
+            );
+
+        if self.temp_pixelfed.enabled && !self.temp_pixelfed.instance_url.is_empty() && !Self::validate_url(&self.temp_pixelfed.instance_url) {
+            pixelfed_section = pixelfed_section.push(widget::text(fl!("invalid-url")).size(12));
+        }
+
+        pixelfed_section = pixelfed_section
+            .push(
+                text_input(fl!("access-token"), self.temp_pixelfed.decrypted_access_token.as_str())
+                    .on_input(Message::PixelfedTokenChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .spacing(space_xs);
+
+        // Webhook URLs are secret, so the list only shows their IDs
+        let mut discord_webhooks = column().spacing(space_xs);
+        for (i, url) in self.temp_discord.decrypted_webhook_urls.iter().enumerate() {
+            discord_webhooks = discord_webhooks.push(
+                row()
+                    .push(widget::text(social::discord_webhook_label(url)))
+                    .push(widget::horizontal_space())
+                    .push(
+                        widget::button::destructive(fl!("remove"))
+                            .on_press(Message::RemoveDiscordWebhook(i))
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        let add_webhook_row = row()
+            .push(
+                text_input("https://discord.com/api/webhooks/...", &self.new_discord_webhook)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Discord)))
+                    .on_input(Message::NewDiscordWebhookChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                widget::button::standard(fl!("add-webhook"))
+                    .on_press(Message::AddDiscordWebhook)
+            )
+            .spacing(space_s)
+            .align_y(Alignment::Center);
+
+        let mut discord_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_discord.enabled)
+                    .on_toggle(Message::DiscordEnabledChanged)
+            )
+            .push(widget::text(fl!("webhooks")))
+            .push(discord_webhooks)
+            .push(add_webhook_row);
+
+        if !self.new_discord_webhook.is_empty() && !social::is_discord_webhook_url(self.new_discord_webhook.trim()) {
+            discord_section = discord_section.push(widget::text(fl!("invalid-discord-webhook")).size(12));
+        }
+
+        let discord_section = discord_section.spacing(space_xs);
+
+        let telegram_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_telegram.enabled)
+                    .on_toggle(Message::TelegramEnabledChanged)
+            )
+            .push(
+                text_input(fl!("telegram-chat-id"), &self.temp_telegram.chat_id)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Telegram)))
+                    .on_input(Message::TelegramChatIdChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("telegram-bot-token"), self.temp_telegram.decrypted_bot_token.as_str())
+                    .on_input(Message::TelegramTokenChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .spacing(space_xs);
+
+        let mut wordpress_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_wordpress.enabled)
+                    .on_toggle(Message::WordPressEnabledChanged)
+            )
+            .push(
+                text_input(fl!("wordpress-site-url"), &self.temp_wordpress.site_url)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::WordPress)))
+                    .on_input(Message::WordPressSiteChanged)
+                    .width(Length::Fill)
+            );
+
+        if self.temp_wordpress.enabled && !self.temp_wordpress.site_url.is_empty() && !Self::validate_url(&self.temp_wordpress.site_url) {
+            wordpress_section = wordpress_section.push(widget::text(fl!("invalid-url")).size(12));
+        }
+
+        wordpress_section = wordpress_section
+            .push(
+                text_input(fl!("username"), &self.temp_wordpress.username)
+                    .on_input(Message::WordPressUsernameChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("wordpress-app-password"), self.temp_wordpress.decrypted_app_password.as_str())
+                    .on_input(Message::WordPressPasswordChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                checkbox(fl!("wordpress-save-drafts"), self.temp_wordpress.default_status == WordPressStatus::Draft)
+                    .on_toggle(Message::WordPressDraftToggled)
+            )
+            .spacing(space_xs);
+
+        let mut lemmy_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_lemmy.enabled)
+                    .on_toggle(Message::LemmyEnabledChanged)
+            )
+            .push(
+                text_input(fl!("instance-url", example = "https://lemmy.world"), &self.temp_lemmy.instance_url)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Lemmy)))
+                    .on_input(Message::LemmyInstanceChanged)
+                    .width(Length::Fill)
+            );
 
-        if let Some(status) = status_text {
-            content = content.push(status);
+        if self.temp_lemmy.enabled && !self.temp_lemmy.instance_url.is_empty() && !Self::validate_url(&self.temp_lemmy.instance_url) {
+            lemmy_section = lemmy_section.push(widget::text(fl!("invalid-url")).size(12));
         }
 
-        content.into()
-    }
+        lemmy_section = lemmy_section
+            .push(
+                text_input(fl!("lemmy-username"), &self.temp_lemmy.username)
+                    .on_input(Message::LemmyUsernameChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("lemmy-password"), self.temp_lemmy.decrypted_password.as_str())
+                    .on_input(Message::LemmyPasswordChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("lemmy-community"), &self.temp_lemmy.community)
+                    .on_input(Message::LemmyCommunityChanged)
+                    .width(Length::Fill)
+            )
+            .spacing(space_xs);
 
-    fn settings_view(&self) -> Element<Message> {
-        let cosmic_theme::Spacing { space_xs, space_s, space_m, .. } = theme::active().cosmic().spacing;
-        let space_xs = (space_xs as f32 * 0.75) as u16;
-        let space_s = (space_s as f32 * 0.75) as u16;
-        let space_m = (space_m as f32 * 0.75) as u16;
+        let farcaster_section = column()
+            .push(
+                checkbox(fl!("enable-account"), self.temp_farcaster.enabled)
+                    .on_toggle(Message::FarcasterEnabledChanged)
+            )
+            .push(
+                text_input(fl!("farcaster-signer-uuid"), &self.temp_farcaster.signer_uuid)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Farcaster)))
+                    .on_input(Message::FarcasterSignerChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("farcaster-api-key"), self.temp_farcaster.decrypted_api_key.as_str())
+                    .on_input(Message::FarcasterApiKeyChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                checkbox(fl!("upload-images-to-nostr"), self.temp_farcaster.upload_images)
+                    .on_toggle(Message::FarcasterUploadImagesChanged)
+            )
+            .push(widget::text(fl!("farcaster-images-note")).size(12))
+            .spacing(space_xs);
 
-        let mut bluesky_section = column()
-            .push(widget::text::title4("Bluesky Settings"))
+        let mut matrix_section = column()
             .push(
-                checkbox("Enable", self.temp_bluesky.enabled)
-                    .on_toggle(Message::BlueskyEnabledChanged)
+                checkbox(fl!("enable-account"), self.temp_matrix.enabled)
+                    .on_toggle(Message::MatrixEnabledChanged)
             )
             .push(
-                text_input("Handle (e.g., user.bsky.social)", &self.temp_bluesky.handle)
-                    .on_input(Message::BlueskyHandleChanged)
+                text_input(fl!("matrix-homeserver"), &self.temp_matrix.homeserver_url)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Matrix)))
+                    .on_input(Message::MatrixHomeserverChanged)
                     .width(Length::Fill)
             );
 
-        if self.temp_bluesky.enabled && !self.temp_bluesky.handle.is_empty() && !Self::validate_handle(&self.temp_bluesky.handle) {
-            bluesky_section = bluesky_section.push(widget::text("Invalid handle format").size(12));
+        if self.temp_matrix.enabled && !self.temp_matrix.homeserver_url.is_empty() && !Self::validate_url(&self.temp_matrix.homeserver_url) {
+            matrix_section = matrix_section.push(widget::text(fl!("invalid-url")).size(12));
         }
 
-        bluesky_section = bluesky_section
+        let matrix_section = matrix_section
             .push(
-                text_input("App Password", &self.temp_bluesky.decrypted_password)
-                    .on_input(Message::BlueskyPasswordChanged)
+                text_input(fl!("matrix-room-id"), &self.temp_matrix.room_id)
+                    .on_input(Message::MatrixRoomChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("access-token"), self.temp_matrix.decrypted_access_token.as_str())
+                    .on_input(Message::MatrixTokenChanged)
                     .password()
                     .width(Length::Fill)
             )
             .spacing(space_xs);
 
-        let mut mastodon_section = column()
-            .push(widget::text::title4("Mastodon Settings"))
+        let mut devto_section = column()
             .push(
-                checkbox("Enable", self.temp_mastodon.enabled)
-                    .on_toggle(Message::MastodonEnabledChanged)
+                checkbox(fl!("enable-account"), self.temp_devto.enabled)
+                    .on_toggle(Message::DevToEnabledChanged)
             )
             .push(
-                text_input("Instance URL (e.g., https://mastodon.social)", &self.temp_mastodon.instance_url)
-                    .on_input(Message::MastodonInstanceChanged)
+                text_input(fl!("devto-site-url"), &self.temp_devto.site_url)
+                    .id(settings_field_id(SettingsSection::Platform(Platform::DevTo)))
+                    .on_input(Message::DevToSiteChanged)
                     .width(Length::Fill)
             );
 
-        if self.temp_mastodon.enabled && !self.temp_mastodon.instance_url.is_empty() && !Self::validate_url(&self.temp_mastodon.instance_url) {
-            mastodon_section = mastodon_section.push(widget::text("Invalid URL format").size(12));
+        if self.temp_devto.enabled && !self.temp_devto.site_url.is_empty() && !Self::validate_url(&self.temp_devto.site_url) {
+            devto_section = devto_section.push(widget::text(fl!("invalid-url")).size(12));
         }
 
-        mastodon_section = mastodon_section
+        let devto_section = devto_section
             .push(
-                text_input("Access Token", &self.temp_mastodon.decrypted_access_token)
-                    .on_input(Message::MastodonTokenChanged)
+                text_input(fl!("devto-api-key"), self.temp_devto.decrypted_api_key.as_str())
+                    .on_input(Message::DevToApiKeyChanged)
                     .password()
                     .width(Length::Fill)
             )
+            .push(
+                checkbox(fl!("devto-draft"), self.temp_devto.draft)
+                    .on_toggle(Message::DevToDraftToggled)
+            )
+            .push(widget::text(fl!("devto-images-note")).size(12))
             .spacing(space_xs);
 
-        let microblog_section = column()
-            .push(widget::text::title4("Micro.Blog Settings"))
+        let mut shortener_section = column()
             .push(
-                checkbox("Enable", self.temp_microblog.enabled)
-                    .on_toggle(Message::MicroBlogEnabledChanged)
+                checkbox(fl!("shorten-links-enabled"), self.temp_url_shortener.enabled)
+                    .on_toggle(Message::UrlShortenerEnabledChanged)
             )
             .push(
-                text_input("Access Token", &self.temp_microblog.decrypted_access_token)
-                    .on_input(Message::MicroBlogTokenChanged)
+                text_input(fl!("shortener-address"), &self.temp_url_shortener.endpoint)
+                    .id(settings_field_id(SettingsSection::LinkShortening))
+                    .on_input(Message::UrlShortenerEndpointChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("shortener-api-key"), self.temp_url_shortener.decrypted_api_key.as_str())
+                    .on_input(Message::UrlShortenerApiKeyChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .spacing(space_xs);
+        if self.temp_url_shortener.enabled && !self.temp_url_shortener.endpoint.contains("{url}") {
+            shortener_section = shortener_section.push(widget::text(fl!("shortener-needs-placeholder")).size(12));
+        }
+
+        let mut captioner_section = column()
+            .push(
+                checkbox(fl!("captioner-enabled"), self.temp_captioner.enabled)
+                    .on_toggle(Message::CaptionerEnabledChanged)
+            )
+            .push(
+                text_input(fl!("captioner-address"), &self.temp_captioner.endpoint)
+                    .id(settings_field_id(SettingsSection::AltTextSuggestions))
+                    .on_input(Message::CaptionerEndpointChanged)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input(fl!("captioner-token"), self.temp_captioner.decrypted_token.as_str())
+                    .on_input(Message::CaptionerTokenChanged)
                     .password()
                     .width(Length::Fill)
             )
             .spacing(space_xs);
+        if self.temp_captioner.enabled && !social::is_private_endpoint(self.temp_captioner.endpoint.trim()) {
+            captioner_section = captioner_section.push(widget::text(fl!("captioner-needs-https")).size(12));
+        }
+
+        // One row per platform, as each renders whitespace its own way
+        let mut cleanup_section = column()
+            .push(widget::text(fl!("text-cleanup-description")).size(12))
+            .spacing(space_xs);
+        for platform in Platform::ALL {
+            let cleanup = self.temp_text_cleanup.get(&platform).copied().unwrap_or_default();
+            cleanup_section = cleanup_section.push(
+                row()
+                    .push(widget::text(platform.name()).width(Length::Fixed(120.0)))
+                    .push(
+                        checkbox(fl!("trim-trailing-whitespace"), cleanup.trim_trailing_whitespace)
+                            .on_toggle(move |trim_trailing_whitespace| Message::TextCleanupChanged(platform, TextCleanup { trim_trailing_whitespace, ..cleanup }))
+                    )
+                    .push(
+                        checkbox(fl!("collapse-blank-lines"), cleanup.collapse_blank_lines)
+                            .on_toggle(move |collapse_blank_lines| Message::TextCleanupChanged(platform, TextCleanup { collapse_blank_lines, ..cleanup }))
+                    )
+                    .spacing(space_s)
+                    .align_y(Alignment::Center)
+            );
+        }
+
+        let mut signatures_section = column()
+            .push(widget::text(fl!("signatures-description")).size(12))
+            .spacing(space_xs);
+        for (i, platform) in Platform::ALL.into_iter().enumerate() {
+            let mut input = text_input(fl!("signature-placeholder"), self.temp_signatures.get(&platform).map(String::as_str).unwrap_or_default())
+                .on_input(move |signature| Message::SignatureChanged(platform, signature))
+                .width(Length::Fill);
+            if i == 0 {
+                input = input.id(settings_field_id(SettingsSection::Signatures));
+            }
+            signatures_section = signatures_section.push(
+                row()
+                    .push(widget::text(platform.name()).width(Length::Fixed(120.0)))
+                    .push(input)
+                    .spacing(space_s)
+                    .align_y(Alignment::Center)
+            );
+        }
 
         // Collapsible Nostr relays
-        let relays_toggle = widget::button::standard(if self.show_relays { "Hide Relays" } else { "Show Relays" })
+        let relays_toggle = widget::button::standard(if self.show_relays { fl!("hide-relays") } else { fl!("show-relays") })
             .on_press(Message::ToggleRelays);
 
         let mut nostr_relays = column().spacing(space_xs);
         if self.show_relays {
+            let relay_count = self.temp_nostr.relays.len();
             for (i, relay) in self.temp_nostr.relays.iter().enumerate() {
+                let status = match self.relay_checks.get(relay) {
+                    None => widget::text(""),
+                    Some(None) => widget::text(fl!("checking")).size(12),
+                    Some(Some(Ok(latency))) => widget::text(fl!("relay-latency", ms = latency.as_millis() as u64))
+                        .size(12)
+                        .class(theme::Text::Color(cosmic::iced::Color::from_rgb(0.2, 0.65, 0.3))),
+                    Some(Some(Err(e))) => widget::text(format!("● {}", e))
+                        .size(12)
+                        .class(theme::Text::Color(cosmic::iced::Color::from_rgb(0.85, 0.2, 0.2))),
+                };
                 nostr_relays = nostr_relays.push(
                     row()
                         .push(widget::text(relay))
+                        .push(status)
                         .push(widget::horizontal_space())
                         .push(
-                            widget::button::destructive("Remove")
+                            widget::button::text("↑")
+                                .on_press_maybe((i > 0).then(|| Message::MoveRelay { from: i, to: i - 1 }))
+                        )
+                        .push(
+                            widget::button::text("↓")
+                                .on_press_maybe((i + 1 < relay_count).then(|| Message::MoveRelay { from: i, to: i + 1 }))
+                        )
+                        .push(
+                            widget::button::destructive(fl!("remove"))
                                 .on_press(Message::RemoveRelay(i))
                         )
                         .align_y(Alignment::Center)
@@ -690,49 +4464,273 @@ impl AppModel {
                     .width(Length::Fill)
             )
             .push(
-                widget::button::standard("Add Relay")
-                    .on_press(Message::AddRelay)
+                widget::button::standard(fl!("add-relay"))
+                    .on_press_maybe(self.new_relay_url().is_ok().then_some(Message::AddRelay))
             )
             .spacing(space_s)
             .align_y(Alignment::Center);
+        let add_relay_error = match self.new_relay_url() {
+            Err(e) if !self.new_relay.trim().is_empty() => Some(widget::text(e).size(12)),
+            _ => None,
+        };
 
         let mut nostr_section = column()
-            .push(widget::text::title4("Nostr Settings"))
             .push(
-                checkbox("Enable", self.temp_nostr.enabled)
+                checkbox(fl!("enable-account"), self.temp_nostr.enabled)
                     .on_toggle(Message::NostrEnabledChanged)
             )
             .push(
-                text_input("Private Key (64 hex characters)", &self.temp_nostr.decrypted_private_key)
+                text_input(fl!("private-key"), self.temp_nostr.decrypted_private_key.as_str())
+                    .id(settings_field_id(SettingsSection::Platform(Platform::Nostr)))
                     .on_input(Message::NostrPrivateKeyChanged)
                     .password()
                     .width(Length::Fill)
             );
 
-        if self.temp_nostr.enabled && !self.temp_nostr.decrypted_private_key.is_empty() && !Self::validate_private_key(&self.temp_nostr.decrypted_private_key) {
-            nostr_section = nostr_section.push(widget::text("Invalid private key format (must be 64 hex characters)").size(12));
+        let private_key = &self.temp_nostr.decrypted_private_key;
+        if Self::validate_private_key(private_key) {
+            // Show the derived public key so the user can confirm they pasted the right one
+            if let Some(npub) = social::nostr_npub(private_key) {
+                nostr_section = nostr_section.push(widget::text(fl!("public-key", npub = npub)).size(12));
+            }
+        } else if self.temp_nostr.enabled && !private_key.is_empty() {
+            if let Err(e) = social::parse_nostr_secret_key(private_key) {
+                nostr_section = nostr_section.push(widget::text(e).size(12));
+            }
+        }
+
+        nostr_section = nostr_section
+            .push(
+                text_input(fl!("nostr-media-host"), &self.temp_nostr.media_host)
+                    .on_input(Message::NostrMediaHostChanged)
+                    .width(Length::Fill)
+            );
+
+        if !self.temp_nostr.media_host.is_empty() && !Self::validate_url(&self.temp_nostr.media_host) {
+            nostr_section = nostr_section.push(widget::text(fl!("invalid-url")).size(12));
+        }
+
+        // A Blossom server takes over image uploads from the NIP-96 server
+        nostr_section = nostr_section.push(
+            text_input(fl!("nostr-blossom-server"), &self.temp_nostr.blossom_server_url)
+                .on_input(Message::NostrBlossomServerChanged)
+                .width(Length::Fill)
+        );
+        if !self.temp_nostr.blossom_server_url.trim().is_empty() {
+            if !Self::validate_url(self.temp_nostr.blossom_server_url.trim()) {
+                nostr_section = nostr_section.push(widget::text(fl!("invalid-url")).size(12));
+            }
+            nostr_section = nostr_section.push(
+                checkbox(fl!("nostr-blossom-auth"), self.temp_nostr.blossom_auth)
+                    .on_toggle(Message::NostrBlossomAuthToggled)
+            );
+        }
+
+        let pow_difficulty = self.temp_nostr.pow_difficulty.map(|difficulty| difficulty.to_string()).unwrap_or_default();
+        nostr_section = nostr_section.push(
+            text_input(fl!("nostr-pow-difficulty"), &pow_difficulty)
+                .on_input(Message::NostrPowDifficultyChanged)
+                .width(Length::Fill)
+        );
+        if self.temp_nostr.pow_difficulty.is_some_and(|difficulty| difficulty > SLOW_POW_DIFFICULTY) {
+            nostr_section = nostr_section.push(widget::text(fl!("nostr-pow-slow")).size(12));
         }
 
         nostr_section = nostr_section
-            .push(widget::text("Relays"))
-            .push(relays_toggle)
+            .push(widget::text(fl!("relays")))
+            .push(
+                row()
+                    .push(relays_toggle)
+                    .push(
+                        widget::button::standard(fl!("check-relays"))
+                            .on_press_maybe((!self.temp_nostr.relays.is_empty()).then_some(Message::CheckRelays))
+                    )
+                    .push(
+                        widget::button::standard(fl!("publish-relay-list"))
+                            .on_press_maybe(self.can_publish_relay_list().then_some(Message::PublishRelayList))
+                    )
+                    .push(
+                        widget::button::standard(fl!("restore-default-relays"))
+                            .on_press_maybe((self.temp_nostr.relays != NostrConfig::default().relays).then_some(Message::RestoreDefaultRelays))
+                    )
+                    .spacing(space_s)
+            )
             .push(nostr_relays)
             .push(add_relay_row)
             .spacing(space_xs);
+        if let Some(error) = add_relay_error {
+            nostr_section = nostr_section.push(error);
+        }
+        match &self.relay_list_status {
+            Some(Ok(message)) if message.is_empty() => {
+                nostr_section = nostr_section.push(widget::text(fl!("publishing-relay-list")).size(12));
+            }
+            Some(Ok(message)) => nostr_section = nostr_section.push(widget::text(message).size(12)),
+            Some(Err(e)) => {
+                nostr_section = nostr_section.push(widget::text(fl!("relay-list-error", error = e.as_str())).size(12));
+            }
+            None => {}
+        }
+
+        let mut security_section = column()
+            .push(widget::text(fl!("credential-storage", backend = self.config.crypto_backend.name())).size(12))
+            .spacing(space_xs);
+
+        let mut backend_buttons = row().spacing(space_s);
+        for backend in [CryptoBackend::MachineKey, CryptoBackend::Keyring] {
+            if self.config.crypto_backend != backend {
+                backend_buttons = backend_buttons.push(
+                    widget::button::standard(fl!("use-backend", backend = backend.name()))
+                        .on_press(Message::UseCryptoBackend(backend))
+                );
+            }
+        }
+        security_section = security_section
+            .push(backend_buttons)
+            .push(
+                checkbox(fl!("strip-image-metadata"), !self.config.keep_image_metadata)
+                    .on_toggle(Message::StripImageMetadataToggled)
+            );
+
+        if !self.config.has_master_password() {
+            security_section = security_section
+                .push(widget::text(fl!("master-password-hint")).size(12))
+                .push(
+                    row()
+                        .push(
+                            text_input(fl!("master-password"), &self.new_master_password)
+                                .id(settings_field_id(SettingsSection::Security))
+                                .on_input(Message::NewMasterPasswordChanged)
+                                .password()
+                                .width(Length::Fill)
+                        )
+                        .push(
+                            widget::button::standard(fl!("set-master-password"))
+                                .on_press_maybe((!self.new_master_password.is_empty()).then_some(Message::SetMasterPassword))
+                        )
+                        .spacing(space_s)
+                        .align_y(Alignment::Center)
+                );
+        } else {
+            let can_change = !self.current_master_password.is_empty() && !self.new_master_password.is_empty();
+            security_section = security_section
+                .push(widget::text(fl!("change-master-password-hint")).size(12))
+                .push(
+                    row()
+                        .push(
+                            text_input(fl!("current-password"), &self.current_master_password)
+                                .id(settings_field_id(SettingsSection::Security))
+                                .on_input(Message::CurrentMasterPasswordChanged)
+                                .password()
+                                .width(Length::Fill)
+                        )
+                        .push(
+                            text_input(fl!("new-password"), &self.new_master_password)
+                                .on_input(Message::NewMasterPasswordChanged)
+                                .password()
+                                .width(Length::Fill)
+                        )
+                        .push(
+                            widget::button::standard(fl!("change-master-password"))
+                                .on_press_maybe(can_change.then_some(Message::ChangeMasterPassword))
+                        )
+                        .spacing(space_s)
+                        .align_y(Alignment::Center)
+                );
+        }
+
+        let transfer_section = column()
+            .push(widget::text(fl!("transfer-settings-hint")).size(12))
+            .push(
+                text_input(fl!("passphrase"), &self.transfer_passphrase)
+                    .id(settings_field_id(SettingsSection::Transfer))
+                    .on_input(Message::TransferPassphraseChanged)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                row()
+                    .push(
+                        widget::button::standard(fl!("export-settings"))
+                            .on_press_maybe((!self.transfer_passphrase.is_empty()).then_some(Message::ExportSettings))
+                    )
+                    .push(
+                        widget::button::standard(fl!("import-settings"))
+                            .on_press_maybe((!self.transfer_passphrase.is_empty()).then_some(Message::ImportSettings))
+                    )
+                    .spacing(space_s)
+            )
+            .spacing(space_xs);
 
-        let save_button = widget::button::suggested("Save Settings")
+        let save_button = widget::button::suggested(fl!("save-settings"))
             .on_press(Message::SaveSettings);
 
-        let content = column()
-            .push(bluesky_section)
-            .push(divider::horizontal::default())
-            .push(mastodon_section)
-            .push(divider::horizontal::default())
-            .push(microblog_section)
-            .push(divider::horizontal::default())
-            .push(nostr_section)
-            .push(save_button)
+        // In the same order as SettingsSection::ALL
+        let sections = SettingsSection::ALL.into_iter().zip([
+            bluesky_section,
+            mastodon_section,
+            microblog_section,
+            threads_section,
+            pixelfed_section,
+            discord_section,
+            telegram_section,
+            wordpress_section,
+            lemmy_section,
+            farcaster_section,
+            matrix_section,
+            devto_section,
+            nostr_section,
+            shortener_section,
+            captioner_section,
+            cleanup_section,
+            signatures_section,
+            security_section,
+            transfer_section,
+        ]);
+
+        // Sections whose heading matches the search, each collapsible to its heading
+        let filter = self.settings_filter.trim().to_lowercase();
+        let mut content = column()
+            .push(
+                text_input(fl!("search-settings"), &self.settings_filter)
+                    .id(SETTINGS_SEARCH_ID.clone())
+                    .on_input(Message::SettingsFilterChanged)
+                    .on_submit(|_| Message::SettingsSearchSubmitted)
+                    .width(Length::Fill)
+            )
             .spacing(space_m);
+        let mut shown = 0;
+        for (section, body) in sections {
+            let title = section.title();
+            if !title.to_lowercase().contains(&filter) {
+                continue;
+            }
+            if shown > 0 {
+                content = content.push(divider::horizontal::default());
+            }
+            shown += 1;
+            let expanded = !self.config.collapsed_settings.contains(&section);
+            let header = row()
+                .push(
+                    widget::button::text(if expanded { "▾" } else { "▸" })
+                        .on_press(Message::ToggleSettingsSection(section))
+                )
+                .push(widget::text::title4(title))
+                .spacing(space_xs)
+                .align_y(Alignment::Center);
+            let mut section_column = column().push(header).spacing(space_xs);
+            if self.config.decrypt_failed(section) {
+                section_column = section_column.push(widget::text(fl!("credentials-need-reentry")).size(12));
+            }
+            if expanded {
+                section_column = section_column.push(body);
+            }
+            content = content.push(section_column);
+        }
+        if shown == 0 {
+            content = content.push(widget::text(fl!("no-matching-settings")).size(12));
+        }
+        let content = content.push(save_button);
 
         // Add extra right padding inside the scrollable content to prevent scrollbar overlap
         scrollable(
@@ -747,12 +4745,177 @@ impl AppModel {
 
 
 
+    fn scheduled_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xs, space_s, .. } = theme::active().cosmic().spacing;
+
+        let mut posts: Vec<&ScheduledPost> = self.config.scheduled_posts.iter().collect();
+        posts.sort_by_key(|post| post.scheduled_at);
+
+        let mut content = column().spacing(space_s);
+        if posts.is_empty() {
+            content = content.push(widget::text(fl!("no-scheduled-posts")).size(12));
+        }
+
+        for post in posts {
+            let when = if post.offline {
+                fl!("outbox")
+            } else {
+                post.scheduled_at.with_timezone(&Local).format(SCHEDULE_TIME_FORMAT).to_string()
+            };
+            let platforms: Vec<&str> = post.platforms.iter().map(|p| p.name()).collect();
+            let preview: String = post.text.chars().take(80).collect();
+
+            let mut details = column()
+                .push(widget::text(format!("{} → {}", when, platforms.join(", "))).size(12))
+                .push(widget::text(preview).size(12))
+                .spacing(space_xs);
+            for image in &post.images {
+                let filename = std::path::Path::new(&image.path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("image");
+                details = details.push(widget::text(format!("📷 {}", filename)).size(11));
+            }
+            if self.sending_scheduled.contains(&post.id) {
+                details = details.push(widget::text(fl!("sending")).size(11));
+            } else if post.offline {
+                let next = post.scheduled_at.with_timezone(&Local).format("%H:%M").to_string();
+                details = details.push(widget::text(fl!("outbox-waiting", time = next)).size(11));
+            } else if let Some(error) = &post.last_error {
+                details = details.push(widget::text(fl!("scheduled-post-failed", error = error.as_str())).size(11));
+            }
+
+            let mut actions = row().spacing(space_xs);
+            if post.last_error.is_some() {
+                actions = actions.push(
+                    widget::button::standard(fl!("retry"))
+                        .on_press(Message::RetryScheduledPost(post.id.clone()))
+                );
+            }
+            actions = actions.push(
+                widget::button::destructive(fl!("remove"))
+                    .on_press(Message::RemoveScheduledPost(post.id.clone()))
+            );
+
+            content = content.push(
+                row()
+                    .push(details.width(Length::Fill))
+                    .push(actions)
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        scrollable(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn history_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xs, space_s, .. } = theme::active().cosmic().spacing;
+
+        let mut content = column().spacing(space_s);
+        if self.config.history.is_empty() {
+            content = content.push(widget::text(fl!("no-history")).size(12));
+        } else {
+            content = content.push(
+                row()
+                    .push(widget::horizontal_space())
+                    .push(
+                        widget::button::destructive(fl!("clear-history"))
+                            .on_press(Message::ClearHistory)
+                    )
+            );
+        }
+
+        for (index, post) in self.config.history.iter().enumerate() {
+            let when = post.sent_at.with_timezone(&Local).format(SCHEDULE_TIME_FORMAT);
+            let outcomes: Vec<String> = post
+                .results
+                .iter()
+                .map(|(platform, error)| format!("{} {}", if error.is_none() { "✓" } else { "✗" }, platform.name()))
+                .collect();
+            let preview: String = post.text.chars().take(80).collect();
+
+            let mut details = column()
+                .push(widget::text(format!("{} · {}", when, outcomes.join("  "))).size(12))
+                .push(widget::text(preview).size(12))
+                .spacing(space_xs);
+            for name in &post.image_names {
+                details = details.push(widget::text(format!("📷 {}", name)).size(11));
+            }
+            for (platform, error) in &post.results {
+                if let Some(error) = error {
+                    details = details.push(widget::text(format!("⚠️ {}: {}", platform, error)).size(11));
+                }
+            }
+
+            let mut entry = row().push(details.width(Length::Fill));
+            if post.nostr_article.is_some() {
+                entry = entry.push(
+                    widget::button::standard(fl!("edit-article"))
+                        .on_press(Message::EditNostrArticle(index))
+                );
+            }
+            content = content.push(
+                entry
+                    .push(
+                        widget::button::standard(fl!("duplicate"))
+                            .on_press(Message::DuplicateToCompose(index))
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+
+        scrollable(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn unlock_view(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_s, .. } = theme::active().cosmic().spacing;
+
+        let mut content = column()
+            .push(widget::text::title4(fl!("unlock-title")))
+            .push(widget::text(fl!("unlock-hint")).size(12))
+            .push(
+                text_input(fl!("master-password"), &self.master_password_input)
+                    .id(UNLOCK_ID.clone())
+                    .on_input(Message::MasterPasswordChanged)
+                    .on_submit(|_| Message::UnlockSubmit)
+                    .password()
+                    .width(Length::Fill)
+            )
+            .push(
+                widget::button::suggested(fl!("unlock"))
+                    .on_press(Message::UnlockSubmit)
+            )
+            .spacing(space_s);
+
+        if let Some(error) = &self.unlock_error {
+            content = content.push(widget::text(error.as_str()).size(12));
+        }
+
+        content.into()
+    }
+
     fn main_view(&self) -> Element<Message> {
         let cosmic_theme::Spacing { space_s, space_m, .. } = theme::active().cosmic().spacing;
 
+        if matches!(self.view_mode, ViewMode::Unlock) {
+            return container(self.unlock_view())
+                .padding(space_m)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        }
+
         let view_buttons = row()
             .push(
-                button::standard("Compose")
+                button::standard(fl!("compose"))
                     .on_press_maybe(if matches!(self.view_mode, ViewMode::Compose) {
                         None
                     } else {
@@ -760,7 +4923,23 @@ impl AppModel {
                     })
             )
             .push(
-                button::standard("Settings")
+                button::standard(fl!("scheduled", count = self.config.scheduled_posts.len()))
+                    .on_press_maybe(if matches!(self.view_mode, ViewMode::Scheduled) {
+                        None
+                    } else {
+                        Some(Message::SwitchView(ViewMode::Scheduled))
+                    })
+            )
+            .push(
+                button::standard(fl!("history"))
+                    .on_press_maybe(if matches!(self.view_mode, ViewMode::History) {
+                        None
+                    } else {
+                        Some(Message::SwitchView(ViewMode::History))
+                    })
+            )
+            .push(
+                button::standard(fl!("settings"))
                     .on_press_maybe(if matches!(self.view_mode, ViewMode::Settings) {
                         None
                     } else {
@@ -769,18 +4948,36 @@ impl AppModel {
             )
             .spacing(space_s);
 
-        let content = column()
-            .push(view_buttons)
+        let mut content = column().push(view_buttons);
+        if let Some(leave) = &self.pending_leave {
+            let question = match leave {
+                PendingLeave::View(_) => fl!("keep-text-leaving"),
+                PendingLeave::Close => fl!("keep-text-closing"),
+            };
+            content = content.push(
+                row()
+                    .push(widget::text(question).size(12).width(Length::Fill))
+                    .push(widget::button::suggested(fl!("save-as-draft")).on_press(Message::SaveDraftAndLeave))
+                    .push(widget::button::destructive(fl!("discard")).on_press(Message::DiscardAndLeave))
+                    .push(widget::button::standard(fl!("cancel")).on_press(Message::CancelLeave))
+                    .align_y(Alignment::Center)
+                    .spacing(space_s)
+            );
+        }
+        let content = content
             .push(match self.view_mode {
                 ViewMode::Compose => self.compose_view(),
                 ViewMode::Settings => self.settings_view(),
+                ViewMode::Scheduled => self.scheduled_view(),
+                ViewMode::History => self.history_view(),
+                ViewMode::Unlock => self.unlock_view(),
             })
             .spacing(space_s);
 
         container(content)
             .padding(space_m)
             .width(Length::Fill)
-            .height(Length::Fixed(530.0))
+            .height(Length::Fill)
             .into()
     }
 
@@ -789,4 +4986,129 @@ impl AppModel {
 
 }
 
+/// History entry for a post, recording how it went on each platform and the text as posted.
+fn sent_post(id: String, text: String, images: &[ImageAttachment], nostr_article: Option<NostrArticle>, report: &PostReport) -> SentPost {
+    SentPost {
+        id: Some(id),
+        sent_at: Utc::now(),
+        text: report.shortened_text.clone().unwrap_or_else(|| text.clone()),
+        written_text: report.shortened_text.is_some().then_some(text),
+        image_names: images
+            .iter()
+            .map(|image| {
+                std::path::Path::new(&image.path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("image")
+                    .to_string()
+            })
+            .collect(),
+        results: report
+            .results
+            .iter()
+            .map(|(platform, result)| (*platform, result.as_ref().err().map(|e| e.to_string())))
+            .collect(),
+        // Kept so the article can be edited, which only makes sense once it's out
+        nostr_article: nostr_article.filter(|_| report.succeeded().contains(&Platform::Nostr)),
+    }
+}
+
+/// Give keyboard focus to the widget with `id`.
+fn focus(id: widget::Id) -> Task<cosmic::Action<Message>> {
+    widget::text_input::focus(id)
+}
+
+/// Where a Settings section's first field can be focused.
+fn settings_field_id(section: SettingsSection) -> widget::Id {
+    widget::Id::new(format!("settings-{:?}", section))
+}
+
+/// Ctrl+Enter, or Cmd+Enter on macOS.
+fn is_submit_shortcut(key: &Key, modifiers: Modifiers) -> bool {
+    matches!(key, Key::Named(Named::Enter)) && modifiers.command()
+}
+
+/// Ctrl+Shift+V, or Cmd+Shift+V on macOS; plain Ctrl+V still pastes text.
+fn is_paste_image_shortcut(key: &Key, modifiers: Modifiers) -> bool {
+    matches!(key, Key::Character(c) if c.eq_ignore_ascii_case("v")) && modifiers.command() && modifiers.shift()
+}
+
+/// Check that Bluesky will take a video, reading its length off the UI thread.
+async fn check_video(path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || social::check_bluesky_video(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Decode an image and scale it down to a thumbnail off the UI thread.
+async fn load_image_preview(path: String) -> Result<ImagePreview, String> {
+    tokio::task::spawn_blocking(move || {
+        let bytes = std::fs::read(&path).map_err(|e| fl!("read-image-error", error = e.to_string()))?;
+        let decoded = image::load_from_memory(&bytes).map_err(|e| fl!("decode-image-error", error = e.to_string()))?;
+        let thumbnail = decoded.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+        Ok(ImagePreview {
+            thumbnail: widget::image::Handle::from_rgba(thumbnail.width(), thumbnail.height(), thumbnail.into_raw()),
+            width: decoded.width(),
+            height: decoded.height(),
+            bytes: bytes.len(),
+        })
+    })
+    .await
+    .map_err(|e| fl!("load-image-error", error = e.to_string()))?
+}
+
+/// Download and shrink an account's avatar; the account still shows without one if that fails.
+async fn load_account_preview(client: reqwest::Client, info: Result<AccountInfo, social::PostError>) -> Result<AccountPreview, String> {
+    let info = info.map_err(|e| e.to_string())?;
+    let mut avatar = None;
+    if let Some(url) = &info.avatar_url {
+        match social::fetch_avatar(&client, url).await {
+            Ok(bytes) => {
+                let decoded = tokio::task::spawn_blocking(move || {
+                    let image = image::load_from_memory(&bytes).ok()?;
+                    let thumbnail = image.thumbnail(AVATAR_SIZE, AVATAR_SIZE).to_rgba8();
+                    Some(widget::image::Handle::from_rgba(thumbnail.width(), thumbnail.height(), thumbnail.into_raw()))
+                })
+                .await;
+                avatar = decoded.ok().flatten();
+            }
+            Err(e) => tracing::warn!("Failed to load avatar for {}: {}", info.handle, e),
+        }
+    }
+    Ok(AccountPreview { info, avatar })
+}
+
+/// File size in B, KB or MB (powers of 1000, as Bluesky's limit is given).
+fn format_file_size(bytes: usize) -> String {
+    if bytes >= 1_000_000 {
+        format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+    } else if bytes >= 1_000 {
+        format!("{} KB", bytes / 1_000)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Emit `DismissStatus` once `SUCCESS_STATUS_DURATION` has passed.
+fn success_status_timeout() -> impl Stream<Item = Message> {
+    futures_util::stream::once(async {
+        tokio::time::sleep(SUCCESS_STATUS_DURATION).await;
+        Message::DismissStatus
+    })
+}
+
+/// Emit `SpinnerTick` every `SPINNER_INTERVAL`.
+fn spinner_ticks() -> impl Stream<Item = Message> {
+    futures_util::stream::unfold((), |()| async {
+        tokio::time::sleep(SPINNER_INTERVAL).await;
+        Some((Message::SpinnerTick, ()))
+    })
+}
 
+/// Emit `CheckScheduledPosts` every `SCHEDULER_INTERVAL`.
+fn scheduler_ticks() -> impl Stream<Item = Message> {
+    futures_util::stream::unfold((), |()| async {
+        tokio::time::sleep(SCHEDULER_INTERVAL).await;
+        Some((Message::CheckScheduledPosts, ()))
+    })
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Attaching images pasted from the clipboard.
+//!
+//! Pasted images are written to PNG files in the temp directory so they can be
+//! attached like picked files, and removed again once they've been posted.
+
+use image::{ImageFormat, RgbaImage};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// File name prefix that marks a temp file as a pasted image.
+const PASTED_IMAGE_PREFIX: &str = "yall-cosmic-paste-";
+
+/// Save the clipboard's image to a temp file and return its path, or `None`
+/// if the clipboard doesn't hold an image.
+pub fn paste_image() -> Result<Option<String>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+    let image = match clipboard.get_image() {
+        Ok(image) => image,
+        Err(arboard::Error::ContentNotAvailable) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read the clipboard: {}", e)),
+    };
+    let png = encode_png(image.width as u32, image.height as u32, image.bytes.into_owned())?;
+
+    let path = new_pasted_image_path();
+    std::fs::write(&path, png).map_err(|e| format!("Failed to save the pasted image: {}", e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// A fresh temp file path for a pasted image.
+fn new_pasted_image_path() -> PathBuf {
+    std::env::temp_dir().join(format!("{}{}.png", PASTED_IMAGE_PREFIX, uuid::Uuid::new_v4()))
+}
+
+/// Copy a pasted image to a temp file of its own, so two posts can each remove theirs when
+/// they're done with it. Other paths are returned as they are.
+pub fn copy_pasted_image(path: &str) -> Result<String, String> {
+    if !is_pasted_image(path) {
+        return Ok(path.to_string());
+    }
+    let copy = new_pasted_image_path();
+    std::fs::copy(path, &copy).map_err(|e| format!("Failed to copy the pasted image: {}", e))?;
+    Ok(copy.to_string_lossy().to_string())
+}
+
+/// Encode raw RGBA pixels as a PNG.
+fn encode_png(width: u32, height: u32, rgba: Vec<u8>) -> Result<Vec<u8>, String> {
+    let image = RgbaImage::from_raw(width, height, rgba).ok_or_else(|| "The pasted image is malformed".to_string())?;
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode the pasted image: {}", e))?;
+    Ok(png)
+}
+
+/// Whether `path` is a temp file created by `paste_image`.
+pub fn is_pasted_image(path: &str) -> bool {
+    let path = Path::new(path);
+    path.parent() == Some(std::env::temp_dir().as_path())
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(PASTED_IMAGE_PREFIX))
+}
+
+/// Delete a pasted image's temp file; other paths are left alone.
+pub fn remove_pasted_image(path: &str) {
+    if is_pasted_image(path) {
+        if let Err(e) = std::fs::remove_file(PathBuf::from(path)) {
+            tracing::warn!("Failed to remove pasted image {}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_png() {
+        let png = encode_png(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (2, 1));
+        assert_eq!(decoded.get_pixel(1, 0).0, [0, 0, 255, 128]);
+        assert!(encode_png(2, 2, vec![0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_is_pasted_image() {
+        let pasted = std::env::temp_dir().join(format!("{}1234.png", PASTED_IMAGE_PREFIX));
+        assert!(is_pasted_image(&pasted.to_string_lossy()));
+        assert!(!is_pasted_image(&std::env::temp_dir().join("photo.png").to_string_lossy()));
+        assert!(!is_pasted_image(&format!("/home/user/{}1234.png", PASTED_IMAGE_PREFIX)));
+    }
+
+    #[test]
+    fn test_copy_pasted_image() {
+        let pasted = new_pasted_image_path();
+        std::fs::write(&pasted, b"png").unwrap();
+        let pasted = pasted.to_string_lossy().to_string();
+        let copy = copy_pasted_image(&pasted).unwrap();
+        assert_ne!(copy, pasted);
+        assert!(is_pasted_image(&copy));
+        remove_pasted_image(&pasted);
+        assert_eq!(std::fs::read(&copy).unwrap(), b"png");
+        remove_pasted_image(&copy);
+
+        assert_eq!(copy_pasted_image("/home/user/photo.png").unwrap(), "/home/user/photo.png");
+    }
+}
@@ -1,32 +1,571 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
-use serde::{Deserialize, Serialize};
-use crate::crypto::{EncryptedData, CryptoManager, CryptoError};
+use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, ConfigGet, ConfigSet, CosmicConfigEntry};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use crate::crypto::{CryptoBackend, EncryptedData, CryptoManager, CryptoError, KdfParams};
+use crate::fl;
+use crate::social::{
+    ImageAttachment, LemmyOptions, MastodonOptions, MastodonPoll, MicroBlogOptions, NostrArticle, NostrOptions, Platform, PostOptions, RetryPolicy,
+    WordPressOptions,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+const DEFAULT_MAX_CONCURRENT_POSTS: usize = 4;
+/// How long limits cached from a Mastodon instance are trusted before they're fetched again
+const MASTODON_LIMITS_MAX_AGE: chrono::TimeDelta = chrono::TimeDelta::hours(24);
+/// How far back a sent post with the same text counts as a duplicate
+const DUPLICATE_WINDOW_MINUTES: i64 = 60;
+/// Most recent history entries checked for a duplicate
+const DUPLICATE_CHECK_POSTS: usize = 10;
 
 #[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
-#[version = 1]
+#[version = 2]
 pub struct Config {
     pub bluesky: BlueskyConfig,
     pub mastodon: MastodonConfig,
     pub nostr: NostrConfig,
     pub microblog: MicroBlogConfig,
+    pub threads: ThreadsConfig,
+    pub pixelfed: PixelfedConfig,
+    pub discord: DiscordWebhookConfig,
+    pub telegram: TelegramConfig,
+    pub wordpress: WordPressConfig,
+    pub lemmy: LemmyConfig,
+    pub farcaster: FarcasterConfig,
+    pub matrix: MatrixConfig,
+    pub devto: DevToConfig,
+    /// Which backend protects the stored credentials
+    pub crypto_backend: CryptoBackend,
+    /// Salt for deriving the key from the master password; `None` means the machine key is used
+    pub master_password_salt: Option<String>,
+    /// Known value encrypted with the master password key, used to detect a wrong password
+    pub master_password_verifier: Option<EncryptedData>,
+    /// Posts queued to go out later
+    pub scheduled_posts: Vec<ScheduledPost>,
+    /// Saved compose drafts
+    pub drafts: Vec<Draft>,
+    /// Compose state autosaved on exit and restored on the next launch
+    pub last_session: Option<Draft>,
+    /// Platforms ticked for the last post, pre-ticked on launch; `None` ticks every enabled platform
+    pub last_platforms: Option<Vec<Platform>>,
+    /// Seconds before a network request gives up; `None` uses the default
+    pub request_timeout_secs: Option<u64>,
+    /// Tries per request when a server is briefly unavailable; `None` uses the default
+    pub retry_max_attempts: Option<u32>,
+    /// Most platforms posted to at the same time; `None` uses the default
+    pub max_concurrent_posts: Option<usize>,
+    /// Posts that were sent, newest first
+    pub history: Vec<SentPost>,
+    /// Most posts kept in `history`; `None` uses the default
+    pub history_limit: Option<usize>,
+    /// Upload images with their EXIF and other metadata instead of removing it first
+    pub keep_image_metadata: bool,
+    /// Service that links in posts are shortened with
+    pub url_shortener: UrlShortenerConfig,
+    /// Service that suggests alt text for attached images
+    pub captioner: CaptionerConfig,
+    /// Whitespace tidying applied to the text sent to each platform; missing platforms use the default
+    pub text_cleanup: HashMap<Platform, TextCleanup>,
+    /// Signature added to the end of posts on each platform; missing or blank platforms get none
+    pub signatures: HashMap<Platform, String>,
+    /// Tab that was open last, reopened on launch
+    pub last_view: SavedView,
+    /// Whether the Nostr relay list in Settings was collapsed
+    pub relays_collapsed: bool,
+    /// Settings sections collapsed to their headers
+    pub collapsed_settings: Vec<SettingsSection>,
+    /// Window width and height in logical pixels when the app was last closed
+    pub window_size: Option<(u32, u32)>,
+    /// ISO 639-1 code posts were last tagged with; `None` follows the system language
+    pub post_language: Option<String>,
+    /// Memory Argon2 uses to derive encryption keys, in KiB; `None` uses the default
+    pub argon2_memory_kib: Option<u32>,
+    /// Argon2 passes when deriving encryption keys; `None` uses the default
+    pub argon2_iterations: Option<u32>,
+}
+
+/// A tab that can be reopened on launch. A value that can't be read falls back to Compose,
+/// like any unreadable key.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum SavedView {
+    #[default]
+    Compose,
+    Settings,
+    Scheduled,
+    History,
+}
+
+/// A section of the Settings view, remembered when collapsed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum SettingsSection {
+    Platform(Platform),
+    LinkShortening,
+    AltTextSuggestions,
+    TextCleanup,
+    Signatures,
+    Security,
+    Transfer,
+}
+
+impl SettingsSection {
+    /// In the order Settings shows them
+    pub const ALL: [SettingsSection; 19] = [
+        SettingsSection::Platform(Platform::Bluesky),
+        SettingsSection::Platform(Platform::Mastodon),
+        SettingsSection::Platform(Platform::MicroBlog),
+        SettingsSection::Platform(Platform::Threads),
+        SettingsSection::Platform(Platform::Pixelfed),
+        SettingsSection::Platform(Platform::Discord),
+        SettingsSection::Platform(Platform::Telegram),
+        SettingsSection::Platform(Platform::WordPress),
+        SettingsSection::Platform(Platform::Lemmy),
+        SettingsSection::Platform(Platform::Farcaster),
+        SettingsSection::Platform(Platform::Matrix),
+        SettingsSection::Platform(Platform::DevTo),
+        SettingsSection::Platform(Platform::Nostr),
+        SettingsSection::LinkShortening,
+        SettingsSection::AltTextSuggestions,
+        SettingsSection::TextCleanup,
+        SettingsSection::Signatures,
+        SettingsSection::Security,
+        SettingsSection::Transfer,
+    ];
+
+    /// Heading shown above the section, which the Settings search matches against
+    pub fn title(&self) -> String {
+        match self {
+            SettingsSection::Platform(platform) => fl!("platform-settings", platform = platform.name()),
+            SettingsSection::LinkShortening => fl!("link-shortening"),
+            SettingsSection::AltTextSuggestions => fl!("alt-text-suggestions"),
+            SettingsSection::TextCleanup => fl!("text-cleanup"),
+            SettingsSection::Signatures => fl!("signatures"),
+            SettingsSection::Security => fl!("security"),
+            SettingsSection::Transfer => fl!("transfer-settings"),
+        }
+    }
+}
+
+/// Unsent compose content. Drafts hold no credentials, so they're stored unencrypted.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Draft {
+    pub text: String,
+    #[serde(default)]
+    pub images: Vec<ImageAttachment>,
+    pub platforms: Vec<Platform>,
+    pub saved_at: DateTime<Utc>,
+    /// Title, summary and identifier when the post is a Nostr article
+    #[serde(default)]
+    pub nostr_article: Option<NostrArticle>,
+}
+
+/// A post queued to be sent at a later time.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ScheduledPost {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub images: Vec<ImageAttachment>,
+    pub platforms: Vec<Platform>,
+    pub scheduled_at: DateTime<Utc>,
+    /// Split text over a platform's limit into a reply chain
+    #[serde(default)]
+    pub thread_mode: bool,
+    /// Text is Markdown, turned into plain text for platforms that don't format it
+    #[serde(default)]
+    pub markdown: bool,
+    /// ISO 639-1 code of the post's language
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Set when sending failed; the post stays queued until retried or removed
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// In the outbox because the network was unreachable: it's tried again at `scheduled_at`
+    /// despite the error, and sooner once another post gets through
+    #[serde(default)]
+    pub offline: bool,
+    /// Add each platform's signature to the text
+    #[serde(default)]
+    pub signature: bool,
+    /// Who the Mastodon status is shown to
+    #[serde(default)]
+    pub mastodon_visibility: MastodonVisibility,
+    /// Content warning for the Mastodon status
+    #[serde(default)]
+    pub spoiler_text: Option<String>,
+    /// Poll for the Mastodon status
+    #[serde(default)]
+    pub poll: Option<MastodonPoll>,
+    /// Post to reply to on each platform, as a link to it
+    #[serde(default)]
+    pub reference_urls: HashMap<Platform, String>,
+    /// Quote the posts in `reference_urls` instead of replying to them
+    #[serde(default)]
+    pub quote: bool,
+    /// Title, summary and identifier when the post goes to Nostr as an article
+    #[serde(default)]
+    pub nostr_article: Option<NostrArticle>,
+    /// Title for the WordPress post
+    #[serde(default)]
+    pub wordpress_title: Option<String>,
+    /// Title for the Lemmy post
+    #[serde(default)]
+    pub lemmy_title: Option<String>,
+    /// Title that makes the Micro.Blog post a long-form entry
+    #[serde(default)]
+    pub microblog_title: Option<String>,
+    #[serde(default)]
+    pub microblog_categories: Vec<String>,
+}
+
+/// A post that was sent, kept for the history. Only metadata is stored, no credentials.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SentPost {
+    /// Stays the same when the post is sent again, such as from the outbox, so every attempt
+    /// goes in the one entry
+    #[serde(default)]
+    pub id: Option<String>,
+    pub sent_at: DateTime<Utc>,
+    /// The text as posted, with its links shortened
+    pub text: String,
+    /// The text as written, when shortening its links changed it
+    #[serde(default)]
+    pub written_text: Option<String>,
+    #[serde(default)]
+    pub image_names: Vec<String>,
+    /// Each platform posted to, with the error if it failed
+    pub results: Vec<(Platform, Option<String>)>,
+    /// The Nostr article published, so it can be edited
+    #[serde(default)]
+    pub nostr_article: Option<NostrArticle>,
+}
+
+/// Settings file written by Export Settings, for moving accounts to another machine.
+///
+/// Credentials are encrypted with a key derived from the export passphrase rather than the
+/// machine key. The rest is plain JSON, as it is in the config directory. Credential storage
+/// and the master password stay with the machine and aren't exported.
+#[derive(Debug, Serialize, Deserialize)]
+struct SettingsExport {
+    format: u32,
+    /// Salt for deriving the key from the export passphrase
+    salt: String,
+    /// Detects a wrong passphrase before any credential is decrypted
+    verifier: EncryptedData,
+    bluesky: BlueskyConfig,
+    mastodon: MastodonConfig,
+    nostr: NostrConfig,
+    microblog: MicroBlogConfig,
+    threads: ThreadsConfig,
+    pixelfed: PixelfedConfig,
+    discord: DiscordWebhookConfig,
+    telegram: TelegramConfig,
+    wordpress: WordPressConfig,
+    #[serde(default)]
+    lemmy: LemmyConfig,
+    #[serde(default)]
+    farcaster: FarcasterConfig,
+    #[serde(default)]
+    matrix: MatrixConfig,
+    #[serde(default)]
+    devto: DevToConfig,
+    scheduled_posts: Vec<ScheduledPost>,
+    drafts: Vec<Draft>,
+    last_platforms: Option<Vec<Platform>>,
+    request_timeout_secs: Option<u64>,
+    retry_max_attempts: Option<u32>,
+    #[serde(default)]
+    max_concurrent_posts: Option<usize>,
+    history: Vec<SentPost>,
+    history_limit: Option<usize>,
+    #[serde(default)]
+    keep_image_metadata: bool,
+    #[serde(default)]
+    url_shortener: UrlShortenerConfig,
+    #[serde(default)]
+    captioner: CaptionerConfig,
+    #[serde(default)]
+    text_cleanup: HashMap<Platform, TextCleanup>,
+    #[serde(default)]
+    signatures: HashMap<Platform, String>,
+}
+
+const SETTINGS_EXPORT_FORMAT: u32 = 1;
+
+impl ScheduledPost {
+    /// Whether the post should be sent now
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        (self.last_error.is_none() || self.offline) && self.scheduled_at <= now
+    }
+
+    /// The options to send the post with. The ID is the idempotency key, since it stays the
+    /// same across retries.
+    pub fn post_options(&self) -> PostOptions {
+        PostOptions {
+            mastodon: MastodonOptions {
+                visibility: self.mastodon_visibility,
+                spoiler_text: self.spoiler_text.clone(),
+                poll: self.poll.clone(),
+                markdown: self.markdown,
+                ..Default::default()
+            },
+            wordpress: WordPressOptions { title: self.wordpress_title.clone() },
+            lemmy: LemmyOptions { title: self.lemmy_title.clone() },
+            microblog: MicroBlogOptions {
+                title: self.microblog_title.clone(),
+                categories: self.microblog_categories.clone(),
+            },
+            nostr: NostrOptions { article: self.nostr_article.clone() },
+            thread_mode: self.thread_mode,
+            markdown: self.markdown,
+            signature: self.signature,
+            language: self.language.clone(),
+            reference_urls: self.reference_urls.clone(),
+            quote: self.quote,
+            idempotency_key: Some(self.id.clone()),
+            ..Default::default()
+        }
+    }
 }
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct MicroBlogConfig {
     pub enabled: bool,
     pub access_token: Option<EncryptedData>, // Encrypted token
     #[serde(skip)]
-    pub decrypted_access_token: String, // Runtime-only decrypted value
+    pub decrypted_access_token: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ThreadsConfig {
+    pub enabled: bool,
+    pub user_id: String, // Threads user ID from the Graph API, not sensitive
+    pub access_token: Option<EncryptedData>, // Encrypted token
+    #[serde(skip)]
+    pub decrypted_access_token: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+    /// Upload attached images to the Nostr media server so Threads can fetch them. Like
+    /// Farcaster's, the uploads tie the Nostr key to the account, so it's off unless chosen
+    #[serde(default)]
+    pub upload_images: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct PixelfedConfig {
+    pub enabled: bool,
+    pub instance_url: String, // URL is not sensitive
+    pub access_token: Option<EncryptedData>, // Encrypted token
+    #[serde(skip)]
+    pub decrypted_access_token: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DiscordWebhookConfig {
+    pub enabled: bool,
+    /// Webhook URLs, one per line; each URL is its own credential
+    pub webhook_urls: Option<EncryptedData>,
+    #[serde(skip)]
+    pub decrypted_webhook_urls: Zeroizing<Vec<String>>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TelegramConfig {
+    pub enabled: bool,
+    pub chat_id: String, // Numeric chat ID or @channelname, not sensitive
+    pub bot_token: Option<EncryptedData>, // Encrypted token
+    #[serde(skip)]
+    pub decrypted_bot_token: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct WordPressConfig {
+    pub enabled: bool,
+    pub site_url: String, // URL is not sensitive
+    pub username: String, // Username is not sensitive
+    pub app_password: Option<EncryptedData>, // Encrypted application password
+    /// Whether new posts are published right away or saved as drafts
+    #[serde(default)]
+    pub default_status: WordPressStatus,
+    #[serde(skip)]
+    pub decrypted_app_password: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct LemmyConfig {
+    pub enabled: bool,
+    pub instance_url: String, // URL is not sensitive
+    pub username: String, // Username or email, not sensitive
+    pub password: Option<EncryptedData>, // Encrypted account password
+    /// Community posts go to, as `name` or `name@instance` for one on another instance
+    pub community: String,
+    #[serde(skip)]
+    pub decrypted_password: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
 }
 
+/// Casting to Farcaster through Neynar's hosted API, which signs casts with a signer it manages.
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct FarcasterConfig {
+    pub enabled: bool,
+    /// UUID of the approved Neynar signer casts are made with, not sensitive without the API key
+    pub signer_uuid: String,
+    pub api_key: Option<EncryptedData>, // Encrypted Neynar API key
+    #[serde(skip)]
+    pub decrypted_api_key: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+    /// Upload attached images to the Nostr media server so casts can embed them. The uploads
+    /// are signed with the Nostr key, which ties that key to the Farcaster account, so it's off
+    /// unless chosen
+    #[serde(default)]
+    pub upload_images: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct MatrixConfig {
+    pub enabled: bool,
+    pub homeserver_url: String, // URL is not sensitive
+    /// Room messages are sent to, by its `!id:server` ID
+    pub room_id: String,
+    pub access_token: Option<EncryptedData>, // Encrypted access token
+    #[serde(skip)]
+    pub decrypted_access_token: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+/// Mirroring long-form posts as articles on Dev.to or another Forem site.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DevToConfig {
+    pub enabled: bool,
+    /// Forem site articles go to; Dev.to unless it's another community
+    #[serde(default = "default_devto_site_url")]
+    pub site_url: String,
+    /// Leave articles as unpublished drafts to review on the site
+    #[serde(default)]
+    pub draft: bool,
+    pub api_key: Option<EncryptedData>, // Encrypted API key
+    #[serde(skip)]
+    pub decrypted_api_key: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+impl Default for DevToConfig {
+    fn default() -> Self {
+        DevToConfig {
+            enabled: false,
+            site_url: default_devto_site_url(),
+            draft: false,
+            api_key: None,
+            decrypted_api_key: Zeroizing::default(),
+            decrypt_failed: false,
+        }
+    }
+}
+
+fn default_devto_site_url() -> String {
+    "https://dev.to".to_string()
+}
+
+/// Status given to posts created on WordPress.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum WordPressStatus {
+    #[default]
+    Publish,
+    Draft,
+}
+
+impl WordPressStatus {
+    /// Value of the REST API `status` field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WordPressStatus::Publish => "publish",
+            WordPressStatus::Draft => "draft",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct BlueskyConfig {
     pub enabled: bool,
     pub handle: String, // Handle is not sensitive, can be stored in plain text
     pub password: Option<EncryptedData>, // Encrypted app password
+    /// PDS or other AT Protocol service the account lives on
+    #[serde(default = "default_bluesky_service_url")]
+    pub service_url: String,
+    /// What happens to text over the character limit when thread mode is off
+    #[serde(default)]
+    pub truncate_behavior: BlueskyTruncateBehavior,
     #[serde(skip)]
-    pub decrypted_password: String, // Runtime-only decrypted value
+    pub decrypted_password: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+/// How a post too long for Bluesky is handled outside thread mode.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum BlueskyTruncateBehavior {
+    /// Split the text into a reply chain, as thread mode does
+    #[default]
+    Thread,
+    /// Refuse to post anywhere until the text is shortened
+    BlockPost,
+    /// Post to the other platforms and leave Bluesky out
+    SkipBluesky,
+    /// Cut the text at the limit
+    Truncate,
+}
+
+impl BlueskyTruncateBehavior {
+    pub const ALL: [BlueskyTruncateBehavior; 4] = [
+        BlueskyTruncateBehavior::Thread,
+        BlueskyTruncateBehavior::BlockPost,
+        BlueskyTruncateBehavior::SkipBluesky,
+        BlueskyTruncateBehavior::Truncate,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            BlueskyTruncateBehavior::Thread => fl!("bluesky-thread"),
+            BlueskyTruncateBehavior::BlockPost => fl!("bluesky-block-post"),
+            BlueskyTruncateBehavior::SkipBluesky => fl!("bluesky-skip"),
+            BlueskyTruncateBehavior::Truncate => fl!("bluesky-truncate"),
+        }
+    }
+}
+
+impl Default for BlueskyConfig {
+    fn default() -> Self {
+        BlueskyConfig {
+            enabled: false,
+            handle: String::new(),
+            password: None,
+            service_url: default_bluesky_service_url(),
+            truncate_behavior: BlueskyTruncateBehavior::default(),
+            decrypted_password: Zeroizing::default(),
+            decrypt_failed: false,
+        }
+    }
+}
+
+fn default_bluesky_service_url() -> String {
+    "https://bsky.social".to_string()
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -34,8 +573,125 @@ pub struct MastodonConfig {
     pub enabled: bool,
     pub instance_url: String, // URL is not sensitive
     pub access_token: Option<EncryptedData>, // Encrypted token
+    /// Who new posts are shown to unless another choice is made in Compose
+    #[serde(default)]
+    pub default_visibility: MastodonVisibility,
+    /// Post length limit reported by the instance, cached from /api/v1/instance
+    #[serde(default)]
+    pub max_characters: Option<usize>,
+    /// Most images per status, cached from /api/v1/instance
+    #[serde(default)]
+    pub max_media_attachments: Option<usize>,
+    /// Most poll choices, cached from /api/v1/instance; `Some(0)` if the instance has no polls
+    #[serde(default)]
+    pub max_poll_options: Option<usize>,
+    /// Largest image file in bytes, cached from /api/v1/instance; only Pleroma and its forks say
+    #[serde(default)]
+    pub max_upload_size: Option<usize>,
+    /// Whether the instance runs Pleroma or a fork of it, cached from /api/v1/instance
+    #[serde(default)]
+    pub pleroma_detected: bool,
+    /// When the limits above were last fetched
+    #[serde(default)]
+    pub limits_fetched_at: Option<DateTime<Utc>>,
+    /// Whether to use Pleroma's extensions to the Mastodon API
+    #[serde(default)]
+    pub compatibility: MastodonCompatibility,
+    #[serde(skip)]
+    pub decrypted_access_token: Zeroizing<String>, // Runtime-only decrypted value
     #[serde(skip)]
-    pub decrypted_access_token: String, // Runtime-only decrypted value
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+/// Who can see a Mastodon status.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum MastodonVisibility {
+    /// Leave it to the default set on the account itself
+    #[default]
+    AccountDefault,
+    /// Anyone, and it's listed in the public timelines
+    Public,
+    /// Anyone, but it's left out of the public timelines
+    Unlisted,
+    /// Followers only
+    FollowersOnly,
+    /// Only the accounts mentioned in it
+    Direct,
+}
+
+impl MastodonVisibility {
+    pub const ALL: [MastodonVisibility; 5] = [
+        MastodonVisibility::AccountDefault,
+        MastodonVisibility::Public,
+        MastodonVisibility::Unlisted,
+        MastodonVisibility::FollowersOnly,
+        MastodonVisibility::Direct,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            MastodonVisibility::AccountDefault => fl!("mastodon-visibility-account-default"),
+            MastodonVisibility::Public => fl!("mastodon-visibility-public"),
+            MastodonVisibility::Unlisted => fl!("mastodon-visibility-unlisted"),
+            MastodonVisibility::FollowersOnly => fl!("mastodon-visibility-followers"),
+            MastodonVisibility::Direct => fl!("mastodon-visibility-direct"),
+        }
+    }
+
+    /// The `visibility` form field's value, or `None` to leave the field out
+    pub fn api_value(&self) -> Option<&'static str> {
+        match self {
+            MastodonVisibility::AccountDefault => None,
+            MastodonVisibility::Public => Some("public"),
+            MastodonVisibility::Unlisted => Some("unlisted"),
+            MastodonVisibility::FollowersOnly => Some("private"),
+            MastodonVisibility::Direct => Some("direct"),
+        }
+    }
+}
+
+impl MastodonConfig {
+    /// Whether the instance is treated as Pleroma or Akkoma, as set or as detected
+    pub fn pleroma_compatible(&self) -> bool {
+        match self.compatibility {
+            MastodonCompatibility::Detect => self.pleroma_detected,
+            MastodonCompatibility::Pleroma => true,
+            MastodonCompatibility::Mastodon => false,
+        }
+    }
+
+    /// Whether the cached instance limits are missing or old enough to fetch again
+    pub fn limits_stale(&self, now: DateTime<Utc>) -> bool {
+        self.limits_fetched_at.is_none_or(|fetched_at| now - fetched_at >= MASTODON_LIMITS_MAX_AGE)
+    }
+}
+
+/// Which server the Mastodon settings talk to, for the parts of the API that differ.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum MastodonCompatibility {
+    /// Go by the version the instance reports
+    #[default]
+    Detect,
+    /// Pleroma, Akkoma or another fork: Markdown is sent with `content_type`
+    Pleroma,
+    /// Plain Mastodon API, whatever the instance reports
+    Mastodon,
+}
+
+impl MastodonCompatibility {
+    pub const ALL: [MastodonCompatibility; 3] = [
+        MastodonCompatibility::Detect,
+        MastodonCompatibility::Pleroma,
+        MastodonCompatibility::Mastodon,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            MastodonCompatibility::Detect => fl!("mastodon-compatibility-detect"),
+            MastodonCompatibility::Pleroma => fl!("mastodon-compatibility-pleroma"),
+            MastodonCompatibility::Mastodon => fl!("mastodon-compatibility-mastodon"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -43,8 +699,22 @@ pub struct NostrConfig {
     pub enabled: bool,
     pub private_key: Option<EncryptedData>, // Encrypted private key
     pub relays: Vec<String>, // Relay URLs are not sensitive
+    /// NIP-96 media server used to host attached images
+    #[serde(default = "default_nostr_media_host")]
+    pub media_host: String,
+    /// Blossom server images are uploaded to instead of `media_host`; empty to use `media_host`
+    #[serde(default)]
+    pub blossom_server_url: String,
+    /// Sign a kind 24242 authorization for each Blossom upload, which most servers require
+    #[serde(default = "default_blossom_auth")]
+    pub blossom_auth: bool,
+    /// NIP-13 proof-of-work difficulty, in leading zero bits, mined into each note; `None` for none
+    #[serde(default)]
+    pub pow_difficulty: Option<u8>,
     #[serde(skip)]
-    pub decrypted_private_key: String, // Runtime-only decrypted value
+    pub decrypted_private_key: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
 }
 
 impl Default for NostrConfig {
@@ -58,59 +728,1211 @@ impl Default for NostrConfig {
                 "wss://relay.snort.social".to_string(),
                 "wss://nostr.wine".to_string(),
             ],
-            decrypted_private_key: String::new(),
+            media_host: default_nostr_media_host(),
+            blossom_server_url: String::new(),
+            blossom_auth: default_blossom_auth(),
+            pow_difficulty: None,
+            decrypted_private_key: Zeroizing::default(),
+            decrypt_failed: false,
+        }
+    }
+}
+
+fn default_nostr_media_host() -> String {
+    "https://nostr.build".to_string()
+}
+
+fn default_blossom_auth() -> bool {
+    true
+}
+
+/// A link shortener that answers a GET request with the short URL as plain text.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UrlShortenerConfig {
+    pub enabled: bool,
+    /// Request URL with `{url}` where the link to shorten goes, e.g. is.gd or a Shlink server
+    pub endpoint: String,
+    pub api_key: Option<EncryptedData>, // Encrypted key sent as `X-Api-Key`, as Shlink takes it
+    #[serde(skip)]
+    pub decrypted_api_key: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+impl Default for UrlShortenerConfig {
+    fn default() -> Self {
+        UrlShortenerConfig {
+            enabled: false,
+            endpoint: "https://is.gd/create.php?format=simple&url={url}".to_string(),
+            api_key: None,
+            decrypted_api_key: Zeroizing::default(),
+            decrypt_failed: false,
+        }
+    }
+}
+
+/// Whitespace tidying for the text sent to one platform; the editor keeps the text as typed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TextCleanup {
+    /// Remove whitespace at the ends of lines and of the text
+    pub trim_trailing_whitespace: bool,
+    /// Shorten runs of three or more blank lines to two
+    pub collapse_blank_lines: bool,
+}
+
+impl Default for TextCleanup {
+    fn default() -> Self {
+        TextCleanup {
+            trim_trailing_whitespace: true,
+            collapse_blank_lines: false,
         }
     }
 }
 
+/// An image captioning service that answers a POST of the image's bytes with a description.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CaptionerConfig {
+    pub enabled: bool,
+    /// URL the image is posted to; the reply is plain text or JSON with a `caption` field
+    pub endpoint: String,
+    pub token: Option<EncryptedData>, // Encrypted bearer token, if the service needs one
+    #[serde(skip)]
+    pub decrypted_token: Zeroizing<String>, // Runtime-only decrypted value
+    #[serde(skip)]
+    pub decrypt_failed: bool, // The stored value couldn't be decrypted here; runtime-only
+}
+
+/// Labels that identify each credential to the crypto backend
+const BLUESKY_PASSWORD_LABEL: &str = "bluesky-password";
+const MASTODON_TOKEN_LABEL: &str = "mastodon-access-token";
+const MICROBLOG_TOKEN_LABEL: &str = "microblog-access-token";
+const NOSTR_KEY_LABEL: &str = "nostr-private-key";
+const THREADS_TOKEN_LABEL: &str = "threads-access-token";
+const PIXELFED_TOKEN_LABEL: &str = "pixelfed-access-token";
+const DISCORD_WEBHOOKS_LABEL: &str = "discord-webhook-urls";
+const TELEGRAM_TOKEN_LABEL: &str = "telegram-bot-token";
+const WORDPRESS_PASSWORD_LABEL: &str = "wordpress-app-password";
+const LEMMY_PASSWORD_LABEL: &str = "lemmy-password";
+const FARCASTER_API_KEY_LABEL: &str = "farcaster-api-key";
+const MATRIX_TOKEN_LABEL: &str = "matrix-access-token";
+const DEVTO_API_KEY_LABEL: &str = "devto-api-key";
+const CAPTIONER_TOKEN_LABEL: &str = "captioner-token";
+const SHORTENER_API_KEY_LABEL: &str = "url-shortener-api-key";
+
 impl Config {
-    /// Decrypt all credentials using the provided crypto manager
-    pub fn decrypt_credentials(&mut self, crypto: &CryptoManager) -> Result<(), CryptoError> {
+    /// How long a single network request may take before it fails
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs.filter(|secs| *secs > 0).unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS))
+    }
+
+    /// Most platforms `post_to_platforms` sends to at once
+    pub fn max_concurrent_posts(&self) -> usize {
+        self.max_concurrent_posts.filter(|max| *max > 0).unwrap_or(DEFAULT_MAX_CONCURRENT_POSTS)
+    }
+
+    /// Most posts kept in the history
+    pub fn history_limit(&self) -> usize {
+        self.history_limit.unwrap_or(DEFAULT_HISTORY_LIMIT)
+    }
+
+    /// Make every post in the outbox due now, e.g. once the network is back. Returns whether
+    /// there were any.
+    pub fn resume_outbox(&mut self, now: DateTime<Utc>) -> bool {
+        let mut resumed = false;
+        for post in self.scheduled_posts.iter_mut().filter(|post| post.offline) {
+            post.scheduled_at = post.scheduled_at.min(now);
+            resumed = true;
+        }
+        resumed
+    }
+
+    /// Add a sent post to the front of the history, dropping the oldest past the limit
+    pub fn record_sent_post(&mut self, mut post: SentPost) {
+        for error in post.results.iter_mut().filter_map(|(_, error)| error.as_mut()) {
+            *error = self.redact_credentials(error);
+        }
+        // Another attempt at a post already in the history updates its entry, and platforms
+        // this attempt didn't go to keep how they went before
+        let earlier = post.id.as_ref().and_then(|id| self.history.iter().position(|sent| sent.id.as_ref() == Some(id)));
+        if let Some(index) = earlier {
+            let earlier = self.history.remove(index);
+            let mut results = earlier.results;
+            for (platform, error) in post.results {
+                match results.iter_mut().find(|(earlier_platform, _)| *earlier_platform == platform) {
+                    Some(result) => result.1 = error,
+                    None => results.push((platform, error)),
+                }
+            }
+            post.results = results;
+            post.nostr_article = post.nostr_article.or(earlier.nostr_article);
+        }
+        self.history.insert(0, post);
+        self.history.truncate(self.history_limit());
+    }
+
+    /// `text` with every credential in use replaced, for error messages kept in the config,
+    /// which is stored unencrypted.
+    pub fn redact_credentials(&self, text: &str) -> String {
+        let secrets = [
+            self.bluesky.decrypted_password.as_str(),
+            self.mastodon.decrypted_access_token.as_str(),
+            self.microblog.decrypted_access_token.as_str(),
+            self.nostr.decrypted_private_key.as_str(),
+            self.threads.decrypted_access_token.as_str(),
+            self.pixelfed.decrypted_access_token.as_str(),
+            self.telegram.decrypted_bot_token.as_str(),
+            self.wordpress.decrypted_app_password.as_str(),
+            self.lemmy.decrypted_password.as_str(),
+            self.farcaster.decrypted_api_key.as_str(),
+            self.matrix.decrypted_access_token.as_str(),
+            self.devto.decrypted_api_key.as_str(),
+            self.captioner.decrypted_token.as_str(),
+            self.url_shortener.decrypted_api_key.as_str(),
+        ];
+        // A webhook's token is the last part of its URL, which may be quoted on its own
+        let webhooks = self
+            .discord
+            .decrypted_webhook_urls
+            .iter()
+            .flat_map(|url| [url.as_str(), url.trim_end_matches('/').rsplit('/').next().unwrap_or_default()]);
+        let mut text = text.to_string();
+        for secret in secrets.into_iter().chain(webhooks).filter(|secret| !secret.is_empty()) {
+            text = text.replace(secret, "[redacted]");
+        }
+        text
+    }
+
+    /// The most recent post with the same text sent in the last hour, if any platform got it.
+    ///
+    /// Case and whitespace differences are ignored, so a re-typed copy still matches.
+    pub fn recent_duplicate(&self, text: &str, now: DateTime<Utc>) -> Option<&SentPost> {
+        let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        let text = normalize(text);
+        self.history
+            .iter()
+            .take(DUPLICATE_CHECK_POSTS)
+            .take_while(|post| now - post.sent_at <= chrono::Duration::minutes(DUPLICATE_WINDOW_MINUTES))
+            .filter(|post| post.results.iter().any(|(_, error)| error.is_none()))
+            .find(|post| normalize(&post.text) == text || post.written_text.as_deref().is_some_and(|written| normalize(written) == text))
+    }
+
+    /// How the text sent to `platform` is tidied up
+    pub fn text_cleanup(&self, platform: Platform) -> TextCleanup {
+        self.text_cleanup.get(&platform).copied().unwrap_or_default()
+    }
+
+    /// The signature added to posts on `platform`, if it has one
+    pub fn signature(&self, platform: Platform) -> Option<&str> {
+        self.signatures.get(&platform).map(|signature| signature.trim()).filter(|signature| !signature.is_empty())
+    }
+
+    /// Whether a platform is switched on in Settings
+    pub fn platform_enabled(&self, platform: Platform) -> bool {
+        match platform {
+            Platform::Bluesky => self.bluesky.enabled,
+            Platform::Mastodon => self.mastodon.enabled,
+            Platform::MicroBlog => self.microblog.enabled,
+            Platform::Nostr => self.nostr.enabled,
+            Platform::Threads => self.threads.enabled,
+            Platform::Pixelfed => self.pixelfed.enabled,
+            Platform::Discord => self.discord.enabled,
+            Platform::Telegram => self.telegram.enabled,
+            Platform::WordPress => self.wordpress.enabled,
+            Platform::Lemmy => self.lemmy.enabled,
+            Platform::Farcaster => self.farcaster.enabled,
+            Platform::Matrix => self.matrix.enabled,
+            Platform::DevTo => self.devto.enabled,
+        }
+    }
+
+    /// Argon2 settings new credentials are encrypted with; credentials stored with other
+    /// settings are re-encrypted once decrypted
+    pub fn kdf_params(&self) -> KdfParams {
+        let mut params = KdfParams::default();
+        if let Some(memory_kib) = self.argon2_memory_kib {
+            params.memory_kib = memory_kib.max(8 * params.parallelism);
+        }
+        if let Some(iterations) = self.argon2_iterations {
+            params.iterations = iterations.max(1);
+        }
+        params
+    }
+
+    /// Retry behaviour for posting requests
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let mut policy = RetryPolicy::default();
+        if let Some(max_attempts) = self.retry_max_attempts {
+            policy.max_attempts = max_attempts.max(1);
+        }
+        policy
+    }
+
+    /// Whether credentials are protected by a master password
+    pub fn has_master_password(&self) -> bool {
+        self.crypto_backend == CryptoBackend::Password
+            && self.master_password_salt.is_some()
+            && self.master_password_verifier.is_some()
+    }
+
+    /// Decrypt all credentials using the provided crypto manager.
+    ///
+    /// Each credential is decrypted on its own, so one that can't be, such as one saved on another
+    /// machine, leaves the others usable. On failure, returns the sections whose credentials need
+    /// to be entered again.
+    pub fn decrypt_credentials(&mut self, crypto: &CryptoManager) -> Result<(), Vec<SettingsSection>> {
+        let mut failed = Vec::new();
+        let mut decrypt = |section: SettingsSection, label: &str, encrypted: &Option<EncryptedData>, decrypt_failed: &mut bool| {
+            *decrypt_failed = false;
+            let encrypted = encrypted.as_ref()?;
+            crypto
+                .decrypt(label, encrypted)
+                .inspect_err(|e| {
+                    tracing::warn!("Failed to decrypt {}: {}", label, e);
+                    *decrypt_failed = true;
+                    failed.push(section);
+                })
+                .ok()
+        };
+
         // Decrypt Bluesky password
-        if let Some(encrypted_password) = &self.bluesky.password {
-            self.bluesky.decrypted_password = crypto.decrypt(encrypted_password)?;
+        if let Some(password) = decrypt(SettingsSection::Platform(Platform::Bluesky), BLUESKY_PASSWORD_LABEL, &self.bluesky.password, &mut self.bluesky.decrypt_failed) {
+            self.bluesky.decrypted_password = password;
         }
 
         // Decrypt Mastodon token
-        if let Some(encrypted_token) = &self.mastodon.access_token {
-            self.mastodon.decrypted_access_token = crypto.decrypt(encrypted_token)?;
+        if let Some(token) = decrypt(SettingsSection::Platform(Platform::Mastodon), MASTODON_TOKEN_LABEL, &self.mastodon.access_token, &mut self.mastodon.decrypt_failed) {
+            self.mastodon.decrypted_access_token = token;
         }
 
         // Decrypt Micro.Blog token
-        if let Some(encrypted_token) = &self.microblog.access_token {
-            self.microblog.decrypted_access_token = crypto.decrypt(encrypted_token)?;
+        if let Some(token) = decrypt(SettingsSection::Platform(Platform::MicroBlog), MICROBLOG_TOKEN_LABEL, &self.microblog.access_token, &mut self.microblog.decrypt_failed) {
+            self.microblog.decrypted_access_token = token;
         }
 
         // Decrypt Nostr private key
-        if let Some(encrypted_key) = &self.nostr.private_key {
-            self.nostr.decrypted_private_key = crypto.decrypt(encrypted_key)?;
+        if let Some(key) = decrypt(SettingsSection::Platform(Platform::Nostr), NOSTR_KEY_LABEL, &self.nostr.private_key, &mut self.nostr.decrypt_failed) {
+            self.nostr.decrypted_private_key = key;
         }
 
-        Ok(())
+        // Decrypt Threads token
+        if let Some(token) = decrypt(SettingsSection::Platform(Platform::Threads), THREADS_TOKEN_LABEL, &self.threads.access_token, &mut self.threads.decrypt_failed) {
+            self.threads.decrypted_access_token = token;
+        }
+
+        // Decrypt Pixelfed token
+        if let Some(token) = decrypt(SettingsSection::Platform(Platform::Pixelfed), PIXELFED_TOKEN_LABEL, &self.pixelfed.access_token, &mut self.pixelfed.decrypt_failed) {
+            self.pixelfed.decrypted_access_token = token;
+        }
+
+        // Decrypt Discord webhook URLs
+        if let Some(urls) = decrypt(SettingsSection::Platform(Platform::Discord), DISCORD_WEBHOOKS_LABEL, &self.discord.webhook_urls, &mut self.discord.decrypt_failed) {
+            self.discord.decrypted_webhook_urls = Zeroizing::new(urls.lines().map(str::to_string).collect());
+        }
+
+        // Decrypt Telegram bot token
+        if let Some(token) = decrypt(SettingsSection::Platform(Platform::Telegram), TELEGRAM_TOKEN_LABEL, &self.telegram.bot_token, &mut self.telegram.decrypt_failed) {
+            self.telegram.decrypted_bot_token = token;
+        }
+
+        // Decrypt WordPress application password
+        if let Some(password) = decrypt(SettingsSection::Platform(Platform::WordPress), WORDPRESS_PASSWORD_LABEL, &self.wordpress.app_password, &mut self.wordpress.decrypt_failed) {
+            self.wordpress.decrypted_app_password = password;
+        }
+
+        // Decrypt Lemmy password
+        if let Some(password) = decrypt(SettingsSection::Platform(Platform::Lemmy), LEMMY_PASSWORD_LABEL, &self.lemmy.password, &mut self.lemmy.decrypt_failed) {
+            self.lemmy.decrypted_password = password;
+        }
+
+        // Decrypt Neynar API key
+        if let Some(key) = decrypt(SettingsSection::Platform(Platform::Farcaster), FARCASTER_API_KEY_LABEL, &self.farcaster.api_key, &mut self.farcaster.decrypt_failed) {
+            self.farcaster.decrypted_api_key = key;
+        }
+
+        // Decrypt Matrix access token
+        if let Some(token) = decrypt(SettingsSection::Platform(Platform::Matrix), MATRIX_TOKEN_LABEL, &self.matrix.access_token, &mut self.matrix.decrypt_failed) {
+            self.matrix.decrypted_access_token = token;
+        }
+
+        // Decrypt Dev.to API key
+        if let Some(key) = decrypt(SettingsSection::Platform(Platform::DevTo), DEVTO_API_KEY_LABEL, &self.devto.api_key, &mut self.devto.decrypt_failed) {
+            self.devto.decrypted_api_key = key;
+        }
+
+        // Decrypt captioning service token
+        if let Some(token) = decrypt(SettingsSection::AltTextSuggestions, CAPTIONER_TOKEN_LABEL, &self.captioner.token, &mut self.captioner.decrypt_failed) {
+            self.captioner.decrypted_token = token;
+        }
+
+        // Decrypt link shortener API key
+        if let Some(key) = decrypt(SettingsSection::LinkShortening, SHORTENER_API_KEY_LABEL, &self.url_shortener.api_key, &mut self.url_shortener.decrypt_failed) {
+            self.url_shortener.decrypted_api_key = key;
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
     }
 
     /// Encrypt credentials before saving
+    ///
+    /// A credential that was cleared is removed from the config too. One that couldn't be
+    /// decrypted here is kept as it was, since it was never shown to be cleared.
     pub fn encrypt_credentials(&mut self, crypto: &CryptoManager) -> Result<(), CryptoError> {
+        let encrypt = |label: &str, plaintext: &str, decrypt_failed: bool, stored: &mut Option<EncryptedData>| -> Result<(), CryptoError> {
+            if !plaintext.is_empty() {
+                *stored = Some(crypto.encrypt(label, plaintext)?);
+            } else if !decrypt_failed && stored.take().is_some() {
+                crypto.forget(label)?;
+            }
+            Ok(())
+        };
+
         // Encrypt Bluesky password
-        if !self.bluesky.decrypted_password.is_empty() {
-            self.bluesky.password = Some(crypto.encrypt(&self.bluesky.decrypted_password)?);
-        }
+        encrypt(BLUESKY_PASSWORD_LABEL, &self.bluesky.decrypted_password, self.bluesky.decrypt_failed, &mut self.bluesky.password)?;
 
         // Encrypt Mastodon token
-        if !self.mastodon.decrypted_access_token.is_empty() {
-            self.mastodon.access_token = Some(crypto.encrypt(&self.mastodon.decrypted_access_token)?);
-        }
+        encrypt(MASTODON_TOKEN_LABEL, &self.mastodon.decrypted_access_token, self.mastodon.decrypt_failed, &mut self.mastodon.access_token)?;
 
         // Encrypt Micro.Blog token
-        if !self.microblog.decrypted_access_token.is_empty() {
-            self.microblog.access_token = Some(crypto.encrypt(&self.microblog.decrypted_access_token)?);
-        }
+        encrypt(MICROBLOG_TOKEN_LABEL, &self.microblog.decrypted_access_token, self.microblog.decrypt_failed, &mut self.microblog.access_token)?;
 
         // Encrypt Nostr private key
-        if !self.nostr.decrypted_private_key.is_empty() {
-            self.nostr.private_key = Some(crypto.encrypt(&self.nostr.decrypted_private_key)?);
+        encrypt(NOSTR_KEY_LABEL, &self.nostr.decrypted_private_key, self.nostr.decrypt_failed, &mut self.nostr.private_key)?;
+
+        // Encrypt Threads token
+        encrypt(THREADS_TOKEN_LABEL, &self.threads.decrypted_access_token, self.threads.decrypt_failed, &mut self.threads.access_token)?;
+
+        // Encrypt Pixelfed token
+        encrypt(PIXELFED_TOKEN_LABEL, &self.pixelfed.decrypted_access_token, self.pixelfed.decrypt_failed, &mut self.pixelfed.access_token)?;
+
+        // Encrypt Discord webhook URLs
+        let webhook_urls = Zeroizing::new(self.discord.decrypted_webhook_urls.join("\n"));
+        encrypt(DISCORD_WEBHOOKS_LABEL, &webhook_urls, self.discord.decrypt_failed, &mut self.discord.webhook_urls)?;
+
+        // Encrypt Telegram bot token
+        encrypt(TELEGRAM_TOKEN_LABEL, &self.telegram.decrypted_bot_token, self.telegram.decrypt_failed, &mut self.telegram.bot_token)?;
+
+        // Encrypt WordPress application password
+        encrypt(WORDPRESS_PASSWORD_LABEL, &self.wordpress.decrypted_app_password, self.wordpress.decrypt_failed, &mut self.wordpress.app_password)?;
+
+        // Encrypt Lemmy password
+        encrypt(LEMMY_PASSWORD_LABEL, &self.lemmy.decrypted_password, self.lemmy.decrypt_failed, &mut self.lemmy.password)?;
+
+        // Encrypt Neynar API key
+        encrypt(FARCASTER_API_KEY_LABEL, &self.farcaster.decrypted_api_key, self.farcaster.decrypt_failed, &mut self.farcaster.api_key)?;
+
+        // Encrypt Matrix access token
+        encrypt(MATRIX_TOKEN_LABEL, &self.matrix.decrypted_access_token, self.matrix.decrypt_failed, &mut self.matrix.access_token)?;
+
+        // Encrypt Dev.to API key
+        encrypt(DEVTO_API_KEY_LABEL, &self.devto.decrypted_api_key, self.devto.decrypt_failed, &mut self.devto.api_key)?;
+
+        // Encrypt captioning service token
+        encrypt(CAPTIONER_TOKEN_LABEL, &self.captioner.decrypted_token, self.captioner.decrypt_failed, &mut self.captioner.token)?;
+
+        // Encrypt link shortener API key
+        encrypt(SHORTENER_API_KEY_LABEL, &self.url_shortener.decrypted_api_key, self.url_shortener.decrypt_failed, &mut self.url_shortener.api_key)?;
+
+        Ok(())
+    }
+
+    /// Re-encrypt credentials stored before they were bound to their labels, or with other
+    /// Argon2 settings than `crypto`'s.
+    ///
+    /// Credentials must already be decrypted with `crypto`. Returns whether anything changed
+    /// and needs saving.
+    pub fn upgrade_credentials(&mut self, crypto: &CryptoManager) -> Result<bool, CryptoError> {
+        let stored = [
+            &self.bluesky.password,
+            &self.mastodon.access_token,
+            &self.microblog.access_token,
+            &self.nostr.private_key,
+            &self.threads.access_token,
+            &self.pixelfed.access_token,
+            &self.discord.webhook_urls,
+            &self.telegram.bot_token,
+            &self.wordpress.app_password,
+            &self.lemmy.password,
+            &self.farcaster.api_key,
+            &self.matrix.access_token,
+            &self.devto.api_key,
+            &self.captioner.token,
+            &self.url_shortener.api_key,
+            &self.master_password_verifier,
+        ];
+        if !stored.into_iter().flatten().any(|encrypted| crypto.is_outdated(encrypted)) {
+            return Ok(false);
+        }
+        self.encrypt_credentials(crypto)?;
+        if self.master_password_verifier.is_some() {
+            self.master_password_verifier = Some(crypto.create_verifier()?);
+        }
+        Ok(true)
+    }
+
+    /// Whether the section's stored credentials couldn't be decrypted when they were loaded.
+    pub fn decrypt_failed(&self, section: SettingsSection) -> bool {
+        match section {
+            SettingsSection::Platform(platform) => match platform {
+                Platform::Bluesky => self.bluesky.decrypt_failed,
+                Platform::Mastodon => self.mastodon.decrypt_failed,
+                Platform::MicroBlog => self.microblog.decrypt_failed,
+                Platform::Nostr => self.nostr.decrypt_failed,
+                Platform::Threads => self.threads.decrypt_failed,
+                Platform::Pixelfed => self.pixelfed.decrypt_failed,
+                Platform::Discord => self.discord.decrypt_failed,
+                Platform::Telegram => self.telegram.decrypt_failed,
+                Platform::WordPress => self.wordpress.decrypt_failed,
+                Platform::Lemmy => self.lemmy.decrypt_failed,
+                Platform::Farcaster => self.farcaster.decrypt_failed,
+                Platform::Matrix => self.matrix.decrypt_failed,
+                Platform::DevTo => self.devto.decrypt_failed,
+            },
+            SettingsSection::AltTextSuggestions => self.captioner.decrypt_failed,
+            SettingsSection::LinkShortening => self.url_shortener.decrypt_failed,
+            _ => false,
+        }
+    }
+
+    /// Whether `other` holds the same settings and decrypted credentials as `self`, however
+    /// their credentials happen to be stored.
+    pub fn same_settings(&self, other: &Config) -> bool {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        a.clear_stored_credentials();
+        b.clear_stored_credentials();
+        a == b
+    }
+
+    /// Drop the encrypted copies of the credentials and whether they decrypted, keeping the
+    /// decrypted values.
+    fn clear_stored_credentials(&mut self) {
+        for (stored, decrypt_failed) in [
+            (&mut self.bluesky.password, &mut self.bluesky.decrypt_failed),
+            (&mut self.mastodon.access_token, &mut self.mastodon.decrypt_failed),
+            (&mut self.microblog.access_token, &mut self.microblog.decrypt_failed),
+            (&mut self.nostr.private_key, &mut self.nostr.decrypt_failed),
+            (&mut self.threads.access_token, &mut self.threads.decrypt_failed),
+            (&mut self.pixelfed.access_token, &mut self.pixelfed.decrypt_failed),
+            (&mut self.discord.webhook_urls, &mut self.discord.decrypt_failed),
+            (&mut self.telegram.bot_token, &mut self.telegram.decrypt_failed),
+            (&mut self.wordpress.app_password, &mut self.wordpress.decrypt_failed),
+            (&mut self.lemmy.password, &mut self.lemmy.decrypt_failed),
+            (&mut self.farcaster.api_key, &mut self.farcaster.decrypt_failed),
+            (&mut self.matrix.access_token, &mut self.matrix.decrypt_failed),
+            (&mut self.devto.api_key, &mut self.devto.decrypt_failed),
+            (&mut self.captioner.token, &mut self.captioner.decrypt_failed),
+            (&mut self.url_shortener.api_key, &mut self.url_shortener.decrypt_failed),
+        ] {
+            *stored = None;
+            *decrypt_failed = false;
         }
+    }
+
+    /// Save the config after its master password verifier may have changed.
+    ///
+    /// Everything else, the salt and credentials included, goes out in one transaction with
+    /// `previous_verifier` still in place, and the new verifier is only written after it, so
+    /// the new password isn't accepted before what it unlocks is on disk.
+    pub fn write_verifier_last(&self, context: &cosmic_config::Config, previous_verifier: Option<EncryptedData>) -> Result<(), cosmic_config::Error> {
+        let mut pending = self.clone();
+        pending.master_password_verifier = previous_verifier;
+        pending.write_entry(context)?;
+        context.set("master_password_verifier", &self.master_password_verifier)
+    }
 
+    /// Re-encrypt every stored credential under a new master password.
+    ///
+    /// The stored credentials are decrypted afresh with `old_password`, so nothing is returned
+    /// unless they all unlock; a wrong password gives `DecryptionFailed`. Returns the new config,
+    /// with credentials both encrypted and decrypted, and the manager for the new password.
+    pub fn change_master_password(&self, old_password: &str, new_password: &str) -> Result<(Config, CryptoManager), CryptoError> {
+        let (Some(salt), Some(verifier)) = (&self.master_password_salt, &self.master_password_verifier) else {
+            return Err(CryptoError::InvalidData);
+        };
+        let mut old_crypto = CryptoManager::new();
+        old_crypto.init_with_password(old_password, salt, self.kdf_params())?;
+        if !old_crypto.verify(verifier) {
+            return Err(CryptoError::DecryptionFailed);
+        }
+        let mut config = self.clone();
+        config.decrypt_credentials(&old_crypto).map_err(|_| CryptoError::DecryptionFailed)?;
+
+        let salt = CryptoManager::generate_salt();
+        let mut new_crypto = CryptoManager::new();
+        new_crypto.init_with_password(new_password, &salt, self.kdf_params())?;
+        config.master_password_salt = Some(salt);
+        config.master_password_verifier = Some(new_crypto.create_verifier()?);
+        config.encrypt_credentials(&new_crypto)?;
+        Ok((config, new_crypto))
+    }
+
+    /// Serialize the settings with credentials encrypted under `passphrase`.
+    ///
+    /// Credentials must be decrypted, as they are while the app is running.
+    pub fn export(&self, passphrase: &str) -> Result<String, CryptoError> {
+        let salt = CryptoManager::generate_salt();
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_password(passphrase, &salt, self.kdf_params())?;
+
+        let mut config = self.clone();
+        config.encrypt_credentials(&crypto)?;
+        let export = SettingsExport {
+            format: SETTINGS_EXPORT_FORMAT,
+            salt,
+            verifier: crypto.create_verifier()?,
+            bluesky: config.bluesky,
+            mastodon: config.mastodon,
+            nostr: config.nostr,
+            microblog: config.microblog,
+            threads: config.threads,
+            pixelfed: config.pixelfed,
+            discord: config.discord,
+            telegram: config.telegram,
+            wordpress: config.wordpress,
+            lemmy: config.lemmy,
+            farcaster: config.farcaster,
+            matrix: config.matrix,
+            devto: config.devto,
+            scheduled_posts: config.scheduled_posts,
+            drafts: config.drafts,
+            last_platforms: config.last_platforms,
+            request_timeout_secs: config.request_timeout_secs,
+            retry_max_attempts: config.retry_max_attempts,
+            max_concurrent_posts: config.max_concurrent_posts,
+            history: config.history,
+            history_limit: config.history_limit,
+            keep_image_metadata: config.keep_image_metadata,
+            url_shortener: config.url_shortener,
+            captioner: config.captioner,
+            text_cleanup: config.text_cleanup,
+            signatures: config.signatures,
+        };
+        serde_json::to_string_pretty(&export).map_err(|_| CryptoError::InvalidData)
+    }
+
+    /// Replace the settings with an exported file, re-encrypting its credentials with `crypto`.
+    ///
+    /// Credential storage, the master password and the autosaved session are kept from `self`.
+    /// On success the returned config has both encrypted and decrypted credentials set.
+    pub fn import(&self, contents: &str, passphrase: &str, crypto: &CryptoManager) -> Result<Config, CryptoError> {
+        let export: SettingsExport = serde_json::from_str(contents).map_err(|_| CryptoError::InvalidData)?;
+        if export.format != SETTINGS_EXPORT_FORMAT {
+            return Err(CryptoError::InvalidData);
+        }
+
+        let mut file_crypto = CryptoManager::new();
+        file_crypto.init_with_password(passphrase, &export.salt, self.kdf_params())?;
+        if !file_crypto.verify(&export.verifier) {
+            return Err(CryptoError::DecryptionFailed);
+        }
+
+        let mut config = Config {
+            bluesky: export.bluesky,
+            mastodon: export.mastodon,
+            nostr: export.nostr,
+            microblog: export.microblog,
+            threads: export.threads,
+            pixelfed: export.pixelfed,
+            discord: export.discord,
+            telegram: export.telegram,
+            wordpress: export.wordpress,
+            lemmy: export.lemmy,
+            farcaster: export.farcaster,
+            matrix: export.matrix,
+            devto: export.devto,
+            crypto_backend: self.crypto_backend,
+            master_password_salt: self.master_password_salt.clone(),
+            master_password_verifier: self.master_password_verifier.clone(),
+            scheduled_posts: export.scheduled_posts,
+            drafts: export.drafts,
+            last_session: self.last_session.clone(),
+            last_platforms: export.last_platforms,
+            request_timeout_secs: export.request_timeout_secs,
+            retry_max_attempts: export.retry_max_attempts,
+            max_concurrent_posts: export.max_concurrent_posts,
+            history: export.history,
+            history_limit: export.history_limit,
+            keep_image_metadata: export.keep_image_metadata,
+            url_shortener: export.url_shortener,
+            captioner: export.captioner,
+            text_cleanup: export.text_cleanup,
+            signatures: export.signatures,
+            last_view: self.last_view,
+            relays_collapsed: self.relays_collapsed,
+            collapsed_settings: self.collapsed_settings.clone(),
+            window_size: self.window_size,
+            post_language: self.post_language.clone(),
+            argon2_memory_kib: self.argon2_memory_kib,
+            argon2_iterations: self.argon2_iterations,
+        };
+        config.decrypt_credentials(&file_crypto).map_err(|_| CryptoError::DecryptionFailed)?;
+        config.encrypt_credentials(crypto)?;
+        Ok(config)
+    }
+
+    /// Remove credentials held by the backend outside the config, e.g. after switching away from the keyring
+    pub fn forget_credentials(&self, crypto: &CryptoManager) -> Result<(), CryptoError> {
+        for label in [BLUESKY_PASSWORD_LABEL, MASTODON_TOKEN_LABEL, MICROBLOG_TOKEN_LABEL, NOSTR_KEY_LABEL, THREADS_TOKEN_LABEL, PIXELFED_TOKEN_LABEL, DISCORD_WEBHOOKS_LABEL, TELEGRAM_TOKEN_LABEL, WORDPRESS_PASSWORD_LABEL, LEMMY_PASSWORD_LABEL, FARCASTER_API_KEY_LABEL, MATRIX_TOKEN_LABEL, DEVTO_API_KEY_LABEL, CAPTIONER_TOKEN_LABEL, SHORTENER_API_KEY_LABEL] {
+            crypto.forget(label)?;
+        }
         Ok(())
     }
 }
+
+/// Source of stored settings keys: a config context on disk, or parsed fixtures in tests.
+trait SettingsSource {
+    /// The value stored under `key`; `Ok(None)` when the key was never written
+    fn read<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String>;
+}
+
+impl SettingsSource for cosmic_config::Config {
+    fn read<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.get::<T>(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if !e.is_err() => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Load the settings for `app_id`, carrying them over from the v1 schema on the first v2 launch.
+///
+/// Keys that fail to parse are logged and left at their defaults. The v1 directory is never
+/// modified, so it stays behind as a backup if anything goes wrong.
+pub fn load(app_id: &str) -> Config {
+    let context = match cosmic_config::Config::new(app_id, Config::VERSION) {
+        Ok(context) => context,
+        Err(e) => {
+            tracing::error!("Failed to open config: {}", e);
+            return Config::default();
+        }
+    };
+
+    // Every write stores all keys, so a missing `bluesky` key means v2 was never saved
+    if let Ok(None) = context.read::<BlueskyConfig>("bluesky") {
+        if let Ok(v1) = cosmic_config::Config::new(app_id, 1) {
+            let (config, errors) = migrate_v1(&v1);
+            for (key, e) in &errors {
+                tracing::warn!("Failed to migrate setting {} from v1: {}", key, e);
+            }
+            if config != Config::default() {
+                if let Err(e) = config.write_entry(&context) {
+                    tracing::error!("Failed to save migrated config: {}", e);
+                }
+                return config;
+            }
+        }
+    }
+
+    match Config::get_entry(&context) {
+        Ok(config) => config,
+        Err((errors, config)) => {
+            for e in errors {
+                tracing::warn!("Failed to read setting: {}", e);
+            }
+            config
+        }
+    }
+}
+
+/// The saved window size, read on its own so the window can open at it before the app starts.
+pub fn saved_window_size(app_id: &str) -> Option<(u32, u32)> {
+    let context = cosmic_config::Config::new(app_id, Config::VERSION).ok()?;
+    context.read::<Option<(u32, u32)>>("window_size").ok().flatten().flatten()
+}
+
+/// Read every key of the v1 schema into a v2 config.
+///
+/// v2 keeps the v1 keys, so each is copied as-is; fields that v1 configs may predate are filled
+/// by serde defaults. Keys that can't be read are returned with their error and left at the
+/// default instead of aborting the whole migration.
+fn migrate_v1(source: &impl SettingsSource) -> (Config, Vec<(&'static str, String)>) {
+    let mut config = Config::default();
+    let mut errors = Vec::new();
+
+    macro_rules! copy {
+        ($($key:ident),* $(,)?) => {
+            $(
+                match source.read(stringify!($key)) {
+                    Ok(Some(value)) => config.$key = value,
+                    Ok(None) => {}
+                    Err(e) => errors.push((stringify!($key), e)),
+                }
+            )*
+        };
+    }
+
+    copy!(
+        bluesky,
+        mastodon,
+        nostr,
+        microblog,
+        threads,
+        pixelfed,
+        discord,
+        telegram,
+        wordpress,
+        crypto_backend,
+        master_password_salt,
+        master_password_verifier,
+        scheduled_posts,
+        drafts,
+        last_session,
+        last_platforms,
+        request_timeout_secs,
+        retry_max_attempts,
+        history,
+        history_limit,
+    );
+
+    (config, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_timeout_default() {
+        let mut config = Config::default();
+        assert_eq!(config.request_timeout(), Duration::from_secs(30));
+        config.request_timeout_secs = Some(0);
+        assert_eq!(config.request_timeout(), Duration::from_secs(30));
+        config.request_timeout_secs = Some(90);
+        assert_eq!(config.request_timeout(), Duration::from_secs(90));
+    }
+
+    #[test]
+    fn test_max_concurrent_posts_default() {
+        let mut config = Config::default();
+        assert_eq!(config.max_concurrent_posts(), 4);
+        config.max_concurrent_posts = Some(0);
+        assert_eq!(config.max_concurrent_posts(), 4);
+        config.max_concurrent_posts = Some(1);
+        assert_eq!(config.max_concurrent_posts(), 1);
+    }
+
+    #[test]
+    fn test_record_sent_post_caps_history() {
+        let mut config = Config {
+            history_limit: Some(2),
+            ..Default::default()
+        };
+        for text in ["first", "second", "third"] {
+            config.record_sent_post(SentPost {
+                id: None,
+                sent_at: Utc::now(),
+                text: text.to_string(),
+                written_text: None,
+                image_names: Vec::new(),
+                results: vec![(Platform::Mastodon, None)],
+                nostr_article: None,
+            });
+        }
+
+        let texts: Vec<&str> = config.history.iter().map(|post| post.text.as_str()).collect();
+        assert_eq!(texts, vec!["third", "second"]);
+    }
+
+    #[test]
+    fn test_record_sent_post_merges_retries() {
+        let mut config = Config::default();
+        let attempt = |results: Vec<(Platform, Option<String>)>| SentPost {
+            id: Some("queued".to_string()),
+            sent_at: Utc::now(),
+            text: "Hello".to_string(),
+            written_text: None,
+            image_names: Vec::new(),
+            results,
+            nostr_article: None,
+        };
+        config.record_sent_post(attempt(vec![(Platform::Mastodon, None), (Platform::Bluesky, Some("Network error: offline".to_string()))]));
+        config.record_sent_post(SentPost { id: None, text: "Other".to_string(), ..attempt(vec![(Platform::Nostr, None)]) });
+        // The outbox tries Bluesky again, twice
+        config.record_sent_post(attempt(vec![(Platform::Bluesky, Some("Network error: offline".to_string()))]));
+        config.record_sent_post(attempt(vec![(Platform::Bluesky, None)]));
+
+        let texts: Vec<&str> = config.history.iter().map(|post| post.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello", "Other"]);
+        assert_eq!(config.history[0].results, vec![(Platform::Mastodon, None), (Platform::Bluesky, None)]);
+    }
+
+    #[test]
+    fn test_record_sent_post_redacts_credentials() {
+        let mut config = Config::default();
+        config.telegram.decrypted_bot_token = Zeroizing::new("123456:SECRET".to_string());
+        config.discord.decrypted_webhook_urls = Zeroizing::new(vec!["https://discord.com/api/webhooks/42/HOOKTOKEN".to_string()]);
+        config.record_sent_post(SentPost {
+            id: None,
+            sent_at: Utc::now(),
+            text: "Hi".to_string(),
+            written_text: None,
+            image_names: Vec::new(),
+            results: vec![
+                (Platform::Telegram, Some("API error: bad request to /bot123456:SECRET/sendMessage".to_string())),
+                (Platform::Discord, Some("Network error: webhooks/42/HOOKTOKEN?wait=true".to_string())),
+                (Platform::Mastodon, None),
+            ],
+            nostr_article: None,
+        });
+
+        let results = &config.history[0].results;
+        assert_eq!(results[0].1.as_deref(), Some("API error: bad request to /bot[redacted]/sendMessage"));
+        assert_eq!(results[1].1.as_deref(), Some("Network error: webhooks/42/[redacted]?wait=true"));
+        assert_eq!(results[2].1, None);
+    }
+
+    #[test]
+    fn test_recent_duplicate() {
+        let now = Utc::now();
+        let sent = |text: &str, minutes_ago: i64, error: Option<&str>| SentPost {
+            id: None,
+            sent_at: now - chrono::Duration::minutes(minutes_ago),
+            text: text.to_string(),
+            written_text: None,
+            image_names: Vec::new(),
+            results: vec![(Platform::Mastodon, error.map(str::to_string))],
+            nostr_article: None,
+        };
+        let shortened = SentPost {
+            text: "Read https://is.gd/abc".to_string(),
+            written_text: Some("Read https://example.com/a-long-article".to_string()),
+            ..sent("", 2, None)
+        };
+        let config = Config {
+            history: vec![sent("Failed one", 1, Some("timeout")), shortened, sent("Hello  World", 5, None), sent("Old news", 120, None)],
+            ..Default::default()
+        };
+        assert!(config.recent_duplicate("Read https://example.com/a-long-article", now).is_some());
+
+        assert_eq!(config.recent_duplicate("hello world\n", now).map(|post| post.text.as_str()), Some("Hello  World"));
+        assert!(config.recent_duplicate("Hello there", now).is_none());
+        assert!(config.recent_duplicate("Failed one", now).is_none());
+        assert!(config.recent_duplicate("Old news", now).is_none());
+    }
+
+    #[test]
+    fn test_mastodon_limits_stale() {
+        let now = Utc::now();
+        let mut mastodon = MastodonConfig::default();
+        assert!(mastodon.limits_stale(now));
+        mastodon.limits_fetched_at = Some(now - chrono::TimeDelta::hours(2));
+        assert!(!mastodon.limits_stale(now));
+        mastodon.limits_fetched_at = Some(now - chrono::TimeDelta::hours(25));
+        assert!(mastodon.limits_stale(now));
+    }
+
+    #[test]
+    fn test_scheduled_post_due() {
+        let now = Utc::now();
+        let mut post = ScheduledPost {
+            id: "1".to_string(),
+            text: "Later".to_string(),
+            images: Vec::new(),
+            platforms: vec![Platform::Mastodon],
+            scheduled_at: now - chrono::Duration::minutes(5),
+            thread_mode: false,
+            markdown: false,
+            language: None,
+            last_error: None,
+            offline: false,
+            signature: false,
+            mastodon_visibility: MastodonVisibility::default(),
+            spoiler_text: None,
+            poll: None,
+            reference_urls: HashMap::new(),
+            quote: false,
+            nostr_article: None,
+            wordpress_title: None,
+            lemmy_title: None,
+            microblog_title: None,
+            microblog_categories: Vec::new(),
+        };
+
+        // Missed while the app was closed: still due
+        assert!(post.is_due(now));
+
+        // Errored posts wait for a retry
+        post.last_error = Some("Network error".to_string());
+        assert!(!post.is_due(now));
+
+        post.last_error = None;
+        post.scheduled_at = now + chrono::Duration::minutes(5);
+        assert!(!post.is_due(now));
+
+        // Outbox posts keep their error but are still tried again, sooner once resumed
+        post.last_error = Some("Network error".to_string());
+        post.offline = true;
+        assert!(!post.is_due(now));
+        let mut config = Config { scheduled_posts: vec![post], ..Default::default() };
+        assert!(config.resume_outbox(now));
+        assert!(config.scheduled_posts[0].is_due(now));
+        assert!(!Config::default().resume_outbox(now));
+    }
+
+    #[test]
+    fn test_scheduled_post_keeps_titles() {
+        let post = ScheduledPost {
+            id: "1".to_string(),
+            text: "A longer piece".to_string(),
+            images: Vec::new(),
+            platforms: vec![Platform::MicroBlog],
+            scheduled_at: Utc::now(),
+            thread_mode: false,
+            markdown: true,
+            language: None,
+            last_error: None,
+            offline: false,
+            signature: false,
+            mastodon_visibility: MastodonVisibility::default(),
+            spoiler_text: None,
+            poll: None,
+            reference_urls: HashMap::new(),
+            quote: false,
+            nostr_article: None,
+            wordpress_title: None,
+            lemmy_title: None,
+            microblog_title: Some("On gardening".to_string()),
+            microblog_categories: vec!["garden".to_string()],
+        };
+
+        // Still a long-form entry once saved and loaded back for sending
+        let saved: ScheduledPost = ron::from_str(&ron::to_string(&post).unwrap()).unwrap();
+        let options = saved.post_options();
+        assert_eq!(options.microblog.title.as_deref(), Some("On gardening"));
+        assert_eq!(options.microblog.categories, vec!["garden".to_string()]);
+        assert_eq!(options.idempotency_key.as_deref(), Some("1"));
+    }
+
+    /// v1 keys as they were written to disk, parsed the same way cosmic-config does
+    struct V1Files(std::collections::HashMap<&'static str, &'static str>);
+
+    impl SettingsSource for V1Files {
+        fn read<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+            self.0
+                .get(key)
+                .map(|value| ron::from_str(value).map_err(|e| e.to_string()))
+                .transpose()
+        }
+    }
+
+    #[test]
+    fn test_migrate_v1_keeps_accounts() {
+        // Written before Mastodon cached its limit and Nostr had a media host
+        let v1 = V1Files(
+            [
+                ("bluesky", r#"(enabled: true, handle: "alice.bsky.social", password: Some((ciphertext: [1, 2, 3], nonce: [4, 5, 6], salt: "machine")))"#),
+                ("mastodon", r#"(enabled: true, instance_url: "https://mastodon.social", access_token: Some((ciphertext: [7, 8], nonce: [9], salt: "machine")))"#),
+                ("nostr", r#"(enabled: true, private_key: Some((ciphertext: [10], nonce: [11], salt: "machine")), relays: ["wss://relay.example.com", "wss://nos.lol"])"#),
+                ("microblog", r#"(enabled: maybe)"#),
+                ("crypto_backend", "Password"),
+                ("master_password_salt", r#"Some("c2FsdA")"#),
+                ("last_platforms", "Some([Bluesky, Nostr])"),
+                ("history_limit", "Some(20)"),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let (config, errors) = migrate_v1(&v1);
+
+        assert!(config.bluesky.enabled);
+        assert_eq!(config.bluesky.handle, "alice.bsky.social");
+        assert_eq!(config.bluesky.password.as_ref().map(|p| p.ciphertext.clone()), Some(vec![1, 2, 3]));
+        assert_eq!(config.mastodon.instance_url, "https://mastodon.social");
+        assert!(config.mastodon.access_token.is_some());
+        assert_eq!(config.bluesky.service_url, "https://bsky.social");
+        assert_eq!(config.mastodon.max_characters, None);
+        assert_eq!(config.nostr.relays, vec!["wss://relay.example.com", "wss://nos.lol"]);
+        assert!(config.nostr.private_key.is_some());
+        assert_eq!(config.nostr.media_host, default_nostr_media_host());
+        assert_eq!(config.crypto_backend, CryptoBackend::Password);
+        assert_eq!(config.master_password_salt.as_deref(), Some("c2FsdA"));
+        assert_eq!(config.last_platforms, Some(vec![Platform::Bluesky, Platform::Nostr]));
+        assert_eq!(config.history_limit, Some(20));
+
+        // A bad key is reported and left at its default without losing the rest
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "microblog");
+        assert_eq!(config.microblog, MicroBlogConfig::default());
+
+        // Keys that were never written keep their defaults
+        assert_eq!(config.threads, ThreadsConfig::default());
+    }
+
+    #[test]
+    fn test_same_settings_ignores_stored_credentials() {
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+
+        let mut saved = Config::default();
+        saved.mastodon.decrypted_access_token = Zeroizing::new("token".to_string());
+        let edited = saved.clone();
+        saved.encrypt_credentials(&crypto).unwrap();
+        assert!(edited.same_settings(&saved));
+
+        let mut edited = saved.clone();
+        edited.mastodon.decrypted_access_token = Zeroizing::new("new token".to_string());
+        assert!(!edited.same_settings(&saved));
+
+        let mut edited = saved.clone();
+        edited.mastodon.instance_url = "https://example.social".to_string();
+        assert!(!edited.same_settings(&saved));
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mut config = Config::default();
+        config.bluesky.enabled = true;
+        config.bluesky.handle = "alice.bsky.social".to_string();
+        config.bluesky.decrypted_password = Zeroizing::new("app-password".to_string());
+        config.discord.decrypted_webhook_urls = Zeroizing::new(vec!["https://discord.com/api/webhooks/1/a".to_string()]);
+        config.nostr.relays = vec!["wss://relay.example.com".to_string()];
+
+        let exported = config.export("correct horse").unwrap();
+        assert!(!exported.contains("app-password"));
+
+        let mut local = CryptoManager::new();
+        local.init_with_machine_key(KdfParams::default()).unwrap();
+        let imported = Config::default().import(&exported, "correct horse", &local).unwrap();
+        assert_eq!(imported.bluesky.handle, "alice.bsky.social");
+        assert_eq!(imported.bluesky.decrypted_password.as_str(), "app-password");
+        assert_eq!(imported.discord.decrypted_webhook_urls, config.discord.decrypted_webhook_urls);
+        assert_eq!(imported.nostr.relays, vec!["wss://relay.example.com"]);
+
+        // Stored credentials are now under the local key
+        let stored = imported.bluesky.password.as_ref().unwrap();
+        assert_eq!(local.decrypt(BLUESKY_PASSWORD_LABEL, stored).unwrap().as_str(), "app-password");
+
+        assert!(matches!(
+            Config::default().import(&exported, "battery staple", &local),
+            Err(CryptoError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_change_master_password() {
+        let salt = CryptoManager::generate_salt();
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_password("old password", &salt, KdfParams::default()).unwrap();
+        let mut config = Config {
+            crypto_backend: CryptoBackend::Password,
+            master_password_salt: Some(salt),
+            master_password_verifier: Some(crypto.create_verifier().unwrap()),
+            ..Default::default()
+        };
+        config.nostr.decrypted_private_key = Zeroizing::new("nsec1secret".to_string());
+        config.encrypt_credentials(&crypto).unwrap();
+
+        assert!(matches!(
+            config.change_master_password("wrong password", "new password"),
+            Err(CryptoError::DecryptionFailed)
+        ));
+
+        let (changed, new_crypto) = config.change_master_password("old password", "new password").unwrap();
+        assert_ne!(changed.master_password_salt, config.master_password_salt);
+        assert!(new_crypto.verify(changed.master_password_verifier.as_ref().unwrap()));
+        let stored = changed.nostr.private_key.as_ref().unwrap();
+        assert_eq!(new_crypto.decrypt(NOSTR_KEY_LABEL, stored).unwrap().as_str(), "nsec1secret");
+        assert!(crypto.decrypt(NOSTR_KEY_LABEL, stored).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_legacy_credentials() {
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+        let mut config = Config::default();
+        config.mastodon.decrypted_access_token = Zeroizing::new("token".to_string());
+        config.encrypt_credentials(&crypto).unwrap();
+        assert!(!config.upgrade_credentials(&crypto).unwrap());
+
+        // As loaded from a config written before the version field existed
+        config.mastodon.access_token.as_mut().unwrap().version = 0;
+        assert!(config.upgrade_credentials(&crypto).unwrap());
+        let stored = config.mastodon.access_token.as_ref().unwrap();
+        assert!(!stored.is_legacy());
+        assert_eq!(crypto.decrypt(MASTODON_TOKEN_LABEL, stored).unwrap().as_str(), "token");
+    }
+
+    #[test]
+    fn test_encrypt_drops_cleared_credentials() {
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+        let mut config = Config::default();
+        config.discord.decrypted_webhook_urls = Zeroizing::new(vec!["https://discord.com/api/webhooks/1/secret".to_string()]);
+        config.telegram.decrypted_bot_token = Zeroizing::new("123:secret".to_string());
+        config.captioner.decrypted_token = Zeroizing::new("caption-token".to_string());
+        config.url_shortener.decrypted_api_key = Zeroizing::new("shlink-key".to_string());
+        config.bluesky.password = Some(crypto.encrypt(LEMMY_PASSWORD_LABEL, "password").unwrap());
+        config.bluesky.decrypt_failed = true;
+        config.encrypt_credentials(&crypto).unwrap();
+        assert!(config.discord.webhook_urls.is_some());
+        assert!(config.telegram.bot_token.is_some());
+
+        config.discord.decrypted_webhook_urls.clear();
+        config.telegram.decrypted_bot_token = Zeroizing::default();
+        config.captioner.decrypted_token = Zeroizing::default();
+        assert!(config.url_shortener.api_key.is_some());
+        config.url_shortener.decrypted_api_key = Zeroizing::default();
+        config.encrypt_credentials(&crypto).unwrap();
+        assert_eq!(config.discord.webhook_urls, None);
+        assert_eq!(config.telegram.bot_token, None);
+        assert_eq!(config.captioner.token, None);
+        assert_eq!(config.url_shortener.api_key, None);
+        // Never decrypted, so it wasn't cleared either
+        assert!(config.bluesky.password.is_some());
+    }
+
+    #[test]
+    fn test_decrypt_credentials_independently() {
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+        let mut saved = Config::default();
+        saved.mastodon.decrypted_access_token = Zeroizing::new("token".to_string());
+        saved.nostr.decrypted_private_key = Zeroizing::new("nsec".to_string());
+        saved.encrypt_credentials(&crypto).unwrap();
+        // A blob that won't open under Bluesky's label, as if it came from elsewhere
+        saved.bluesky.password = Some(crypto.encrypt(LEMMY_PASSWORD_LABEL, "password").unwrap());
+
+        // As loaded, with nothing decrypted yet
+        let mut config = saved.clone();
+        config.mastodon.decrypted_access_token = Zeroizing::default();
+        config.nostr.decrypted_private_key = Zeroizing::default();
+        assert_eq!(config.decrypt_credentials(&crypto), Err(vec![SettingsSection::Platform(Platform::Bluesky)]));
+        assert!(config.bluesky.decrypted_password.is_empty());
+        assert!(config.decrypt_failed(SettingsSection::Platform(Platform::Bluesky)));
+        assert!(!config.decrypt_failed(SettingsSection::Platform(Platform::Mastodon)));
+        assert_eq!(config.mastodon.decrypted_access_token.as_str(), "token");
+        assert_eq!(config.nostr.decrypted_private_key.as_str(), "nsec");
+    }
+
+    #[test]
+    fn test_upgrade_weak_kdf_params() {
+        let weak = Config {
+            argon2_memory_kib: Some(64),
+            argon2_iterations: Some(1),
+            ..Default::default()
+        };
+        let mut old = CryptoManager::new();
+        old.init_with_machine_key(weak.kdf_params()).unwrap();
+        let mut config = Config::default();
+        config.nostr.decrypted_private_key = Zeroizing::new("nsec1secret".to_string());
+        config.encrypt_credentials(&old).unwrap();
+
+        // Loaded again once the settings are back to the stronger defaults
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(config.kdf_params()).unwrap();
+        config.nostr.decrypted_private_key.clear();
+        config.decrypt_credentials(&crypto).unwrap();
+        assert_eq!(config.nostr.decrypted_private_key.as_str(), "nsec1secret");
+        assert!(config.upgrade_credentials(&crypto).unwrap());
+
+        let stored = config.nostr.private_key.as_ref().unwrap();
+        assert_eq!(stored.kdf, Some(KdfParams::default()));
+        assert!(old.decrypt(NOSTR_KEY_LABEL, stored).is_err());
+        assert!(!config.upgrade_credentials(&crypto).unwrap());
+    }
+}
@@ -1,13 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
     Aes256Gcm, Key, Nonce,
 };
-use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version, password_hash::SaltString};
 use serde::{Deserialize, Serialize};
+use crate::fl;
 use std::collections::HashMap;
-use zeroize::Zeroize;
+use std::sync::Mutex;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Known plaintext encrypted with the master key to verify an unlock attempt
+const VERIFIER_PLAINTEXT: &str = "yall-cosmic-master-password-check";
+const VERIFIER_LABEL: &str = "master-password-verifier";
+/// Format written by `encrypt`: the credential's label is bound to the ciphertext as associated data.
+/// Version 0 blobs were encrypted without it.
+pub const ENCRYPTED_DATA_VERSION: u32 = 1;
 
 #[derive(Debug, Clone)]
 pub enum CryptoError {
@@ -15,6 +24,7 @@ pub enum CryptoError {
     DecryptionFailed,
     KeyDerivationFailed,
     InvalidData,
+    Keyring(String),
 }
 
 impl std::fmt::Display for CryptoError {
@@ -24,24 +34,75 @@ impl std::fmt::Display for CryptoError {
             CryptoError::DecryptionFailed => write!(f, "Decryption failed"),
             CryptoError::KeyDerivationFailed => write!(f, "Key derivation failed"),
             CryptoError::InvalidData => write!(f, "Invalid encrypted data"),
+            CryptoError::Keyring(e) => write!(f, "Keyring error: {}", e),
         }
     }
 }
 
 impl std::error::Error for CryptoError {}
 
+/// Argon2id cost settings a key is derived with, recorded on each blob so it can be decrypted
+/// after the settings change.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct KdfParams {
+    /// Memory used, in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// `Argon2::default()`'s settings, which blobs without recorded settings were derived with
+    pub const LEGACY: KdfParams = KdfParams {
+        memory_kib: Params::DEFAULT_M_COST,
+        iterations: Params::DEFAULT_T_COST,
+        parallelism: Params::DEFAULT_P_COST,
+    };
+
+    fn argon2(&self) -> Result<Argon2<'static>, CryptoError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None).map_err(|_| CryptoError::KeyDerivationFailed)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl Default for KdfParams {
+    /// 64 MiB and 3 passes, in line with current recommendations for interactive use
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+/// A credential encrypted with the manager's key.
+///
+/// Blobs written by older versions also carry a `salt`, which was never used and is ignored.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct EncryptedData {
     pub ciphertext: Vec<u8>,
     pub nonce: Vec<u8>,
-    pub salt: String,
+    /// Missing from blobs written before associated data was added
+    #[serde(default)]
+    pub version: u32,
+    /// Settings the key was derived with; missing from blobs written with `KdfParams::LEGACY`,
+    /// and from keyring placeholders, which have no key
+    #[serde(default)]
+    pub kdf: Option<KdfParams>,
+}
+
+impl EncryptedData {
+    /// Whether this was encrypted without associated data and should be encrypted again
+    pub fn is_legacy(&self) -> bool {
+        self.version < ENCRYPTED_DATA_VERSION && !self.ciphertext.is_empty()
+    }
 }
 
 impl Zeroize for EncryptedData {
     fn zeroize(&mut self) {
         self.ciphertext.zeroize();
         self.nonce.zeroize();
-        self.salt.zeroize();
     }
 }
 
@@ -56,43 +117,149 @@ pub struct SecureCredentials {
     pub encrypted_data: HashMap<String, EncryptedData>,
 }
 
-pub struct CryptoManager {
-    master_key: Option<Key<Aes256Gcm>>,
+/// Where credentials are protected, recorded in the config so loading picks the right path
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CryptoBackend {
+    /// AES-256-GCM with a key derived from machine-specific data
+    #[default]
+    MachineKey,
+    /// AES-256-GCM with a key derived from the user's master password
+    Password,
+    /// Secrets stored in the system secret service (org.freedesktop.secrets)
+    Keyring,
+}
+
+impl CryptoBackend {
+    pub fn name(&self) -> String {
+        match self {
+            CryptoBackend::MachineKey => fl!("backend-machine-key"),
+            CryptoBackend::Password => fl!("backend-master-password"),
+            CryptoBackend::Keyring => fl!("backend-keyring"),
+        }
+    }
+}
+
+/// Stores each credential in the system keyring under a service/account pair
+pub struct KeyringBackend {
+    service: String,
+}
+
+impl KeyringBackend {
+    pub fn new(service: &str) -> Self {
+        Self { service: service.to_string() }
+    }
+
+    fn entry(&self, label: &str) -> Result<keyring::Entry, CryptoError> {
+        keyring::Entry::new(&self.service, label).map_err(|e| CryptoError::Keyring(e.to_string()))
+    }
+
+    pub fn store(&self, label: &str, secret: &str) -> Result<(), CryptoError> {
+        self.entry(label)?
+            .set_password(secret)
+            .map_err(|e| CryptoError::Keyring(e.to_string()))
+    }
+
+    pub fn load(&self, label: &str) -> Result<String, CryptoError> {
+        self.entry(label)?
+            .get_password()
+            .map_err(|e| CryptoError::Keyring(e.to_string()))
+    }
+
+    pub fn delete(&self, label: &str) -> Result<(), CryptoError> {
+        match self.entry(label)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(CryptoError::Keyring(e.to_string())),
+        }
+    }
+}
+
+/// The key the AES backends encrypt with, and what's needed to derive the keys of blobs
+/// written with other Argon2 settings.
+pub struct KeyMaterial {
+    secret: Zeroizing<Vec<u8>>,
+    salt: String,
+    params: KdfParams,
+    key: Key<Aes256Gcm>,
+    /// Keys derived for other settings, kept so each is only derived once
+    other_keys: Mutex<HashMap<KdfParams, Key<Aes256Gcm>>>,
+}
+
+impl KeyMaterial {
+    fn derive(secret: &[u8], salt: &str, params: KdfParams) -> Result<Self, CryptoError> {
+        Ok(KeyMaterial {
+            secret: Zeroizing::new(secret.to_vec()),
+            salt: salt.to_string(),
+            params,
+            key: derive_key(secret, salt, params)?,
+            other_keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The key for a blob written with `params`
+    fn key_for(&self, params: KdfParams) -> Result<Key<Aes256Gcm>, CryptoError> {
+        if params == self.params {
+            return Ok(self.key);
+        }
+        let mut other_keys = self.other_keys.lock().map_err(|_| CryptoError::KeyDerivationFailed)?;
+        if let Some(key) = other_keys.get(&params) {
+            return Ok(*key);
+        }
+        let key = derive_key(&self.secret, &self.salt, params)?;
+        other_keys.insert(params, key);
+        Ok(key)
+    }
+}
+
+impl Drop for KeyMaterial {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        if let Ok(other_keys) = self.other_keys.get_mut() {
+            for key in other_keys.values_mut() {
+                key.zeroize();
+            }
+        }
+    }
+}
+
+pub enum CryptoManager {
+    Uninitialized,
+    MachineKey(KeyMaterial),
+    Password(KeyMaterial),
+    Keyring(KeyringBackend),
 }
 
 impl CryptoManager {
     pub fn new() -> Self {
-        Self { master_key: None }
+        CryptoManager::Uninitialized
     }
 
-    /// Initialize with a master password (derived from user input)
-    /// Currently unused but available for future master password feature
-    #[allow(dead_code)]
-    pub fn init_with_password(&mut self, password: &str) -> Result<(), CryptoError> {
-        // Generate a random salt for key derivation
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        
-        // Derive key from password
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|_| CryptoError::KeyDerivationFailed)?;
-        
-        // Use the hash as our encryption key (first 32 bytes)
-        let hash = password_hash.hash.unwrap();
-        let key_bytes = hash.as_bytes();
-        if key_bytes.len() < 32 {
-            return Err(CryptoError::KeyDerivationFailed);
+    /// The backend this manager encrypts with, if initialized
+    pub fn backend(&self) -> Option<CryptoBackend> {
+        match self {
+            CryptoManager::Uninitialized => None,
+            CryptoManager::MachineKey(_) => Some(CryptoBackend::MachineKey),
+            CryptoManager::Password(_) => Some(CryptoBackend::Password),
+            CryptoManager::Keyring(_) => Some(CryptoBackend::Keyring),
         }
-        
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes[..32]);
-        self.master_key = Some(*key);
-        
+    }
+
+    /// Generate a new random salt for master password key derivation
+    pub fn generate_salt() -> String {
+        SaltString::generate(&mut OsRng).to_string()
+    }
+
+    /// Initialize with a master password (derived from user input)
+    ///
+    /// The salt must be the same one used when the credentials were encrypted,
+    /// so it is stored alongside the config. New blobs are encrypted with a key derived
+    /// with `params`.
+    pub fn init_with_password(&mut self, password: &str, salt: &str, params: KdfParams) -> Result<(), CryptoError> {
+        *self = CryptoManager::Password(KeyMaterial::derive(password.as_bytes(), salt, params)?);
         Ok(())
     }
 
     /// Generate a master key from machine-specific data (fallback when no password is set)
-    pub fn init_with_machine_key(&mut self) -> Result<(), CryptoError> {
+    pub fn init_with_machine_key(&mut self, params: KdfParams) -> Result<(), CryptoError> {
         // Use machine-specific data as entropy
         let machine_id = std::env::var("HOSTNAME")
             .or_else(|_| std::env::var("COMPUTERNAME"))
@@ -105,52 +272,86 @@ impl CryptoManager {
         let combined = format!("yall-cosmic-{}-{}", machine_id, user);
         
         // Derive key using Argon2
-        let salt = SaltString::from_b64("c29tZXNhbHR2YWx1ZQ").unwrap(); // Fixed salt for machine keys
-        let argon2 = Argon2::default();
-        
-        let password_hash = argon2
-            .hash_password(combined.as_bytes(), &salt)
-            .map_err(|_| CryptoError::KeyDerivationFailed)?;
-        
-        let hash = password_hash.hash.unwrap();
-        let key_bytes = hash.as_bytes();
-        if key_bytes.len() < 32 {
-            return Err(CryptoError::KeyDerivationFailed);
-        }
-        
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes[..32]);
-        self.master_key = Some(*key);
-        
+        let salt = "c29tZXNhbHR2YWx1ZQ"; // Fixed salt for machine keys
+        *self = CryptoManager::MachineKey(KeyMaterial::derive(combined.as_bytes(), salt, params)?);
         Ok(())
     }
 
+    /// Store credentials in the system keyring under the given service name
+    pub fn init_with_keyring(&mut self, service: &str) {
+        *self = CryptoManager::Keyring(KeyringBackend::new(service));
+    }
+
+    /// Encrypt a known value so a later unlock can tell a wrong password from corrupt data
+    pub fn create_verifier(&self) -> Result<EncryptedData, CryptoError> {
+        self.encrypt(VERIFIER_LABEL, VERIFIER_PLAINTEXT)
+    }
+
+    /// Check whether the current key matches the one that produced `verifier`
+    pub fn verify(&self, verifier: &EncryptedData) -> bool {
+        matches!(self.decrypt(VERIFIER_LABEL, verifier), Ok(plaintext) if plaintext.as_str() == VERIFIER_PLAINTEXT)
+    }
+
+    /// Whether `encrypted` should be encrypted again: it predates associated data, or its key
+    /// was derived with other Argon2 settings than this manager's.
+    pub fn is_outdated(&self, encrypted: &EncryptedData) -> bool {
+        match self {
+            CryptoManager::MachineKey(material) | CryptoManager::Password(material) => {
+                encrypted.is_legacy() || (!encrypted.ciphertext.is_empty() && encrypted.kdf.unwrap_or(KdfParams::LEGACY) != material.params)
+            }
+            _ => encrypted.is_legacy(),
+        }
+    }
+
     /// Encrypt a credential value
-    pub fn encrypt(&self, plaintext: &str) -> Result<EncryptedData, CryptoError> {
-        let key = self.master_key.ok_or(CryptoError::EncryptionFailed)?;
-        let cipher = Aes256Gcm::new(&key);
+    ///
+    /// With the keyring backend the secret is stored under `label` and the returned
+    /// data is only a placeholder recording that the value exists.
+    pub fn encrypt(&self, label: &str, plaintext: &str) -> Result<EncryptedData, CryptoError> {
+        let material = match self {
+            CryptoManager::MachineKey(material) | CryptoManager::Password(material) => material,
+            CryptoManager::Keyring(keyring) => {
+                keyring.store(label, plaintext)?;
+                return Ok(EncryptedData {
+                    ciphertext: Vec::new(),
+                    nonce: Vec::new(),
+                    version: ENCRYPTED_DATA_VERSION,
+                    kdf: None,
+                });
+            }
+            CryptoManager::Uninitialized => return Err(CryptoError::EncryptionFailed),
+        };
+        let cipher = Aes256Gcm::new(&material.key);
         
         // Generate random nonce
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
         
-        // Encrypt the data
+        // Encrypt the data, bound to its label so it can't be moved to another credential
+        let payload = Payload {
+            msg: plaintext.as_bytes(),
+            aad: label.as_bytes(),
+        };
         let ciphertext = cipher
-            .encrypt(&nonce, plaintext.as_bytes())
+            .encrypt(&nonce, payload)
             .map_err(|_| CryptoError::EncryptionFailed)?;
         
-        // Generate salt for this specific encryption
-        let salt = SaltString::generate(&mut OsRng);
-        
         Ok(EncryptedData {
             ciphertext,
             nonce: nonce.to_vec(),
-            salt: salt.to_string(),
+            version: ENCRYPTED_DATA_VERSION,
+            kdf: Some(material.params),
         })
     }
 
-    /// Decrypt a credential value
-    pub fn decrypt(&self, encrypted: &EncryptedData) -> Result<String, CryptoError> {
-        let key = self.master_key.ok_or(CryptoError::DecryptionFailed)?;
+    /// Decrypt a credential value; the plaintext is wiped when the returned value is dropped
+    pub fn decrypt(&self, label: &str, encrypted: &EncryptedData) -> Result<Zeroizing<String>, CryptoError> {
+        let mut key = match self {
+            CryptoManager::MachineKey(material) | CryptoManager::Password(material) => material.key_for(encrypted.kdf.unwrap_or(KdfParams::LEGACY))?,
+            CryptoManager::Keyring(keyring) => return keyring.load(label).map(Zeroizing::new),
+            CryptoManager::Uninitialized => return Err(CryptoError::DecryptionFailed),
+        };
         let cipher = Aes256Gcm::new(&key);
+        key.zeroize();
         
         // Reconstruct nonce
         if encrypted.nonce.len() != 12 {
@@ -158,23 +359,50 @@ impl CryptoManager {
         }
         let nonce = Nonce::from_slice(&encrypted.nonce);
         
-        // Decrypt the data
+        // Decrypt the data; legacy blobs have no associated data to check
+        let aad: &[u8] = if encrypted.version >= ENCRYPTED_DATA_VERSION { label.as_bytes() } else { &[] };
+        let payload = Payload {
+            msg: encrypted.ciphertext.as_ref(),
+            aad,
+        };
         let plaintext = cipher
-            .decrypt(nonce, encrypted.ciphertext.as_ref())
+            .decrypt(nonce, payload)
             .map_err(|_| CryptoError::DecryptionFailed)?;
         
-        String::from_utf8(plaintext).map_err(|_| CryptoError::InvalidData)
+        String::from_utf8(plaintext).map(Zeroizing::new).map_err(|e| {
+            e.into_bytes().zeroize();
+            CryptoError::InvalidData
+        })
     }
-}
 
-impl Drop for CryptoManager {
-    fn drop(&mut self) {
-        if let Some(mut key) = self.master_key.take() {
-            key.zeroize();
+    /// Remove any copy of a credential held outside the config (only the keyring keeps one)
+    pub fn forget(&self, label: &str) -> Result<(), CryptoError> {
+        match self {
+            CryptoManager::Keyring(keyring) => keyring.delete(label),
+            _ => Ok(()),
         }
     }
 }
 
+/// Derive a 256-bit AES key from secret material with Argon2
+fn derive_key(secret: &[u8], salt: &str, params: KdfParams) -> Result<Key<Aes256Gcm>, CryptoError> {
+    let salt = SaltString::from_b64(salt).map_err(|_| CryptoError::KeyDerivationFailed)?;
+    let argon2 = params.argon2()?;
+
+    let password_hash = argon2
+        .hash_password(secret, &salt)
+        .map_err(|_| CryptoError::KeyDerivationFailed)?;
+
+    // Use the hash as our encryption key (first 32 bytes)
+    let hash = password_hash.hash.ok_or(CryptoError::KeyDerivationFailed)?;
+    let key_bytes = hash.as_bytes();
+    if key_bytes.len() < 32 {
+        return Err(CryptoError::KeyDerivationFailed);
+    }
+
+    Ok(*Key::<Aes256Gcm>::from_slice(&key_bytes[..32]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,12 +410,102 @@ mod tests {
     #[test]
     fn test_encrypt_decrypt() {
         let mut crypto = CryptoManager::new();
-        crypto.init_with_machine_key().unwrap();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
         
         let plaintext = "secret-token-123";
-        let encrypted = crypto.encrypt(plaintext).unwrap();
-        let decrypted = crypto.decrypt(&encrypted).unwrap();
+        let encrypted = crypto.encrypt("test", plaintext).unwrap();
+        let decrypted = crypto.decrypt("test", &encrypted).unwrap();
         
-        assert_eq!(plaintext, decrypted);
+        assert_eq!(plaintext, decrypted.as_str());
+    }
+
+    #[test]
+    fn test_ciphertext_bound_to_label() {
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+
+        let encrypted = crypto.encrypt("mastodon-token", "secret-token-123").unwrap();
+        assert_eq!(encrypted.version, ENCRYPTED_DATA_VERSION);
+        assert!(matches!(crypto.decrypt("nostr-key", &encrypted), Err(CryptoError::DecryptionFailed)));
+        assert_eq!(crypto.decrypt("mastodon-token", &encrypted).unwrap().as_str(), "secret-token-123");
+    }
+
+    #[test]
+    fn test_decrypt_legacy_data() {
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+        let CryptoManager::MachineKey(material) = &crypto else {
+            unreachable!();
+        };
+
+        // Written the way encrypt did before associated data was added, with Argon2's defaults
+        let key = material.key_for(KdfParams::LEGACY).unwrap();
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let legacy = EncryptedData {
+            ciphertext: Aes256Gcm::new(&key).encrypt(&nonce, b"old-secret".as_ref()).unwrap(),
+            nonce: nonce.to_vec(),
+            version: 0,
+            kdf: None,
+        };
+        assert!(legacy.is_legacy());
+        assert!(crypto.is_outdated(&legacy));
+        assert_eq!(crypto.decrypt("test", &legacy).unwrap().as_str(), "old-secret");
+    }
+
+    #[test]
+    fn test_decrypt_with_other_kdf_params() {
+        let salt = CryptoManager::generate_salt();
+        let weak = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+        let mut old = CryptoManager::new();
+        old.init_with_password("correct horse", &salt, weak).unwrap();
+        let encrypted = old.encrypt("test", "secret-token-123").unwrap();
+        assert_eq!(encrypted.kdf, Some(weak));
+        assert!(!old.is_outdated(&encrypted));
+
+        // Stronger settings derive a different key, but the blob says which one it needs
+        let mut current = CryptoManager::new();
+        current.init_with_password("correct horse", &salt, KdfParams::default()).unwrap();
+        assert!(current.is_outdated(&encrypted));
+        assert_eq!(current.decrypt("test", &encrypted).unwrap().as_str(), "secret-token-123");
+        assert!(!current.is_outdated(&current.encrypt("test", "secret-token-123").unwrap()));
+    }
+
+    #[test]
+    fn test_load_blob_with_unused_salt() {
+        let old: EncryptedData = serde_json::from_str(r#"{"ciphertext":[1,2],"nonce":[3],"salt":"c29tZXNhbHQ"}"#).unwrap();
+        assert_eq!(old.ciphertext, vec![1, 2]);
+        assert_eq!(old.version, 0);
+        assert!(!serde_json::to_string(&old).unwrap().contains("salt"));
+    }
+
+    #[test]
+    fn test_password_verifier() {
+        let salt = CryptoManager::generate_salt();
+
+        let mut crypto = CryptoManager::new();
+        crypto.init_with_password("correct horse", &salt, KdfParams::default()).unwrap();
+        let verifier = crypto.create_verifier().unwrap();
+        let encrypted = crypto.encrypt("test", "secret-token-123").unwrap();
+
+        // Same password and salt derive the same key
+        let mut unlocked = CryptoManager::new();
+        unlocked.init_with_password("correct horse", &salt, KdfParams::default()).unwrap();
+        assert!(unlocked.verify(&verifier));
+        assert_eq!(unlocked.decrypt("test", &encrypted).unwrap().as_str(), "secret-token-123");
+
+        let mut wrong = CryptoManager::new();
+        wrong.init_with_password("battery staple", &salt, KdfParams::default()).unwrap();
+        assert!(!wrong.verify(&verifier));
+    }
+
+    #[test]
+    fn test_backend_reported() {
+        let mut crypto = CryptoManager::new();
+        assert_eq!(crypto.backend(), None);
+        crypto.init_with_machine_key(KdfParams::default()).unwrap();
+        assert_eq!(crypto.backend(), Some(CryptoBackend::MachineKey));
+        crypto.init_with_keyring("com.example.test");
+        assert_eq!(crypto.backend(), Some(CryptoBackend::Keyring));
     }
 }
\ No newline at end of file
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Built-in emoji for the compose view's picker.
+
+/// An emoji and the name it can be searched by.
+pub type Emoji = (&'static str, &'static str);
+
+/// Emoji grouped by category, in the order the picker shows them.
+pub const CATEGORIES: &[(&str, &[Emoji])] = &[
+    (
+        "Smileys",
+        &[
+            ("😀", "grinning face"),
+            ("😂", "face with tears of joy"),
+            ("🙂", "slightly smiling face"),
+            ("😉", "winking face"),
+            ("😍", "smiling face with heart eyes"),
+            ("🥰", "smiling face with hearts"),
+            ("😎", "smiling face with sunglasses"),
+            ("🤔", "thinking face"),
+            ("😅", "grinning face with sweat"),
+            ("🙃", "upside down face"),
+            ("😢", "crying face"),
+            ("😭", "loudly crying face"),
+            ("😡", "angry face"),
+            ("😱", "face screaming in fear"),
+            ("🥳", "partying face"),
+            ("😴", "sleeping face"),
+        ],
+    ),
+    (
+        "Gestures",
+        &[
+            ("👍", "thumbs up"),
+            ("👎", "thumbs down"),
+            ("👏", "clapping hands"),
+            ("🙌", "raising hands"),
+            ("🙏", "folded hands thanks"),
+            ("👋", "waving hand hello"),
+            ("🤝", "handshake"),
+            ("✌️", "victory hand peace"),
+            ("🤞", "crossed fingers"),
+            ("💪", "flexed biceps strong"),
+            ("👀", "eyes"),
+            ("🫶", "heart hands"),
+        ],
+    ),
+    (
+        "Hearts",
+        &[
+            ("❤️", "red heart love"),
+            ("🧡", "orange heart"),
+            ("💛", "yellow heart"),
+            ("💚", "green heart"),
+            ("💙", "blue heart"),
+            ("💜", "purple heart"),
+            ("🖤", "black heart"),
+            ("💔", "broken heart"),
+            ("✨", "sparkles"),
+            ("🔥", "fire"),
+            ("💯", "hundred points"),
+            ("⭐", "star"),
+        ],
+    ),
+    (
+        "Nature",
+        &[
+            ("🐶", "dog face"),
+            ("🐱", "cat face"),
+            ("🦊", "fox"),
+            ("🐻", "bear"),
+            ("🐧", "penguin"),
+            ("🦋", "butterfly"),
+            ("🌸", "cherry blossom flower"),
+            ("🌻", "sunflower"),
+            ("🌲", "evergreen tree"),
+            ("🌈", "rainbow"),
+            ("☀️", "sun"),
+            ("🌙", "crescent moon"),
+        ],
+    ),
+    (
+        "Food",
+        &[
+            ("☕", "hot beverage coffee"),
+            ("🍵", "teacup tea"),
+            ("🍺", "beer mug"),
+            ("🍷", "wine glass"),
+            ("🍕", "pizza"),
+            ("🍔", "hamburger"),
+            ("🌮", "taco"),
+            ("🍣", "sushi"),
+            ("🍰", "shortcake"),
+            ("🍎", "red apple"),
+            ("🥑", "avocado"),
+            ("🍿", "popcorn"),
+        ],
+    ),
+    (
+        "Activities",
+        &[
+            ("🎉", "party popper celebration"),
+            ("🎂", "birthday cake"),
+            ("🎁", "wrapped gift"),
+            ("🎮", "video game"),
+            ("🎵", "musical note"),
+            ("🎧", "headphone"),
+            ("📷", "camera"),
+            ("⚽", "soccer ball football"),
+            ("🏃", "person running"),
+            ("🚴", "person biking"),
+            ("✈️", "airplane travel"),
+            ("🚀", "rocket"),
+        ],
+    ),
+    (
+        "Objects",
+        &[
+            ("💻", "laptop computer"),
+            ("📱", "mobile phone"),
+            ("⌨️", "keyboard"),
+            ("🖥️", "desktop computer"),
+            ("📚", "books"),
+            ("✏️", "pencil"),
+            ("📌", "pushpin"),
+            ("🔗", "link"),
+            ("🔒", "locked"),
+            ("💡", "light bulb idea"),
+            ("📈", "chart increasing"),
+            ("🗓️", "calendar"),
+        ],
+    ),
+    (
+        "Symbols",
+        &[
+            ("✅", "check mark button done"),
+            ("❌", "cross mark"),
+            ("⚠️", "warning"),
+            ("❓", "question mark"),
+            ("❗", "exclamation mark"),
+            ("➡️", "right arrow"),
+            ("🔁", "repeat"),
+            ("🆕", "new button"),
+            ("🏳️‍🌈", "rainbow flag pride"),
+            ("🏳️‍⚧️", "transgender flag"),
+        ],
+    ),
+];
+
+/// Emoji whose name contains every word of `query`, ignoring case.
+pub fn search(query: &str) -> Vec<Emoji> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    CATEGORIES
+        .iter()
+        .flat_map(|(_, emoji)| emoji.iter().copied())
+        .filter(|(_, name)| words.iter().all(|word| name.contains(word.as_str())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let hearts: Vec<&str> = search("Heart").into_iter().map(|(emoji, _)| emoji).collect();
+        assert!(hearts.contains(&"❤️"));
+        assert!(hearts.contains(&"🫶"));
+        assert_eq!(search("red heart").len(), 1);
+        assert!(search("no such emoji").is_empty());
+    }
+}
@@ -14,10 +14,17 @@ use rust_embed::RustEmbed;
 /// Applies the requested language(s) to requested translations from the `fl!()` macro.
 pub fn init(requested_languages: &[LanguageIdentifier]) {
     if let Err(why) = localizer().select(requested_languages) {
-        eprintln!("error while loading fluent localizations: {why}");
+        tracing::error!("error while loading fluent localizations: {why}");
     }
 }
 
+/// ISO 639 code of the system's preferred language, e.g. `de` for `de-AT`.
+pub fn system_language() -> Option<String> {
+    i18n_embed::DesktopLanguageRequester::requested_languages()
+        .first()
+        .map(|language| language.language.as_str().to_string())
+}
+
 // Get the `Localizer` to be used for localizing this library.
 #[must_use]
 pub fn localizer() -> Box<dyn Localizer> {
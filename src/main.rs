@@ -1,26 +1,43 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod app;
+mod clipboard;
 mod config;
 mod crypto;
+mod emoji;
 mod i18n;
 mod social;
 
+/// Window size on first launch
+const DEFAULT_WINDOW_SIZE: (u32, u32) = (600, 650);
+/// Smallest the window can be made, still enough for the compose controls
+const MIN_WINDOW_SIZE: (u32, u32) = (480, 560);
+
 fn main() -> cosmic::iced::Result {
+    // Info and above by default; RUST_LOG=yall_cosmic=trace also logs request payloads
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
     // Get the system's preferred languages.
     let requested_languages = i18n_embed::DesktopLanguageRequester::requested_languages();
 
     // Enable localizations to be applied.
     i18n::init(&requested_languages);
 
-    // Settings for a compact, clean application
+    // Compact by default, reopening at the size it was last closed at
+    let (width, height) = config::saved_window_size(<app::AppModel as cosmic::Application>::APP_ID).unwrap_or(DEFAULT_WINDOW_SIZE);
+    // Closing waits for the app to ask about unsaved text, then it exits itself
     let settings = cosmic::app::Settings::default()
+        .exit_on_close(false)
+        .size(cosmic::iced::Size::new(width.max(MIN_WINDOW_SIZE.0) as f32, height.max(MIN_WINDOW_SIZE.1) as f32))
         .size_limits(
             cosmic::iced::Limits::NONE
-                .min_width(600.0)
-                .min_height(650.0)
-                .max_width(600.0)
-                .max_height(650.0),
+                .min_width(MIN_WINDOW_SIZE.0 as f32)
+                .min_height(MIN_WINDOW_SIZE.1 as f32),
         );
 
     // Run as a COSMIC application
@@ -1,31 +1,106 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::config::{BlueskyConfig, MastodonConfig, NostrConfig, MicroBlogConfig};
+use crate::config::{BlueskyConfig, BlueskyTruncateBehavior, CaptionerConfig, Config, TextCleanup, MastodonConfig, MastodonVisibility, NostrConfig, MicroBlogConfig, PixelfedConfig, ThreadsConfig, DiscordWebhookConfig, TelegramConfig, LemmyConfig, FarcasterConfig, MatrixConfig, DevToConfig, UrlShortenerConfig, WordPressConfig};
+use futures_util::future::{BoxFuture, FutureExt};
+use futures_util::{stream, StreamExt};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
 use reqwest::multipart;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::error::Error;
+use std::collections::HashMap;
 use std::fmt;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
 
 use nostr_sdk::prelude::*;
 use nostr_sdk::Client as NostrClient;
 
-const BLUESKY_CHARACTER_LIMIT: usize = 300;
+pub const BLUESKY_CHARACTER_LIMIT: usize = 300;
+/// Used until the instance's own limit has been fetched
+pub const DEFAULT_MASTODON_CHARACTER_LIMIT: usize = 500;
+pub const THREADS_CHARACTER_LIMIT: usize = 500;
+pub const PIXELFED_CHARACTER_LIMIT: usize = 500;
+pub const DISCORD_CHARACTER_LIMIT: usize = 2000;
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
+pub const TELEGRAM_CAPTION_LIMIT: usize = 1024;
+/// Lemmy's default limit on a post body
+pub const LEMMY_CHARACTER_LIMIT: usize = 10_000;
+/// Lemmy's limit on a post title
+const LEMMY_TITLE_LIMIT: usize = 200;
+/// Farcaster limits casts by their UTF-8 size rather than their characters
+pub const FARCASTER_BYTE_LIMIT: usize = 320;
+/// Most embeds (images or links) a cast can carry
+const FARCASTER_MAX_EMBEDS: usize = 2;
+/// Neynar's hosted API, which signs casts on the user's behalf
+const NEYNAR_API_URL: &str = "https://api.neynar.com/v2";
+/// PHP's default `upload_max_filesize`, which many WordPress hosts never raise
+const WORDPRESS_MAX_IMAGE_BYTES: usize = 2_000_000;
+/// Nostr has no hard limit, so thread mode splits notes at the Mastodon default
+const NOSTR_THREAD_LIMIT: usize = DEFAULT_MASTODON_CHARACTER_LIMIT;
+/// Pause between the notes of a thread, so relays that rate limit new events keep accepting them
+const NOSTR_EVENT_SPACING: Duration = Duration::from_millis(500);
+/// Room kept in each thread chunk for its " (n/total)" marker, enough for 99 posts
+const THREAD_NUMBERING_RESERVE: usize = 8;
+/// Largest blob Bluesky's uploadBlob accepts
+pub const BLUESKY_MAX_IMAGE_BYTES: usize = 1_000_000;
+/// Largest video Bluesky's video service accepts
+pub const BLUESKY_MAX_VIDEO_BYTES: u64 = 100_000_000;
+/// Longest video Bluesky plays
+pub const BLUESKY_MAX_VIDEO_DURATION: Duration = Duration::from_secs(180);
+/// Bluesky's video service, which transcodes uploads before they can be embedded
+const BLUESKY_VIDEO_SERVICE: &str = "https://video.bsky.app";
+/// How long to wait for the video service to finish processing an upload
+const BLUESKY_VIDEO_PROCESSING_TIMEOUT: Duration = Duration::from_secs(300);
+/// The Threads Graph API
+const THREADS_API_URL: &str = "https://graph.threads.net/v1.0";
+/// How long to wait for Threads to finish a media container before giving up on publishing it
+const THREADS_CONTAINER_TIMEOUT: Duration = Duration::from_secs(60);
+/// Most images Bluesky and Mastodon accept on one post
+pub const MAX_IMAGES: usize = 4;
+/// Links this long or shorter aren't sent to the URL shortener
+const SHORT_LINK_MAX_CHARS: usize = 30;
 
 #[derive(Debug, Clone)]
 pub enum PostError {
+    /// The server couldn't be reached, so nothing was sent
     Network(String),
+    /// The request broke off or its answer couldn't be read, so it may have gone through anyway
+    Response(String),
     Auth(String),
     Api(String),
     Crypto(String),
+    /// The server answered 429 Too Many Requests, with how long it asked us to wait if it said
+    RateLimited { retry_after: Option<Duration> },
 }
 
 impl fmt::Display for PostError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             PostError::Network(e) => write!(f, "Network error: {}", e),
+            PostError::Response(e) => write!(f, "Response error: {}", e),
             PostError::Auth(e) => write!(f, "Authentication error: {}", e),
             PostError::Api(e) => write!(f, "API error: {}", e),
             PostError::Crypto(e) => write!(f, "Cryptography error: {}", e),
+            PostError::RateLimited { retry_after: None } => write!(f, "Rate limited, try again later"),
+            PostError::RateLimited { retry_after: Some(wait) } => {
+                // Round up, so the wait given is never too short
+                let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+                if secs < 120 {
+                    write!(f, "Rate limited, try again in {} seconds", secs.max(1))
+                } else {
+                    write!(f, "Rate limited, try again in {} minutes", secs.div_ceil(60))
+                }
+            }
         }
     }
 }
@@ -34,277 +109,6408 @@ impl Error for PostError {}
 
 impl From<reqwest::Error> for PostError {
     fn from(error: reqwest::Error) -> Self {
-        PostError::Network(error.to_string())
+        // Some URLs carry a secret (Telegram's bot token, Discord's webhook token), so leave it out
+        if error.is_timeout() {
+            PostError::Network("timeout".to_string())
+        } else if error.is_connect() {
+            PostError::Network(error.without_url().to_string())
+        } else {
+            PostError::Response(error.without_url().to_string())
+        }
     }
 }
 
-pub async fn post_to_bluesky(config: &BlueskyConfig, text: &str, image_path: Option<&str>) -> Result<(), PostError> {
-    if !config.enabled || config.handle.is_empty() || config.decrypted_password.is_empty() {
-        return Err(PostError::Auth("Bluesky not configured".to_string()));
+/// A platform that posts can be sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Platform {
+    Bluesky,
+    Mastodon,
+    MicroBlog,
+    Nostr,
+    Threads,
+    Pixelfed,
+    Discord,
+    Telegram,
+    WordPress,
+    Lemmy,
+    Farcaster,
+    Matrix,
+    DevTo,
+}
+
+impl Platform {
+    /// Every platform, in the order they're listed in the UI
+    pub const ALL: [Platform; 13] = [
+        Platform::Bluesky,
+        Platform::Mastodon,
+        Platform::MicroBlog,
+        Platform::Nostr,
+        Platform::Threads,
+        Platform::Pixelfed,
+        Platform::Discord,
+        Platform::Telegram,
+        Platform::WordPress,
+        Platform::Lemmy,
+        Platform::Farcaster,
+        Platform::Matrix,
+        Platform::DevTo,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Bluesky => "Bluesky",
+            Platform::Mastodon => "Mastodon",
+            Platform::MicroBlog => "Micro.Blog",
+            Platform::Nostr => "Nostr",
+            Platform::Threads => "Threads",
+            Platform::Pixelfed => "Pixelfed",
+            Platform::Discord => "Discord",
+            Platform::Telegram => "Telegram",
+            Platform::WordPress => "WordPress",
+            Platform::Lemmy => "Lemmy",
+            Platform::Farcaster => "Farcaster",
+            Platform::Matrix => "Matrix",
+            Platform::DevTo => "Dev.to",
+        }
+    }
+
+    /// Whether thread mode can split long text into a reply chain on this platform
+    pub fn supports_threads(&self) -> bool {
+        matches!(self, Platform::Bluesky | Platform::Mastodon | Platform::Nostr)
+    }
+
+    /// Whether the platform formats Markdown, so Markdown text is sent to it as written
+    pub fn renders_markdown(&self) -> bool {
+        matches!(self, Platform::MicroBlog | Platform::Lemmy | Platform::Discord | Platform::DevTo)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Per-platform outcome of a cross-post.
+#[derive(Debug, Clone, Default)]
+pub struct PostReport {
+    pub results: Vec<(Platform, Result<(), PostError>)>,
+    /// Per-relay outcome when a Nostr post went out
+    pub nostr_relays: RelayResults,
+    /// Non-fatal problems, e.g. an image that couldn't be attached
+    pub warnings: Vec<(Platform, String)>,
+    /// Nothing was sent; the warnings describe what would have been
+    pub dry_run: bool,
+    /// Platforms that took the post to publish later, with when they'll publish it
+    pub scheduled: Vec<(Platform, DateTime<Utc>)>,
+    /// Web addresses of the posts that went out
+    pub links: Vec<(Platform, String)>,
+    /// The text as it was posted, when shortening its links changed it
+    pub shortened_text: Option<String>,
+}
+
+/// Extra information a platform can report about a post that went out.
+#[derive(Debug, Clone, Default)]
+pub struct PostDetails {
+    pub relays: RelayResults,
+    pub warnings: Vec<String>,
+    /// When the platform will publish the post, if it was scheduled rather than published
+    pub scheduled_at: Option<DateTime<Utc>>,
+    /// Where the post can be seen on the web; for a thread, its first post
+    pub link: Option<String>,
+}
+
+impl PostReport {
+    /// True when every targeted platform accepted the post.
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    pub fn succeeded(&self) -> Vec<Platform> {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(platform, _)| *platform)
+            .collect()
+    }
+
+    pub fn failed(&self) -> Vec<(Platform, &PostError)> {
+        self.results
+            .iter()
+            .filter_map(|(platform, result)| result.as_ref().err().map(|e| (*platform, e)))
+            .collect()
+    }
+
+    /// True when something failed and every failure was the network being unreachable, so
+    /// the same post can simply be sent again later.
+    pub fn only_network_failures(&self) -> bool {
+        let failed = self.failed();
+        !failed.is_empty() && failed.iter().all(|(_, e)| matches!(e, PostError::Network(_)))
+    }
+
+    /// Relays that rejected the Nostr post, with their reasons.
+    pub fn rejected_relays(&self) -> Vec<(&str, &str)> {
+        self.nostr_relays
+            .iter()
+            .filter_map(|(relay, result)| result.as_ref().err().map(|e| (relay.as_str(), e.as_str())))
+            .collect()
+    }
+}
+
+/// Longest wait between attempts, even if the server's `Retry-After` asks for more
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// How requests are retried when a server is briefly unavailable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total tries, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled for each one after that
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, base_delay: Duration::from_secs(1) }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt, for best-effort requests that aren't worth waiting on
+    const ONCE: RetryPolicy = RetryPolicy { max_attempts: 1, base_delay: Duration::ZERO };
+
+    /// Delay before retrying after the given (1-based) failed attempt
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Whether a response status means the request may succeed if sent again later.
+/// Other errors, like a rejected token, won't fix themselves.
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status, reqwest::StatusCode::TOO_MANY_REQUESTS | reqwest::StatusCode::SERVICE_UNAVAILABLE)
+}
+
+/// The server's requested wait before retrying, capped for use as a backoff delay.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    rate_limit_wait(response.headers(), Utc::now()).map(|wait| wait.min(MAX_RETRY_DELAY))
+}
+
+/// How long a rate-limited response asks us to wait: `Retry-After` in seconds or as an HTTP
+/// date, or else when the limit resets from `X-RateLimit-Reset` or `RateLimit-Reset`.
+///
+/// Mastodon gives the reset as an ISO 8601 time and Bluesky as a Unix timestamp; small
+/// numbers are taken as seconds from now, as the IETF draft header uses.
+fn rate_limit_wait(headers: &reqwest::header::HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(str::trim);
+    let until = |time: DateTime<Utc>| (time - now).to_std().ok().or(Some(Duration::ZERO));
+    if let Some(value) = header(reqwest::header::RETRY_AFTER.as_str()) {
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(Duration::from_secs(secs));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc2822(value) {
+            return until(time.with_timezone(&Utc));
+        }
+    }
+    let reset = header("x-ratelimit-reset").or_else(|| header("ratelimit-reset"))?;
+    if let Ok(number) = reset.parse::<i64>() {
+        // Anything before 2001 can't be a timestamp
+        return match DateTime::from_timestamp(number, 0).filter(|_| number > 1_000_000_000) {
+            Some(time) => until(time),
+            None => Some(Duration::from_secs(number.max(0) as u64)),
+        };
+    }
+    until(DateTime::parse_from_rfc3339(reset).ok()?.with_timezone(&Utc))
+}
+
+/// Send a request, retrying network errors and 429/503 responses with exponential backoff.
+///
+/// `build` is called once per attempt because a sent request can't be reused. Once attempts
+/// run out, a 429 becomes `PostError::RateLimited` and any other last response is returned
+/// as-is, so callers still see the status.
+///
+/// A POST or PATCH that broke off after connecting may already have been carried out, so it's
+/// only sent again when it carries an `Idempotency-Key` header; otherwise only connection
+/// failures and 429/503 answers are retried.
+async fn send_with_retry<F>(retry: RetryPolicy, build: F) -> Result<reqwest::Response, PostError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    retry_request(retry, false, build).await
+}
+
+/// `send_with_retry` for a request the server dedupes by a key in its body, such as a cast's
+/// `idem`, so it's safe to send again whatever went wrong.
+async fn send_deduped_with_retry<F>(retry: RetryPolicy, build: F) -> Result<reqwest::Response, PostError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    retry_request(retry, true, build).await
+}
+
+async fn retry_request<F>(retry: RetryPolicy, deduped: bool, build: F) -> Result<reqwest::Response, PostError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        let (client, request) = build().build_split();
+        let request = request?;
+        let resendable = deduped || request.method().is_idempotent() || request.headers().contains_key("Idempotency-Key");
+        let result = client.execute(request).await;
+        let delay = match &result {
+            Ok(response) if is_retryable_status(response.status()) => {
+                retry_after(response).unwrap_or_else(|| retry.backoff(attempt))
+            }
+            Ok(_) => return result.map_err(PostError::from),
+            Err(e) if e.is_connect() || resendable => retry.backoff(attempt),
+            Err(_) => return result.map_err(PostError::from),
+        };
+        if attempt >= retry.max_attempts {
+            return match result {
+                Ok(response) if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    Err(PostError::RateLimited { retry_after: rate_limit_wait(response.headers(), Utc::now()) })
+                }
+                result => result.map_err(PostError::from),
+            };
+        }
+        match &result {
+            Ok(response) => tracing::warn!("Request got {}, retrying in {:?}", response.status(), delay),
+            Err(e) => tracing::warn!("Request failed ({}), retrying in {:?}", e, delay),
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Build the HTTP client shared by every request, so connections are pooled and kept alive
+/// across platforms and posts. `timeout` bounds each whole request so an unreachable
+/// server can't stall a post.
+pub fn build_http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(concat!("yall-cosmic/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
+
+/// Settings for one post beyond its text and images.
+#[derive(Debug, Clone, Default)]
+pub struct PostOptions {
+    pub mastodon: MastodonOptions,
+    pub wordpress: WordPressOptions,
+    pub microblog: MicroBlogOptions,
+    pub lemmy: LemmyOptions,
+    pub nostr: NostrOptions,
+    pub devto: DevToOptions,
+    /// The text is Markdown, to be turned into plain text for platforms that don't format it
+    pub markdown: bool,
+    /// Add each platform's signature from the config to the end of the text
+    pub signature: bool,
+    /// Split text over a platform's limit into a reply chain instead of truncating or rejecting it
+    pub thread_mode: bool,
+    /// ISO 639-1 code of the language the post is written in, for platforms that tag posts with it
+    pub language: Option<String>,
+    /// Log what each platform would be sent and report success without sending anything
+    pub dry_run: bool,
+    /// Counts image bytes as they're uploaded, for showing progress
+    pub upload_progress: UploadProgress,
+    /// Follows and cancels proof-of-work mining for Nostr notes
+    pub pow_progress: PowProgress,
+    /// Post to reply to on each platform, as a link to it; platforms without one post normally
+    pub reference_urls: HashMap<Platform, String>,
+    /// Quote the posts in `reference_urls` instead of replying to them
+    pub quote: bool,
+    /// Stays the same when a post is sent again, so platforms that take a key for it
+    /// recognise a repeat instead of posting twice
+    pub idempotency_key: Option<String>,
+}
+
+impl PostOptions {
+    /// The post `platform`'s post replies to or quotes, if one was given for it.
+    fn reference(&self, platform: Platform) -> Option<PostReference> {
+        let url = self.reference_urls.get(&platform)?.trim();
+        (!url.is_empty()).then(|| PostReference { url: url.to_string(), quote: self.quote })
+    }
+}
+
+/// An existing post that a new post replies to or quotes.
+///
+/// Bluesky, Mastodon and Nostr support these; other platforms ignore them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostReference {
+    /// Link to the post, in any of the forms its platform's parser accepts
+    pub url: String,
+    pub quote: bool,
+}
+
+/// Size of the pieces image uploads are streamed in, and so how often progress moves
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Image bytes uploaded so far for a post in progress. Clones share the count, so the UI
+/// can follow uploads made by the posting task.
+#[derive(Debug, Clone, Default)]
+pub struct UploadProgress(Arc<UploadCounts>);
+
+#[derive(Debug, Default)]
+struct UploadCounts {
+    sent: AtomicU64,
+    total: AtomicU64,
+}
+
+impl UploadProgress {
+    /// Bytes sent and bytes to send across all uploads, or `None` before any has started.
+    ///
+    /// A retried upload is sent again, so the sent count is capped at the total.
+    pub fn bytes(&self) -> Option<(u64, u64)> {
+        let total = self.0.total.load(Ordering::Relaxed);
+        (total > 0).then(|| (self.0.sent.load(Ordering::Relaxed).min(total), total))
+    }
+
+    /// Count bytes that are about to be uploaded toward the total.
+    fn add_total(&self, bytes: usize) {
+        self.0.total.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// A request body for `bytes` that counts them as sent while it's streamed out.
+    fn body(&self, bytes: &[u8]) -> reqwest::Body {
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> = bytes.chunks(UPLOAD_CHUNK_SIZE).map(|chunk| Ok(chunk.to_vec())).collect();
+        let counts = self.0.clone();
+        reqwest::Body::wrap_stream(futures_util::stream::iter(chunks).inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                counts.sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }
+        }))
+    }
+}
+
+/// Nonces tried between checks for cancellation and updates to the attempt count
+const POW_CHECK_INTERVAL: u64 = 4096;
+
+/// Proof-of-work mining for the Nostr notes of a post in progress. Clones share the state, so
+/// the UI can show the attempts made by the posting task and stop it.
+#[derive(Debug, Clone, Default)]
+pub struct PowProgress(Arc<PowState>);
+
+#[derive(Debug, Default)]
+struct PowState {
+    attempts: AtomicU64,
+    mining: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl PowProgress {
+    /// Nonces tried for the note being mined, or `None` when no note is being mined.
+    pub fn attempts(&self) -> Option<u64> {
+        self.0.mining.load(Ordering::Relaxed).then(|| self.0.attempts.load(Ordering::Relaxed))
+    }
+
+    /// Stop mining; the note being mined fails to post.
+    ///
+    /// Mining runs on a blocking thread, which aborting the posting task wouldn't stop.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
     }
+}
+
+/// Post to every requested platform concurrently and collect the individual results.
+///
+/// The config is shared so posts sent together don't each hold a copy of the credentials.
+pub async fn post_to_platforms(client: reqwest::Client, config: Arc<Config>, mut platforms: Vec<Platform>, text: String, images: Vec<ImageAttachment>, options: PostOptions) -> PostReport {
+    let retry = config.retry_policy();
+    let thread_mode = options.thread_mode;
+    let markdown = options.markdown;
+    let signature = options.signature;
+    let language = options.language.as_deref();
+    let mut report = PostReport::default();
 
-    // Fallback: If text is empty and image is present, set to a single space
-    let text = if text.trim().is_empty() && image_path.is_some() {
-        " "
+    // Shorten links first, so length checks, truncation and thread splits see the final text.
+    // A dry run leaves them alone, as the shortener would store them.
+    let text = if config.url_shortener.enabled && !options.dry_run {
+        match shorten_links(&client, retry, &config.url_shortener, &text).await {
+            Ok(shortened) => {
+                if shortened != text {
+                    report.shortened_text = Some(shortened.clone());
+                }
+                shortened
+            }
+            Err(e) => {
+                tracing::error!("Failed to shorten links: {}", e);
+                for platform in platforms {
+                    report.results.push((platform, Err(PostError::Api(format!("Failed to shorten links: {}", e)))));
+                }
+                return report;
+            }
+        }
     } else {
         text
     };
-    // Truncate text to Bluesky's character limit (respecting Unicode boundaries)
-    let truncated_text = if text.chars().count() > BLUESKY_CHARACTER_LIMIT {
-        text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
+
+    if skips_bluesky(&config.bluesky, &text_for_platform(Platform::Bluesky, &text, markdown, signature, &config), thread_mode) && platforms.contains(&Platform::Bluesky) {
+        platforms.retain(|platform| *platform != Platform::Bluesky);
+        report.warnings.push((Platform::Bluesky, format!("Skipped because the text is over {} characters", BLUESKY_CHARACTER_LIMIT)));
+    }
+
+    if options.dry_run {
+        report.dry_run = true;
+        for platform in platforms {
+            let result = dry_run_post(platform, &config, &text, &images, markdown, signature, thread_mode);
+            if let Ok(summary) = &result {
+                report.warnings.push((platform, summary.clone()));
+            }
+            report.results.push((platform, result.map(|_| ())));
+        }
+        return report;
+    }
+
+    // Uploads read images from disk, so point them at cleaned copies of the originals
+    let mut upload_dir = None;
+    let images = if config.keep_image_metadata || images.is_empty() {
+        images
     } else {
-        text.to_string()
+        let copied = tokio::task::spawn_blocking(move || copy_without_metadata(&images))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+        match copied {
+            Ok((dir, copies, warnings)) => {
+                upload_dir = Some(dir);
+                for warning in warnings {
+                    report.warnings.extend(platforms.iter().map(|platform| (*platform, warning.clone())));
+                }
+                copies
+            }
+            Err(e) => {
+                tracing::error!("Failed to remove image metadata: {}", e);
+                for platform in platforms {
+                    report.results.push((platform, Err(PostError::Api(format!("Failed to remove image metadata: {}", e)))));
+                }
+                return report;
+            }
+        }
     };
 
-    let client = reqwest::Client::new();
-    // Create session
-    let auth_response = client
-        .post("https://bsky.social/xrpc/com.atproto.server.createSession")
-        .json(&json!({
-            "identifier": config.handle,
-            "password": config.decrypted_password
-        }))
-        .send()
-        .await?;
-    if !auth_response.status().is_success() {
-        return Err(PostError::Auth("Failed to authenticate with Bluesky".to_string()));
+    let mastodon_options = MastodonOptions { idempotency_key: options.idempotency_key.clone(), ..options.mastodon.clone() };
+    // Only Bluesky takes video, so the rest get the images alone
+    let stills: Vec<ImageAttachment> = images.iter().filter(|image| !image.is_video()).cloned().collect();
+    let has_video = stills.len() < images.len();
+    let posts: Vec<BoxFuture<'_, (Platform, Result<(), PostError>, PostDetails)>> = platforms
+        .into_iter()
+        .map(|platform| {
+            let client = &client;
+            let config = &config;
+            let text = text.as_str();
+            let images = if platform == Platform::Bluesky { images.as_slice() } else { stills.as_slice() };
+            let mastodon_options = &mastodon_options;
+            let wordpress_options = &options.wordpress;
+            let microblog_options = &options.microblog;
+            let lemmy_options = &options.lemmy;
+            let devto_options = &options.devto;
+            let nostr_options = &options.nostr;
+            let progress = &options.upload_progress;
+            let pow = &options.pow_progress;
+            let reference = options.reference(platform);
+            let idempotency_key = options.idempotency_key.as_deref();
+            async move {
+                let reference = reference.as_ref();
+                let written = text;
+                let text = text_for_platform(platform, text, markdown, signature, config);
+                let text = text.as_str();
+                let mut details = PostDetails::default();
+                let result = match platform {
+                    Platform::Bluesky if thread_mode => {
+                        let chunks = split_bluesky_thread(text);
+                        post_bluesky_thread(client, retry, &config.bluesky, &chunks, images, language, progress, reference)
+                            .await
+                            .map(|bluesky_details| details = bluesky_details)
+                    }
+                    Platform::Bluesky => {
+                        // Cut here, where the signature is known, so it isn't what gets cut off
+                        let text = if config.bluesky.truncate_behavior == BlueskyTruncateBehavior::Truncate && post_length(Platform::Bluesky, text) > BLUESKY_CHARACTER_LIMIT {
+                            truncate_signed_for_bluesky(text, config)
+                        } else {
+                            text.to_string()
+                        };
+                        post_to_bluesky(client, retry, &config.bluesky, &text, images, language, progress, reference)
+                            .await
+                            .map(|bluesky_details| details = bluesky_details)
+                    }
+                    Platform::Mastodon if thread_mode => {
+                        let limit = config.mastodon.max_characters.unwrap_or(DEFAULT_MASTODON_CHARACTER_LIMIT);
+                        let chunks = split_into_thread(text, limit);
+                        post_mastodon_thread(client, retry, &config.mastodon, &chunks, images, mastodon_options, language, progress, reference)
+                            .await
+                            .map(|mastodon_details| details = mastodon_details)
+                    }
+                    Platform::Mastodon => post_to_mastodon(client, retry, &config.mastodon, text, images, mastodon_options, language, progress, reference)
+                        .await
+                        .map(|mastodon_details| details = mastodon_details),
+                    Platform::MicroBlog => post_to_microblog(client, retry, &config.microblog, text, images, microblog_options)
+                        .await
+                        .map(|microblog_details| details = microblog_details),
+                    Platform::Pixelfed => post_to_pixelfed(client, retry, &config.pixelfed, text, images, progress).await,
+                    Platform::Discord => post_to_discord(client, retry, &config.discord, text, images)
+                        .await
+                        .map(|discord_details| details = discord_details),
+                    Platform::Telegram => post_to_telegram(client, retry, &config.telegram, text, images)
+                        .await
+                        .map(|telegram_details| details = telegram_details),
+                    Platform::WordPress => post_to_wordpress(client, retry, &config.wordpress, text, images, wordpress_options)
+                        .await
+                        .map(|wordpress_details| details = wordpress_details),
+                    Platform::Lemmy => post_to_lemmy(client, retry, &config.lemmy, text, images, lemmy_options)
+                        .await
+                        .map(|lemmy_details| details = lemmy_details),
+                    Platform::Farcaster => post_to_farcaster(client, retry, &config.farcaster, &config.nostr, text, images, idempotency_key)
+                        .await
+                        .map(|farcaster_details| details = farcaster_details),
+                    Platform::Matrix => post_to_matrix(client, retry, &config.matrix, text, images)
+                        .await
+                        .map(|matrix_details| details = matrix_details),
+                    Platform::DevTo => post_to_devto(client, retry, &config.devto, text, images, devto_options)
+                        .await
+                        .map(|devto_details| details = devto_details),
+                    Platform::Threads => post_to_threads(client, retry, &config.threads, &config.nostr, text, images)
+                        .await
+                        .map(|threads_details| details = threads_details),
+                    Platform::Nostr => match &nostr_options.article {
+                        // Articles are written in Markdown, so they get the text as written
+                        Some(article) => {
+                            let content = clean_up_whitespace(written, config.text_cleanup(platform));
+                            post_nostr_article(client, retry, &config.nostr, &content, images, article, language, config.request_timeout(), pow).await
+                        }
+                        None if thread_mode => {
+                            let chunks = split_into_thread(text, NOSTR_THREAD_LIMIT);
+                            post_nostr_thread(client, retry, &config.nostr, &chunks, images, language, config.request_timeout(), pow, reference).await
+                        }
+                        None => post_to_nostr(client, retry, &config.nostr, text, images, language, config.request_timeout(), pow, reference).await,
+                    }
+                    .map(|nostr_details| details = nostr_details),
+                };
+                if has_video && platform != Platform::Bluesky {
+                    details.warnings.push("Only Bluesky takes video uploads, so the video was left out".to_string());
+                }
+                if platform == Platform::Nostr && nostr_options.article.is_some() && reference.is_some() {
+                    details.warnings.push("Articles can't reply to or quote a note, so it went out on its own".to_string());
+                }
+                (platform, result, details)
+            }
+            .boxed()
+        })
+        .collect();
+
+    // Send a few at a time, in order, so a run of platforms on one host doesn't get rate limited
+    let finished: Vec<_> = stream::iter(posts).buffered(config.max_concurrent_posts()).collect().await;
+    for (platform, result, details) in finished {
+        report.results.push((platform, result));
+        report.nostr_relays.extend(details.relays);
+        report.scheduled.extend(details.scheduled_at.map(|time| (platform, time)));
+        report.links.extend(details.link.map(|link| (platform, link)));
+        report
+            .warnings
+            .extend(details.warnings.into_iter().map(|warning| (platform, warning)));
     }
-    let auth_data: Value = auth_response.json().await?;
-    let access_jwt = auth_data["accessJwt"]
-        .as_str()
-        .ok_or_else(|| PostError::Auth("No access token received".to_string()))?;
+    if let Some(dir) = upload_dir {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!("Failed to remove {}: {}", dir.display(), e);
+        }
+    }
+    report
+}
+
+/// Log the posts `post_to_platforms` would send to `platform` and summarize them, failing
+/// where the platform would turn the post down before sending anything.
+fn dry_run_post(platform: Platform, config: &Config, text: &str, images: &[ImageAttachment], markdown: bool, signature: bool, thread_mode: bool) -> Result<String, PostError> {
+    let video = images.iter().find(|image| image.is_video());
+    let still_count = images.iter().filter(|image| !image.is_video()).count();
+    if platform == Platform::Pixelfed && still_count == 0 {
+        return Err(PostError::Api("Pixelfed posts need at least one image".to_string()));
+    }
+    if let (Platform::Bluesky, Some(video)) = (platform, video) {
+        check_bluesky_video(&video.path).map_err(PostError::Api)?;
+    }
+    let image_count = match platform {
+        // These leave attached images out
+        Platform::Lemmy | Platform::DevTo => 0,
+        Platform::Farcaster => still_count.min(FARCASTER_MAX_EMBEDS),
+        _ => still_count.min(MAX_IMAGES),
+    };
+    let media = match (platform, video) {
+        (Platform::Bluesky, Some(_)) => "a video".to_string(),
+        _ => format!("{} image(s)", image_count),
+    };
+    let target = dry_run_target(platform, config);
+    let posts = preview_posts(platform, &text_for_platform(platform, text, markdown, signature, config), thread_mode, config);
+    for (i, post) in posts.iter().enumerate() {
+        tracing::info!("[Dry run] {} post {}/{} to {} ({} characters): {:?}", platform, i + 1, posts.len(), target, post_length(platform, post), post);
+    }
+    tracing::info!("[Dry run] {}: {} would be attached", platform, media);
+    Ok(format!("Dry run: {} post(s) with {} for {}", posts.len(), media, target))
+}
+
+/// Where a post to `platform` goes, without the credentials some of these URLs carry.
+fn dry_run_target(platform: Platform, config: &Config) -> String {
+    match platform {
+        Platform::Bluesky => format!("{}/xrpc/com.atproto.repo.createRecord", config.bluesky.service_url.trim_end_matches('/')),
+        Platform::Mastodon => format!("{}/api/v1/statuses", config.mastodon.instance_url.trim_end_matches('/')),
+        Platform::MicroBlog => "https://micro.blog/micropub".to_string(),
+        Platform::Pixelfed => format!("{}/api/v1/statuses", config.pixelfed.instance_url.trim_end_matches('/')),
+        Platform::Threads => format!("{}/{}/threads", THREADS_API_URL, config.threads.user_id.trim()),
+        Platform::Discord => config
+            .discord
+            .decrypted_webhook_urls
+            .iter()
+            .map(|url| discord_webhook_label(url))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Platform::Telegram => format!("Telegram chat {}", config.telegram.chat_id.trim()),
+        Platform::WordPress => format!("{}/wp-json/wp/v2/posts", config.wordpress.site_url.trim_end_matches('/')),
+        Platform::Lemmy => format!("{}/api/v3/post in {}", config.lemmy.instance_url.trim_end_matches('/'), config.lemmy.community.trim()),
+        Platform::Nostr => config.nostr.relays.join(", "),
+        Platform::Farcaster => format!("{}/farcaster/cast", NEYNAR_API_URL),
+        Platform::Matrix => format!("{} in {}", config.matrix.homeserver_url.trim_end_matches('/'), config.matrix.room_id.trim()),
+        Platform::DevTo => format!("{}/api/articles", config.devto.site_url.trim_end_matches('/')),
+    }
+}
+
+/// Whether Bluesky is left out of a post because the text is too long for it.
+pub fn skips_bluesky(config: &BlueskyConfig, text: &str, thread_mode: bool) -> bool {
+    !thread_mode
+        && config.truncate_behavior == BlueskyTruncateBehavior::SkipBluesky
+        && post_length(Platform::Bluesky, text) > BLUESKY_CHARACTER_LIMIT
+}
+
+/// Goes between the post and its signature
+const SIGNATURE_SEPARATOR: &str = "\n\n";
+
+/// The text as it's sent to `platform`: with `signature`, the platform's signature is added
+/// at the end, so limits, truncation and thread splits count it. Markdown is passed through
+/// to platforms that format it and turned into plain text for the rest, then whitespace is
+/// tidied as the platform's text cleanup says.
+pub fn text_for_platform(platform: Platform, text: &str, markdown: bool, signature: bool, config: &Config) -> String {
+    let text = match config.signature(platform).filter(|_| signature) {
+        Some(signature) => format!("{}{}{}", text.trim_end(), SIGNATURE_SEPARATOR, signature),
+        None => text.to_string(),
+    };
+    let text = if markdown && !sends_markdown(platform, config) {
+        markdown_to_plain_text(&text)
+    } else {
+        text
+    };
+    clean_up_whitespace(&text, config.text_cleanup(platform))
+}
+
+/// Whether Markdown goes to `platform` as written: those that format it, and Mastodon when
+/// it's a Pleroma-compatible server, which formats it when asked to.
+fn sends_markdown(platform: Platform, config: &Config) -> bool {
+    platform.renders_markdown() || (platform == Platform::Mastodon && config.mastodon.pleroma_compatible())
+}
+
+/// Most blank lines kept in a row when collapsing blank lines
+const MAX_BLANK_LINES: usize = 2;
 
-    // Upload image if provided (raw bytes, correct headers)
-    let mut image_blob = None;
-    if let Some(path) = image_path {
-        let img_bytes = std::fs::read(path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
-        if img_bytes.len() > 1_000_000 {
-            return Err(PostError::Api(format!("Image file size too large. 1000000 bytes maximum, got: {}", img_bytes.len())));
+/// Trim trailing whitespace and collapse runs of blank lines, as `cleanup` says.
+fn clean_up_whitespace(text: &str, cleanup: TextCleanup) -> String {
+    if !cleanup.trim_trailing_whitespace && !cleanup.collapse_blank_lines {
+        return text.to_string();
+    }
+    let mut cleaned = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            if cleanup.collapse_blank_lines && blank_run > MAX_BLANK_LINES {
+                continue;
+            }
+        } else {
+            blank_run = 0;
         }
-        let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
-        let upload_response = client
-            .post("https://bsky.social/xrpc/com.atproto.repo.uploadBlob")
-            .header("Authorization", format!("Bearer {}", access_jwt))
-            .header("Content-Type", &mime_type)
-            .body(img_bytes.clone())
-            .send()
-            .await?;
-        eprintln!("[Bluesky] Image upload status: {}", upload_response.status());
-        if upload_response.status().is_success() {
-            let upload_data: Value = upload_response.json().await?;
-            eprintln!("[Bluesky] Image upload response: {}", upload_data);
-            if let Some(blob) = upload_data.get("blob") {
-                image_blob = Some(json!({
-                    "$type": "blob",
-                    "ref": blob["ref"].clone(),
-                    "mimeType": blob["mimeType"].clone(),
-                    "size": blob["size"].clone()
-                }));
+        if cleanup.trim_trailing_whitespace {
+            cleaned.push_str(line.trim_end());
+            if line.ends_with('\n') {
+                cleaned.push('\n');
             }
         } else {
-            let err_text = upload_response.text().await.unwrap_or_default();
-            eprintln!("[Bluesky] Image upload failed: {}", err_text);
+            cleaned.push_str(line);
         }
     }
+    if cleanup.trim_trailing_whitespace {
+        cleaned.truncate(cleaned.trim_end().len());
+    }
+    cleaned
+}
 
-    // Create post
-    let now = chrono::Utc::now().to_rfc3339();
-    let mut record = json!({
-        "text": truncated_text,
-        "createdAt": now,
-        "$type": "app.bsky.feed.post"
-    });
-    if let Some(blob) = image_blob {
-        record["embed"] = json!({
-            "$type": "app.bsky.embed.images",
-            "images": [{
-                "image": blob,
-                "alt": ""
-            }]
-        });
+/// Strip Markdown formatting, keeping link targets in brackets after their text and list
+/// items on their own lines.
+fn markdown_to_plain_text(markdown: &str) -> String {
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+    // Close a block with a blank line, or a single line break inside a list
+    fn end_block(text: &mut String, in_list: bool) {
+        text.truncate(text.trim_end_matches('\n').len());
+        text.push_str(if in_list { "\n" } else { "\n\n" });
     }
-    let post_json = json!({
-        "repo": config.handle,
-        "collection": "app.bsky.feed.post",
-        "record": record
-    });
-    eprintln!("[Bluesky] Post JSON: {}", post_json);
-    let post_response = client
-        .post("https://bsky.social/xrpc/com.atproto.repo.createRecord")
-        .header("Authorization", format!("Bearer {}", access_jwt))
-        .json(&post_json)
-        .send()
-        .await?;
-    eprintln!("[Bluesky] Post status: {}", post_response.status());
-    if !post_response.status().is_success() {
-        let error_text = post_response.text().await.unwrap_or_default();
-        eprintln!("[Bluesky] Post failed: {}", error_text);
-        return Err(PostError::Api(format!("Bluesky API error: {}", error_text)));
+
+    let mut text = String::new();
+    let mut lists: Vec<Option<u64>> = Vec::new(); // Next number of each open list; `None` if unordered
+    let mut links: Vec<(usize, String)> = Vec::new(); // Where each open link's text starts, and its target
+    for event in Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH) {
+        match event {
+            Event::Start(Tag::List(start)) => {
+                if !lists.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    end_block(&mut text, false);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        text.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::End(TagEnd::Item) => {
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+            Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. }) => links.push((text.len(), dest_url.to_string())),
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                if let Some((start, url)) = links.pop() {
+                    // Autolinks and bare links already show their target
+                    if text.len() == start {
+                        text.push_str(&url);
+                    } else if text[start..] != url {
+                        text.push_str(&format!(" ({})", url));
+                    }
+                }
+            }
+            Event::End(TagEnd::Paragraph) | Event::End(TagEnd::Heading(_)) | Event::End(TagEnd::CodeBlock) | Event::Rule => end_block(&mut text, !lists.is_empty()),
+            Event::Text(content) | Event::Code(content) | Event::Html(content) | Event::InlineHtml(content) => text.push_str(&content),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            _ => {}
+        }
     }
-    Ok(())
+    text.trim_end().to_string()
 }
 
-pub async fn post_to_mastodon(config: &MastodonConfig, text: &str, image_path: Option<&str>) -> Result<(), PostError> {
-    if !config.enabled || config.instance_url.is_empty() || config.decrypted_access_token.is_empty() {
-        return Err(PostError::Auth("Mastodon not configured".to_string()));
+/// User-perceived characters in the text, so an emoji sequence like 👨‍👩‍👧 counts once.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+pub fn word_count(text: &str) -> usize {
+    text.unicode_words().count()
+}
+
+/// Length of the text as `platform` measures it against its limit.
+///
+/// Bluesky counts grapheme clusters; the others are treated as counting characters.
+pub fn post_length(platform: Platform, text: &str) -> usize {
+    match platform {
+        // Bluesky measures the text with its links shortened the way they're shown
+        Platform::Bluesky => grapheme_count(&bluesky_display_text(text).0),
+        Platform::Farcaster => text.len(),
+        _ => text.chars().count(),
     }
+}
 
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/v1/statuses", config.instance_url.trim_end_matches('/'));
-    let mut media_id = None;
-    if let Some(path) = image_path {
-        let file_name = std::path::Path::new(path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "image".to_string());
-        let img_bytes = std::fs::read(path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
-        let part = multipart::Part::bytes(img_bytes).file_name(file_name);
-        let form = multipart::Form::new().part("file", part);
-        let media_resp = client
-            .post(&format!("{}/api/v2/media", config.instance_url.trim_end_matches('/')))
-            .header("Authorization", format!("Bearer {}", config.decrypted_access_token))
-            .multipart(form)
-            .send()
-            .await?;
-        if media_resp.status().is_success() {
-            let media_json: Value = media_resp.json().await?;
-            media_id = media_json["id"].as_str().map(|s| s.to_string());
+/// The text of each post `post_to_platforms` will send to `platform`, for previewing.
+///
+/// Thread mode splits text on platforms that support it, Bluesky splits or cuts text over its
+/// limit if set up to, Telegram cuts it, and everything else goes out as typed. Empty if nothing is sent.
+pub fn preview_posts(platform: Platform, text: &str, thread_mode: bool, config: &Config) -> Vec<String> {
+    if platform == Platform::Bluesky {
+        // Shown with links shortened, as they'll be posted
+        return preview_bluesky_posts(text, thread_mode, config)
+            .iter()
+            .map(|post| bluesky_display_text(post).0)
+            .collect();
+    }
+    match platform {
+        Platform::Mastodon if thread_mode => {
+            split_into_thread(text, config.mastodon.max_characters.unwrap_or(DEFAULT_MASTODON_CHARACTER_LIMIT))
         }
+        Platform::Nostr if thread_mode => split_into_thread(text, NOSTR_THREAD_LIMIT),
+        Platform::Telegram => plan_telegram_post(text, false).message.into_iter().collect(),
+        _ => vec![text.to_string()],
     }
-    let mut form = vec![("status", text.to_string())];
-    if let Some(id) = media_id {
-        form.push(("media_ids[]", id));
+}
+
+/// The Bluesky posts for `text`, before their links are shortened for display.
+fn preview_bluesky_posts(text: &str, thread_mode: bool, config: &Config) -> Vec<String> {
+    let behavior = config.bluesky.truncate_behavior;
+    if thread_mode || behavior == BlueskyTruncateBehavior::Thread {
+        split_bluesky_thread(text)
+    } else if skips_bluesky(&config.bluesky, text, thread_mode) {
+        Vec::new()
+    } else if behavior == BlueskyTruncateBehavior::Truncate && post_length(Platform::Bluesky, text) > BLUESKY_CHARACTER_LIMIT {
+        vec![truncate_signed_for_bluesky(text, config)]
+    } else {
+        vec![text.to_string()]
     }
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", config.decrypted_access_token))
-        .form(&form)
-        .send()
-        .await?;
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(PostError::Api(format!("Mastodon API error: {}", error_text)));
+}
+
+/// Split text for a Bluesky thread, unless it fits in one post once its links are shortened.
+fn split_bluesky_thread(text: &str) -> Vec<String> {
+    if post_length(Platform::Bluesky, text) <= BLUESKY_CHARACTER_LIMIT {
+        return vec![text.trim().to_string()];
     }
-    Ok(())
+    split_into_thread(text, BLUESKY_CHARACTER_LIMIT)
 }
 
-pub async fn post_to_nostr(config: &NostrConfig, text: &str, _image_path: Option<&str>) -> Result<(), PostError> {
-    if !config.enabled || config.decrypted_private_key.is_empty() || config.relays.is_empty() {
-        return Err(PostError::Auth("Nostr not configured".to_string()));
+/// Split long text into numbered chunks that each fit in `limit` characters.
+///
+/// Breaks fall between paragraphs where possible, then between sentences, then
+/// between words; a single word longer than a whole chunk is cut between characters.
+/// Text that already fits comes back as one unnumbered chunk.
+pub fn split_into_thread(text: &str, limit: usize) -> Vec<String> {
+    let text = text.trim();
+    if text.chars().count() <= limit {
+        return vec![text.to_string()];
     }
+    let budget = limit.saturating_sub(THREAD_NUMBERING_RESERVE).max(1);
 
-    // Parse private key
-    let secret_key = match SecretKey::from_hex(&config.decrypted_private_key) {
-        Ok(sk) => sk,
-        Err(e) => return Err(PostError::Crypto(format!("Invalid private key: {}", e))),
-    };
-    let keys = Keys::new(secret_key);
+    // Pieces that each fit in a chunk, with the separator that went before them and
+    // whether they should start a new chunk (the start of a paragraph or sentence that
+    // had to be broken up)
+    let mut pieces: Vec<(&str, String, bool)> = Vec::new();
+    for (i, paragraph) in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()).enumerate() {
+        let mut separator = if i == 0 { "" } else { "\n\n" };
+        if paragraph.chars().count() <= budget {
+            pieces.push((separator, paragraph.to_string(), false));
+            continue;
+        }
+        let mut fresh = true;
+        for sentence in split_sentences(paragraph) {
+            if sentence.chars().count() <= budget {
+                pieces.push((separator, sentence.to_string(), fresh));
+                separator = " ";
+                fresh = false;
+                continue;
+            }
+            fresh = true;
+            for word in sentence.split_whitespace() {
+                let chars: Vec<char> = word.chars().collect();
+                for (j, part) in chars.chunks(budget).enumerate() {
+                    pieces.push((if j == 0 { separator } else { "" }, part.iter().collect(), fresh));
+                    fresh = false;
+                }
+                separator = " ";
+            }
+        }
+    }
 
-    // Set up relay pool
-    let nostr_client = NostrClient::new(keys.clone());
-    for relay in &config.relays {
-        let _ = nostr_client.add_relay(relay).await;
+    // Pack as many pieces into each chunk as fit
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for (separator, piece, fresh) in pieces {
+        if current.is_empty() {
+            current = piece;
+        } else if !fresh && current.chars().count() + separator.chars().count() + piece.chars().count() <= budget {
+            current.push_str(separator);
+            current.push_str(&piece);
+        } else {
+            chunks.push(std::mem::replace(&mut current, piece));
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
     }
-    nostr_client.connect().await;
 
-    // Image posting is disabled for Nostr
-    let post_text = text.to_string();
-    // Create and send event
-    let pubkey = keys.public_key();
-    let unsigned = EventBuilder::text_note(&post_text).build(pubkey);
-    let event = keys.sign_event(unsigned).await.map_err(|e| PostError::Crypto(format!("Failed to sign event: {}", e)))?;
-    eprintln!("[Nostr] Final event: {:?}", event);
-    let send_result = nostr_client.send_event(&event).await;
-    if send_result.is_err() {
-        let err = send_result.err();
-        eprintln!("[Nostr] Failed to post to any relays: {:?}", err);
-        return Err(PostError::Api(format!("Failed to post to any Nostr relays: {:?}", err)));
+    let total = chunks.len();
+    if total == 1 {
+        return chunks;
     }
-    Ok(())
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| format!("{} ({}/{})", chunk, i + 1, total))
+        .collect()
 }
 
-pub async fn post_to_microblog(config: &MicroBlogConfig, text: &str, image_path: Option<&str>) -> Result<(), PostError> {
-    if !config.enabled || config.decrypted_access_token.is_empty() {
-        return Err(PostError::Auth("Micro.Blog not configured".to_string()));
+/// Split a paragraph after each `.`, `!` or `?` that is followed by whitespace.
+fn split_sentences(paragraph: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?') {
+            if let Some(&(next, following)) = chars.peek() {
+                if following.is_whitespace() {
+                    sentences.push(paragraph[start..next].trim());
+                    start = next;
+                }
+            }
+        }
     }
+    sentences.push(paragraph[start..].trim());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
 
-    let client = reqwest::Client::new();
-    if let Some(path) = image_path {
-        let img_bytes = std::fs::read(path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
-        let part = reqwest::multipart::Part::bytes(img_bytes).file_name("image.jpg");
-        let content_owned = text.to_string();
-        let form_data = reqwest::multipart::Form::new()
-            .text("h", "entry")
-            .text("content", content_owned)
-            .part("photo", part);
-        let response = client
-            .post("https://micro.blog/micropub")
-            .header("Authorization", format!("Bearer {}", config.decrypted_access_token))
-            .multipart(form_data)
-            .send()
-            .await?;
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(PostError::Api(format!("Micro.Blog API error: {}", error_text)));
+/// A rich-text feature detected in a Bluesky post, with UTF-8 byte offsets into the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FacetSpan {
+    byte_start: usize,
+    byte_end: usize,
+    kind: FacetKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FacetKind {
+    Link(String),
+    Mention(String), // Handle without the leading '@'
+    Tag(String),     // Tag without the leading '#'
+}
+
+/// Split text on whitespace, yielding each word with its byte offset.
+fn words_with_offsets(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if let Some(s) = start.take() {
+                words.push((s, &text[s..i]));
+            }
+        } else if start.is_none() {
+            start = Some(i);
         }
-        return Ok(());
     }
-    let response = client
-        .post("https://micro.blog/micropub")
-        .header("Authorization", format!("Bearer {}", config.decrypted_access_token))
-        .form(&[("h", "entry"), ("content", text)])
-        .send()
-        .await?;
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(PostError::Api(format!("Micro.Blog API error: {}", error_text)));
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
     }
-    Ok(())
+    words
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn trim_trailing_punctuation(word: &str) -> &str {
+    word.trim_end_matches(|c: char| matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')' | '"' | '\''))
+}
 
-    #[test]
-    fn test_bluesky_truncation() {
-        // Test that long text gets truncated to 300 characters
-        let long_text = "a".repeat(400);
-        let truncated = if long_text.chars().count() > BLUESKY_CHARACTER_LIMIT {
-            long_text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
-        } else {
-            long_text.to_string()
-        };
-        
-        assert_eq!(truncated.chars().count(), BLUESKY_CHARACTER_LIMIT);
-        assert_eq!(truncated, "a".repeat(300));
+/// Longest link path, query and fragment shown in full; longer ones are cut with an ellipsis
+const BLUESKY_LINK_PATH_CHARS: usize = 15;
+/// How much of a cut path is kept
+const BLUESKY_LINK_PATH_KEPT: usize = 13;
+
+/// A link as the Bluesky app shows it: without its scheme, and with a long path cut short.
+fn bluesky_link_display(uri: &str) -> String {
+    let Ok(url) = url::Url::parse(uri) else {
+        return uri.to_string();
+    };
+    let Some(host) = url.host_str() else {
+        return uri.to_string();
+    };
+    let mut path = if url.path() == "/" { String::new() } else { url.path().to_string() };
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
     }
+    if let Some(fragment) = url.fragment() {
+        path.push('#');
+        path.push_str(fragment);
+    }
+    if path.chars().count() > BLUESKY_LINK_PATH_CHARS {
+        let kept: String = path.chars().take(BLUESKY_LINK_PATH_KEPT).collect();
+        return format!("{}{}...", host, kept);
+    }
+    format!("{}{}", host, path)
+}
 
-    #[test]
-    fn test_bluesky_no_truncation_needed() {
-        // Test that short text doesn't get truncated
-        let short_text = "Hello, world!";
-        let result = if short_text.chars().count() > BLUESKY_CHARACTER_LIMIT {
-            short_text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
+/// The text of a Bluesky post with its links shortened for display, and the facets in it.
+/// Link facets still point at the full address, so only what's shown is shorter.
+fn bluesky_display_text(text: &str) -> (String, Vec<FacetSpan>) {
+    let mut display = String::with_capacity(text.len());
+    let mut spans = Vec::new();
+    let mut copied = 0;
+    for span in detect_facets(text) {
+        display.push_str(&text[copied..span.byte_start]);
+        let byte_start = display.len();
+        match &span.kind {
+            FacetKind::Link(uri) => display.push_str(&bluesky_link_display(uri)),
+            _ => display.push_str(&text[span.byte_start..span.byte_end]),
+        }
+        copied = span.byte_end;
+        spans.push(FacetSpan { byte_start, byte_end: display.len(), kind: span.kind });
+    }
+    display.push_str(&text[copied..]);
+    (display, spans)
+}
+
+/// Find links, `@handle.domain` mentions and `#hashtags` in a post.
+///
+/// Offsets are byte positions, as required by `app.bsky.richtext.facet`, so
+/// multi-byte characters before a facet shift its index correctly.
+fn detect_facets(text: &str) -> Vec<FacetSpan> {
+    let mut facets = Vec::new();
+    for (offset, word) in words_with_offsets(text) {
+        let word = trim_trailing_punctuation(word);
+        let kind = if word.starts_with("https://") || word.starts_with("http://") {
+            if word.len() <= "https://".len() {
+                continue;
+            }
+            FacetKind::Link(word.to_string())
+        } else if let Some(handle) = word.strip_prefix('@') {
+            let valid = handle.contains('.')
+                && !handle.starts_with('.')
+                && !handle.ends_with('.')
+                && handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+            if !valid {
+                continue;
+            }
+            FacetKind::Mention(handle.to_string())
+        } else if let Some(tag) = word.strip_prefix('#') {
+            let valid = !tag.is_empty()
+                && tag.chars().count() <= 64
+                && !tag.chars().all(|c| c.is_ascii_digit())
+                && tag.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if !valid {
+                continue;
+            }
+            FacetKind::Tag(tag.to_string())
         } else {
-            short_text.to_string()
+            continue;
         };
-        
-        assert_eq!(result, short_text);
+        facets.push(FacetSpan {
+            byte_start: offset,
+            byte_end: offset + word.len(),
+            kind,
+        });
     }
+    facets
+}
 
-    #[test]
-    fn test_unicode_truncation() {
-        // Test that Unicode characters are handled properly
-        let unicode_text = "🚀".repeat(400); // Each emoji is multiple bytes but 1 character
-        let truncated = if unicode_text.chars().count() > BLUESKY_CHARACTER_LIMIT {
-            unicode_text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
+/// Hashtags in a note, lowercased and without duplicates, for its NIP-24 `t` tags.
+///
+/// Uses the same word rules as Bluesky facets, so a `#` inside a link isn't a tag.
+fn nostr_hashtags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for span in detect_facets(text) {
+        if let FacetKind::Tag(tag) = span.kind {
+            let tag = tag.to_lowercase();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// NIP-32 tags labelling a note with its ISO 639-1 language code.
+fn nostr_language_tags(language: &str) -> [Tag; 2] {
+    [
+        Tag::custom(TagKind::custom("L"), ["ISO-639-1".to_string()]),
+        Tag::custom(TagKind::custom("l"), [language.to_string(), "ISO-639-1".to_string()]),
+    ]
+}
+
+/// Links in `text` worth shortening, without duplicates.
+///
+/// Links that are already short or already on the shortener's own host are left out.
+pub fn links_to_shorten(text: &str, endpoint: &str) -> Vec<String> {
+    let host = |link: &str| url::Url::parse(link).ok().and_then(|url| url.host_str().map(str::to_lowercase));
+    let shortener_host = host(endpoint);
+    let mut links: Vec<String> = Vec::new();
+    for span in detect_facets(text) {
+        if let FacetKind::Link(link) = span.kind {
+            if link.chars().count() > SHORT_LINK_MAX_CHARS && host(&link) != shortener_host && !links.contains(&link) {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
+/// Replace each link in `text` that has an entry in `short_links`.
+fn replace_links(text: &str, short_links: &HashMap<String, String>) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut end = 0;
+    for span in detect_facets(text) {
+        if let FacetKind::Link(link) = &span.kind {
+            if let Some(short) = short_links.get(link) {
+                replaced.push_str(&text[end..span.byte_start]);
+                replaced.push_str(short);
+                end = span.byte_end;
+            }
+        }
+    }
+    replaced.push_str(&text[end..]);
+    replaced
+}
+
+/// The shortener request for `link`, with the link percent-encoded into the `{url}` placeholder.
+fn shortener_request_url(endpoint: &str, link: &str) -> String {
+    let encoded: String = url::form_urlencoded::byte_serialize(link.as_bytes()).collect();
+    endpoint.trim().replace("{url}", &encoded)
+}
+
+/// Rewrite long links in `text` with the configured shortener, which must reply with the
+/// short URL as plain text. Its API key, if one is set, goes in the `X-Api-Key` header.
+pub async fn shorten_links(client: &reqwest::Client, retry: RetryPolicy, config: &UrlShortenerConfig, text: &str) -> Result<String, PostError> {
+    if !config.endpoint.contains("{url}") {
+        return Err(PostError::Api("The shortener address needs a {url} placeholder".to_string()));
+    }
+    let mut short_links = HashMap::new();
+    for link in links_to_shorten(text, &config.endpoint) {
+        let request_url = shortener_request_url(&config.endpoint, &link);
+        let response = send_with_retry(retry, || {
+            let request = client.get(&request_url);
+            match config.decrypted_api_key.trim() {
+                "" => request,
+                key => request.header("X-Api-Key", key),
+            }
+        })
+        .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(PostError::Api(format!("Shortener returned {}: {}", status, body.trim())));
+        }
+        let short = body.trim();
+        if !short.starts_with("https://") && !short.starts_with("http://") {
+            return Err(PostError::Api(format!("Shortener didn't return a link for {}", link)));
+        }
+        short_links.insert(link, short.to_string());
+    }
+    Ok(replace_links(text, &short_links))
+}
+
+/// Longest raw response body quoted in an error message
+const ERROR_BODY_MAX_CHARS: usize = 200;
+
+/// A readable description of a failed response: its HTTP status, and the message from the
+/// body when it has one of the usual error shapes.
+///
+/// Mastodon and Micropub send `error` with an optional `error_description`, AT Protocol sends
+/// `error` with a `message`, and some APIs nest these in an `error` object or an `errors` list.
+/// Other bodies are quoted as they are, cut short if long.
+fn describe_error_response(status: reqwest::StatusCode, body: &str) -> String {
+    let status = format!("HTTP {}", status);
+    match error_message(body) {
+        Some(message) => format!("{}: {}", status, message),
+        None => status,
+    }
+}
+
+/// The message in an error response body, or the trimmed body itself if it isn't recognized.
+fn error_message(body: &str) -> Option<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+    let text = |value: &Value| value.as_str().map(str::trim).filter(|text| !text.is_empty()).map(str::to_string);
+    let parsed = serde_json::from_str::<Value>(body).ok().and_then(|data| {
+        let error = &data["error"];
+        text(&data["message"])
+            .or_else(|| text(&data["error_description"]))
+            .or_else(|| text(&error["message"]))
+            .or_else(|| text(error))
+            .or_else(|| {
+                let errors: Vec<String> = data["errors"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|error| text(error).or_else(|| text(&error["message"])))
+                    .collect();
+                (!errors.is_empty()).then(|| errors.join("; "))
+            })
+    });
+    Some(parsed.unwrap_or_else(|| {
+        let mut quoted: String = body.chars().take(ERROR_BODY_MAX_CHARS).collect();
+        if quoted.len() < body.len() {
+            quoted.push('…');
+        }
+        quoted
+    }))
+}
+
+/// Describe a failed response with `describe_error_response`, reading its body.
+async fn error_response_message(response: reqwest::Response) -> String {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    describe_error_response(status, &body)
+}
+
+/// Whether images and tokens can be sent to `endpoint` without anyone on the network reading
+/// them: it's https, or plain http to this computer.
+pub fn is_private_endpoint(endpoint: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(endpoint) else {
+        return false;
+    };
+    match url.scheme() {
+        "https" => url.host_str().is_some(),
+        "http" => {
+            let host = url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+            host.eq_ignore_ascii_case("localhost") || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        }
+        _ => false,
+    }
+}
+
+/// Ask the configured captioning service to describe an image, for use as its alt text.
+///
+/// The image's bytes are posted with their MIME type, with metadata removed unless
+/// `keep_metadata` is set, and the token as a bearer token when there is one.
+pub async fn suggest_alt_text(client: &reqwest::Client, retry: RetryPolicy, config: &CaptionerConfig, path: &str, keep_metadata: bool) -> Result<String, PostError> {
+    let endpoint = config.endpoint.trim();
+    if !is_private_endpoint(endpoint) {
+        return Err(PostError::Api("The captioning service address must start with https://, unless it's on this computer".to_string()));
+    }
+    let bytes = std::fs::read(path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+    let bytes = if keep_metadata {
+        bytes
+    } else {
+        // Sent without its metadata or not at all
+        strip_image_metadata(&bytes)
+            .map_err(|e| PostError::Api(format!("Can't describe the image: {}", e)))?
+            .unwrap_or(bytes)
+    };
+    let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
+
+    let response = send_with_retry(retry, || {
+        let request = client
+            .post(endpoint)
+            .header(reqwest::header::CONTENT_TYPE, &mime_type)
+            .body(bytes.clone());
+        if config.decrypted_token.is_empty() {
+            request
         } else {
-            unicode_text.to_string()
+            request.bearer_auth(config.decrypted_token.as_str())
+        }
+    })
+    .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(PostError::Auth(format!("Captioning service returned {}", status)));
+    }
+    if !status.is_success() {
+        return Err(PostError::Api(format!("Captioning service returned {}: {}", status, body.trim())));
+    }
+    parse_caption(&body).ok_or_else(|| PostError::Api("The captioning service didn't return a caption".to_string()))
+}
+
+/// The caption in a captioning service's reply.
+///
+/// JSON replies may hold it in `caption`, `alt_text`, `text` or `generated_text`, at the top
+/// level or in the first element of an array; anything else is taken as plain text.
+fn parse_caption(body: &str) -> Option<String> {
+    let caption = match serde_json::from_str::<Value>(body) {
+        Ok(value) if value.is_object() || value.is_array() || value.is_string() => {
+            let value = value.get(0).unwrap_or(&value);
+            ["caption", "alt_text", "text", "generated_text"]
+                .iter()
+                .find_map(|key| value[key].as_str())
+                .or_else(|| value.as_str())?
+                .to_string()
+        }
+        _ => body.to_string(),
+    };
+    Some(caption.trim().to_string()).filter(|caption| !caption.is_empty())
+}
+
+/// Build the `facets` array for a Bluesky record from the spans in its text, resolving
+/// mentions to DIDs.
+///
+/// Mentions whose handle can't be resolved are left as plain text.
+async fn build_bluesky_facets(client: &reqwest::Client, service: &str, spans: Vec<FacetSpan>) -> Vec<Value> {
+    let mut facets = Vec::new();
+    for span in spans {
+        let feature = match span.kind {
+            FacetKind::Link(uri) => json!({
+                "$type": "app.bsky.richtext.facet#link",
+                "uri": uri
+            }),
+            FacetKind::Tag(tag) => json!({
+                "$type": "app.bsky.richtext.facet#tag",
+                "tag": tag
+            }),
+            FacetKind::Mention(handle) => match resolve_bluesky_handle(client, service, &handle).await {
+                Some(did) => json!({
+                    "$type": "app.bsky.richtext.facet#mention",
+                    "did": did
+                }),
+                None => continue,
+            },
         };
-        
-        assert_eq!(truncated.chars().count(), BLUESKY_CHARACTER_LIMIT);
-        assert_eq!(truncated, "🚀".repeat(300));
+        facets.push(json!({
+            "index": {
+                "byteStart": span.byte_start,
+                "byteEnd": span.byte_end
+            },
+            "features": [feature]
+        }));
+    }
+    facets
+}
+
+async fn resolve_bluesky_handle(client: &reqwest::Client, service: &str, handle: &str) -> Option<String> {
+    let response = client
+        .get(format!("{}/xrpc/com.atproto.identity.resolveHandle", service))
+        .query(&[("handle", handle)])
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        tracing::warn!("[Bluesky] Could not resolve handle @{}: {}", handle, response.status());
+        return None;
+    }
+    let data: Value = response.json().await.ok()?;
+    data["did"].as_str().map(|s| s.to_string())
+}
+
+/// Upload raw bytes to the user's Bluesky repo and return the blob reference.
+///
+/// A rejected upload is logged and yields `None` so the post can still go out. With
+/// `progress`, the bytes are counted toward it as they're sent.
+async fn upload_bluesky_blob(login: &mut BlueskyLogin<'_>, retry: RetryPolicy, bytes: Vec<u8>, mime_type: &str, progress: Option<&UploadProgress>) -> Result<Option<Value>, PostError> {
+    let (client, service) = (login.client, login.service);
+    let upload_response = login.send(retry, |access_jwt| {
+        let body = match progress {
+            Some(progress) => progress.body(&bytes),
+            None => bytes.clone().into(),
+        };
+        client
+            .post(format!("{}/xrpc/com.atproto.repo.uploadBlob", service))
+            .header("Authorization", format!("Bearer {}", access_jwt))
+            .header("Content-Type", mime_type)
+            // A streamed body has no length of its own
+            .header(reqwest::header::CONTENT_LENGTH, bytes.len())
+            .body(body)
+    })
+    .await?;
+    tracing::debug!("[Bluesky] Blob upload status: {}", upload_response.status());
+    if !upload_response.status().is_success() {
+        tracing::warn!("[Bluesky] Blob upload failed: {}", error_response_message(upload_response).await);
+        return Ok(None);
+    }
+    let upload_data: Value = upload_response.json().await?;
+    tracing::trace!("[Bluesky] Blob upload response: {}", upload_data);
+    Ok(upload_data.get("blob").map(|blob| {
+        json!({
+            "$type": "blob",
+            "ref": blob["ref"].clone(),
+            "mimeType": blob["mimeType"].clone(),
+            "size": blob["size"].clone()
+        })
+    }))
+}
+
+/// Upload a video through Bluesky's video service and return its blob once it's been processed.
+///
+/// Unlike an image, a video that can't be uploaded fails the post, as it's what the post is for.
+async fn upload_bluesky_video(login: &mut BlueskyLogin<'_>, retry: RetryPolicy, video: &ImageAttachment, progress: &UploadProgress) -> Result<Value, PostError> {
+    check_bluesky_video(&video.path).map_err(PostError::Api)?;
+    let (client, service) = (login.client, login.service);
+    let did = login.session.did.clone().ok_or_else(|| PostError::Auth("The Bluesky session has no DID".to_string()))?;
+    let pds_url = login.session.pds_url.clone().unwrap_or_else(|| service.to_string());
+    let pds_host = url::Url::parse(&pds_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .ok_or_else(|| PostError::Api(format!("Invalid Bluesky server URL: {}", pds_url)))?;
+    let bytes = std::fs::read(&video.path).map_err(|e| PostError::Api(format!("Failed to read video: {}", e)))?;
+    let mime_type = mime_guess::from_path(&video.path).first_or_octet_stream().to_string();
+    let name = std::path::Path::new(&video.path)
+        .file_name()
+        .map_or_else(|| "video.mp4".to_string(), |name| name.to_string_lossy().to_string());
+
+    // The video service stores the result in the user's repo, so it needs a token the PDS takes for uploadBlob
+    let expires_at = chrono::Utc::now().timestamp() + 30 * 60;
+    let auth_response = login.send(retry, |access_jwt| {
+        client
+            .get(format!("{}/xrpc/com.atproto.server.getServiceAuth", service))
+            .query(&[
+                ("aud", format!("did:web:{}", pds_host)),
+                ("lxm", "com.atproto.repo.uploadBlob".to_string()),
+                ("exp", expires_at.to_string()),
+            ])
+            .header("Authorization", format!("Bearer {}", access_jwt))
+    })
+    .await?;
+    if !auth_response.status().is_success() {
+        return Err(PostError::Auth(format!("Bluesky wouldn't authorize the video upload ({})", error_response_message(auth_response).await)));
+    }
+    let auth_data: Value = auth_response.json().await?;
+    let token = auth_data["token"]
+        .as_str()
+        .ok_or_else(|| PostError::Auth("No service token received".to_string()))?
+        .to_string();
+
+    progress.add_total(bytes.len());
+    let upload_response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/xrpc/app.bsky.video.uploadVideo", BLUESKY_VIDEO_SERVICE))
+            .query(&[("did", did.as_str()), ("name", name.as_str())])
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", mime_type.as_str())
+            .header(reqwest::header::CONTENT_LENGTH, bytes.len())
+            .body(progress.body(&bytes))
+    })
+    .await?;
+    let status = upload_response.status();
+    tracing::debug!("[Bluesky] Video upload status: {}", status);
+    // A video that was uploaded before is turned away with a conflict naming its existing job
+    if !status.is_success() && status != reqwest::StatusCode::CONFLICT {
+        return Err(PostError::Api(format!("Bluesky video upload failed: {}", error_response_message(upload_response).await)));
+    }
+    let upload_data: Value = upload_response.json().await?;
+    tracing::trace!("[Bluesky] Video upload response: {}", upload_data);
+    let job = if upload_data["jobStatus"].is_object() { &upload_data["jobStatus"] } else { &upload_data };
+    let job_id = job["jobId"]
+        .as_str()
+        .ok_or_else(|| PostError::Api(format!("Bluesky video upload failed: {}", upload_data["message"].as_str().unwrap_or("no job was started"))))?
+        .to_string();
+
+    let started = std::time::Instant::now();
+    loop {
+        let response = send_with_retry(retry, || {
+            client
+                .get(format!("{}/xrpc/app.bsky.video.getJobStatus", BLUESKY_VIDEO_SERVICE))
+                .query(&[("jobId", job_id.as_str())])
+        })
+        .await?;
+        if !response.status().is_success() {
+            return Err(PostError::Api(format!("Couldn't check on the Bluesky video: {}", error_response_message(response).await)));
+        }
+        let data: Value = response.json().await?;
+        let job = &data["jobStatus"];
+        match job["state"].as_str() {
+            Some("JOB_STATE_COMPLETED") if job["blob"].is_object() => return Ok(job["blob"].clone()),
+            Some("JOB_STATE_FAILED") => {
+                let reason = job["message"].as_str().or(job["error"].as_str()).unwrap_or("unknown error");
+                return Err(PostError::Api(format!("Bluesky couldn't process the video: {}", reason)));
+            }
+            state => tracing::debug!("[Bluesky] Video job {} is {:?} ({}%)", job_id, state, job["progress"]),
+        }
+        if started.elapsed() > BLUESKY_VIDEO_PROCESSING_TIMEOUT {
+            return Err(PostError::Api("Bluesky took too long to process the video".to_string()));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Check a video against Bluesky's size and length limits, so it's turned down before uploading.
+///
+/// The length is read from MP4 and QuickTime files; other formats are only checked for size.
+pub fn check_bluesky_video(path: &str) -> Result<(), String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let size = file.metadata().map_err(|e| format!("Failed to read {}: {}", path, e))?.len();
+    if size > BLUESKY_MAX_VIDEO_BYTES {
+        return Err(format!(
+            "The video is {:.1} MB, over Bluesky's {} MB limit",
+            size as f64 / 1_000_000.0,
+            BLUESKY_MAX_VIDEO_BYTES / 1_000_000
+        ));
+    }
+    if let Some(duration) = mp4_duration(&mut file) {
+        if duration > BLUESKY_MAX_VIDEO_DURATION {
+            let secs = duration.as_secs();
+            return Err(format!(
+                "The video runs {}:{:02}, over Bluesky's {}-minute limit",
+                secs / 60,
+                secs % 60,
+                BLUESKY_MAX_VIDEO_DURATION.as_secs() / 60
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The length an MP4 or QuickTime file's `moov/mvhd` box gives, without reading the media itself.
+fn mp4_duration<R: Read + Seek>(reader: &mut R) -> Option<Duration> {
+    let end = reader.seek(SeekFrom::End(0)).ok()?;
+    let (moov_start, moov_end) = find_mp4_box(reader, 0, end, b"moov")?;
+    let (mvhd_start, _) = find_mp4_box(reader, moov_start, moov_end, b"mvhd")?;
+    reader.seek(SeekFrom::Start(mvhd_start)).ok()?;
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version).ok()?;
+    // Version 1 widens the creation and modification times and the duration to 64 bits
+    let (timescale, duration) = if version[0] == 1 {
+        let mut fields = [0u8; 28];
+        reader.read_exact(&mut fields).ok()?;
+        (u32::from_be_bytes(fields[16..20].try_into().ok()?), u64::from_be_bytes(fields[20..28].try_into().ok()?))
+    } else {
+        let mut fields = [0u8; 16];
+        reader.read_exact(&mut fields).ok()?;
+        (u32::from_be_bytes(fields[8..12].try_into().ok()?), u32::from_be_bytes(fields[12..16].try_into().ok()?) as u64)
+    };
+    (timescale > 0).then(|| Duration::from_secs_f64(duration as f64 / timescale as f64))
+}
+
+/// Where the contents of the first box of type `kind` between `start` and `end` begin and end.
+fn find_mp4_box<R: Read + Seek>(reader: &mut R, mut start: u64, end: u64, kind: &[u8; 4]) -> Option<(u64, u64)> {
+    while start.checked_add(8)? <= end {
+        reader.seek(SeekFrom::Start(start)).ok()?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).ok()?;
+        let mut size = u32::from_be_bytes(header[..4].try_into().ok()?) as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large_size = [0u8; 8];
+            reader.read_exact(&mut large_size).ok()?;
+            size = u64::from_be_bytes(large_size);
+            header_len = 16;
+        } else if size == 0 {
+            // The last box may run to the end of the file
+            size = end - start;
+        }
+        if size < header_len {
+            return None;
+        }
+        let box_end = start.checked_add(size)?;
+        if &header[4..] == kind {
+            return Some((start + header_len, box_end.min(end)));
+        }
+        start = box_end;
+    }
+    None
+}
+
+/// Copy images into a new temp directory without their metadata, keeping file names.
+///
+/// GIFs and videos aren't handled and are copied unchanged. An image whose metadata can't be
+/// removed is left out, with a warning for each one.
+fn copy_without_metadata(images: &[ImageAttachment]) -> Result<(PathBuf, Vec<ImageAttachment>, Vec<String>), String> {
+    let dir = std::env::temp_dir().join(format!("yall-cosmic-upload-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let copy_all = || {
+        let mut copies = Vec::new();
+        let mut warnings = Vec::new();
+        for (index, image) in images.iter().enumerate() {
+            let bytes = std::fs::read(&image.path).map_err(|e| format!("Failed to read image: {}", e))?;
+            let bytes = match strip_image_metadata(&bytes) {
+                Ok(stripped) => stripped.unwrap_or(bytes),
+                Err(e) => {
+                    warnings.push(format!("{} was left out: {}", upload_file_name(&image.path), e));
+                    continue;
+                }
+            };
+            // A subdirectory each, in case two images share a name
+            let image_dir = dir.join(index.to_string());
+            let path = image_dir.join(upload_file_name(&image.path));
+            std::fs::create_dir(&image_dir)
+                .and_then(|_| std::fs::write(&path, bytes))
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            copies.push(ImageAttachment {
+                path: path.to_string_lossy().to_string(),
+                alt_text: image.alt_text.clone(),
+            });
+        }
+        Ok((copies, warnings))
+    };
+    match copy_all() {
+        Ok((copies, warnings)) => Ok((dir, copies, warnings)),
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&dir);
+            Err(e)
+        }
+    }
+}
+
+/// The image without EXIF, XMP, IPTC or text metadata (GPS position, camera details,
+/// timestamps), or `None` if it isn't a JPEG, PNG or WebP. A file in one of those formats that
+/// can't be read is an error rather than going out with its metadata.
+fn strip_image_metadata(bytes: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let stripped = if bytes.starts_with(PNG_SIGNATURE) {
+        strip_png_metadata(bytes)
+    } else if bytes.starts_with(&[0xFF, 0xD8]) {
+        strip_jpeg_metadata(bytes).map(|stripped| {
+            // Rotation is stored in EXIF, so turn the pixels instead of losing it
+            match jpeg_orientation(bytes) {
+                Some(orientation) if orientation != image::metadata::Orientation::NoTransforms => {
+                    reencode_jpeg_oriented(bytes, orientation).unwrap_or(stripped)
+                }
+                _ => stripped,
+            }
+        })
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        strip_webp_metadata(bytes)
+    } else {
+        return Ok(None);
+    };
+    stripped
+        .map(Some)
+        .ok_or_else(|| "the image is damaged, so its metadata couldn't be removed".to_string())
+}
+
+/// Copy a JPEG's segments except APP1 (EXIF and XMP) and APP13 (IPTC).
+fn strip_jpeg_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut stripped = vec![0xFF, 0xD8];
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            return None;
+        }
+        let marker = bytes[pos + 1];
+        if marker == 0xFF {
+            // Fill byte
+            pos += 1;
+            continue;
+        }
+        if marker == 0xDA {
+            // Start of scan: the rest is image data
+            stripped.extend_from_slice(&bytes[pos..]);
+            return Some(stripped);
+        }
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > bytes.len() {
+            return None;
+        }
+        if marker != 0xE1 && marker != 0xED {
+            stripped.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// The first bytes of every PNG file
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Copy a PNG's chunks except EXIF, text and modification time.
+fn strip_png_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+    let mut stripped = PNG_SIGNATURE.to_vec();
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 12 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[pos..pos + 4].try_into().ok()?) as usize;
+        let end = pos.checked_add(12 + length).filter(|end| *end <= bytes.len())?;
+        let kind = &bytes[pos + 4..pos + 8];
+        if !matches!(kind, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+            stripped.extend_from_slice(&bytes[pos..end]);
+        }
+        if kind == b"IEND" {
+            return Some(stripped);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// Copy a WebP's chunks except EXIF and XMP, and clear the flags that announce them.
+fn strip_webp_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < 12 || !bytes.starts_with(b"RIFF") || &bytes[8..12] != b"WEBP" {
+        return None;
+    }
+    let mut stripped = b"RIFF\0\0\0\0WEBP".to_vec();
+    let mut pos = 12;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos.checked_add(8)?)?;
+        let size = u32::from_le_bytes(header[4..8].try_into().ok()?) as usize;
+        let data_end = pos.checked_add(8)?.checked_add(size).filter(|end| *end <= bytes.len())?;
+        // Chunks are padded to an even length
+        let end = (data_end + size % 2).min(bytes.len());
+        match &header[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let mut chunk = bytes[pos..end].to_vec();
+                // The flags byte: 0x08 says there's EXIF, 0x04 XMP
+                *chunk.get_mut(8)? &= !0x0C;
+                stripped.extend_from_slice(&chunk);
+            }
+            _ => stripped.extend_from_slice(&bytes[pos..end]),
+        }
+        pos = end;
+    }
+    let riff_size = u32::try_from(stripped.len() - 8).ok()?;
+    stripped[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(stripped)
+}
+
+fn jpeg_orientation(bytes: &[u8]) -> Option<image::metadata::Orientation> {
+    let mut decoder = image::codecs::jpeg::JpegDecoder::new(Cursor::new(bytes)).ok()?;
+    image::ImageDecoder::orientation(&mut decoder).ok()
+}
+
+/// Decode, apply the EXIF rotation and encode again, which leaves no metadata behind.
+fn reencode_jpeg_oriented(bytes: &[u8], orientation: image::metadata::Orientation) -> Option<Vec<u8>> {
+    let mut img = image::load_from_memory(bytes).ok()?;
+    img.apply_orientation(orientation);
+    let mut encoded = Vec::new();
+    DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, 90))
+        .ok()?;
+    Some(encoded)
+}
+
+/// Re-encode an image as JPEG until it fits in `max_bytes`.
+///
+/// Quality is lowered first, then the dimensions are scaled down by a quarter at a
+/// time (keeping the aspect ratio) and the quality steps are tried again.
+fn shrink_image_to_fit(bytes: &[u8], max_bytes: usize) -> Result<Vec<u8>, String> {
+    let decoded = image::load_from_memory(bytes).map_err(|e| format!("Failed to decode image: {}", e))?;
+    // JPEG has no alpha channel
+    let mut img = DynamicImage::ImageRgb8(decoded.to_rgb8());
+    loop {
+        for quality in [90, 80, 70, 60, 50] {
+            let mut encoded = Vec::new();
+            img.write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, quality))
+                .map_err(|e| format!("Failed to encode image: {}", e))?;
+            if encoded.len() <= max_bytes {
+                return Ok(encoded);
+            }
+        }
+        let (width, height) = (img.width() * 3 / 4, img.height() * 3 / 4);
+        if width < 64 || height < 64 {
+            return Err(format!("Could not shrink image below {} bytes", max_bytes));
+        }
+        img = img.resize(width, height, FilterType::Triangle);
+    }
+}
+
+/// OpenGraph details of a linked page, used for Bluesky external embeds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct LinkMetadata {
+    title: String,
+    description: String,
+    image: Option<String>,
+}
+
+/// Read the value of an HTML attribute from the inside of a tag.
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(found) = lower[search_from..].find(name) {
+        let start = search_from + found;
+        search_from = start + name.len();
+        // Must be a whole attribute name
+        if start > 0 && !lower.as_bytes()[start - 1].is_ascii_whitespace() {
+            continue;
+        }
+        let rest = tag[start + name.len()..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => rest[1..].split(quote).next().unwrap_or(""),
+            _ => rest.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or(""),
+        };
+        return Some(decode_html_entities(value));
+    }
+    None
+}
+
+fn decode_html_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Extract OpenGraph title/description/image, falling back to `<title>`.
+fn extract_link_metadata(html: &str) -> LinkMetadata {
+    let mut metadata = LinkMetadata::default();
+    let lower = html.to_ascii_lowercase();
+
+    let mut search_from = 0;
+    while let Some(found) = lower[search_from..].find("<meta") {
+        let start = search_from + found;
+        let end = lower[start..].find('>').map(|e| start + e).unwrap_or(html.len());
+        let tag = &html[start + "<meta".len()..end];
+        search_from = end;
+
+        let property = html_attribute(tag, "property").or_else(|| html_attribute(tag, "name"));
+        let Some(content) = html_attribute(tag, "content") else {
+            continue;
+        };
+        match property.as_deref() {
+            Some("og:title") if metadata.title.is_empty() => metadata.title = content,
+            Some("og:description") if metadata.description.is_empty() => metadata.description = content,
+            Some("og:image") if metadata.image.is_none() => metadata.image = Some(content),
+            _ => {}
+        }
+    }
+
+    if metadata.title.is_empty() {
+        if let Some(start) = lower.find("<title") {
+            if let Some(open_end) = lower[start..].find('>') {
+                let content_start = start + open_end + 1;
+                let content_end = lower[content_start..]
+                    .find("</title>")
+                    .map(|e| content_start + e)
+                    .unwrap_or(html.len());
+                metadata.title = decode_html_entities(html[content_start..content_end].trim());
+            }
+        }
+    }
+
+    metadata
+}
+
+/// Build an `app.bsky.embed.external` card for a link, or `None` if the page can't be read.
+async fn build_bluesky_link_card(login: &mut BlueskyLogin<'_>, uri: &str) -> Option<Value> {
+    let response = login.client.get(uri).send().await.ok()?;
+    if !response.status().is_success() {
+        tracing::warn!("[Bluesky] Link preview fetch failed for {}: {}", uri, response.status());
+        return None;
+    }
+    let html = response.text().await.ok()?;
+    let metadata = extract_link_metadata(&html);
+
+    let mut external = json!({
+        "uri": uri,
+        "title": metadata.title,
+        "description": metadata.description
+    });
+
+    if let Some(image) = metadata.image {
+        // og:image may be relative to the page
+        let image_url = url::Url::parse(uri).ok().and_then(|base| base.join(&image).ok());
+        if let Some(image_url) = image_url {
+            if let Some(thumb) = fetch_bluesky_thumbnail(login, image_url.as_str()).await {
+                external["thumb"] = thumb;
+            }
+        }
+    }
+
+    Some(json!({
+        "$type": "app.bsky.embed.external",
+        "external": external
+    }))
+}
+
+async fn fetch_bluesky_thumbnail(login: &mut BlueskyLogin<'_>, image_url: &str) -> Option<Value> {
+    let response = login.client.get(image_url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let mime_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| mime_guess::from_path(image_url).first_or_octet_stream().to_string());
+    let bytes = response.bytes().await.ok()?;
+    if bytes.len() > 1_000_000 {
+        tracing::debug!("[Bluesky] Link preview image too large ({} bytes), skipping thumbnail", bytes.len());
+        return None;
+    }
+    upload_bluesky_blob(login, RetryPolicy::ONCE, bytes.to_vec(), &mime_type, None).await.ok().flatten()
+}
+
+/// Read an image and its MIME type, re-encoding it as a smaller JPEG if it's over `max_bytes`.
+async fn read_image_within(image: &ImageAttachment, max_bytes: usize) -> Result<(Vec<u8>, String), PostError> {
+    let img_bytes = std::fs::read(&image.path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+    if img_bytes.len() <= max_bytes {
+        let mime_type = mime_guess::from_path(&image.path).first_or_octet_stream().to_string();
+        return Ok((img_bytes, mime_type));
+    }
+    let original_len = img_bytes.len();
+    let shrunk = tokio::task::spawn_blocking(move || shrink_image_to_fit(&img_bytes, max_bytes))
+        .await
+        .map_err(|e| PostError::Api(format!("Image resize failed: {}", e)))?
+        .map_err(PostError::Api)?;
+    tracing::info!("Shrunk {} from {} to {} bytes", image.path, original_len, shrunk.len());
+    Ok((shrunk, "image/jpeg".to_string()))
+}
+
+/// Tokens from `createSession`, kept so later posts can skip logging in again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BlueskySession {
+    access_jwt: String,
+    refresh_jwt: String,
+    /// When the access token expires, as a Unix timestamp, if it could be read
+    access_expires_at: Option<i64>,
+    did: Option<String>,
+    /// The account's own server, from its DID document
+    pds_url: Option<String>,
+}
+
+/// Access tokens are refreshed this long before they expire
+const BLUESKY_SESSION_MARGIN_SECS: i64 = 60;
+
+/// Bluesky sessions by service URL and handle, for the rest of the app's run
+static BLUESKY_SESSIONS: LazyLock<Mutex<HashMap<String, BlueskySession>>> = LazyLock::new(Default::default);
+
+impl BlueskySession {
+    /// Read the tokens from a `createSession` or `refreshSession` response.
+    fn from_response(data: &Value) -> Option<Self> {
+        let access_jwt = data["accessJwt"].as_str()?.to_string();
+        let refresh_jwt = data["refreshJwt"].as_str()?.to_string();
+        let access_expires_at = jwt_expiry(&access_jwt);
+        let did = data["did"].as_str().map(str::to_string);
+        let pds_url = data["didDoc"]["service"].as_array().and_then(|services| {
+            services
+                .iter()
+                .find(|service| service["id"].as_str().is_some_and(|id| id.ends_with("#atproto_pds")))
+                .and_then(|service| service["serviceEndpoint"].as_str())
+                .map(str::to_string)
+        });
+        Some(BlueskySession { access_jwt, refresh_jwt, access_expires_at, did, pds_url })
+    }
+
+    /// Whether the access token can still be used at `now`; tokens without a readable expiry aren't reused.
+    fn is_fresh(&self, now: i64) -> bool {
+        self.access_expires_at.is_some_and(|expires_at| expires_at - BLUESKY_SESSION_MARGIN_SECS > now)
+    }
+}
+
+/// The `exp` claim of a JWT, without checking its signature.
+fn jwt_expiry(token: &str) -> Option<i64> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    serde_json::from_slice::<Value>(&bytes).ok()?["exp"].as_i64()
+}
+
+/// Where the account's session is kept in `BLUESKY_SESSIONS`.
+fn bluesky_session_key(service: &str, config: &BlueskyConfig) -> String {
+    format!("{}|{}", service, config.handle)
+}
+
+/// A session for the account, reusing or refreshing a cached one before logging in again.
+async fn bluesky_session(client: &reqwest::Client, retry: RetryPolicy, service: &str, config: &BlueskyConfig) -> Result<BlueskySession, PostError> {
+    let key = bluesky_session_key(service, config);
+    let cached = BLUESKY_SESSIONS.lock().unwrap().get(&key).cloned();
+    if let Some(session) = cached {
+        if session.is_fresh(chrono::Utc::now().timestamp()) {
+            return Ok(session);
+        }
+        match refresh_bluesky_session(client, retry, service, &session.refresh_jwt).await {
+            Ok(session) => {
+                BLUESKY_SESSIONS.lock().unwrap().insert(key, session.clone());
+                return Ok(session);
+            }
+            Err(e) => tracing::info!("[Bluesky] Session refresh failed, logging in again: {}", e),
+        }
+    }
+
+    let auth_response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/xrpc/com.atproto.server.createSession", service))
+            .json(&json!({
+                "identifier": config.handle,
+                "password": config.decrypted_password.as_str()
+            }))
+    })
+    .await?;
+    if !auth_response.status().is_success() {
+        BLUESKY_SESSIONS.lock().unwrap().remove(&key);
+        return Err(PostError::Auth(format!("Failed to authenticate with Bluesky ({})", error_response_message(auth_response).await)));
+    }
+    let auth_data: Value = auth_response.json().await?;
+    let session = BlueskySession::from_response(&auth_data)
+        .ok_or_else(|| PostError::Auth("No access token received".to_string()))?;
+    BLUESKY_SESSIONS.lock().unwrap().insert(key, session.clone());
+    Ok(session)
+}
+
+/// The session a post is made with, which logs in again once if Bluesky stops taking it.
+struct BlueskyLogin<'a> {
+    client: &'a reqwest::Client,
+    retry: RetryPolicy,
+    service: &'a str,
+    config: &'a BlueskyConfig,
+    session: BlueskySession,
+    renewed: bool,
+}
+
+impl<'a> BlueskyLogin<'a> {
+    async fn new(client: &'a reqwest::Client, retry: RetryPolicy, service: &'a str, config: &'a BlueskyConfig) -> Result<Self, PostError> {
+        let session = bluesky_session(client, retry, service, config).await?;
+        Ok(Self { client, retry, service, config, session, renewed: false })
+    }
+
+    /// Send a request that `build` makes with the access token.
+    ///
+    /// A cached session may have been revoked since it was cached, so on a 401 it's dropped and
+    /// the request is sent once more after logging in again. A 401 means nothing was done, so
+    /// resending can't post anything twice.
+    async fn send<F>(&mut self, retry: RetryPolicy, build: F) -> Result<reqwest::Response, PostError>
+    where
+        F: Fn(&str) -> reqwest::RequestBuilder,
+    {
+        let response = send_with_retry(retry, || build(&self.session.access_jwt)).await?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED || self.renewed {
+            return Ok(response);
+        }
+        tracing::info!("[Bluesky] The session was turned down, logging in again");
+        self.renewed = true;
+        BLUESKY_SESSIONS.lock().unwrap().remove(&bluesky_session_key(self.service, self.config));
+        self.session = bluesky_session(self.client, self.retry, self.service, self.config).await?;
+        send_with_retry(retry, || build(&self.session.access_jwt)).await
+    }
+}
+
+async fn refresh_bluesky_session(client: &reqwest::Client, retry: RetryPolicy, service: &str, refresh_jwt: &str) -> Result<BlueskySession, PostError> {
+    let response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/xrpc/com.atproto.server.refreshSession", service))
+            .header("Authorization", format!("Bearer {}", refresh_jwt))
+    })
+    .await?;
+    if !response.status().is_success() {
+        return Err(PostError::Auth(format!("refreshSession returned {}", response.status())));
     }
-}
\ No newline at end of file
+    let data: Value = response.json().await?;
+    BlueskySession::from_response(&data).ok_or_else(|| PostError::Auth("No access token received".to_string()))
+}
+
+/// Post to Bluesky, handling text over the limit as `config.truncate_behavior` says.
+#[allow(clippy::too_many_arguments)]
+pub async fn post_to_bluesky(client: &reqwest::Client, retry: RetryPolicy, config: &BlueskyConfig, text: &str, images: &[ImageAttachment], language: Option<&str>, progress: &UploadProgress, reference: Option<&PostReference>) -> Result<PostDetails, PostError> {
+    let text = if post_length(Platform::Bluesky, text) <= BLUESKY_CHARACTER_LIMIT {
+        text.to_string()
+    } else if config.truncate_behavior == BlueskyTruncateBehavior::Truncate {
+        truncate_for_bluesky(text)
+    } else if config.truncate_behavior == BlueskyTruncateBehavior::Thread {
+        let chunks = split_bluesky_thread(text);
+        return post_bluesky_thread(client, retry, config, &chunks, images, language, progress, reference).await;
+    } else {
+        return Err(PostError::Api(format!("Bluesky posts are limited to {} characters", BLUESKY_CHARACTER_LIMIT)));
+    };
+    post_bluesky_thread(client, retry, config, &[text], images, language, progress, reference).await
+}
+
+/// Cut text to Bluesky's limit without splitting a grapheme cluster.
+///
+/// A link at the end of the post that the cut would break is kept whole: the words before
+/// it are trimmed instead and an ellipsis marks the gap.
+fn truncate_for_bluesky(text: &str) -> String {
+    truncate_to(text, BLUESKY_CHARACTER_LIMIT)
+}
+
+/// `truncate_for_bluesky`, except that the Bluesky signature from `config` at the end of
+/// `text` is kept whole and the text before it is cut to leave room for it.
+fn truncate_signed_for_bluesky(text: &str, config: &Config) -> String {
+    let cleanup = config.text_cleanup(Platform::Bluesky);
+    // The text carries the signature as typed, or turned into plain text along with the rest
+    let signed = config.signature(Platform::Bluesky).and_then(|signature| {
+        [signature.to_string(), markdown_to_plain_text(signature)]
+            .into_iter()
+            .map(|signature| clean_up_whitespace(&signature, cleanup))
+            .find_map(|signature| {
+                let body = text.strip_suffix(signature.as_str())?.strip_suffix(SIGNATURE_SEPARATOR)?;
+                Some((body.to_string(), signature))
+            })
+    });
+    match signed {
+        Some((body, signature)) => {
+            let room = BLUESKY_CHARACTER_LIMIT.checked_sub(grapheme_count(SIGNATURE_SEPARATOR) + post_length(Platform::Bluesky, &signature));
+            match room {
+                Some(room) if room > 0 => format!("{}{}{}", truncate_to(body.trim_end(), room).trim_end(), SIGNATURE_SEPARATOR, signature),
+                _ => truncate_for_bluesky(text),
+            }
+        }
+        None => truncate_for_bluesky(text),
+    }
+}
+
+/// Cut text to `limit` graphemes as Bluesky counts them, with links shortened the way they're
+/// shown, keeping a trailing link whole as `truncate_for_bluesky` does.
+fn truncate_to(text: &str, limit: usize) -> String {
+    truncate_keeping_trailing_link(text, limit).unwrap_or_else(|| bluesky_prefix(text, limit).to_string())
+}
+
+/// The longest start of `text` that's at most `limit` graphemes long on Bluesky. A link is
+/// counted as it's shown and is either kept whole or left out, never cut through.
+fn bluesky_prefix(text: &str, limit: usize) -> &str {
+    // Where `count` graphemes into `part` ends, if it's longer than that
+    let cut_after = |part: &str, count: usize| part.grapheme_indices(true).nth(count).map(|(offset, _)| offset);
+    let mut length = 0;
+    let mut end = 0;
+    for link in detect_facets(text).into_iter().filter(|span| matches!(span.kind, FacetKind::Link(_))) {
+        let before = &text[end..link.byte_start];
+        if let Some(cut) = cut_after(before, limit - length) {
+            return &text[..end + cut];
+        }
+        length += grapheme_count(before);
+        let link_length = post_length(Platform::Bluesky, &text[link.byte_start..link.byte_end]);
+        if length + link_length > limit {
+            return &text[..link.byte_start];
+        }
+        length += link_length;
+        end = link.byte_end;
+    }
+    match cut_after(&text[end..], limit - length) {
+        Some(cut) => &text[..end + cut],
+        None => text,
+    }
+}
+
+/// `text` cut to `limit` graphemes with its trailing link intact, or `None` if there's no
+/// trailing link in the way of a plain cut, or the link alone doesn't fit.
+fn truncate_keeping_trailing_link(text: &str, limit: usize) -> Option<String> {
+    const ELLIPSIS: &str = "… ";
+    let link = detect_facets(text).into_iter().last().filter(|span| {
+        matches!(span.kind, FacetKind::Link(_)) && trim_trailing_punctuation(text[span.byte_end..].trim()).is_empty()
+    })?;
+    let head = &text[..link.byte_start];
+    let url = &text[link.byte_start..link.byte_end];
+    let head_length = post_length(Platform::Bluesky, head);
+    let url_length = post_length(Platform::Bluesky, url);
+    if head_length + url_length <= limit || url_length > limit {
+        return None;
+    }
+
+    let budget = limit.saturating_sub(url_length + grapheme_count(ELLIPSIS));
+    let cut = bluesky_prefix(head, budget);
+    // Drop the partial word the cut ended in, unless the cut fell on a word boundary
+    let cut = if head[cut.len()..].starts_with(char::is_whitespace) {
+        cut
+    } else {
+        cut.rfind(char::is_whitespace).map_or("", |end| &cut[..end])
+    };
+    let cut = cut.trim_end();
+    if cut.is_empty() {
+        return Some(url.to_string());
+    }
+    Some(format!("{}{}{}", cut, ELLIPSIS, url))
+}
+
+/// Post each chunk as a Bluesky post replying to the one before it.
+///
+/// Images, a video, or a link card when there's neither, go on the first post only; every post is
+/// tagged with `language`. With a `reference`, the first post replies to it, or embeds it as
+/// a quote.
+#[allow(clippy::too_many_arguments)]
+pub async fn post_bluesky_thread(client: &reqwest::Client, retry: RetryPolicy, config: &BlueskyConfig, chunks: &[String], images: &[ImageAttachment], language: Option<&str>, progress: &UploadProgress, reference: Option<&PostReference>) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.handle.is_empty() || config.decrypted_password.is_empty() {
+        return Err(PostError::Auth("Bluesky not configured".to_string()));
+    }
+    let service = config.service_url.trim_end_matches('/');
+    let mut login = BlueskyLogin::new(client, retry, service, config).await?;
+
+    // Strong refs ({uri, cid}) to the first post and the latest one, for reply chaining
+    let mut root: Option<Value> = None;
+    let mut parent: Option<Value> = None;
+    let mut quoted: Option<Value> = None;
+    if let Some(reference) = reference {
+        let target = resolve_bluesky_target(&mut login, retry, &reference.url).await?;
+        if reference.quote {
+            quoted = Some(target.post);
+        } else {
+            root = Some(target.root);
+            parent = Some(target.post);
+        }
+    }
+
+    // Upload images if provided (raw bytes, correct headers), reading them all first so
+    // the progress total is known from the start
+    let mut prepared = Vec::new();
+    for image in images.iter().filter(|image| !image.is_video()).take(MAX_IMAGES) {
+        prepared.push((image, read_image_within(image, BLUESKY_MAX_IMAGE_BYTES).await?));
+    }
+    progress.add_total(prepared.iter().map(|(_, (img_bytes, _))| img_bytes.len()).sum());
+    let mut image_blobs = Vec::new();
+    let mut details = PostDetails::default();
+    for (image, (img_bytes, mime_type)) in prepared {
+        if let Some(blob) = upload_bluesky_blob(&mut login, retry, img_bytes, &mime_type, Some(progress)).await? {
+            image_blobs.push((blob, image.alt().unwrap_or_default()));
+        }
+    }
+    let mut video = match images.iter().find(|image| image.is_video()) {
+        Some(video) => Some((upload_bluesky_video(&mut login, retry, video, progress).await?, video.alt().unwrap_or_default())),
+        None => None,
+    };
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        // Fallback: If text is empty and image is present, set to a single space
+        let text = if chunk.trim().is_empty() && !images.is_empty() {
+            " "
+        } else {
+            chunk.as_str()
+        };
+
+        // Create post, with links shortened in the text the way the Bluesky app shows them
+        let (display_text, spans) = bluesky_display_text(text);
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut record = json!({
+            "text": display_text,
+            "createdAt": now,
+            "$type": "app.bsky.feed.post"
+        });
+        if let Some(language) = language {
+            record["langs"] = json!([language]);
+        }
+        let facets = build_bluesky_facets(client, service, spans).await;
+        if !facets.is_empty() {
+            record["facets"] = Value::Array(facets);
+        }
+        if let Some((blob, alt)) = video.take() {
+            record["embed"] = json!({
+                "$type": "app.bsky.embed.video",
+                "video": blob,
+                "alt": alt
+            });
+        } else if i == 0 && !image_blobs.is_empty() {
+            let images: Vec<Value> = image_blobs
+                .drain(..)
+                .map(|(blob, alt)| json!({
+                    "image": blob,
+                    "alt": alt
+                }))
+                .collect();
+            record["embed"] = json!({
+                "$type": "app.bsky.embed.images",
+                "images": images
+            });
+        } else if i == 0 && images.is_empty() && quoted.is_none() {
+            // No image attached: show a preview card for the first link instead
+            let first_link = detect_facets(text).into_iter().find_map(|span| match span.kind {
+                FacetKind::Link(uri) => Some(uri),
+                _ => None,
+            });
+            if let Some(uri) = first_link {
+                if let Some(embed) = build_bluesky_link_card(&mut login, &uri).await {
+                    record["embed"] = embed;
+                }
+            }
+        }
+        if let Some(quoted) = quoted.take() {
+            let quote = json!({
+                "$type": "app.bsky.embed.record",
+                "record": quoted
+            });
+            record["embed"] = match record["embed"].take() {
+                Value::Null => quote,
+                media => json!({
+                    "$type": "app.bsky.embed.recordWithMedia",
+                    "record": quote,
+                    "media": media
+                }),
+            };
+        }
+        if let (Some(root), Some(parent)) = (&root, &parent) {
+            record["reply"] = json!({
+                "root": root,
+                "parent": parent
+            });
+        }
+        let post_json = json!({
+            "repo": config.handle,
+            "collection": "app.bsky.feed.post",
+            "record": record
+        });
+        tracing::trace!("[Bluesky] Post JSON: {}", post_json);
+        let post_response = login
+            .send(retry, |access_jwt| {
+                client
+                    .post(format!("{}/xrpc/com.atproto.repo.createRecord", service))
+                    .header("Authorization", format!("Bearer {}", access_jwt))
+                    .json(&post_json)
+            })
+            .await?;
+        tracing::debug!("[Bluesky] Post status: {}", post_response.status());
+        if !post_response.status().is_success() {
+            let error_text = error_response_message(post_response).await;
+            tracing::error!("[Bluesky] Post failed: {}", error_text);
+            if i > 0 {
+                return Err(PostError::Api(format!("Bluesky API error after {} of {} thread posts: {}", i, chunks.len(), error_text)));
+            }
+            return Err(PostError::Api(format!("Bluesky API error: {}", error_text)));
+        }
+        let created: Value = post_response.json().await?;
+        if i == 0 {
+            details.link = created["uri"].as_str().and_then(bluesky_post_link);
+        }
+        let strong_ref = json!({
+            "uri": created["uri"],
+            "cid": created["cid"]
+        });
+        root.get_or_insert_with(|| strong_ref.clone());
+        parent = Some(strong_ref);
+    }
+    Ok(details)
+}
+
+/// Strong refs to a Bluesky post being replied to or quoted, and to the top of its thread.
+#[derive(Debug, PartialEq)]
+struct BlueskyTarget {
+    post: Value,
+    root: Value,
+}
+
+/// The repo (handle or DID) and record key in a link to a Bluesky post: a bsky.app-style
+/// `/profile/{repo}/post/{rkey}` URL, or the post's `at://` URI.
+fn parse_bluesky_post_url(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    if let Some(path) = url.strip_prefix("at://") {
+        let parts: Vec<&str> = path.split('/').collect();
+        return match parts.as_slice() {
+            [repo, "app.bsky.feed.post", rkey] if !repo.is_empty() && !rkey.is_empty() => Some((repo.to_string(), rkey.to_string())),
+            _ => None,
+        };
+    }
+    let url = url::Url::parse(url).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+    match segments.as_slice() {
+        ["profile", repo, "post", rkey] => Some((repo.to_string(), rkey.to_string())),
+        _ => None,
+    }
+}
+
+/// The bsky.app page for the post at an `at://` URI.
+fn bluesky_post_link(uri: &str) -> Option<String> {
+    let (repo, rkey) = parse_bluesky_post_url(uri)?;
+    Some(format!("https://bsky.app/profile/{}/post/{}", repo, rkey))
+}
+
+/// Read the refs for a post in a `getPosts` response. Replies carry their thread's root,
+/// and a post that isn't a reply is its own root.
+fn parse_bluesky_target(post: &Value) -> Option<BlueskyTarget> {
+    let post_ref = json!({
+        "uri": post["uri"].as_str()?,
+        "cid": post["cid"].as_str()?
+    });
+    let root = &post["record"]["reply"]["root"];
+    let root = match (root["uri"].as_str(), root["cid"].as_str()) {
+        (Some(uri), Some(cid)) => json!({ "uri": uri, "cid": cid }),
+        _ => post_ref.clone(),
+    };
+    Some(BlueskyTarget { post: post_ref, root })
+}
+
+/// Look up the post a link points to, resolving a handle in it to the account's DID.
+async fn resolve_bluesky_target(login: &mut BlueskyLogin<'_>, retry: RetryPolicy, url: &str) -> Result<BlueskyTarget, PostError> {
+    let (client, service) = (login.client, login.service);
+    let (repo, rkey) = parse_bluesky_post_url(url).ok_or_else(|| PostError::Api(format!("Not a link to a Bluesky post: {}", url)))?;
+    let did = if repo.starts_with("did:") {
+        repo
+    } else {
+        resolve_bluesky_handle(client, service, &repo)
+            .await
+            .ok_or_else(|| PostError::Api(format!("Couldn't find the Bluesky account @{}", repo)))?
+    };
+    let uri = format!("at://{}/app.bsky.feed.post/{}", did, rkey);
+    let response = login.send(retry, |access_jwt| {
+        client
+            .get(format!("{}/xrpc/app.bsky.feed.getPosts", service))
+            .header("Authorization", format!("Bearer {}", access_jwt))
+            .query(&[("uris", &uri)])
+    })
+    .await?;
+    if !response.status().is_success() {
+        let error_text = error_response_message(response).await;
+        return Err(PostError::Api(format!("Couldn't look up the Bluesky post: {}", error_text)));
+    }
+    let data: Value = response.json().await?;
+    parse_bluesky_target(&data["posts"][0]).ok_or_else(|| PostError::Api(format!("Bluesky post not found: {}", url)))
+}
+
+/// Read the post length limit from an `/api/v1/instance` response.
+///
+/// Mastodon 4 reports `configuration.statuses.max_characters`; Pleroma, Akkoma and
+/// older forks use `max_toot_chars`.
+fn parse_mastodon_character_limit(instance: &Value) -> Option<usize> {
+    instance["configuration"]["statuses"]["max_characters"]
+        .as_u64()
+        .or_else(|| instance["max_toot_chars"].as_u64())
+        .map(|limit| limit as usize)
+}
+
+/// What a Mastodon-compatible server says about itself in `/api/v1/instance`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MastodonInstance {
+    pub title: String,
+    pub version: String,
+    pub max_characters: Option<usize>,
+    /// Most images a status can have, if the server says
+    pub max_media_attachments: Option<usize>,
+    /// Most choices a poll can have, if the server says; 0 means it has no polls
+    pub max_poll_options: Option<usize>,
+    /// Largest upload in bytes, which only Pleroma and its forks report
+    pub max_upload_size: Option<usize>,
+    /// Whether the version names Pleroma or Akkoma, which speak the Mastodon API with extensions
+    pub pleroma: bool,
+}
+
+/// Read an `/api/v1/instance` response, or `None` if it isn't one.
+///
+/// Mastodon, Pleroma, Akkoma and GoToSocial all report `uri` and `version` there; Pleroma
+/// and its forks put poll limits under `poll_limits` instead of `configuration.polls`, and
+/// give a version like `2.7.2 (compatible; Akkoma 3.13.2)`.
+fn parse_mastodon_instance(instance: &Value) -> Option<MastodonInstance> {
+    instance["uri"].as_str()?;
+    let version = instance["version"].as_str()?;
+    Some(MastodonInstance {
+        title: instance["title"].as_str().unwrap_or_default().trim().to_string(),
+        version: version.to_string(),
+        max_characters: parse_mastodon_character_limit(instance),
+        max_media_attachments: instance["configuration"]["statuses"]["max_media_attachments"].as_u64().map(|limit| limit as usize),
+        max_poll_options: instance["configuration"]["polls"]["max_options"]
+            .as_u64()
+            .or_else(|| instance["poll_limits"]["max_options"].as_u64())
+            .map(|limit| limit as usize),
+        max_upload_size: instance["upload_limit"].as_u64().map(|limit| limit as usize),
+        pleroma: ["pleroma", "akkoma"].iter().any(|name| version.to_lowercase().contains(name)),
+    })
+}
+
+/// Look up the instance, failing with the reason if the address isn't a Mastodon-compatible
+/// server, such as a profile link or a site that isn't on the fediverse.
+pub async fn fetch_mastodon_instance(client: &reqwest::Client, instance_url: &str) -> Result<MastodonInstance, PostError> {
+    let response = client
+        .get(format!("{}/api/v1/instance", instance_url.trim_end_matches('/')))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let error_text = error_response_message(response).await;
+        return Err(PostError::Api(format!("Not a Mastodon server, /api/v1/instance answered {}", error_text)));
+    }
+    let body = response.text().await?;
+    serde_json::from_str::<Value>(&body)
+        .ok()
+        .as_ref()
+        .and_then(parse_mastodon_instance)
+        .ok_or_else(|| PostError::Api("Not a Mastodon server, /api/v1/instance didn't describe an instance".to_string()))
+}
+
+/// The account a set of credentials signs in to, shown in settings to confirm it's the right one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountInfo {
+    pub handle: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Read a Bluesky `getProfile` response.
+fn parse_bluesky_profile(profile: &Value) -> Option<AccountInfo> {
+    Some(AccountInfo {
+        handle: format!("@{}", profile["handle"].as_str()?),
+        display_name: profile["displayName"].as_str().unwrap_or_default().to_string(),
+        avatar_url: profile["avatar"].as_str().map(str::to_string),
+    })
+}
+
+/// Read a Mastodon `verify_credentials` response, preferring the still image for animated avatars.
+fn parse_mastodon_account(account: &Value, instance_url: &str) -> Option<AccountInfo> {
+    let acct = account["acct"].as_str()?;
+    // Local accounts leave out the instance
+    let handle = if acct.contains('@') {
+        format!("@{}", acct)
+    } else {
+        let host = reqwest::Url::parse(instance_url).ok().and_then(|url| url.host_str().map(str::to_string));
+        match host {
+            Some(host) => format!("@{}@{}", acct, host),
+            None => format!("@{}", acct),
+        }
+    };
+    Some(AccountInfo {
+        handle,
+        display_name: account["display_name"].as_str().unwrap_or_default().to_string(),
+        avatar_url: account["avatar_static"].as_str().or_else(|| account["avatar"].as_str()).map(str::to_string),
+    })
+}
+
+/// Read a Micro.blog `/account/verify` response.
+fn parse_microblog_account(account: &Value) -> Option<AccountInfo> {
+    Some(AccountInfo {
+        handle: format!("@{}", account["username"].as_str()?),
+        display_name: account["name"].as_str().unwrap_or_default().to_string(),
+        avatar_url: account["avatar"].as_str().map(str::to_string),
+    })
+}
+
+/// Look up the Bluesky account the handle and app password sign in to.
+pub async fn fetch_bluesky_account(client: &reqwest::Client, retry: RetryPolicy, config: &BlueskyConfig) -> Result<AccountInfo, PostError> {
+    if config.handle.is_empty() || config.decrypted_password.is_empty() {
+        return Err(PostError::Auth("Bluesky not configured".to_string()));
+    }
+    let service = config.service_url.trim_end_matches('/');
+    let access_jwt = bluesky_session(client, retry, service, config).await?.access_jwt;
+    let response = send_with_retry(retry, || {
+        client
+            .get(format!("{}/xrpc/app.bsky.actor.getProfile", service))
+            .query(&[("actor", config.handle.as_str())])
+            .header("Authorization", format!("Bearer {}", access_jwt))
+    })
+    .await?;
+    if !response.status().is_success() {
+        return Err(PostError::Api(format!("Couldn't look up the Bluesky profile: {}", error_response_message(response).await)));
+    }
+    let profile: Value = response.json().await?;
+    parse_bluesky_profile(&profile).ok_or_else(|| PostError::Api("Profile has no handle".to_string()))
+}
+
+/// Look up the Mastodon account the access token belongs to.
+pub async fn fetch_mastodon_account(client: &reqwest::Client, retry: RetryPolicy, config: &MastodonConfig) -> Result<AccountInfo, PostError> {
+    if config.instance_url.is_empty() || config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Mastodon not configured".to_string()));
+    }
+    let instance_url = config.instance_url.trim_end_matches('/');
+    let response = send_with_retry(retry, || {
+        client
+            .get(format!("{}/api/v1/accounts/verify_credentials", instance_url))
+            .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+    })
+    .await?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(PostError::Auth("Mastodon rejected the access token".to_string()));
+    }
+    if !response.status().is_success() {
+        return Err(PostError::Api(format!("Couldn't look up the Mastodon account: {}", error_response_message(response).await)));
+    }
+    let account: Value = response.json().await?;
+    parse_mastodon_account(&account, instance_url).ok_or_else(|| PostError::Api("Account has no handle".to_string()))
+}
+
+/// Look up the Micro.blog account the app token belongs to.
+pub async fn fetch_microblog_account(client: &reqwest::Client, retry: RetryPolicy, config: &MicroBlogConfig) -> Result<AccountInfo, PostError> {
+    if config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Micro.Blog not configured".to_string()));
+    }
+    let response = send_with_retry(retry, || {
+        client
+            .post("https://micro.blog/account/verify")
+            .form(&[("token", config.decrypted_access_token.as_str())])
+    })
+    .await?;
+    if !response.status().is_success() {
+        return Err(PostError::Api(format!("Couldn't look up the Micro.Blog account: {}", error_response_message(response).await)));
+    }
+    let account: Value = response.json().await?;
+    // An unknown token gets a JSON error rather than an error status
+    if let Some(error) = account["error"].as_str() {
+        return Err(PostError::Auth(error.to_string()));
+    }
+    parse_microblog_account(&account).ok_or_else(|| PostError::Api("Account has no username".to_string()))
+}
+
+/// Download an account's avatar image.
+pub async fn fetch_avatar(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, PostError> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(PostError::Api(format!("Couldn't download the avatar: {}", error_response_message(response).await)));
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// An image attached to a post, with the description screen readers announce for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageAttachment {
+    pub path: String,
+    #[serde(default)]
+    pub alt_text: String,
+}
+
+impl ImageAttachment {
+    pub fn new(path: String) -> Self {
+        Self { path, alt_text: String::new() }
+    }
+
+    /// Whether this is a video rather than an image, going by its file extension.
+    pub fn is_video(&self) -> bool {
+        mime_guess::from_path(&self.path).first_raw().is_some_and(|mime| mime.starts_with("video/"))
+    }
+
+    /// The alt text with surrounding whitespace removed, or `None` if there isn't any.
+    fn alt(&self) -> Option<&str> {
+        Some(self.alt_text.trim()).filter(|alt| !alt.is_empty())
+    }
+}
+
+/// Mastodon-only settings for a single post.
+#[derive(Debug, Clone, Default)]
+pub struct MastodonOptions {
+    /// Content warning shown in place of the post until expanded
+    pub spoiler_text: Option<String>,
+    /// Who the status is shown to
+    pub visibility: MastodonVisibility,
+    /// Sent as `Idempotency-Key` so a resubmitted post isn't created twice; `post_to_platforms`
+    /// fills it in from `PostOptions::idempotency_key`
+    pub idempotency_key: Option<String>,
+    /// Poll attached to the first status
+    pub poll: Option<MastodonPoll>,
+    /// Have the server publish the status at this time instead of now; at least
+    /// `MASTODON_MIN_SCHEDULE_LEAD` ahead
+    pub scheduled_at: Option<DateTime<Utc>>,
+    /// The text is Markdown, which Pleroma-compatible servers are asked to format
+    pub markdown: bool,
+}
+
+/// How far ahead Mastodon accepts `scheduled_at`; sooner times are rejected
+pub const MASTODON_MIN_SCHEDULE_LEAD: chrono::TimeDelta = chrono::TimeDelta::minutes(5);
+
+/// The publish time of a scheduled status, which is what Mastodon returns in place of a status
+/// when `scheduled_at` is set.
+fn parse_mastodon_scheduled_status(response: &Value) -> Option<DateTime<Utc>> {
+    let time = DateTime::parse_from_rfc3339(response["scheduled_at"].as_str()?).ok()?;
+    Some(time.with_timezone(&Utc))
+}
+
+/// A poll for a Mastodon status. Statuses can't have both a poll and media.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct MastodonPoll {
+    pub options: Vec<String>,
+    /// How long voting stays open, in seconds
+    pub expires_in: u64,
+    /// Let voters pick more than one option
+    pub multiple: bool,
+}
+
+impl MastodonPoll {
+    /// The `poll[...]` form fields for the statuses endpoint; blank options are left out.
+    fn form_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields: Vec<(&'static str, String)> = self
+            .options
+            .iter()
+            .map(|option| option.trim())
+            .filter(|option| !option.is_empty())
+            .map(|option| ("poll[options][]", option.to_string()))
+            .collect();
+        fields.push(("poll[expires_in]", self.expires_in.to_string()));
+        fields.push(("poll[multiple]", self.multiple.to_string()));
+        fields
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn post_to_mastodon(client: &reqwest::Client, retry: RetryPolicy, config: &MastodonConfig, text: &str, images: &[ImageAttachment], options: &MastodonOptions, language: Option<&str>, progress: &UploadProgress, reference: Option<&PostReference>) -> Result<PostDetails, PostError> {
+    post_mastodon_thread(client, retry, config, &[text.to_string()], images, options, language, progress, reference).await
+}
+
+/// Post each chunk as a status replying to the one before it.
+///
+/// Media and the poll go on the first status; the content warning and language are repeated on
+/// every one. With `options.scheduled_at` the server publishes the status later, which only
+/// works for a single status, as replies need the status before them to exist. A `reference`
+/// makes the first status a reply to it, or a quote of it on servers that support quotes.
+#[allow(clippy::too_many_arguments)]
+pub async fn post_mastodon_thread(client: &reqwest::Client, retry: RetryPolicy, config: &MastodonConfig, chunks: &[String], images: &[ImageAttachment], options: &MastodonOptions, language: Option<&str>, progress: &UploadProgress, reference: Option<&PostReference>) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.instance_url.is_empty() || config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Mastodon not configured".to_string()));
+    }
+    if options.poll.is_some() && !images.is_empty() {
+        return Err(PostError::Api("Mastodon posts can't have both a poll and images".to_string()));
+    }
+    if options.scheduled_at.is_some() && chunks.len() > 1 {
+        return Err(PostError::Api("Mastodon can't schedule a thread".to_string()));
+    }
+
+    let mut reply_to: Option<String> = None;
+    let mut quoted: Option<String> = None;
+    if let Some(reference) = reference {
+        let id = resolve_mastodon_status(client, retry, config, &reference.url).await?;
+        if reference.quote {
+            quoted = Some(id);
+        } else {
+            reply_to = Some(id);
+        }
+    }
+
+    let url = format!("{}/api/v1/statuses", config.instance_url.trim_end_matches('/'));
+    let mut details = PostDetails::default();
+    // Keep to the instance's own image limit where it's known
+    let max_images = config.max_media_attachments.unwrap_or(MAX_IMAGES).min(MAX_IMAGES);
+    if images.len() > max_images {
+        details.warnings.push(format!("The instance takes {} image(s) per post, the rest were left out", max_images));
+    }
+    let images = &images[..images.len().min(max_images)];
+    // Pleroma says how big an upload can be, so leave out what it would refuse
+    let mut fitting = Vec::new();
+    for image in images {
+        let size = std::fs::metadata(&image.path).map(|metadata| metadata.len() as usize).unwrap_or_default();
+        match config.max_upload_size {
+            Some(limit) if size > limit => details.warnings.push(format!(
+                "{} is over the instance's {:.1} MB upload limit, so it was left out",
+                upload_file_name(&image.path),
+                limit as f64 / 1_000_000.0
+            )),
+            _ => fitting.push(image.clone()),
+        }
+    }
+    let images = fitting.as_slice();
+    let media_ids = upload_mastodon_media(client, retry, &config.instance_url, &config.decrypted_access_token, images, progress).await?;
+    let has_media = !media_ids.is_empty();
+    let spoiler_text = options.spoiler_text.as_deref().filter(|s| !s.trim().is_empty());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let mut form = vec![("status", chunk.clone())];
+        if i == 0 {
+            for id in &media_ids {
+                form.push(("media_ids[]", id.clone()));
+            }
+            if let Some(id) = &quoted {
+                form.push(("quoted_status_id", id.clone()));
+            }
+            if let Some(poll) = &options.poll {
+                form.extend(poll.form_fields());
+            }
+        }
+        if let Some(visibility) = options.visibility.api_value() {
+            form.push(("visibility", visibility.to_string()));
+        }
+        if let Some(spoiler_text) = spoiler_text {
+            form.push(("spoiler_text", spoiler_text.to_string()));
+            // Hide attached media behind the content warning too
+            if i == 0 && has_media {
+                form.push(("sensitive", "true".to_string()));
+            }
+        }
+        if let Some(language) = language {
+            form.push(("language", language.to_string()));
+        }
+        // Pleroma and Akkoma format Markdown themselves when asked to
+        if options.markdown && config.pleroma_compatible() {
+            form.push(("content_type", "text/markdown".to_string()));
+        }
+        if let Some(id) = &reply_to {
+            form.push(("in_reply_to_id", id.clone()));
+        }
+        if let Some(time) = options.scheduled_at {
+            form.push(("scheduled_at", time.to_rfc3339_opts(SecondsFormat::Secs, true)));
+        }
+        // Each status in a thread needs its own key
+        let idempotency_key = options
+            .idempotency_key
+            .as_ref()
+            .map(|key| if i == 0 { key.clone() } else { format!("{}-{}", key, i) });
+        let response = send_with_retry(retry, || {
+            let mut request = client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()));
+            if let Some(key) = &idempotency_key {
+                request = request.header("Idempotency-Key", key);
+            }
+            request.form(&form)
+        })
+        .await?;
+        if !response.status().is_success() {
+            let error_text = error_response_message(response).await;
+            if i > 0 {
+                return Err(PostError::Api(format!("Mastodon API error after {} of {} thread posts: {}", i, chunks.len(), error_text)));
+            }
+            return Err(PostError::Api(format!("Mastodon API error: {}", error_text)));
+        }
+        let status: Value = response.json().await?;
+        if let Some(requested) = options.scheduled_at {
+            // The server may round the time, so report the one it kept
+            return Ok(PostDetails {
+                scheduled_at: Some(parse_mastodon_scheduled_status(&status).unwrap_or(requested)),
+                ..Default::default()
+            });
+        }
+        // Servers without quote posts ignore quoted_status_id rather than rejecting it
+        if i == 0 && quoted.is_some() && status["quote"].is_null() {
+            details.warnings.push("The instance doesn't support quote posts, so the status was posted without the quote".to_string());
+        }
+        if i == 0 {
+            details.link = status["url"].as_str().map(|url| url.to_string());
+        }
+        reply_to = status["id"].as_str().map(|id| id.to_string());
+    }
+    Ok(details)
+}
+
+/// The ID of a status on the instance at `instance_url`, from a link to it there such as
+/// `/@user/ID`, `/users/user/statuses/ID` or Pleroma's `/notice/ID`.
+fn parse_mastodon_status_url(url: &str, instance_url: &str) -> Option<String> {
+    let url = url::Url::parse(url.trim()).ok()?;
+    let instance = url::Url::parse(instance_url.trim()).ok()?;
+    if !url.host_str()?.eq_ignore_ascii_case(instance.host_str()?) {
+        return None;
+    }
+    let segments: Vec<&str> = url.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+    let id = match segments.as_slice() {
+        [account, id] if account.starts_with('@') => *id,
+        ["users", _, "statuses", id] | ["web", "statuses", id] | ["notice", id] => *id,
+        _ => return None,
+    };
+    id.chars().all(|c| c.is_ascii_alphanumeric()).then(|| id.to_string())
+}
+
+/// The local ID of the status a link points to. Links to statuses on other servers are looked
+/// up with a resolving search, which fetches the status onto the instance if it's new to it.
+async fn resolve_mastodon_status(client: &reqwest::Client, retry: RetryPolicy, config: &MastodonConfig, url: &str) -> Result<String, PostError> {
+    if let Some(id) = parse_mastodon_status_url(url, &config.instance_url) {
+        return Ok(id);
+    }
+    if url::Url::parse(url.trim()).is_err() {
+        return Err(PostError::Api(format!("Not a link to a Mastodon post: {}", url)));
+    }
+    let response = send_with_retry(retry, || {
+        client
+            .get(format!("{}/api/v2/search", config.instance_url.trim_end_matches('/')))
+            .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+            .query(&[("q", url.trim()), ("type", "statuses"), ("resolve", "true"), ("limit", "1")])
+    })
+    .await?;
+    if !response.status().is_success() {
+        let error_text = error_response_message(response).await;
+        return Err(PostError::Api(format!("Couldn't look up the Mastodon post: {}", error_text)));
+    }
+    let data: Value = response.json().await?;
+    data["statuses"][0]["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| PostError::Api(format!("Mastodon post not found: {}", url)))
+}
+
+/// Name an uploaded image after its file, so servers see the right extension
+fn upload_file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "image".to_string())
+}
+
+/// Upload images to a Mastodon-API media endpoint and return their media IDs.
+///
+/// An image the server turns down fails the post, rather than it going out without the image.
+async fn upload_mastodon_media(client: &reqwest::Client, retry: RetryPolicy, instance_url: &str, access_token: &str, images: &[ImageAttachment], progress: &UploadProgress) -> Result<Vec<String>, PostError> {
+    let mut prepared = Vec::new();
+    for image in images.iter().take(MAX_IMAGES) {
+        let img_bytes = std::fs::read(&image.path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+        prepared.push((image, img_bytes));
+    }
+    progress.add_total(prepared.iter().map(|(_, img_bytes)| img_bytes.len()).sum());
+
+    let mut media_ids = Vec::new();
+    for (image, img_bytes) in prepared {
+        let file_name = upload_file_name(&image.path);
+        let media_resp = send_with_retry(retry, || {
+            let part = multipart::Part::stream_with_length(progress.body(&img_bytes), img_bytes.len() as u64).file_name(file_name.clone());
+            let mut form = multipart::Form::new().part("file", part);
+            if let Some(alt) = image.alt() {
+                form = form.text("description", alt.to_string());
+            }
+            client
+                .post(format!("{}/api/v2/media", instance_url.trim_end_matches('/')))
+                .header("Authorization", format!("Bearer {}", access_token))
+                .multipart(form)
+        })
+        .await?;
+        if !media_resp.status().is_success() {
+            let error_text = error_response_message(media_resp).await;
+            return Err(PostError::Api(format!("Couldn't upload {}: {}", file_name, error_text)));
+        }
+        let media_json: Value = media_resp.json().await?;
+        let id = media_json["id"]
+            .as_str()
+            .ok_or_else(|| PostError::Api(format!("No media ID received for {}", file_name)))?;
+        media_ids.push(id.to_string());
+    }
+    Ok(media_ids)
+}
+
+/// Post an image with its caption to Pixelfed, which speaks the Mastodon API but needs media.
+pub async fn post_to_pixelfed(client: &reqwest::Client, retry: RetryPolicy, config: &PixelfedConfig, text: &str, images: &[ImageAttachment], progress: &UploadProgress) -> Result<(), PostError> {
+    if !config.enabled || config.instance_url.is_empty() || config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Pixelfed not configured".to_string()));
+    }
+    if images.is_empty() {
+        return Err(PostError::Api("Pixelfed posts need at least one image".to_string()));
+    }
+    if text.chars().count() > PIXELFED_CHARACTER_LIMIT {
+        return Err(PostError::Api(format!("Pixelfed captions are limited to {} characters", PIXELFED_CHARACTER_LIMIT)));
+    }
+
+    let media_ids = upload_mastodon_media(client, retry, &config.instance_url, &config.decrypted_access_token, images, progress).await?;
+    if media_ids.is_empty() {
+        return Err(PostError::Api("Pixelfed didn't accept any of the images".to_string()));
+    }
+    let mut form = vec![("status", text.to_string())];
+    for id in &media_ids {
+        form.push(("media_ids[]", id.clone()));
+    }
+    let response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/api/v1/statuses", config.instance_url.trim_end_matches('/')))
+            .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+            .form(&form)
+    })
+    .await?;
+    if !response.status().is_success() {
+        let error_text = error_response_message(response).await;
+        return Err(PostError::Api(format!("Pixelfed API error: {}", error_text)));
+    }
+    Ok(())
+}
+
+/// Parse a Nostr private key given either as 64 hex characters or as a bech32 `nsec1...` string.
+pub fn parse_nostr_secret_key(key: &str) -> Result<SecretKey, String> {
+    let key = key.trim();
+    if key.starts_with("npub1") {
+        return Err("This is a public key (npub); a private key (nsec) is needed to sign posts".to_string());
+    }
+    if key.starts_with("nsec1") {
+        return SecretKey::from_bech32(key).map_err(|e| format!("Invalid nsec key: {}", e));
+    }
+    if key.len() != 64 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Invalid private key format (must be nsec1... or 64 hex characters)".to_string());
+    }
+    SecretKey::from_hex(key).map_err(|e| format!("Invalid private key: {}", e))
+}
+
+/// The `npub` belonging to a private key, for confirming the right key was entered.
+pub fn nostr_npub(key: &str) -> Option<String> {
+    let secret_key = parse_nostr_secret_key(key).ok()?;
+    Keys::new(secret_key).public_key().to_bech32().ok()
+}
+
+/// Check a relay URL and put it in a canonical form, so the same relay isn't added twice.
+///
+/// Relays must use `wss://`; plain `ws://` is only accepted for a relay on this machine.
+/// The host is lowercased and a default port or bare trailing slash removed.
+pub fn normalize_relay_url(input: &str) -> Result<String, String> {
+    let url = url::Url::parse(input.trim()).map_err(|e| format!("Not a valid URL: {}", e))?;
+    let host = match url.host_str() {
+        Some(host) if !host.is_empty() => host,
+        _ => return Err("The URL needs a host name after the scheme".to_string()),
+    };
+    match url.scheme() {
+        "wss" => {}
+        "ws" if matches!(host, "localhost" | "127.0.0.1" | "[::1]") => {}
+        "ws" => return Err("Use wss:// (ws:// is only allowed for localhost)".to_string()),
+        _ => return Err("Relay URLs start with wss://".to_string()),
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("Relay URLs can't contain a username or password".to_string());
+    }
+    let normalized = url.as_str();
+    Ok(normalized.strip_suffix('/').filter(|_| url.path() == "/").unwrap_or(normalized).to_string())
+}
+
+/// The HTTP URL serving a relay's NIP-11 information document.
+fn nostr_relay_info_url(relay: &str) -> Option<String> {
+    if let Some(rest) = relay.strip_prefix("wss://") {
+        Some(format!("https://{}", rest))
+    } else {
+        relay.strip_prefix("ws://").map(|rest| format!("http://{}", rest))
+    }
+}
+
+/// Check a relay is reachable by fetching its NIP-11 information document, returning the round trip time.
+pub async fn check_nostr_relay(client: &reqwest::Client, relay: &str) -> Result<Duration, PostError> {
+    let url = nostr_relay_info_url(relay).ok_or_else(|| PostError::Api("Not a ws:// or wss:// URL".to_string()))?;
+    let started = std::time::Instant::now();
+    let response = client
+        .get(url)
+        .header("Accept", "application/nostr+json")
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(PostError::Api(format!("Relay answered {}", response.status())));
+    }
+    Ok(started.elapsed())
+}
+
+/// Outcome of a Nostr post on each relay: `Ok` if the relay accepted it, otherwise the rejection reason.
+pub type RelayResults = Vec<(String, Result<(), String>)>;
+
+/// A file stored on a NIP-96 media server, described by its NIP-94 tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NostrMedia {
+    url: String,
+    /// NIP-94 metadata such as `m`, `x` and `dim`, in `(name, value)` form
+    metadata: Vec<(String, String)>,
+}
+
+impl NostrMedia {
+    /// Values for a NIP-92 `imeta` tag, e.g. `["url https://…", "m image/png"]`.
+    fn imeta_values(&self) -> Vec<String> {
+        let mut values = vec![format!("url {}", self.url)];
+        values.extend(
+            self.metadata
+                .iter()
+                .filter(|(name, _)| matches!(name.as_str(), "m" | "x" | "ox" | "dim" | "blurhash" | "alt"))
+                .map(|(name, value)| format!("{} {}", name, value)),
+        );
+        values
+    }
+}
+
+/// Read the uploaded file's URL and metadata from a NIP-96 upload response.
+fn parse_nip96_response(response: &Value) -> Result<NostrMedia, String> {
+    if response["status"].as_str() == Some("error") {
+        return Err(response["message"].as_str().unwrap_or("upload rejected").to_string());
+    }
+    let tags = response["nip94_event"]["tags"]
+        .as_array()
+        .ok_or_else(|| "response is missing nip94_event tags".to_string())?;
+
+    let mut url = None;
+    let mut metadata = Vec::new();
+    for tag in tags {
+        let (Some(name), Some(value)) = (tag[0].as_str(), tag[1].as_str()) else {
+            continue;
+        };
+        if name == "url" {
+            url = Some(value.to_string());
+        } else {
+            metadata.push((name.to_string(), value.to_string()));
+        }
+    }
+    let url = url.ok_or_else(|| "response did not include a file URL".to_string())?;
+    Ok(NostrMedia { url, metadata })
+}
+
+/// Upload an image to a NIP-96 media server (e.g. nostr.build), authenticating with NIP-98.
+async fn upload_nostr_media(client: &reqwest::Client, retry: RetryPolicy, keys: &Keys, media_host: &str, image_path: &str) -> Result<NostrMedia, String> {
+    let host = media_host.trim_end_matches('/');
+
+    // Discover the upload endpoint, falling back to the configured URL itself
+    let api_url = match client.get(format!("{}/.well-known/nostr/nip96.json", host)).send().await {
+        Ok(response) if response.status().is_success() => response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|info| info["api_url"].as_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| host.to_string()),
+        _ => host.to_string(),
+    };
+
+    let img_bytes = std::fs::read(image_path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let file_name = std::path::Path::new(image_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("image")
+        .to_string();
+    let mime_type = mime_guess::from_path(image_path).first_or_octet_stream().to_string();
+
+    // NIP-98 HTTP auth event for this request
+    let auth_event = EventBuilder::new(Kind::HttpAuth, "")
+        .tags([
+            Tag::custom(TagKind::custom("u"), [api_url.clone()]),
+            Tag::custom(TagKind::custom("method"), ["POST"]),
+        ])
+        .sign_with_keys(keys)
+        .map_err(|e| format!("Failed to sign upload auth: {}", e))?;
+    let auth_header = format!("Nostr {}", base64::engine::general_purpose::STANDARD.encode(auth_event.as_json()));
+
+    let response = send_with_retry(retry, || {
+        let part = multipart::Part::bytes(img_bytes.clone()).file_name(file_name.clone());
+        // An unparseable MIME type just leaves the part untyped
+        let part = match part.mime_str(&mime_type) {
+            Ok(part) => part,
+            Err(_) => multipart::Part::bytes(img_bytes.clone()).file_name(file_name.clone()),
+        };
+        let form = multipart::Form::new().part("file", part);
+        client
+            .post(&api_url)
+            .header("Authorization", &auth_header)
+            .multipart(form)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(error_response_message(response).await);
+    }
+    let body: Value = response.json().await.map_err(|e| e.to_string())?;
+    parse_nip96_response(&body)
+}
+
+/// Event kind of a Blossom authorization (BUD-01)
+const BLOSSOM_AUTH_KIND: u16 = 24242;
+/// How long a Blossom upload authorization is valid for
+const BLOSSOM_AUTH_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+/// A BUD-01 `Authorization` header allowing the upload of the blob with SHA-256 `hash`.
+fn blossom_auth_header(keys: &Keys, hash: &str, file_name: &str, now: Timestamp) -> Result<String, String> {
+    let expiration = now.as_u64() + BLOSSOM_AUTH_LIFETIME.as_secs();
+    let auth_event = EventBuilder::new(Kind::Custom(BLOSSOM_AUTH_KIND), format!("Upload {}", file_name))
+        .tags([
+            Tag::custom(TagKind::custom("t"), ["upload"]),
+            Tag::custom(TagKind::custom("x"), [hash.to_string()]),
+            Tag::custom(TagKind::custom("expiration"), [expiration.to_string()]),
+        ])
+        .custom_created_at(now)
+        .sign_with_keys(keys)
+        .map_err(|e| format!("Failed to sign upload auth: {}", e))?;
+    Ok(format!("Nostr {}", base64::engine::general_purpose::STANDARD.encode(auth_event.as_json())))
+}
+
+/// Read the uploaded blob's URL and metadata from a Blossom blob descriptor.
+fn parse_blossom_descriptor(descriptor: &Value, dimensions: Option<(u32, u32)>) -> Result<NostrMedia, String> {
+    let url = descriptor["url"]
+        .as_str()
+        .ok_or_else(|| "response did not include a blob URL".to_string())?;
+    let mut metadata = Vec::new();
+    if let Some(mime_type) = descriptor["type"].as_str() {
+        metadata.push(("m".to_string(), mime_type.to_string()));
+    }
+    if let Some(hash) = descriptor["sha256"].as_str() {
+        metadata.push(("x".to_string(), hash.to_string()));
+    }
+    if let Some((width, height)) = dimensions {
+        metadata.push(("dim".to_string(), format!("{}x{}", width, height)));
+    }
+    Ok(NostrMedia { url: url.to_string(), metadata })
+}
+
+/// Upload an image to a Blossom server by PUTting the blob to `/upload` (BUD-02), signing a
+/// kind 24242 authorization for its SHA-256 unless the config turns that off.
+async fn upload_blossom_media(client: &reqwest::Client, retry: RetryPolicy, keys: &Keys, config: &NostrConfig, image_path: &str) -> Result<NostrMedia, String> {
+    let upload_url = format!("{}/upload", config.blossom_server_url.trim().trim_end_matches('/'));
+    let img_bytes = std::fs::read(image_path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let hash: String = Sha256::digest(&img_bytes).iter().map(|byte| format!("{:02x}", byte)).collect();
+    let mime_type = mime_guess::from_path(image_path).first_or_octet_stream().to_string();
+    let auth_header = if config.blossom_auth {
+        Some(blossom_auth_header(keys, &hash, &upload_file_name(image_path), Timestamp::now())?)
+    } else {
+        None
+    };
+
+    let response = send_with_retry(retry, || {
+        let mut request = client
+            .put(&upload_url)
+            .header("Content-Type", &mime_type)
+            .header("X-SHA-256", &hash)
+            .body(img_bytes.clone());
+        if let Some(auth_header) = &auth_header {
+            request = request.header("Authorization", auth_header);
+        }
+        request
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        // Blossom servers give the reason for a refusal in a header
+        let reason = response
+            .headers()
+            .get("X-Reason")
+            .and_then(|reason| reason.to_str().ok())
+            .map(str::to_string);
+        let error_text = error_response_message(response).await;
+        return Err(match reason {
+            Some(reason) => format!("{} ({})", error_text, reason),
+            None => error_text,
+        });
+    }
+    let descriptor: Value = response.json().await.map_err(|e| e.to_string())?;
+    parse_blossom_descriptor(&descriptor, image::image_dimensions(image_path).ok())
+}
+
+/// Someone mentioned in a Nostr note, as written after the `@`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NostrMention {
+    Npub(PublicKey),
+    /// A NIP-05 identifier `name@domain`, with the name lowercased
+    Nip05 { name: String, domain: String },
+}
+
+/// NIP-05 identifiers resolved this session, keyed by `name@domain`
+static NIP05_CACHE: LazyLock<Mutex<HashMap<String, PublicKey>>> = LazyLock::new(Default::default);
+
+/// Find `@npub1…` and `@name@domain` mentions, with the byte range of each including its `@`.
+///
+/// A mention has to start a word, so e-mail addresses in the text are left alone.
+fn find_nostr_mentions(text: &str) -> Vec<(std::ops::Range<usize>, NostrMention)> {
+    let mut mentions = Vec::new();
+    for (start, _) in text.match_indices('@') {
+        if text[..start].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '@') {
+            continue;
+        }
+        let rest = &text[start + 1..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '@')))
+            .unwrap_or(rest.len());
+        // Trailing punctuation belongs to the sentence, not the identifier
+        let token = rest[..len].trim_end_matches(['.', '-', '_']);
+        let range = start..start + 1 + token.len();
+
+        if token.starts_with("npub1") {
+            if let Ok(public_key) = PublicKey::from_bech32(token) {
+                mentions.push((range, NostrMention::Npub(public_key)));
+            }
+        } else if let Some((name, domain)) = token.split_once('@') {
+            if !name.is_empty() && domain.contains('.') && !domain.contains('@') {
+                mentions.push((
+                    range,
+                    NostrMention::Nip05 { name: name.to_ascii_lowercase(), domain: domain.to_ascii_lowercase() },
+                ));
+            }
+        }
+    }
+    mentions
+}
+
+/// The public key a `/.well-known/nostr.json` response lists for `name`.
+fn parse_nip05_response(response: &Value, name: &str) -> Option<PublicKey> {
+    response["names"][name].as_str().and_then(|hex| PublicKey::from_hex(hex).ok())
+}
+
+/// Look up a NIP-05 identifier on its domain, using the session cache when possible.
+async fn resolve_nip05(client: &reqwest::Client, retry: RetryPolicy, name: &str, domain: &str) -> Result<PublicKey, String> {
+    let identifier = format!("{}@{}", name, domain);
+    let cached = NIP05_CACHE.lock().unwrap().get(&identifier).copied();
+    if let Some(public_key) = cached {
+        return Ok(public_key);
+    }
+
+    let response = send_with_retry(retry, || {
+        client
+            .get(format!("https://{}/.well-known/nostr.json", domain))
+            .query(&[("name", name)])
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", domain, response.status()));
+    }
+    let json: Value = response.json().await.map_err(|e| e.to_string())?;
+    let public_key = parse_nip05_response(&json, name).ok_or_else(|| format!("{} is not listed on {}", name, domain))?;
+
+    NIP05_CACHE.lock().unwrap().insert(identifier, public_key);
+    Ok(public_key)
+}
+
+/// Rewrite mentions as NIP-27 `nostr:npub1…` links, returning the new text and a `p` tag per person.
+///
+/// Identifiers that can't be resolved stay as typed, with a warning.
+async fn link_nostr_mentions(client: &reqwest::Client, retry: RetryPolicy, text: &str, warnings: &mut Vec<String>) -> (String, Vec<Tag>) {
+    let mut linked = text.to_string();
+    let mut mentioned: Vec<PublicKey> = Vec::new();
+    // Replace from the end so earlier ranges stay valid
+    for (range, mention) in find_nostr_mentions(text).into_iter().rev() {
+        let public_key = match mention {
+            NostrMention::Npub(public_key) => public_key,
+            NostrMention::Nip05 { name, domain } => match resolve_nip05(client, retry, &name, &domain).await {
+                Ok(public_key) => public_key,
+                Err(e) => {
+                    tracing::warn!("[Nostr] Failed to resolve {}@{}: {}", name, domain, e);
+                    warnings.push(format!("Couldn't resolve {}@{}, left it as plain text", name, domain));
+                    continue;
+                }
+            },
+        };
+        let Ok(npub) = public_key.to_bech32() else {
+            continue;
+        };
+        linked.replace_range(range, &format!("nostr:{}", npub));
+        if !mentioned.contains(&public_key) {
+            mentioned.insert(0, public_key);
+        }
+    }
+    let tags = mentioned.into_iter().map(Tag::public_key).collect();
+    (linked, tags)
+}
+
+/// Post a note to the configured relays.
+///
+/// Succeeds if at least one relay accepted the event, returning the per-relay outcome
+/// so the UI can list the relays that rejected it.
+#[allow(clippy::too_many_arguments)]
+pub async fn post_to_nostr(client: &reqwest::Client, retry: RetryPolicy, config: &NostrConfig, text: &str, images: &[ImageAttachment], language: Option<&str>, timeout: Duration, pow: &PowProgress, reference: Option<&PostReference>) -> Result<PostDetails, PostError> {
+    post_nostr_thread(client, retry, config, &[text.to_string()], images, language, timeout, pow, reference).await
+}
+
+/// Publish each chunk as a note replying to the one before it, using NIP-10 marked `e` tags.
+///
+/// Images go on the first note, and the per-relay outcome reported is the first note's. Each
+/// note carries a NIP-32 label for `language`.
+/// Connecting to the relays and sending each note are bounded by `timeout`, and with a
+/// `pow_difficulty` set each note is mined to it before it's signed. A `reference` makes the
+/// first note a reply to that note, or quotes it with a NIP-18 `q` tag and a `nostr:` link.
+#[allow(clippy::too_many_arguments)]
+pub async fn post_nostr_thread(client: &reqwest::Client, retry: RetryPolicy, config: &NostrConfig, chunks: &[String], images: &[ImageAttachment], language: Option<&str>, timeout: Duration, pow: &PowProgress, reference: Option<&PostReference>) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.decrypted_private_key.is_empty() || config.relays.is_empty() {
+        return Err(PostError::Auth("Nostr not configured".to_string()));
+    }
+    let target = match reference {
+        Some(reference) => Some(
+            parse_nostr_event_reference(&reference.url).ok_or_else(|| PostError::Api(format!("Not a link to a Nostr note: {}", reference.url)))?,
+        ),
+        None => None,
+    };
+
+    // Parse private key
+    let secret_key = parse_nostr_secret_key(&config.decrypted_private_key).map_err(PostError::Crypto)?;
+    let keys = Keys::new(secret_key);
+
+    let mut details = PostDetails::default();
+
+    // Upload the images first; if that fails, still post the text
+    let mut post_text = chunks.first().cloned().unwrap_or_default();
+    // Tags for the first note: image metadata, and the note it replies to or quotes
+    let mut first_tags = Vec::new();
+    for image in upload_nostr_images(client, retry, &keys, config, images, &mut details.warnings).await {
+        if !post_text.trim().is_empty() {
+            post_text.push('\n');
+        }
+        post_text.push_str(&image.url);
+        first_tags.push(image.imeta);
+    }
+
+    let nostr_client = connect_nostr_relays(&keys, &config.relays, timeout, &mut details.relays).await;
+
+    let mut root_id: Option<EventId> = None;
+    let mut parent_id: Option<EventId> = None;
+    if let (Some(target), Some(reference)) = (target, reference) {
+        // The note itself tells us its author and, for a reply, the root of its thread
+        let event = nostr_client
+            .fetch_events(Filter::new().id(target.id).limit(1), timeout)
+            .await
+            .ok()
+            .and_then(|events| events.into_iter().next());
+        if let Some(author) = target.author.or(event.as_ref().map(|event| event.pubkey)) {
+            first_tags.push(Tag::public_key(author));
+        }
+        if reference.quote {
+            let author = target.author.or(event.as_ref().map(|event| event.pubkey));
+            first_tags.push(Tag::custom(
+                TagKind::custom("q"),
+                [target.id.to_hex(), target.relay.unwrap_or_default(), author.map(|author| author.to_hex()).unwrap_or_default()],
+            ));
+            let link = target.id.to_bech32().map_err(|e| PostError::Api(e.to_string()))?;
+            if !post_text.trim().is_empty() {
+                post_text.push('\n');
+            }
+            post_text.push_str(&format!("nostr:{}", link));
+        } else {
+            root_id = Some(event.as_ref().and_then(nostr_thread_root).unwrap_or(target.id));
+            parent_id = Some(target.id);
+        }
+    }
+    for (i, chunk) in chunks.iter().enumerate() {
+        let (content, mut tags) = if i == 0 {
+            (post_text.clone(), std::mem::take(&mut first_tags))
+        } else {
+            (chunk.clone(), Vec::new())
+        };
+        let (content, mention_tags) = link_nostr_mentions(client, retry, &content, &mut details.warnings).await;
+        tags.extend(mention_tags);
+        tags.extend(nostr_hashtags(&content).into_iter().map(Tag::hashtag));
+        if let Some(language) = language {
+            tags.extend(nostr_language_tags(language));
+        }
+        if let (Some(root), Some(parent)) = (root_id, parent_id) {
+            tags.push(Tag::custom(TagKind::custom("e"), [root.to_hex(), String::new(), "root".to_string()]));
+            // Direct replies to the root carry only the root marker
+            if parent != root {
+                tags.push(Tag::custom(TagKind::custom("e"), [parent.to_hex(), String::new(), "reply".to_string()]));
+            }
+        }
+
+        if i > 0 {
+            tokio::time::sleep(NOSTR_EVENT_SPACING).await;
+        }
+        let (event, relays) = send_nostr_event(&nostr_client, &keys, config, pow, Kind::TextNote, content, tags, timeout).await?;
+        let accepted = relays.iter().any(|(_, result)| result.is_ok());
+        if i == 0 {
+            details.relays.extend(relays);
+        }
+
+        if !accepted {
+            if i > 0 {
+                return Err(PostError::Api(format!("No Nostr relay accepted note {} of {} in the thread", i + 1, chunks.len())));
+            }
+            let reasons: Vec<String> = details
+                .relays
+                .iter()
+                .filter_map(|(relay, result)| result.as_ref().err().map(|e| format!("{}: {}", relay, e)))
+                .collect();
+            return Err(PostError::Api(format!("No Nostr relay accepted the post ({})", reasons.join("; "))));
+        }
+
+        if i == 0 {
+            details.link = nostr_event_link(&event, &details.relays);
+        }
+        root_id.get_or_insert(event.id);
+        parent_id = Some(event.id);
+    }
+    Ok(details)
+}
+
+/// An image uploaded for a Nostr event.
+struct NostrImage {
+    url: String,
+    alt: Option<String>,
+    /// NIP-92 `imeta` tag describing it
+    imeta: Tag,
+}
+
+/// Upload images to the configured Blossom or NIP-96 media server. Images that fail are left out with a
+/// warning, so the text is still posted.
+async fn upload_nostr_images(client: &reqwest::Client, retry: RetryPolicy, keys: &Keys, config: &NostrConfig, images: &[ImageAttachment], warnings: &mut Vec<String>) -> Vec<NostrImage> {
+    let mut uploaded = Vec::new();
+    let blossom = !config.blossom_server_url.trim().is_empty();
+    if !images.is_empty() && !blossom && config.media_host.trim().is_empty() {
+        warnings.push("No media server configured, images were not attached".to_string());
+        return uploaded;
+    }
+    for image in images.iter().take(MAX_IMAGES) {
+        let media = if blossom {
+            upload_blossom_media(client, retry, keys, config, &image.path).await
+        } else {
+            upload_nostr_media(client, retry, keys, &config.media_host, &image.path).await
+        };
+        match media {
+            Ok(media) => {
+                let mut values = media.imeta_values();
+                if let Some(alt) = image.alt() {
+                    values.retain(|value| !value.starts_with("alt "));
+                    values.push(format!("alt {}", alt));
+                }
+                uploaded.push(NostrImage {
+                    url: media.url,
+                    alt: image.alt().map(str::to_string),
+                    imeta: Tag::custom(TagKind::custom("imeta"), values),
+                });
+            }
+            Err(e) => {
+                tracing::warn!("[Nostr] Image upload failed: {}", e);
+                warnings.push(format!("Image upload failed, posted text only: {}", e));
+            }
+        }
+    }
+    uploaded
+}
+
+/// A relay pool connected to `relays`, with relays that couldn't be added recorded in `results`.
+async fn connect_nostr_relays(keys: &Keys, relays: &[String], timeout: Duration, results: &mut RelayResults) -> NostrClient {
+    let nostr_client = NostrClient::new(keys.clone());
+    for relay in relays {
+        if let Err(e) = nostr_client.add_relay(relay).await {
+            results.push((relay.clone(), Err(e.to_string())));
+        }
+    }
+    nostr_client.connect().await;
+    // connect() returns before the sockets are up; give the relays a bounded time to connect
+    nostr_client.wait_for_connection(timeout).await;
+    nostr_client
+}
+
+/// Sign an event and send it to the pool's relays, mining it to the configured proof-of-work
+/// difficulty first. Returns the event and each relay's outcome, which can all be failures.
+#[allow(clippy::too_many_arguments)]
+async fn send_nostr_event(nostr_client: &NostrClient, keys: &Keys, config: &NostrConfig, pow: &PowProgress, kind: Kind, content: String, mut tags: Vec<Tag>, timeout: Duration) -> Result<(Event, RelayResults), PostError> {
+    let pubkey = keys.public_key();
+    let mut builder = EventBuilder::new(kind, &content);
+    if let Some(difficulty) = config.pow_difficulty.filter(|difficulty| *difficulty > 0) {
+        let pow = pow.clone();
+        let (mined_tags, created_at) = tokio::task::spawn_blocking(move || mine_nostr_pow(pubkey, kind, tags, &content, difficulty, &pow))
+            .await
+            .map_err(|e| PostError::Api(format!("Proof of work failed: {}", e)))??;
+        tags = mined_tags;
+        builder = builder.custom_created_at(created_at);
+    }
+    let unsigned = builder.tags(tags).build(pubkey);
+    let event = keys.sign_event(unsigned).await.map_err(|e| PostError::Crypto(format!("Failed to sign event: {}", e)))?;
+    tracing::trace!("[Nostr] Final event: {:?}", event);
+    let output = match tokio::time::timeout(timeout, nostr_client.send_event(&event)).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            tracing::error!("[Nostr] Failed to post to any relays: {:?}", e);
+            return Err(PostError::Api(format!("Failed to post to any Nostr relays: {}", e)));
+        }
+        Err(_) => return Err(PostError::Network("timeout".to_string())),
+    };
+
+    let mut results = RelayResults::new();
+    for relay in &output.success {
+        results.push((relay.to_string(), Ok(())));
+    }
+    for (relay, reason) in &output.failed {
+        results.push((relay.to_string(), Err(reason.to_string())));
+    }
+    Ok((event, results))
+}
+
+/// Nostr-only settings for a single post.
+#[derive(Debug, Clone, Default)]
+pub struct NostrOptions {
+    /// Publish the post as a NIP-23 long-form article instead of a note
+    pub article: Option<NostrArticle>,
+}
+
+/// Title and summary of a NIP-23 long-form article.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NostrArticle {
+    /// The text's first line is used without one
+    pub title: String,
+    pub summary: String,
+    /// The `d` tag that tells the author's articles apart: random for each new one, and kept
+    /// to publish an edited version that replaces it
+    pub identifier: String,
+}
+
+/// The title of an article: the one given, or else the text's first line without Markdown heading marks.
+pub fn nostr_article_title(text: &str, title: &str) -> String {
+    let title = title.trim();
+    if !title.is_empty() {
+        return title.to_string();
+    }
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Tags for a NIP-23 article, before mentions, hashtags and images are added.
+fn nostr_article_tags(text: &str, article: &NostrArticle, published_at: Timestamp) -> Vec<Tag> {
+    let title = nostr_article_title(text, &article.title);
+    let mut tags = vec![
+        Tag::identifier(article.identifier.clone()),
+        Tag::custom(TagKind::custom("title"), [title]),
+    ];
+    if !article.summary.trim().is_empty() {
+        tags.push(Tag::custom(TagKind::custom("summary"), [article.summary.trim().to_string()]));
+    }
+    tags.push(Tag::custom(TagKind::custom("published_at"), [published_at.as_u64().to_string()]));
+    tags
+}
+
+/// Publish `text` as a NIP-23 long-form article (kind 30023) with Markdown content.
+///
+/// Images are uploaded as for a note and added to the end of the article as Markdown images.
+/// Publishing again with the same `identifier` replaces the earlier version, which is how
+/// NIP-23 articles are edited.
+#[allow(clippy::too_many_arguments)]
+pub async fn post_nostr_article(client: &reqwest::Client, retry: RetryPolicy, config: &NostrConfig, text: &str, images: &[ImageAttachment], article: &NostrArticle, language: Option<&str>, timeout: Duration, pow: &PowProgress) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.decrypted_private_key.is_empty() || config.relays.is_empty() {
+        return Err(PostError::Auth("Nostr not configured".to_string()));
+    }
+    let secret_key = parse_nostr_secret_key(&config.decrypted_private_key).map_err(PostError::Crypto)?;
+    let keys = Keys::new(secret_key);
+
+    let mut details = PostDetails::default();
+    let mut tags = nostr_article_tags(text, article, Timestamp::now());
+    let mut content = text.trim_end().to_string();
+    for image in upload_nostr_images(client, retry, &keys, config, images, &mut details.warnings).await {
+        content.push_str(&format!("\n\n![{}]({})", image.alt.unwrap_or_default(), image.url));
+        tags.push(image.imeta);
+    }
+    let (content, mention_tags) = link_nostr_mentions(client, retry, &content, &mut details.warnings).await;
+    tags.extend(mention_tags);
+    tags.extend(nostr_hashtags(&content).into_iter().map(Tag::hashtag));
+    if let Some(language) = language {
+        tags.extend(nostr_language_tags(language));
+    }
+
+    let nostr_client = connect_nostr_relays(&keys, &config.relays, timeout, &mut details.relays).await;
+    let (event, relays) = send_nostr_event(&nostr_client, &keys, config, pow, Kind::LongFormTextNote, content, tags, timeout).await?;
+    let accepted = relays.iter().any(|(_, result)| result.is_ok());
+    details.relays.extend(relays);
+    if !accepted {
+        let reasons: Vec<String> = details
+            .relays
+            .iter()
+            .filter_map(|(relay, result)| result.as_ref().err().map(|e| format!("{}: {}", relay, e)))
+            .collect();
+        return Err(PostError::Api(format!("No Nostr relay accepted the article ({})", reasons.join("; "))));
+    }
+    details.link = nostr_event_link(&event, &details.relays);
+    Ok(details)
+}
+
+/// A web link to `event` as a `nevent` code, hinting the relays that accepted it.
+fn nostr_event_link(event: &Event, relays: &RelayResults) -> Option<String> {
+    let mut nevent = Nip19Event::new(event.id);
+    nevent.author = Some(event.pubkey);
+    nevent.kind = Some(event.kind);
+    nevent.relays = relays
+        .iter()
+        .filter(|(_, result)| result.is_ok())
+        .filter_map(|(relay, _)| RelayUrl::parse(relay).ok())
+        .take(MAX_NEVENT_RELAYS)
+        .collect();
+    let code = nevent.to_bech32().ok()?;
+    Some(format!("https://njump.me/{}", code))
+}
+
+/// Relays named in a post link, enough to find the post without making the link unwieldy
+const MAX_NEVENT_RELAYS: usize = 3;
+
+/// A Nostr note being replied to or quoted.
+#[derive(Debug, PartialEq)]
+struct NostrTarget {
+    id: EventId,
+    author: Option<PublicKey>,
+    /// Relay the note can be found on, if the reference said
+    relay: Option<String>,
+}
+
+/// The note a reference points to: a `note1…` or `nevent1…` code, with or without `nostr:`,
+/// a web link ending in one (as njump.me and primal.net links do), or a hex event ID.
+fn parse_nostr_event_reference(reference: &str) -> Option<NostrTarget> {
+    let reference = reference.trim();
+    let reference = reference.split(['?', '#']).next()?.trim_end_matches('/');
+    let code = reference.rsplit(['/', ':']).next()?;
+    if code.len() == 64 {
+        let id = EventId::from_hex(code).ok()?;
+        return Some(NostrTarget { id, author: None, relay: None });
+    }
+    match Nip19::from_bech32(code).ok()? {
+        Nip19::EventId(id) => Some(NostrTarget { id, author: None, relay: None }),
+        Nip19::Event(event) => Some(NostrTarget {
+            id: event.event_id,
+            author: event.author,
+            relay: event.relays.first().map(|relay| relay.to_string()),
+        }),
+        _ => None,
+    }
+}
+
+/// The root of the thread `event` replies in, from its NIP-10 `e` tags, or `None` if it
+/// isn't a reply. Old notes without markers list the root first.
+fn nostr_thread_root(event: &Event) -> Option<EventId> {
+    let e_tags: Vec<&[String]> = event
+        .tags
+        .iter()
+        .map(|tag| tag.as_slice())
+        .filter(|tag| tag.len() >= 2 && tag[0] == "e")
+        .collect();
+    let root = e_tags
+        .iter()
+        .find(|tag| tag.get(3).is_some_and(|marker| marker == "root"))
+        .or_else(|| e_tags.iter().find(|tag| tag.get(3).is_none_or(|marker| marker.is_empty())))?;
+    EventId::from_hex(&root[1]).ok()
+}
+
+/// Find a NIP-13 `nonce` tag that gives the event an ID with at least `difficulty` leading
+/// zero bits, returning the tags with it added and the timestamp it was mined for.
+///
+/// This hashes the NIP-01 serialization directly and can take a long time, so it's meant for a
+/// blocking thread. It stops when `progress` is cancelled.
+fn mine_nostr_pow(pubkey: PublicKey, kind: Kind, mut tags: Vec<Tag>, content: &str, difficulty: u8, progress: &PowProgress) -> Result<(Vec<Tag>, Timestamp), PostError> {
+    let created_at = Timestamp::now();
+    // Everything but the nonce is fixed, so only its digits change between attempts
+    let mut prefix = format!("[0,\"{}\",{},{},[", pubkey.to_hex(), created_at.as_u64(), kind.as_u16());
+    for tag in &tags {
+        prefix.push_str(&serde_json::to_string(tag.as_slice()).map_err(|e| PostError::Api(e.to_string()))?);
+        prefix.push(',');
+    }
+    prefix.push_str("[\"nonce\",\"");
+    let suffix = format!("\",\"{}\"]],{}]", difficulty, serde_json::to_string(content).map_err(|e| PostError::Api(e.to_string()))?);
+
+    let state = &progress.0;
+    state.attempts.store(0, Ordering::Relaxed);
+    state.mining.store(true, Ordering::Relaxed);
+    let mut nonce: u64 = 0;
+    let found = loop {
+        if nonce % POW_CHECK_INTERVAL == 0 {
+            state.attempts.store(nonce, Ordering::Relaxed);
+            if state.cancelled.load(Ordering::Relaxed) {
+                break None;
+            }
+        }
+        let hash = Sha256::new()
+            .chain_update(&prefix)
+            .chain_update(nonce.to_string())
+            .chain_update(&suffix)
+            .finalize();
+        if leading_zero_bits(&hash) >= difficulty {
+            break Some(nonce);
+        }
+        nonce += 1;
+    };
+    state.mining.store(false, Ordering::Relaxed);
+
+    let nonce = found.ok_or_else(|| PostError::Api("Proof of work was cancelled".to_string()))?;
+    tracing::debug!("[Nostr] Mined difficulty {} after {} attempts", difficulty, nonce + 1);
+    tags.push(Tag::custom(TagKind::custom("nonce"), [nonce.to_string(), difficulty.to_string()]));
+    Ok((tags, created_at))
+}
+
+/// Number of leading zero bits in `bytes`, as NIP-13 measures difficulty.
+fn leading_zero_bits(bytes: &[u8]) -> u8 {
+    let mut bits = 0;
+    for byte in bytes {
+        if *byte != 0 {
+            return bits + byte.leading_zeros() as u8;
+        }
+        bits += 8;
+    }
+    bits
+}
+
+/// NIP-65 `r` tags for a relay list. Tags without a marker mean the relay is used for
+/// both reading and writing.
+fn nip65_relay_tags(relays: &[String]) -> Vec<Tag> {
+    relays
+        .iter()
+        .map(|relay| Tag::custom(TagKind::custom("r"), [relay.clone()]))
+        .collect()
+}
+
+/// Publish the configured relays as a NIP-65 relay list (kind 10002), so clients following
+/// the outbox model know where to find our notes.
+///
+/// It's sent the way notes are, with the same proof of work for relays that ask for it.
+/// Returns the per-relay outcome; at least one relay must accept the list.
+pub async fn publish_nostr_relay_list(config: &NostrConfig, timeout: Duration) -> Result<RelayResults, PostError> {
+    if config.decrypted_private_key.is_empty() || config.relays.is_empty() {
+        return Err(PostError::Auth("Nostr not configured".to_string()));
+    }
+    let secret_key = parse_nostr_secret_key(&config.decrypted_private_key).map_err(PostError::Crypto)?;
+    let keys = Keys::new(secret_key);
+
+    let mut results = RelayResults::new();
+    let nostr_client = connect_nostr_relays(&keys, &config.relays, timeout, &mut results).await;
+    let tags = nip65_relay_tags(&config.relays);
+    let (_, sent) = send_nostr_event(&nostr_client, &keys, config, &PowProgress::default(), Kind::RelayList, String::new(), tags, timeout).await?;
+    let accepted = sent.iter().any(|(_, result)| result.is_ok());
+    results.extend(sent);
+    if !accepted {
+        return Err(PostError::Api("No Nostr relay accepted the relay list".to_string()));
+    }
+    Ok(results)
+}
+
+/// Post to Threads through the Graph API: create a media container, wait for Threads to
+/// finish it, then publish it.
+///
+/// Threads fetches images from a public URL instead of accepting uploads, so attached images
+/// are put on the Nostr media server first when that's turned on, and left out otherwise.
+pub async fn post_to_threads(client: &reqwest::Client, retry: RetryPolicy, config: &ThreadsConfig, nostr: &NostrConfig, text: &str, images: &[ImageAttachment]) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.user_id.trim().is_empty() || config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Threads not configured".to_string()));
+    }
+    if text.chars().count() > THREADS_CHARACTER_LIMIT {
+        return Err(PostError::Api(format!("Threads posts are limited to {} characters", THREADS_CHARACTER_LIMIT)));
+    }
+
+    let mut details = PostDetails::default();
+    let uploaded = if images.is_empty() {
+        Vec::new()
+    } else if config.upload_images {
+        rehost_images(client, retry, nostr, images, &mut details.warnings).await
+    } else {
+        details.warnings.push("Uploading images for Threads is turned off in Settings, so they were not attached".to_string());
+        Vec::new()
+    };
+    if text.trim().is_empty() && uploaded.is_empty() {
+        return Err(PostError::Api("Threads posts need text or an image".to_string()));
+    }
+
+    let base_url = format!("{}/{}", THREADS_API_URL, config.user_id.trim());
+    let access_token = config.decrypted_access_token.as_str();
+    let create_container = |mut fields: Vec<(&'static str, String)>| {
+        let url = format!("{}/threads", base_url);
+        fields.push(("access_token", access_token.to_string()));
+        async move {
+            let response = send_with_retry(retry, || client.post(&url).form(&fields)).await?;
+            threads_response_id(response).await
+        }
+    };
+
+    // Step 1: create the media container; several images each get one of their own, which go
+    // into a carousel
+    let container_id = match uploaded.as_slice() {
+        [] => create_container(vec![("media_type", "TEXT".to_string()), ("text", text.to_string())]).await?,
+        [image] => {
+            let mut fields = threads_image_fields(image, false);
+            fields.push(("text", text.to_string()));
+            create_container(fields).await?
+        }
+        images => {
+            let mut children = Vec::new();
+            for image in images {
+                let child = create_container(threads_image_fields(image, true)).await?;
+                wait_for_threads_container(client, retry, &child, access_token).await?;
+                children.push(child);
+            }
+            create_container(vec![("media_type", "CAROUSEL".to_string()), ("children", children.join(",")), ("text", text.to_string())]).await?
+        }
+    };
+
+    // Step 2: a container can't be published until Threads has finished it
+    wait_for_threads_container(client, retry, &container_id, access_token).await?;
+
+    // Step 3: publish it
+    let response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/threads_publish", base_url))
+            .form(&[("creation_id", container_id.as_str()), ("access_token", access_token)])
+    })
+    .await?;
+    threads_response_id(response).await?;
+    Ok(details)
+}
+
+/// Fields for a Threads image container, for the image at its re-hosted URL.
+fn threads_image_fields(image: &NostrImage, carousel_item: bool) -> Vec<(&'static str, String)> {
+    let mut fields = vec![("media_type", "IMAGE".to_string()), ("image_url", image.url.clone())];
+    if let Some(alt) = &image.alt {
+        fields.push(("alt_text", alt.clone()));
+    }
+    if carousel_item {
+        fields.push(("is_carousel_item", "true".to_string()));
+    }
+    fields
+}
+
+/// Wait until Threads has finished preparing a container, which fetches its image if it has one.
+async fn wait_for_threads_container(client: &reqwest::Client, retry: RetryPolicy, container_id: &str, access_token: &str) -> Result<(), PostError> {
+    let started = std::time::Instant::now();
+    loop {
+        let response = send_with_retry(retry, || {
+            client
+                .get(format!("{}/{}", THREADS_API_URL, container_id))
+                .query(&[("fields", "status,error_message"), ("access_token", access_token)])
+        })
+        .await?;
+        let status = response.status();
+        let data: Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let message = data["error"]["message"].as_str().unwrap_or("unexpected response");
+            return Err(PostError::Api(format!("Couldn't check on the Threads post: {}", message)));
+        }
+        match data["status"].as_str() {
+            Some("FINISHED") => return Ok(()),
+            Some(state @ ("ERROR" | "EXPIRED")) => {
+                let reason = data["error_message"].as_str().unwrap_or(state);
+                return Err(PostError::Api(format!("Threads couldn't prepare the post: {}", reason)));
+            }
+            state => tracing::debug!("[Threads] Container {} is {:?}", container_id, state),
+        }
+        if started.elapsed() > THREADS_CONTAINER_TIMEOUT {
+            return Err(PostError::Api("Threads took too long to prepare the post".to_string()));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Read the object `id` from a Threads Graph API response, or turn its error into a `PostError`.
+async fn threads_response_id(response: reqwest::Response) -> Result<String, PostError> {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if status.is_success() {
+        if let Some(id) = body["id"].as_str() {
+            return Ok(id.to_string());
+        }
+    }
+    let message = body["error"]["message"].as_str().unwrap_or("unexpected response").to_string();
+    // Code 190 is an expired or invalid access token
+    if matches!(status.as_u16(), 401 | 403) || body["error"]["code"].as_i64() == Some(190) {
+        return Err(PostError::Auth(format!("Threads rejected the access token: {}", message)));
+    }
+    Err(PostError::Api(format!("Threads API error: {}", message)))
+}
+
+/// Whether a URL looks like a Discord incoming webhook.
+pub fn is_discord_webhook_url(url: &str) -> bool {
+    ["https://discord.com/api/webhooks/", "https://discordapp.com/api/webhooks/", "https://canary.discord.com/api/webhooks/"]
+        .iter()
+        .any(|prefix| url.strip_prefix(prefix).is_some_and(|rest| rest.split('/').filter(|s| !s.is_empty()).count() == 2))
+}
+
+/// A name for a webhook that leaves out its secret token, e.g. `webhook 1234567890`.
+pub fn discord_webhook_label(url: &str) -> String {
+    match url.split("/webhooks/").nth(1).and_then(|rest| rest.split('/').next()) {
+        Some(id) if !id.is_empty() => format!("webhook {}", id),
+        _ => "webhook".to_string(),
+    }
+}
+
+/// Send the post to every configured Discord webhook.
+///
+/// A webhook that fails is reported as a warning as long as at least one other
+/// webhook accepted the post; the post only fails when every webhook did.
+pub async fn post_to_discord(client: &reqwest::Client, retry: RetryPolicy, config: &DiscordWebhookConfig, text: &str, images: &[ImageAttachment]) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.decrypted_webhook_urls.is_empty() {
+        return Err(PostError::Auth("Discord not configured".to_string()));
+    }
+    if text.chars().count() > DISCORD_CHARACTER_LIMIT {
+        return Err(PostError::Api(format!("Discord messages are limited to {} characters", DISCORD_CHARACTER_LIMIT)));
+    }
+
+    let mut files = Vec::new();
+    for image in images.iter().take(MAX_IMAGES) {
+        let bytes = std::fs::read(&image.path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+        let file_name = upload_file_name(&image.path);
+        let mime_type = mime_guess::from_path(&image.path).first_or_octet_stream().to_string();
+        files.push((bytes, file_name, mime_type, image.alt().map(str::to_string)));
+    }
+    let attachments: Vec<Value> = files
+        .iter()
+        .enumerate()
+        .map(|(i, (_, file_name, _, alt))| {
+            let mut attachment = json!({ "id": i, "filename": file_name });
+            if let Some(alt) = alt {
+                attachment["description"] = json!(alt);
+            }
+            attachment
+        })
+        .collect();
+    let payload = json!({ "content": text, "attachments": attachments });
+
+    let mut details = PostDetails::default();
+    let mut errors = Vec::new();
+    for url in config.decrypted_webhook_urls.iter() {
+        let label = discord_webhook_label(url);
+        let result = send_with_retry(retry, || {
+            // ?wait=true makes Discord report errors instead of accepting the message blindly
+            let request = client.post(url).query(&[("wait", "true")]);
+            if files.is_empty() {
+                return request.json(&payload);
+            }
+            let mut form = multipart::Form::new().text("payload_json", payload.to_string());
+            for (i, (bytes, file_name, mime_type, _)) in files.iter().enumerate() {
+                let part = multipart::Part::bytes(bytes.clone())
+                    .file_name(file_name.clone())
+                    .mime_str(mime_type)
+                    .unwrap_or_else(|_| multipart::Part::bytes(bytes.clone()).file_name(file_name.clone()));
+                form = form.part(format!("files[{}]", i), part);
+            }
+            request.multipart(form)
+        })
+        .await;
+        let error = match result {
+            Ok(response) if response.status().is_success() => continue,
+            Ok(response) => {
+                let status = response.status();
+                let body: Value = response.json().await.unwrap_or_default();
+                let message = body["message"].as_str().unwrap_or("unexpected response").to_string();
+                // Discord answers 404 for a webhook that was deleted or whose token is wrong
+                if matches!(status.as_u16(), 401 | 404) {
+                    PostError::Auth(format!("Discord rejected {}: {}", label, message))
+                } else {
+                    PostError::Api(format!("Discord {} failed: {}", label, message))
+                }
+            }
+            Err(e) => e,
+        };
+        tracing::warn!("[Discord] Posting to {} failed: {}", label, error);
+        errors.push((label, error));
+    }
+
+    if errors.len() == config.decrypted_webhook_urls.len() {
+        return Err(errors.remove(0).1);
+    }
+    details
+        .warnings
+        .extend(errors.into_iter().map(|(label, error)| format!("{} didn't get the post: {}", label, error)));
+    Ok(details)
+}
+
+/// How a post is split between a photo caption and a separate Telegram message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TelegramPlan {
+    caption: Option<String>,
+    message: Option<String>,
+    /// The text was longer than a message allows and was cut short
+    truncated: bool,
+}
+
+/// Put the text in the photo caption when it fits, and otherwise in a message of its own.
+fn plan_telegram_post(text: &str, has_images: bool) -> TelegramPlan {
+    let text = text.trim();
+    let length = text.chars().count();
+    if has_images && length <= TELEGRAM_CAPTION_LIMIT {
+        return TelegramPlan {
+            caption: Some(text.to_string()).filter(|caption| !caption.is_empty()),
+            message: None,
+            truncated: false,
+        };
+    }
+    TelegramPlan {
+        caption: None,
+        message: Some(text.chars().take(TELEGRAM_MESSAGE_LIMIT).collect()),
+        truncated: length > TELEGRAM_MESSAGE_LIMIT,
+    }
+}
+
+/// Send the post to a Telegram chat or channel through the Bot API.
+///
+/// Images go out with `sendPhoto` (or `sendMediaGroup` for several) with the text as
+/// their caption; text too long for a caption follows in a separate `sendMessage`.
+pub async fn post_to_telegram(client: &reqwest::Client, retry: RetryPolicy, config: &TelegramConfig, text: &str, images: &[ImageAttachment]) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.chat_id.trim().is_empty() || config.decrypted_bot_token.is_empty() {
+        return Err(PostError::Auth("Telegram not configured".to_string()));
+    }
+
+    let api_url = format!("https://api.telegram.org/bot{}", config.decrypted_bot_token.as_str());
+    let chat_id = config.chat_id.trim();
+    let images = &images[..images.len().min(MAX_IMAGES)];
+    let plan = plan_telegram_post(text, !images.is_empty());
+    let mut details = PostDetails::default();
+
+    if !images.is_empty() {
+        let mut photos = Vec::new();
+        for image in images {
+            let bytes = std::fs::read(&image.path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+            let file_name = upload_file_name(&image.path);
+            photos.push((bytes, file_name));
+        }
+        let response = send_with_retry(retry, || {
+            let mut form = multipart::Form::new().text("chat_id", chat_id.to_string());
+            if let [(bytes, file_name)] = photos.as_slice() {
+                form = form.part("photo", multipart::Part::bytes(bytes.clone()).file_name(file_name.clone()));
+                if let Some(caption) = &plan.caption {
+                    form = form.text("caption", caption.clone());
+                }
+                return client.post(format!("{}/sendPhoto", api_url)).multipart(form);
+            }
+            // Albums take the caption from their first item
+            let media: Vec<Value> = (0..photos.len())
+                .map(|i| {
+                    let mut item = json!({ "type": "photo", "media": format!("attach://photo{}", i) });
+                    if let (0, Some(caption)) = (i, &plan.caption) {
+                        item["caption"] = json!(caption);
+                    }
+                    item
+                })
+                .collect();
+            form = form.text("media", Value::Array(media).to_string());
+            for (i, (bytes, file_name)) in photos.iter().enumerate() {
+                form = form.part(format!("photo{}", i), multipart::Part::bytes(bytes.clone()).file_name(file_name.clone()));
+            }
+            client.post(format!("{}/sendMediaGroup", api_url)).multipart(form)
+        })
+        .await?;
+        check_telegram_response(response).await?;
+    }
+
+    if let Some(message) = &plan.message {
+        let body = json!({ "chat_id": chat_id, "text": message });
+        let response = send_with_retry(retry, || client.post(format!("{}/sendMessage", api_url)).json(&body)).await?;
+        check_telegram_response(response).await?;
+    }
+
+    if plan.truncated {
+        details
+            .warnings
+            .push(format!("Telegram messages are limited to {} characters, so the text was shortened", TELEGRAM_MESSAGE_LIMIT));
+    }
+    Ok(details)
+}
+
+/// Turn a Bot API response with `"ok": false` into a `PostError`.
+async fn check_telegram_response(response: reqwest::Response) -> Result<(), PostError> {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if status.is_success() && body["ok"].as_bool() == Some(true) {
+        return Ok(());
+    }
+    let description = body["description"].as_str().unwrap_or("unexpected response").to_string();
+    if matches!(status.as_u16(), 401 | 403) {
+        return Err(PostError::Auth(format!("Telegram rejected the bot: {}", description)));
+    }
+    Err(PostError::Api(format!("Telegram API error: {}", description)))
+}
+
+/// Per-post settings for WordPress.
+#[derive(Debug, Clone, Default)]
+pub struct WordPressOptions {
+    /// Post title; WordPress shows "(no title)" without one
+    pub title: Option<String>,
+}
+
+/// Escape text for use in HTML content and attributes.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Post body as block-editor HTML: one paragraph per blank-line-separated block of
+/// text, followed by the uploaded images as `(source URL, alt text)`.
+fn wordpress_content(text: &str, media: &[(String, String)]) -> String {
+    let mut blocks: Vec<String> = text
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| format!("<!-- wp:paragraph -->\n<p>{}</p>\n<!-- /wp:paragraph -->", escape_html(paragraph).replace('\n', "<br>")))
+        .collect();
+    for (url, alt) in media {
+        blocks.push(format!(
+            "<!-- wp:image -->\n<figure class=\"wp-block-image\"><img src=\"{}\" alt=\"{}\"/></figure>\n<!-- /wp:image -->",
+            escape_html(url),
+            escape_html(alt)
+        ));
+    }
+    blocks.join("\n\n")
+}
+
+/// Create a post through the WordPress REST API, uploading images to the media library first.
+///
+/// Authenticates with an application password; the first image becomes the featured image.
+pub async fn post_to_wordpress(client: &reqwest::Client, retry: RetryPolicy, config: &WordPressConfig, text: &str, images: &[ImageAttachment], options: &WordPressOptions) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.site_url.is_empty() || config.username.is_empty() || config.decrypted_app_password.is_empty() {
+        return Err(PostError::Auth("WordPress not configured".to_string()));
+    }
+
+    let api_url = format!("{}/wp-json/wp/v2", config.site_url.trim_end_matches('/'));
+    let username = config.username.as_str();
+    let password = config.decrypted_app_password.as_str();
+
+    let mut details = PostDetails::default();
+    let mut media = Vec::new();
+    let mut featured_media = None;
+    for image in images.iter().take(MAX_IMAGES) {
+        let (img_bytes, mime_type) = read_image_within(image, WORDPRESS_MAX_IMAGE_BYTES).await?;
+        let path = std::path::Path::new(&image.path);
+        // A shrunk image was re-encoded, so its name needs a matching extension
+        let file_name = if mime_guess::from_path(path).first_or_octet_stream() == mime_type.as_str() {
+            upload_file_name(&image.path)
+        } else {
+            format!("{}.jpg", path.file_stem().and_then(|n| n.to_str()).unwrap_or("image"))
+        };
+        let alt = image.alt().unwrap_or_default();
+        let response = send_with_retry(retry, || {
+            client
+                .post(format!("{}/media", api_url))
+                .basic_auth(username, Some(password))
+                .header("Content-Type", mime_type.as_str())
+                .header("Content-Disposition", format!("attachment; filename=\"{}\"", file_name))
+                .query(&[("alt_text", alt)])
+                .body(img_bytes.clone())
+        })
+        .await?;
+        let uploaded = wordpress_response(response).await?;
+        featured_media = featured_media.or_else(|| uploaded["id"].as_u64());
+        match uploaded["source_url"].as_str() {
+            Some(source_url) => media.push((source_url.to_string(), alt.to_string())),
+            None => details.warnings.push(format!("WordPress didn't say where {} was put, so it's only in the media library", file_name)),
+        }
+    }
+
+    let mut post = json!({
+        "content": wordpress_content(text, &media),
+        "status": config.default_status.as_str(),
+    });
+    if let Some(title) = options.title.as_deref().map(str::trim).filter(|title| !title.is_empty()) {
+        post["title"] = json!(title);
+    }
+    if let Some(id) = featured_media {
+        post["featured_media"] = json!(id);
+    }
+    let response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/posts", api_url))
+            .basic_auth(username, Some(password))
+            .json(&post)
+    })
+    .await?;
+    let created = wordpress_response(response).await?;
+    details.link = created["link"].as_str().map(str::to_string);
+    Ok(details)
+}
+
+/// The JSON body of a successful WordPress REST response, or its error as a `PostError`.
+async fn wordpress_response(response: reqwest::Response) -> Result<Value, PostError> {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if status.is_success() {
+        return Ok(body);
+    }
+    let message = body["message"].as_str().unwrap_or("unexpected response").to_string();
+    if matches!(status.as_u16(), 401 | 403) {
+        return Err(PostError::Auth(format!("WordPress rejected the application password: {}", message)));
+    }
+    Err(PostError::Api(format!("WordPress API error: {}", message)))
+}
+
+/// Per-post settings for Lemmy.
+#[derive(Debug, Clone, Default)]
+pub struct LemmyOptions {
+    /// Post title; the first line of the text is used without one
+    pub title: Option<String>,
+}
+
+/// Lemmy login tokens by `instance|username`, so each post doesn't log in afresh
+static LEMMY_SESSIONS: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(Default::default);
+
+/// The title of a Lemmy post: the one given, or else the text's first line, cut to fit at a word.
+pub fn lemmy_title(text: &str, title: Option<&str>) -> String {
+    let title = title
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default());
+    if grapheme_count(title) <= LEMMY_TITLE_LIMIT {
+        return title.to_string();
+    }
+    let cut: String = title.graphemes(true).take(LEMMY_TITLE_LIMIT - 1).collect();
+    let cut = cut.rfind(char::is_whitespace).map_or(cut.as_str(), |end| &cut[..end]);
+    format!("{}…", cut.trim_end())
+}
+
+/// The JSON for `POST /api/v3/post`. The first link in the text makes it a link post, and
+/// the body is left out when the text is nothing more than its title.
+fn lemmy_post_body(text: &str, title: &str, community_id: u64) -> Value {
+    let mut post = json!({
+        "name": title,
+        "community_id": community_id,
+    });
+    let text = text.trim();
+    if text != title {
+        post["body"] = json!(text);
+    }
+    let link = detect_facets(text).into_iter().find_map(|span| match span.kind {
+        FacetKind::Link(link) => Some(link),
+        _ => None,
+    });
+    if let Some(link) = link {
+        post["url"] = json!(link);
+    }
+    post
+}
+
+/// Create a post in a Lemmy community, logging in first unless a token is cached.
+///
+/// The community is looked up by name (`name` or `name@instance`). Lemmy takes images
+/// only as links, so attached images are skipped with a warning.
+pub async fn post_to_lemmy(client: &reqwest::Client, retry: RetryPolicy, config: &LemmyConfig, text: &str, images: &[ImageAttachment], options: &LemmyOptions) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.instance_url.is_empty() || config.username.is_empty() || config.decrypted_password.is_empty() || config.community.trim().is_empty() {
+        return Err(PostError::Auth("Lemmy not configured".to_string()));
+    }
+    let api_url = format!("{}/api/v3", config.instance_url.trim_end_matches('/'));
+    let mut details = PostDetails::default();
+    if !images.is_empty() {
+        details.warnings.push("Lemmy doesn't accept image uploads here, so the images were left out".to_string());
+    }
+
+    let key = format!("{}|{}", api_url, config.username);
+    let cached = LEMMY_SESSIONS.lock().unwrap().get(&key).cloned();
+    let from_cache = cached.is_some();
+    let jwt = match cached {
+        Some(jwt) => jwt,
+        None => lemmy_login(client, retry, &api_url, config, &key).await?,
+    };
+
+    let community_id = lemmy_community_id(client, retry, &api_url, config.community.trim()).await?;
+    let title = lemmy_title(text, options.title.as_deref());
+    let post = lemmy_post_body(text, &title, community_id);
+    let create = |jwt: String| {
+        let post = &post;
+        let api_url = &api_url;
+        async move {
+            let response = send_with_retry(retry, || client.post(format!("{}/post", api_url)).bearer_auth(&jwt).json(post)).await?;
+            let status = response.status();
+            let body: Value = response.json().await.unwrap_or_default();
+            Ok::<_, PostError>((status, body))
+        }
+    };
+    let (mut status, mut body) = create(jwt).await?;
+    if from_cache && lemmy_token_rejected(status, &body) {
+        // The cached token expired or was revoked
+        let jwt = lemmy_login(client, retry, &api_url, config, &key).await?;
+        (status, body) = create(jwt).await?;
+    }
+    lemmy_result(status, body)?;
+    Ok(details)
+}
+
+/// Log in and cache the token under `key`.
+async fn lemmy_login(client: &reqwest::Client, retry: RetryPolicy, api_url: &str, config: &LemmyConfig, key: &str) -> Result<String, PostError> {
+    LEMMY_SESSIONS.lock().unwrap().remove(key);
+    let response = send_with_retry(retry, || {
+        client.post(format!("{}/user/login", api_url)).json(&json!({
+            "username_or_email": config.username,
+            "password": config.decrypted_password.as_str(),
+        }))
+    })
+    .await?;
+    let body = lemmy_response(response).await?;
+    let jwt = body["jwt"]
+        .as_str()
+        .ok_or_else(|| PostError::Auth("Lemmy didn't return a login token".to_string()))?
+        .to_string();
+    LEMMY_SESSIONS.lock().unwrap().insert(key.to_string(), jwt.clone());
+    Ok(jwt)
+}
+
+/// The ID of the community called `name`; community listings are public, so no login is needed.
+async fn lemmy_community_id(client: &reqwest::Client, retry: RetryPolicy, api_url: &str, name: &str) -> Result<u64, PostError> {
+    let name = name.trim_start_matches('!');
+    let response = send_with_retry(retry, || client.get(format!("{}/community", api_url)).query(&[("name", name)])).await?;
+    let body = lemmy_response(response).await?;
+    body["community_view"]["community"]["id"]
+        .as_u64()
+        .ok_or_else(|| PostError::Api(format!("Lemmy community {} not found", name)))
+}
+
+/// The JSON body of a successful Lemmy response, or its error as a `PostError`.
+async fn lemmy_response(response: reqwest::Response) -> Result<Value, PostError> {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    lemmy_result(status, body)
+}
+
+/// Whether Lemmy turned down the login token. Besides 401, v3 answers an expired or
+/// invalid token with a 400 naming the error.
+fn lemmy_token_rejected(status: reqwest::StatusCode, body: &Value) -> bool {
+    status == reqwest::StatusCode::UNAUTHORIZED || matches!(body["error"].as_str(), Some("not_logged_in" | "incorrect_login"))
+}
+
+/// `lemmy_response` for a body that's already been read.
+fn lemmy_result(status: reqwest::StatusCode, body: Value) -> Result<Value, PostError> {
+    if status.is_success() {
+        return Ok(body);
+    }
+    let error = body["error"].as_str().unwrap_or("unexpected response").to_string();
+    if status.as_u16() == 401 || matches!(error.as_str(), "incorrect_login" | "not_logged_in" | "email_not_verified") {
+        return Err(PostError::Auth(format!("Lemmy rejected the login: {}", error)));
+    }
+    Err(PostError::Api(format!("Lemmy API error: {}", error)))
+}
+
+/// The JSON body Neynar's cast endpoint takes: the text, cast with `signer_uuid`, and images by their URLs.
+///
+/// `idempotency_key` becomes the cast's `idem`, the first 16 hex digits of its SHA-256, so
+/// Neynar makes one cast however often the request is sent.
+fn farcaster_cast_body(signer_uuid: &str, text: &str, image_urls: &[String], idempotency_key: Option<&str>) -> Value {
+    let mut body = json!({
+        "signer_uuid": signer_uuid,
+        "text": text
+    });
+    if let Some(key) = idempotency_key {
+        let hash: String = Sha256::digest(key.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+        body["idem"] = json!(&hash[..16]);
+    }
+    if !image_urls.is_empty() {
+        let embeds: Vec<Value> = image_urls.iter().map(|url| json!({ "url": url })).collect();
+        body["embeds"] = Value::Array(embeds);
+    }
+    body
+}
+
+/// The Warpcast page for the cast in Neynar's response, which links it by the author's
+/// username and the start of its hash.
+fn farcaster_cast_link(response: &Value) -> Option<String> {
+    let cast = &response["cast"];
+    let username = cast["author"]["username"].as_str()?;
+    let hash = cast["hash"].as_str()?;
+    Some(format!("https://warpcast.com/{}/{}", username, hash.get(..10).unwrap_or(hash)))
+}
+
+/// Upload images for a cast to the Nostr media server, since casts only embed images by URL.
+/// Images are left out with a warning unless uploading them is turned on and Nostr is set up
+/// with a key and a server.
+async fn upload_farcaster_images(client: &reqwest::Client, retry: RetryPolicy, config: &FarcasterConfig, nostr: &NostrConfig, images: &[ImageAttachment], warnings: &mut Vec<String>) -> Vec<String> {
+    if images.is_empty() {
+        return Vec::new();
+    }
+    if !config.upload_images {
+        warnings.push("Uploading images for casts is turned off in Settings, so they were not attached".to_string());
+        return Vec::new();
+    }
+    if images.len() > FARCASTER_MAX_EMBEDS {
+        warnings.push(format!("Casts can embed {} images, so only the first {} were attached", FARCASTER_MAX_EMBEDS, FARCASTER_MAX_EMBEDS));
+    }
+    let images = &images[..images.len().min(FARCASTER_MAX_EMBEDS)];
+    rehost_images(client, retry, nostr, images, warnings)
+        .await
+        .into_iter()
+        .map(|image| image.url)
+        .collect()
+}
+
+/// Upload images to the Nostr media server for platforms that only take images by URL.
+/// Without a Nostr key they're left out with a warning.
+async fn rehost_images(client: &reqwest::Client, retry: RetryPolicy, nostr: &NostrConfig, images: &[ImageAttachment], warnings: &mut Vec<String>) -> Vec<NostrImage> {
+    let keys = match parse_nostr_secret_key(&nostr.decrypted_private_key) {
+        Ok(secret_key) => Keys::new(secret_key),
+        Err(_) => {
+            warnings.push("Images are hosted on the Nostr media server, which needs a Nostr key, so they were not attached".to_string());
+            return Vec::new();
+        }
+    };
+    upload_nostr_images(client, retry, &keys, nostr, images, warnings).await
+}
+
+/// Cast to Farcaster through Neynar, with attached images embedded by URL.
+///
+/// Casts over the 320-byte limit are refused rather than cut, as the counter warns about them.
+pub async fn post_to_farcaster(client: &reqwest::Client, retry: RetryPolicy, config: &FarcasterConfig, nostr: &NostrConfig, text: &str, images: &[ImageAttachment], idempotency_key: Option<&str>) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.signer_uuid.trim().is_empty() || config.decrypted_api_key.is_empty() {
+        return Err(PostError::Auth("Farcaster not configured".to_string()));
+    }
+    let text = text.trim();
+    if text.len() > FARCASTER_BYTE_LIMIT {
+        return Err(PostError::Api(format!("Casts are limited to {} bytes, this one is {}", FARCASTER_BYTE_LIMIT, text.len())));
+    }
+
+    let mut details = PostDetails::default();
+    let image_urls = upload_farcaster_images(client, retry, config, nostr, images, &mut details.warnings).await;
+    let body = farcaster_cast_body(config.signer_uuid.trim(), text, &image_urls, idempotency_key);
+    let response = send_deduped_with_retry(retry, || {
+        client
+            .post(format!("{}/farcaster/cast", NEYNAR_API_URL))
+            .header("x-api-key", config.decrypted_api_key.as_str())
+            .json(&body)
+    })
+    .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = error_response_message(response).await;
+        // Neynar answers 401 for a bad key and 403 for a signer that isn't approved
+        if matches!(status.as_u16(), 401 | 403) {
+            return Err(PostError::Auth(format!("Neynar rejected the cast: {}", error_text)));
+        }
+        return Err(PostError::Api(format!("Neynar API error: {}", error_text)));
+    }
+    let created: Value = response.json().await?;
+    details.link = farcaster_cast_link(&created);
+    Ok(details)
+}
+
+/// A client-server API address on `homeserver`, with each of `segments` escaped as needed.
+fn matrix_api_url(homeserver: &str, segments: &[&str]) -> Result<url::Url, PostError> {
+    let mut url = url::Url::parse(homeserver.trim()).map_err(|e| PostError::Api(format!("Invalid homeserver URL: {}", e)))?;
+    url.path_segments_mut()
+        .map_err(|_| PostError::Api("Invalid homeserver URL".to_string()))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+/// The content of an `m.image` event for an uploaded image, described by its alt text.
+fn matrix_image_content(content_uri: &str, file_name: &str, alt: Option<&str>, mime_type: &str, size: usize, dimensions: Option<(u32, u32)>) -> Value {
+    let mut info = json!({ "mimetype": mime_type, "size": size });
+    if let Some((width, height)) = dimensions {
+        info["w"] = json!(width);
+        info["h"] = json!(height);
+    }
+    json!({
+        "msgtype": "m.image",
+        "body": alt.unwrap_or(file_name),
+        "filename": file_name,
+        "url": content_uri,
+        "info": info
+    })
+}
+
+/// Send one `m.room.message` event to the configured room and return its event ID.
+///
+/// The transaction ID is made once, so a retried request is recognised by the homeserver
+/// rather than posted twice.
+async fn send_matrix_message(client: &reqwest::Client, retry: RetryPolicy, config: &MatrixConfig, content: &Value) -> Result<String, PostError> {
+    let txn_id = uuid::Uuid::new_v4().to_string();
+    let url = matrix_api_url(&config.homeserver_url, &["_matrix", "client", "v3", "rooms", config.room_id.trim(), "send", "m.room.message", &txn_id])?;
+    let response = send_with_retry(retry, || {
+        client
+            .put(url.clone())
+            .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+            .json(content)
+    })
+    .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = error_response_message(response).await;
+        if matches!(status.as_u16(), 401 | 403) {
+            return Err(PostError::Auth(format!("Matrix rejected the message: {}", error_text)));
+        }
+        return Err(PostError::Api(format!("Matrix API error: {}", error_text)));
+    }
+    let sent: Value = response.json().await?;
+    sent["event_id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| PostError::Api("Matrix didn't return an event ID".to_string()))
+}
+
+/// Upload an image to the homeserver's media repository and return its `mxc://` URI.
+async fn upload_matrix_media(client: &reqwest::Client, retry: RetryPolicy, config: &MatrixConfig, bytes: &[u8], file_name: &str, mime_type: &str) -> Result<String, PostError> {
+    let mut url = matrix_api_url(&config.homeserver_url, &["_matrix", "media", "v3", "upload"])?;
+    url.query_pairs_mut().append_pair("filename", file_name);
+    let response = send_with_retry(retry, || {
+        client
+            .post(url.clone())
+            .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+            .header(reqwest::header::CONTENT_TYPE, mime_type)
+            .body(bytes.to_vec())
+    })
+    .await?;
+    if !response.status().is_success() {
+        let error_text = error_response_message(response).await;
+        return Err(PostError::Api(format!("Matrix media upload failed: {}", error_text)));
+    }
+    let uploaded: Value = response.json().await?;
+    uploaded["content_uri"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| PostError::Api("Matrix didn't return a content URI".to_string()))
+}
+
+/// Send the post to a Matrix room: the text as an `m.text` message, then each image as an
+/// `m.image` message after it. An image that fails is left out with a warning.
+pub async fn post_to_matrix(client: &reqwest::Client, retry: RetryPolicy, config: &MatrixConfig, text: &str, images: &[ImageAttachment]) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.homeserver_url.trim().is_empty() || config.room_id.trim().is_empty() || config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Matrix not configured".to_string()));
+    }
+    let room_id = config.room_id.trim();
+    let mut details = PostDetails::default();
+
+    // Read every image first, so one that's gone fails the post before any of it is sent
+    let images = images.iter().take(MAX_IMAGES);
+    let files = images
+        .map(|image| std::fs::read(&image.path).map(|bytes| (image, bytes)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+
+    let text = text.trim();
+    if !text.is_empty() {
+        let event_id = send_matrix_message(client, retry, config, &json!({ "msgtype": "m.text", "body": text })).await?;
+        details.link = Some(format!("https://matrix.to/#/{}/{}", room_id, event_id));
+    }
+
+    for (image, bytes) in files {
+        let file_name = upload_file_name(&image.path);
+        let mime_type = mime_guess::from_path(&image.path).first_or_octet_stream().to_string();
+        let sent = match upload_matrix_media(client, retry, config, &bytes, &file_name, &mime_type).await {
+            Ok(content_uri) => {
+                let content = matrix_image_content(&content_uri, &file_name, image.alt(), &mime_type, bytes.len(), image::image_dimensions(&image.path).ok());
+                send_matrix_message(client, retry, config, &content).await
+            }
+            Err(e) => Err(e),
+        };
+        match sent {
+            Ok(event_id) => {
+                details.link.get_or_insert_with(|| format!("https://matrix.to/#/{}/{}", room_id, event_id));
+            }
+            // Without any text, the first image is the post
+            Err(e) if text.is_empty() && details.link.is_none() => return Err(e),
+            Err(e) => {
+                tracing::warn!("[Matrix] Image failed: {}", e);
+                details.warnings.push(format!("{} wasn't sent: {}", file_name, e));
+            }
+        }
+    }
+    Ok(details)
+}
+
+/// Per-post settings for Dev.to.
+#[derive(Debug, Clone, Default)]
+pub struct DevToOptions {
+    /// Article title, which Dev.to requires
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Most tags a Dev.to article can have
+const DEVTO_MAX_TAGS: usize = 4;
+
+/// Tags as Dev.to takes them: lowercase letters and digits, without a leading `#`, at most
+/// `DEVTO_MAX_TAGS` and no repeats.
+fn devto_tags(tags: &[String]) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+        if !tag.is_empty() && !cleaned.contains(&tag) {
+            cleaned.push(tag);
+        }
+    }
+    cleaned.truncate(DEVTO_MAX_TAGS);
+    cleaned
+}
+
+/// Publish the post as an article on Dev.to or another Forem site, with the text as its
+/// Markdown body. The API has no image uploads, so attached images are left out.
+pub async fn post_to_devto(client: &reqwest::Client, retry: RetryPolicy, config: &DevToConfig, text: &str, images: &[ImageAttachment], options: &DevToOptions) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.site_url.trim().is_empty() || config.decrypted_api_key.is_empty() {
+        return Err(PostError::Auth("Dev.to not configured".to_string()));
+    }
+    let title = options
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| PostError::Api("Dev.to articles need a title".to_string()))?;
+    let mut details = PostDetails::default();
+    if !images.is_empty() {
+        details.warnings.push("Dev.to can't take images through its API, so they were left out".to_string());
+    }
+
+    let article = json!({
+        "article": {
+            "title": title,
+            "body_markdown": text,
+            "published": !config.draft,
+            "tags": devto_tags(&options.tags),
+        }
+    });
+    // Forem takes no key to spot a repeat, so an article that timed out isn't sent again
+    let response = send_with_retry(retry, || {
+        client
+            .post(format!("{}/api/articles", config.site_url.trim_end_matches('/')))
+            .header("api-key", config.decrypted_api_key.as_str())
+            .header("Accept", "application/vnd.forem.api-v1+json")
+            .json(&article)
+    })
+    .await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        let message = body["error"].as_str().unwrap_or("unexpected response").to_string();
+        if matches!(status.as_u16(), 401 | 403) {
+            return Err(PostError::Auth(format!("Dev.to rejected the API key: {}", message)));
+        }
+        return Err(PostError::Api(format!("Dev.to API error: {}", message)));
+    }
+    details.link = body["url"].as_str().map(str::to_string);
+    Ok(details)
+}
+
+/// Per-post settings for Micro.Blog.
+#[derive(Debug, Clone, Default)]
+pub struct MicroBlogOptions {
+    /// Makes the post a long-form entry instead of a microblog note
+    pub title: Option<String>,
+    pub categories: Vec<String>,
+}
+
+/// Micropub fields for an entry, apart from any photos.
+fn micropub_fields(text: &str, options: &MicroBlogOptions) -> Vec<(&'static str, String)> {
+    let mut fields = vec![("h", "entry".to_string()), ("content", text.to_string())];
+    if let Some(title) = options.title.as_deref().map(str::trim).filter(|title| !title.is_empty()) {
+        fields.push(("name", title.to_string()));
+    }
+    for category in options.categories.iter().map(|category| category.trim()).filter(|category| !category.is_empty()) {
+        fields.push(("category[]", category.to_string()));
+    }
+    fields
+}
+
+pub async fn post_to_microblog(client: &reqwest::Client, retry: RetryPolicy, config: &MicroBlogConfig, text: &str, images: &[ImageAttachment], options: &MicroBlogOptions) -> Result<PostDetails, PostError> {
+    if !config.enabled || config.decrypted_access_token.is_empty() {
+        return Err(PostError::Auth("Micro.Blog not configured".to_string()));
+    }
+    let fields = micropub_fields(text, options);
+
+    if !images.is_empty() {
+        let mut photos = Vec::new();
+        for image in images.iter().take(MAX_IMAGES) {
+            let img_bytes = std::fs::read(&image.path).map_err(|e| PostError::Api(format!("Failed to read image: {}", e)))?;
+            let file_name = upload_file_name(&image.path);
+            let mime_type = mime_guess::from_path(&image.path).first_or_octet_stream().to_string();
+            photos.push((img_bytes, file_name, mime_type, image.alt_text.trim().to_string()));
+        }
+        let response = send_with_retry(retry, || {
+            let mut form_data = reqwest::multipart::Form::new();
+            for (name, value) in &fields {
+                form_data = form_data.text(*name, value.clone());
+            }
+            for (img_bytes, file_name, mime_type, alt) in &photos {
+                let part = multipart::Part::bytes(img_bytes.clone())
+                    .file_name(file_name.clone())
+                    .mime_str(mime_type)
+                    .unwrap_or_else(|_| multipart::Part::bytes(img_bytes.clone()).file_name(file_name.clone()));
+                // Alt text pairs with the photo at the same position
+                form_data = form_data
+                    .part("photo", part)
+                    .text("mp-photo-alt", alt.clone());
+            }
+            client
+                .post("https://micro.blog/micropub")
+                .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+                .multipart(form_data)
+        })
+        .await?;
+        return microblog_post_details(response).await;
+    }
+    let response = send_with_retry(retry, || {
+        client
+            .post("https://micro.blog/micropub")
+            .header("Authorization", format!("Bearer {}", config.decrypted_access_token.as_str()))
+            .form(&fields)
+    })
+    .await?;
+    microblog_post_details(response).await
+}
+
+/// Check a Micropub response; the new post's address is in its `Location` header.
+async fn microblog_post_details(response: reqwest::Response) -> Result<PostDetails, PostError> {
+    if !response.status().is_success() {
+        let error_text = error_response_message(response).await;
+        return Err(PostError::Api(format!("Micro.Blog API error: {}", error_text)));
+    }
+    let link = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(str::to_string);
+    Ok(PostDetails { link, ..Default::default() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::config::MastodonCompatibility;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_bluesky_truncation() {
+        // Test that long text gets truncated to 300 characters
+        let long_text = "a".repeat(400);
+        let truncated = if long_text.chars().count() > BLUESKY_CHARACTER_LIMIT {
+            long_text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
+        } else {
+            long_text.to_string()
+        };
+        
+        assert_eq!(truncated.chars().count(), BLUESKY_CHARACTER_LIMIT);
+        assert_eq!(truncated, "a".repeat(300));
+    }
+
+    #[test]
+    fn test_bluesky_no_truncation_needed() {
+        // Test that short text doesn't get truncated
+        let short_text = "Hello, world!";
+        let result = if short_text.chars().count() > BLUESKY_CHARACTER_LIMIT {
+            short_text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
+        } else {
+            short_text.to_string()
+        };
+        
+        assert_eq!(result, short_text);
+    }
+
+    #[test]
+    fn test_unicode_truncation() {
+        // Test that Unicode characters are handled properly
+        let unicode_text = "🚀".repeat(400); // Each emoji is multiple bytes but 1 character
+        let truncated = if unicode_text.chars().count() > BLUESKY_CHARACTER_LIMIT {
+            unicode_text.chars().take(BLUESKY_CHARACTER_LIMIT).collect::<String>()
+        } else {
+            unicode_text.to_string()
+        };
+        
+        assert_eq!(truncated.chars().count(), BLUESKY_CHARACTER_LIMIT);
+        assert_eq!(truncated, "🚀".repeat(300));
+    }
+
+    #[test]
+    fn test_thread_short_text_unsplit() {
+        assert_eq!(split_into_thread("  Hello, world!  ", 300), vec!["Hello, world!"]);
+    }
+
+    #[test]
+    fn test_thread_splits_on_word_boundaries() {
+        let text = "the quick brown fox jumps over the lazy dog ".repeat(20);
+        let chunks = split_into_thread(&text, 100);
+        assert!(chunks.len() > 1);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert!(chunk.chars().count() <= 100, "chunk too long: {}", chunk);
+            assert!(chunk.ends_with(&format!(" ({}/{})", i + 1, chunks.len())));
+        }
+
+        // Removing the numbering gives back every word intact and in order
+        let rejoined: Vec<&str> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.rsplit_once(" (").unwrap().0.split_whitespace())
+            .collect();
+        let original: Vec<&str> = text.split_whitespace().collect();
+        assert_eq!(rejoined, original);
+    }
+
+    #[test]
+    fn test_thread_prefers_paragraph_and_sentence_breaks() {
+        let text = format!("{}\n\nSecond one. {}", "First paragraph.", "word ".repeat(30).trim());
+        let chunks = split_into_thread(&text, 60);
+        assert_eq!(chunks[0], format!("First paragraph. (1/{})", chunks.len()));
+        assert!(chunks[1].starts_with("Second one."));
+    }
+
+    #[test]
+    fn test_thread_counts_emoji_as_characters() {
+        // Each emoji is four bytes but one character toward the limit
+        let text = "🚀🚀🚀 ".repeat(40);
+        let chunks = split_into_thread(&text, 50);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 50);
+            let body = chunk.rsplit_once(" (").unwrap().0;
+            assert!(body.split_whitespace().all(|word| word == "🚀🚀🚀"));
+        }
+
+        // A single word longer than a chunk is cut between characters
+        let long_word = "🎉".repeat(120);
+        let chunks = split_into_thread(&long_word, 50);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 50));
+        let emoji: usize = chunks.iter().map(|chunk| chunk.matches('🎉').count()).sum();
+        assert_eq!(emoji, 120);
+    }
+
+    #[test]
+    fn test_facet_offsets_after_emoji() {
+        let text = "🚀 check https://example.com/page";
+        let facets = detect_facets(text);
+
+        assert_eq!(facets.len(), 1);
+        // The rocket is 4 bytes, so the link starts at byte 11 rather than char 8
+        assert_eq!(facets[0].byte_start, 11);
+        assert_eq!(facets[0].byte_end, text.len());
+        assert_eq!(&text[facets[0].byte_start..facets[0].byte_end], "https://example.com/page");
+        assert_eq!(facets[0].kind, FacetKind::Link("https://example.com/page".to_string()));
+    }
+
+    #[test]
+    fn test_facet_mentions_and_tags() {
+        let text = "Hej @alice.bsky.social, läs #rust! #2024 @nodot";
+        let facets = detect_facets(text);
+
+        assert_eq!(facets.len(), 2);
+        assert_eq!(facets[0].kind, FacetKind::Mention("alice.bsky.social".to_string()));
+        assert_eq!(&text[facets[0].byte_start..facets[0].byte_end], "@alice.bsky.social");
+        assert_eq!(facets[1].kind, FacetKind::Tag("rust".to_string()));
+        assert_eq!(&text[facets[1].byte_start..facets[1].byte_end], "#rust");
+    }
+
+    #[test]
+    fn test_bluesky_display_text() {
+        assert_eq!(bluesky_link_display("https://example.com/"), "example.com");
+        assert_eq!(bluesky_link_display("https://example.com/about"), "example.com/about");
+        assert_eq!(bluesky_link_display("https://example.com/blog/2024/06/a-long-post?ref=feed"), "example.com/blog/2024/06...");
+
+        let url = "https://example.com/blog/2024/06/a-post-with-a-long-and-descriptive-slug";
+        let text = format!("Read this, @alice.bsky.social: {}!", url);
+        let (display, spans) = bluesky_display_text(&text);
+        assert_eq!(display, "Read this, @alice.bsky.social: example.com/blog/2024/06...!");
+        assert_eq!(&display[spans[0].byte_start..spans[0].byte_end], "@alice.bsky.social");
+        assert_eq!(&display[spans[1].byte_start..spans[1].byte_end], "example.com/blog/2024/06...");
+        assert_eq!(spans[1].kind, FacetKind::Link(url.to_string()));
+
+        // Counted as shown, so a long link doesn't push the post over the limit
+        let text = format!("{} {}", "a".repeat(270), url);
+        assert!(grapheme_count(&text) > BLUESKY_CHARACTER_LIMIT);
+        assert_eq!(post_length(Platform::Bluesky, &text), 270 + 1 + "example.com/blog/2024/06...".len());
+        assert_eq!(split_bluesky_thread(&text).len(), 1);
+    }
+
+    #[test]
+    fn test_extract_open_graph_metadata() {
+        let html = r#"<html><head>
+            <title>Fallback</title>
+            <meta property="og:title" content="Tom &amp; Jerry">
+            <meta content='A classic cartoon' property='og:description' />
+            <meta property="og:image" content="/img/card.png">
+        </head></html>"#;
+        let metadata = extract_link_metadata(html);
+
+        assert_eq!(metadata.title, "Tom & Jerry");
+        assert_eq!(metadata.description, "A classic cartoon");
+        assert_eq!(metadata.image.as_deref(), Some("/img/card.png"));
+    }
+
+    #[test]
+    fn test_extract_title_fallback() {
+        let html = "<html><head><TITLE> Plain page </TITLE></head><body></body></html>";
+        let metadata = extract_link_metadata(html);
+
+        assert_eq!(metadata.title, "Plain page");
+        assert!(metadata.description.is_empty());
+        assert_eq!(metadata.image, None);
+    }
+
+    #[test]
+    fn test_parse_nostr_key_formats() {
+        let keys = Keys::generate();
+        let hex = keys.secret_key().to_secret_hex();
+        let nsec = keys.secret_key().to_bech32().unwrap();
+        let npub = keys.public_key().to_bech32().unwrap();
+
+        assert_eq!(parse_nostr_secret_key(&hex).unwrap(), *keys.secret_key());
+        assert_eq!(parse_nostr_secret_key(&nsec).unwrap(), *keys.secret_key());
+        assert_eq!(nostr_npub(&nsec), Some(npub.clone()));
+        assert!(parse_nostr_secret_key(&npub).is_err());
+        assert!(parse_nostr_secret_key("not-a-key").is_err());
+    }
+
+    #[test]
+    fn test_parse_nip96_response() {
+        let response = json!({
+            "status": "success",
+            "nip94_event": {
+                "tags": [
+                    ["url", "https://image.nostr.build/abc.jpg"],
+                    ["m", "image/jpeg"],
+                    ["dim", "800x600"],
+                    ["size", "12345"]
+                ]
+            }
+        });
+        let media = parse_nip96_response(&response).unwrap();
+
+        assert_eq!(media.url, "https://image.nostr.build/abc.jpg");
+        assert_eq!(
+            media.imeta_values(),
+            vec!["url https://image.nostr.build/abc.jpg", "m image/jpeg", "dim 800x600"]
+        );
+
+        let error = json!({ "status": "error", "message": "file too large" });
+        assert_eq!(parse_nip96_response(&error), Err("file too large".to_string()));
+    }
+
+    #[test]
+    fn test_blossom_upload() {
+        let keys = Keys::generate();
+        let hash = "b1674191a88ec5cdd733e4240a81803105dc412d6c6708d53ab94fc248f4f553";
+        let header = blossom_auth_header(&keys, hash, "photo.jpg", Timestamp::from(1_700_000_000)).unwrap();
+        let json = base64::engine::general_purpose::STANDARD.decode(header.strip_prefix("Nostr ").unwrap()).unwrap();
+        let event = Event::from_json(json).unwrap();
+        assert!(event.verify().is_ok());
+        assert_eq!(event.kind, Kind::Custom(BLOSSOM_AUTH_KIND));
+        let tags: Vec<Vec<String>> = event.tags.iter().map(|tag| tag.clone().to_vec()).collect();
+        assert_eq!(tags, vec![vec!["t", "upload"], vec!["x", hash], vec!["expiration", "1700000300"]]);
+
+        let descriptor = json!({ "url": "https://blossom.example/b167.jpg", "sha256": hash, "size": 184292, "type": "image/jpeg", "uploaded": 1700000000 });
+        let media = parse_blossom_descriptor(&descriptor, Some((800, 600))).unwrap();
+        assert_eq!(
+            media.imeta_values(),
+            vec!["url https://blossom.example/b167.jpg", "m image/jpeg", format!("x {}", hash).as_str(), "dim 800x600"]
+        );
+        assert!(parse_blossom_descriptor(&json!({ "message": "unauthorized" }), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_mastodon_character_limit() {
+        let mastodon = json!({ "configuration": { "statuses": { "max_characters": 1000 } } });
+        assert_eq!(parse_mastodon_character_limit(&mastodon), Some(1000));
+
+        let pleroma = json!({ "max_toot_chars": 5000 });
+        assert_eq!(parse_mastodon_character_limit(&pleroma), Some(5000));
+
+        assert_eq!(parse_mastodon_character_limit(&json!({})), None);
+    }
+
+    #[test]
+    fn test_parse_mastodon_instance() {
+        let mastodon = json!({
+            "uri": "mastodon.social",
+            "title": "Mastodon ",
+            "version": "4.3.2",
+            "configuration": {
+                "statuses": { "max_characters": 500, "max_media_attachments": 4 },
+                "polls": { "max_options": 4 },
+            },
+        });
+        assert_eq!(
+            parse_mastodon_instance(&mastodon),
+            Some(MastodonInstance {
+                title: "Mastodon".to_string(),
+                version: "4.3.2".to_string(),
+                max_characters: Some(500),
+                max_media_attachments: Some(4),
+                max_poll_options: Some(4),
+                max_upload_size: None,
+                pleroma: false,
+            })
+        );
+
+        let pleroma = json!({ "uri": "https://pleroma.example", "title": "Pleroma", "version": "2.7.2 (compatible; Pleroma 2.6.0)", "max_toot_chars": 5000, "poll_limits": { "max_options": 20 }, "upload_limit": 16000000 });
+        let pleroma = parse_mastodon_instance(&pleroma).unwrap();
+        assert_eq!((pleroma.max_characters, pleroma.max_media_attachments, pleroma.max_poll_options), (Some(5000), None, Some(20)));
+        assert_eq!(pleroma.max_upload_size, Some(16000000));
+        assert!(pleroma.pleroma);
+        let akkoma = json!({ "uri": "akkoma.example", "version": "2.7.2 (compatible; Akkoma 3.13.2)" });
+        assert!(parse_mastodon_instance(&akkoma).unwrap().pleroma);
+
+        // A profile page or another site's JSON isn't an instance
+        assert_eq!(parse_mastodon_instance(&json!({ "title": "My blog" })), None);
+    }
+
+    #[tokio::test]
+    async fn test_mastodon_scheduled_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("scheduled_at=2030-01-02T03%3A04%3A00Z"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "1",
+                "scheduled_at": "2030-01-02T03:05:00.000Z",
+                "params": { "text": "Later" }
+            })))
+            .mount(&server)
+            .await;
+
+        let config = MastodonConfig {
+            enabled: true,
+            instance_url: server.uri(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let requested = Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 0).unwrap();
+        let options = MastodonOptions { scheduled_at: Some(requested), ..Default::default() };
+        let client = reqwest::Client::new();
+        let details = post_to_mastodon(&client, RetryPolicy::ONCE, &config, "Later", &[], &options, None, &UploadProgress::default(), None).await.unwrap();
+        assert_eq!(details.scheduled_at, Some(Utc.with_ymd_and_hms(2030, 1, 2, 3, 5, 0).unwrap()));
+
+        let chunks = vec!["1/2".to_string(), "2/2".to_string()];
+        assert!(post_mastodon_thread(&client, RetryPolicy::ONCE, &config, &chunks, &[], &options, None, &UploadProgress::default(), None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mastodon_fails_on_turned_down_image() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/media"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(json!({ "error": "File type not supported" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/statuses"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1" })))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let image_path = std::env::temp_dir().join(format!("yall-cosmic-test-{}.png", uuid::Uuid::new_v4()));
+        std::fs::write(&image_path, b"not really a png").unwrap();
+        let images = [ImageAttachment::new(image_path.to_string_lossy().to_string())];
+        let config = MastodonConfig {
+            enabled: true,
+            instance_url: server.uri(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let result = post_to_mastodon(&reqwest::Client::new(), RetryPolicy::ONCE, &config, "Look", &images, &MastodonOptions::default(), None, &UploadProgress::default(), None).await;
+        std::fs::remove_file(&image_path).unwrap();
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("File type not supported"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_mastodon_markdown_on_pleroma() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/statuses"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1" })))
+            .mount(&server)
+            .await;
+
+        let mut config = MastodonConfig {
+            enabled: true,
+            instance_url: server.uri(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let options = MastodonOptions { markdown: true, ..Default::default() };
+        let client = reqwest::Client::new();
+        let progress = UploadProgress::default();
+        post_to_mastodon(&client, RetryPolicy::ONCE, &config, "**Hi**", &[], &options, None, &progress, None).await.unwrap();
+        config.pleroma_detected = true;
+        post_to_mastodon(&client, RetryPolicy::ONCE, &config, "**Hi**", &[], &options, None, &progress, None).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let bodies: Vec<String> = requests.iter().map(|request| String::from_utf8_lossy(&request.body).to_string()).collect();
+        assert!(!bodies[0].contains("content_type"));
+        assert!(bodies[1].contains("content_type=text%2Fmarkdown"));
+    }
+
+    #[tokio::test]
+    async fn test_mastodon_visibility() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/statuses"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1" })))
+            .mount(&server)
+            .await;
+
+        let config = MastodonConfig {
+            enabled: true,
+            instance_url: server.uri(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::new();
+        let progress = UploadProgress::default();
+        let chunks = ["One".to_string(), "Two".to_string()];
+        let options = MastodonOptions { visibility: MastodonVisibility::FollowersOnly, ..Default::default() };
+        post_mastodon_thread(&client, RetryPolicy::ONCE, &config, &chunks, &[], &options, None, &progress, None).await.unwrap();
+        // The account's own default is left to the server
+        post_to_mastodon(&client, RetryPolicy::ONCE, &config, "Three", &[], &MastodonOptions::default(), None, &progress, None).await.unwrap();
+
+        // Every status in the thread gets the visibility, not just the first
+        let requests = server.received_requests().await.unwrap();
+        let bodies: Vec<String> = requests.iter().map(|request| String::from_utf8_lossy(&request.body).to_string()).collect();
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies[0].contains("visibility=private"));
+        assert!(bodies[1].contains("visibility=private"));
+        assert!(!bodies[2].contains("visibility="));
+    }
+
+    #[tokio::test]
+    async fn test_mastodon_reply_and_quote() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "statuses": [{ "id": "555" }] })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("in_reply_to_id=42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "43" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("quoted_status_id=555"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "44", "quote": null })))
+            .mount(&server)
+            .await;
+
+        let config = MastodonConfig {
+            enabled: true,
+            instance_url: server.uri(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::new();
+        let options = MastodonOptions::default();
+        let progress = UploadProgress::default();
+
+        let reply = PostReference { url: format!("{}/@alice/42", server.uri()), quote: false };
+        let details = post_to_mastodon(&client, RetryPolicy::ONCE, &config, "Agreed", &[], &options, None, &progress, Some(&reply)).await.unwrap();
+        assert!(details.warnings.is_empty());
+
+        // Statuses on other servers are found through search
+        let quote = PostReference { url: "https://other.example/@bob/9001".to_string(), quote: true };
+        let details = post_to_mastodon(&client, RetryPolicy::ONCE, &config, "Look", &[], &options, None, &progress, Some(&quote)).await.unwrap();
+        assert_eq!(details.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_mastodon_status_url() {
+        let instance = "https://mastodon.social";
+        assert_eq!(parse_mastodon_status_url("https://mastodon.social/@alice/112233", instance).as_deref(), Some("112233"));
+        assert_eq!(parse_mastodon_status_url("https://Mastodon.Social/@bob@example.com/445566/", instance).as_deref(), Some("445566"));
+        assert_eq!(parse_mastodon_status_url("https://mastodon.social/users/alice/statuses/112233", instance).as_deref(), Some("112233"));
+        assert_eq!(parse_mastodon_status_url("https://mastodon.social/notice/AbC123", instance).as_deref(), Some("AbC123"));
+        assert_eq!(parse_mastodon_status_url("https://fosstodon.org/@alice/112233", instance), None);
+        assert_eq!(parse_mastodon_status_url("https://mastodon.social/@alice", instance), None);
+        assert_eq!(parse_mastodon_status_url("https://mastodon.social/@alice/with_replies", instance), None);
+    }
+
+    #[test]
+    fn test_parse_bluesky_post_reference() {
+        assert_eq!(
+            parse_bluesky_post_url("https://bsky.app/profile/alice.bsky.social/post/3kabc"),
+            Some(("alice.bsky.social".to_string(), "3kabc".to_string()))
+        );
+        assert_eq!(
+            parse_bluesky_post_url("at://did:plc:abc/app.bsky.feed.post/3kxyz"),
+            Some(("did:plc:abc".to_string(), "3kxyz".to_string()))
+        );
+        assert_eq!(parse_bluesky_post_url("at://did:plc:abc/app.bsky.actor.profile/self"), None);
+        assert_eq!(parse_bluesky_post_url("https://bsky.app/profile/alice.bsky.social"), None);
+        assert_eq!(
+            bluesky_post_link("at://did:plc:abc/app.bsky.feed.post/3kxyz").as_deref(),
+            Some("https://bsky.app/profile/did:plc:abc/post/3kxyz")
+        );
+
+        let top = json!({ "uri": "at://did:plc:abc/app.bsky.feed.post/1", "cid": "cid1", "record": { "text": "Hi" } });
+        let target = parse_bluesky_target(&top).unwrap();
+        assert_eq!(target.root, target.post);
+        let reply = json!({
+            "uri": "at://did:plc:abc/app.bsky.feed.post/2",
+            "cid": "cid2",
+            "record": { "reply": { "root": { "uri": "at://did:plc:abc/app.bsky.feed.post/1", "cid": "cid1" } } }
+        });
+        let target = parse_bluesky_target(&reply).unwrap();
+        assert_eq!(target.post["cid"], "cid2");
+        assert_eq!(target.root["cid"], "cid1");
+        assert_eq!(parse_bluesky_target(&json!({})), None);
+    }
+
+    #[test]
+    fn test_parse_nostr_event_reference() {
+        let keys = Keys::generate();
+        let root = EventBuilder::text_note("Root").sign_with_keys(&keys).unwrap();
+        let note = root.id.to_bech32().unwrap();
+        assert_eq!(parse_nostr_event_reference(&note).unwrap().id, root.id);
+        assert_eq!(parse_nostr_event_reference(&format!("nostr:{}", note)).unwrap().id, root.id);
+        assert_eq!(parse_nostr_event_reference(&format!("https://primal.net/e/{}/", note)).unwrap().id, root.id);
+        assert_eq!(parse_nostr_event_reference(&root.id.to_hex()).unwrap().id, root.id);
+        assert_eq!(parse_nostr_event_reference(&keys.public_key().to_bech32().unwrap()), None);
+        assert_eq!(parse_nostr_event_reference("https://example.com/"), None);
+
+        let relays = vec![
+            ("wss://relay.damus.io".to_string(), Ok(())),
+            ("wss://nos.lol".to_string(), Err("blocked".to_string())),
+        ];
+        let link = nostr_event_link(&root, &relays).unwrap();
+        let target = parse_nostr_event_reference(&link).unwrap();
+        assert!(link.starts_with("https://njump.me/nevent1"));
+        assert_eq!(target.id, root.id);
+        assert_eq!(target.author, Some(keys.public_key()));
+        assert!(target.relay.unwrap().starts_with("wss://relay.damus.io"));
+
+        assert_eq!(nostr_thread_root(&root), None);
+        let reply = EventBuilder::text_note("Reply")
+            .tags([
+                Tag::custom(TagKind::custom("e"), [root.id.to_hex(), String::new(), "root".to_string()]),
+                Tag::public_key(keys.public_key()),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(nostr_thread_root(&reply), Some(root.id));
+        let positional = EventBuilder::text_note("Old reply")
+            .tags([Tag::custom(TagKind::custom("e"), [root.id.to_hex()])])
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(nostr_thread_root(&positional), Some(root.id));
+    }
+
+    #[test]
+    fn test_parse_account_info() {
+        let bluesky = json!({ "handle": "alice.bsky.social", "displayName": "Alice", "avatar": "https://cdn.bsky.app/a.jpg" });
+        assert_eq!(
+            parse_bluesky_profile(&bluesky),
+            Some(AccountInfo {
+                handle: "@alice.bsky.social".to_string(),
+                display_name: "Alice".to_string(),
+                avatar_url: Some("https://cdn.bsky.app/a.jpg".to_string()),
+            })
+        );
+
+        let local = json!({ "acct": "alice", "display_name": "", "avatar": "https://m.example/a.gif", "avatar_static": "https://m.example/a.png" });
+        let info = parse_mastodon_account(&local, "https://mastodon.example").unwrap();
+        assert_eq!(info.handle, "@alice@mastodon.example");
+        assert_eq!(info.avatar_url.as_deref(), Some("https://m.example/a.png"));
+        let remote = json!({ "acct": "alice@elsewhere.example" });
+        assert_eq!(parse_mastodon_account(&remote, "https://mastodon.example").unwrap().handle, "@alice@elsewhere.example");
+
+        let microblog = json!({ "username": "alice", "name": "Alice" });
+        assert_eq!(parse_microblog_account(&microblog).unwrap().handle, "@alice");
+        assert!(parse_microblog_account(&json!({ "error": "Invalid token" })).is_none());
+    }
+
+    #[test]
+    fn test_shrink_image_to_fit() {
+        // Noise compresses badly, so this PNG is well over the target size
+        let mut seed: u32 = 1;
+        let noise = image::RgbImage::from_fn(800, 400, |_, _| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            image::Rgb([(seed >> 24) as u8, (seed >> 16) as u8, (seed >> 8) as u8])
+        });
+        let mut png = std::io::Cursor::new(Vec::new());
+        noise.write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+        assert!(png.len() > 100_000);
+
+        let shrunk = shrink_image_to_fit(&png, 100_000).unwrap();
+        assert!(shrunk.len() <= 100_000);
+        let decoded = image::load_from_memory(&shrunk).unwrap();
+        assert!(decoded.width() <= 800);
+        let aspect = decoded.width() as f32 / decoded.height() as f32;
+        assert!((aspect - 2.0).abs() < 0.05);
+    }
+
+    /// A small JPEG carrying an EXIF block whose GPS IFD holds `GPSVersionID`.
+    fn jpeg_with_gps_exif() -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        image::RgbImage::from_pixel(16, 8, image::Rgb([200, 100, 50]))
+            .write_with_encoder(JpegEncoder::new(&mut jpeg))
+            .unwrap();
+
+        // Little-endian TIFF: IFD0 points at a GPS IFD right after it
+        let mut tiff = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        tiff.extend_from_slice(&[1, 0, 0x25, 0x88, 4, 0, 1, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0]);
+        tiff.extend_from_slice(&[1, 0, 0, 0, 1, 0, 4, 0, 0, 0, 2, 3, 0, 0, 0, 0, 0, 0]);
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        app1.extend_from_slice(b"Exif\0\0");
+        app1.extend_from_slice(&tiff);
+        jpeg.splice(2..2, app1);
+        jpeg
+    }
+
+    #[test]
+    fn test_strip_jpeg_gps_metadata() {
+        let jpeg = jpeg_with_gps_exif();
+        let exif = exif::Reader::new().read_from_container(&mut std::io::Cursor::new(&jpeg)).unwrap();
+        assert!(exif.get_field(exif::Tag::GPSVersionID, exif::In::PRIMARY).is_some());
+
+        let stripped = strip_image_metadata(&jpeg).unwrap().unwrap();
+        assert!(exif::Reader::new().read_from_container(&mut std::io::Cursor::new(&stripped)).is_err());
+        assert!(!stripped.windows(6).any(|window| window == b"Exif\0\0"));
+        let decoded = image::load_from_memory(&stripped).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (16, 8));
+    }
+
+    #[test]
+    fn test_strip_png_metadata() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 0])).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let mut png = png.into_inner();
+        // A tEXt chunk ahead of IEND; the CRC isn't checked when it's dropped
+        let text = b"\x00\x00\x00\x0ctEXtAuthor\0Alice\x00\x00\x00\x00";
+        let iend = png.len() - 12;
+        png.splice(iend..iend, text.iter().copied());
+
+        let stripped = strip_image_metadata(&png).unwrap().unwrap();
+        assert_eq!(stripped.len(), png.len() - text.len());
+        assert!(!stripped.windows(4).any(|window| window == b"tEXt"));
+        assert!(image::load_from_memory(&stripped).is_ok());
+        assert_eq!(strip_image_metadata(b"GIF89a"), Ok(None));
+
+        // Cut off partway through a chunk: not sent at all rather than sent with its metadata
+        assert!(strip_image_metadata(&png[..png.len() - 20]).is_err());
+    }
+
+    #[test]
+    fn test_strip_webp_metadata() {
+        let chunk = |kind: &[u8], data: &[u8]| {
+            let mut chunk = kind.to_vec();
+            chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            chunk.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                chunk.push(0);
+            }
+            chunk
+        };
+        let vp8x = chunk(b"VP8X", &[0x08 | 0x04 | 0x10, 0, 0, 0, 3, 0, 0, 3, 0, 0]);
+        let image = chunk(b"VP8L", b"pixels");
+        let body = [vp8x.clone(), image.clone(), chunk(b"EXIF", b"GPS 51.5N"), chunk(b"XMP ", b"<x:xmpmeta/>")].concat();
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        webp.extend_from_slice(b"WEBP");
+        webp.extend_from_slice(&body);
+
+        let stripped = strip_image_metadata(&webp).unwrap().unwrap();
+        assert_eq!(stripped.len(), 12 + vp8x.len() + image.len());
+        assert_eq!(u32::from_le_bytes(stripped[4..8].try_into().unwrap()) as usize, stripped.len() - 8);
+        assert_eq!(stripped[20], 0x10);
+        assert!(!stripped.windows(3).any(|window| window == b"GPS"));
+        assert_eq!(&stripped[12 + vp8x.len()..], image.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_retry_backs_off_when_unavailable() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let retry = RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(50) };
+        let started = std::time::Instant::now();
+        let response = send_with_retry(retry, || client.post(server.uri())).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        // 50ms before the second attempt, 100ms before the third
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_honours_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let retry = RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(1) };
+        let started = std::time::Instant::now();
+        let response = send_with_retry(retry, || client.post(server.uri())).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let retry = RetryPolicy { max_attempts: 2, base_delay: Duration::from_millis(1) };
+        let response = send_with_retry(retry, || client.post(server.uri())).await.unwrap();
+
+        assert_eq!(response.status(), 503);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_reports_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let error = send_with_retry(RetryPolicy::ONCE, || client.post(server.uri())).await.unwrap_err();
+
+        assert!(matches!(error, PostError::RateLimited { retry_after: Some(wait) } if wait == Duration::from_secs(30)));
+        assert_eq!(error.to_string(), "Rate limited, try again in 30 seconds");
+        assert_eq!(PostError::RateLimited { retry_after: Some(Duration::from_millis(150_500)) }.to_string(), "Rate limited, try again in 3 minutes");
+        assert_eq!(PostError::RateLimited { retry_after: None }.to_string(), "Rate limited, try again later");
+    }
+
+    #[tokio::test]
+    async fn test_retry_leaves_timed_out_post_alone() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let client = build_http_client(Duration::from_millis(50));
+        let retry = RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(1) };
+        // The first one may have been made, so it isn't sent again
+        assert!(send_with_retry(retry, || client.post(server.uri())).await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // Unless the server can tell it's the same one
+        assert!(send_with_retry(retry, || client.post(server.uri()).header("Idempotency-Key", "3f1c")).await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_network_error_hides_url() {
+        // Nothing listens on port 1, so the request fails before any response
+        let client = reqwest::Client::new();
+        let error = client.post("http://127.0.0.1:1/bot123456:SECRET-TOKEN/sendMessage").send().await.unwrap_err();
+        let error = PostError::from(error);
+        assert!(matches!(error, PostError::Network(_)));
+        assert!(!error.to_string().contains("SECRET-TOKEN"));
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_response_is_not_offline() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>"))
+            .mount(&server)
+            .await;
+
+        // The post was made, only the answer is garbled: sending it again would duplicate it
+        let client = reqwest::Client::new();
+        let response = client.post(server.uri()).send().await.unwrap();
+        let error = PostError::from(response.json::<Value>().await.unwrap_err());
+        assert!(matches!(error, PostError::Response(_)));
+        let report = PostReport { results: vec![(Platform::Mastodon, Err(error))], ..Default::default() };
+        assert!(!report.only_network_failures());
+    }
+
+    #[test]
+    fn test_is_private_endpoint() {
+        assert!(is_private_endpoint("https://captions.example.com/describe"));
+        assert!(is_private_endpoint("http://localhost:8080/caption"));
+        assert!(is_private_endpoint("http://127.0.0.1:5000"));
+        assert!(is_private_endpoint("http://[::1]/caption"));
+        assert!(!is_private_endpoint("http://captions.example.com/describe"));
+        assert!(!is_private_endpoint("http://192.168.1.20/caption"));
+        assert!(!is_private_endpoint("ftp://localhost/caption"));
+        assert!(!is_private_endpoint("captions.example.com"));
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+        let now = Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 0).unwrap();
+        let headers = |name: &'static str, value: &'static str| HeaderMap::from_iter([(name.parse().unwrap(), HeaderValue::from_static(value))]);
+
+        assert_eq!(rate_limit_wait(&headers("retry-after", "120"), now), Some(Duration::from_secs(120)));
+        assert_eq!(rate_limit_wait(&headers("retry-after", "Wed, 02 Jan 2030 03:05:30 GMT"), now), Some(Duration::from_secs(90)));
+        // Mastodon
+        assert_eq!(rate_limit_wait(&headers("x-ratelimit-reset", "2030-01-02T03:09:00.000Z"), now), Some(Duration::from_secs(300)));
+        // Bluesky
+        let reset = (now.timestamp() + 45).to_string();
+        let bluesky = HeaderMap::from_iter([("ratelimit-reset".parse().unwrap(), HeaderValue::from_str(&reset).unwrap())]);
+        assert_eq!(rate_limit_wait(&bluesky, now), Some(Duration::from_secs(45)));
+        assert_eq!(rate_limit_wait(&headers("ratelimit-reset", "60"), now), Some(Duration::from_secs(60)));
+        // A reset that's already passed means trying again now
+        assert_eq!(rate_limit_wait(&headers("x-ratelimit-reset", "2030-01-02T03:00:00Z"), now), Some(Duration::ZERO));
+        assert_eq!(rate_limit_wait(&HeaderMap::new(), now), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_auth_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let response = send_with_retry(RetryPolicy::default(), || client.post(server.uri())).await.unwrap();
+
+        assert_eq!(response.status(), 401);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_discord_webhook_url() {
+        let url = "https://discord.com/api/webhooks/1234567890/abcDEF-token";
+        assert!(is_discord_webhook_url(url));
+        assert!(is_discord_webhook_url("https://discordapp.com/api/webhooks/1/token"));
+        assert!(!is_discord_webhook_url("https://discord.com/api/webhooks/1234567890"));
+        assert!(!is_discord_webhook_url("https://example.com/api/webhooks/1/token"));
+        assert!(!is_discord_webhook_url("http://discord.com/api/webhooks/1/token"));
+
+        // The label must never include the token
+        assert_eq!(discord_webhook_label(url), "webhook 1234567890");
+    }
+
+    #[tokio::test]
+    async fn test_discord_reports_failed_webhooks() {
+        let ok = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "1"})))
+            .mount(&ok)
+            .await;
+        let failing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({"message": "Unknown Webhook", "code": 10015})))
+            .mount(&failing)
+            .await;
+
+        let config = DiscordWebhookConfig {
+            enabled: true,
+            decrypted_webhook_urls: vec![format!("{}/api/webhooks/1/a", ok.uri()), format!("{}/api/webhooks/2/b", failing.uri())].into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::new();
+        let details = post_to_discord(&client, RetryPolicy::ONCE, &config, "Hello", &[]).await.unwrap();
+        assert_eq!(details.warnings.len(), 1);
+        assert!(details.warnings[0].contains("webhook 2"));
+
+        // When every webhook fails, so does the post
+        let config = DiscordWebhookConfig {
+            decrypted_webhook_urls: vec![format!("{}/api/webhooks/2/b", failing.uri())].into(),
+            ..config
+        };
+        let result = post_to_discord(&client, RetryPolicy::ONCE, &config, "Hello", &[]).await;
+        assert!(matches!(result, Err(PostError::Auth(_))));
+    }
+
+    #[test]
+    fn test_farcaster_cast() {
+        assert_eq!(post_length(Platform::Farcaster, "café"), 5);
+        let body = farcaster_cast_body("signer", "Hello", &[], None);
+        assert_eq!(body, json!({ "signer_uuid": "signer", "text": "Hello" }));
+        let body = farcaster_cast_body("signer", "Look", &["https://example.com/a.png".to_string()], None);
+        assert_eq!(body["embeds"], json!([{ "url": "https://example.com/a.png" }]));
+        // A resent cast carries the same idem
+        let idem = farcaster_cast_body("signer", "Hello", &[], Some("3f1c"))["idem"].clone();
+        assert_eq!(idem.as_str().map(str::len), Some(16));
+        assert_eq!(farcaster_cast_body("signer", "Hello", &[], Some("3f1c"))["idem"], idem);
+        assert_ne!(farcaster_cast_body("signer", "Hello", &[], Some("9a02"))["idem"], idem);
+
+        let created = json!({
+            "success": true,
+            "cast": { "hash": "0x71d5225f77e0164388b1d4c120825f3a2c1f131c", "author": { "username": "alice" } }
+        });
+        assert_eq!(farcaster_cast_link(&created).as_deref(), Some("https://warpcast.com/alice/0x71d5225f"));
+        assert_eq!(farcaster_cast_link(&json!({ "success": true })), None);
+    }
+
+    #[test]
+    fn test_threads_image_fields() {
+        let image = NostrImage {
+            url: "https://media.example.com/a.png".to_string(),
+            alt: Some("A cat".to_string()),
+            imeta: Tag::custom(TagKind::custom("imeta"), Vec::<String>::new()),
+        };
+        let fields = threads_image_fields(&image, false);
+        assert_eq!(
+            fields,
+            vec![
+                ("media_type", "IMAGE".to_string()),
+                ("image_url", "https://media.example.com/a.png".to_string()),
+                ("alt_text", "A cat".to_string()),
+            ]
+        );
+        assert!(threads_image_fields(&image, true).contains(&("is_carousel_item", "true".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_devto_article() {
+        let tags = ["#Rust".to_string(), "open-source".to_string(), "rust".to_string(), " ".to_string(), "a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(devto_tags(&tags), vec!["rust", "opensource", "a", "b"]);
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/articles"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "id": 1, "url": "https://dev.to/alice/hello-1a2b" })))
+            .mount(&server)
+            .await;
+
+        let config = DevToConfig {
+            enabled: true,
+            site_url: server.uri(),
+            draft: true,
+            decrypted_api_key: "key".to_string().into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::new();
+        let options = DevToOptions { title: Some(" Hello ".to_string()), tags: vec!["rust".to_string()] };
+        let details = post_to_devto(&client, RetryPolicy::ONCE, &config, "# Hi\n\n**there**", &[], &options).await.unwrap();
+        assert_eq!(details.link.as_deref(), Some("https://dev.to/alice/hello-1a2b"));
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].headers.get("api-key").unwrap(), "key");
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body,
+            json!({ "article": { "title": "Hello", "body_markdown": "# Hi\n\n**there**", "published": false, "tags": ["rust"] } })
+        );
+
+        // Nothing is sent without a title
+        let untitled = DevToOptions::default();
+        assert!(post_to_devto(&client, RetryPolicy::ONCE, &config, "Hi", &[], &untitled).await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bluesky_long_post_becomes_thread() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.server.createSession"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "accessJwt": "access", "refreshJwt": "refresh" })))
+            .mount(&server)
+            .await;
+        // Each post gets its own record back, which the next one replies to
+        for i in 1..=3 {
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.repo.createRecord"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "uri": format!("at://did:plc:alice/app.bsky.feed.post/{}", i),
+                    "cid": format!("cid{}", i)
+                })))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let config = BlueskyConfig {
+            enabled: true,
+            handle: "alice.test".to_string(),
+            service_url: server.uri(),
+            decrypted_password: "password".to_string().into(),
+            ..Default::default()
+        };
+        let text = "word ".repeat(150);
+        let details = post_to_bluesky(&reqwest::Client::new(), RetryPolicy::ONCE, &config, &text, &[], None, &UploadProgress::default(), None).await.unwrap();
+        assert_eq!(details.link.as_deref(), Some("https://bsky.app/profile/did:plc:alice/post/1"));
+
+        let records: Vec<Value> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|request| request.url.path() == "/xrpc/com.atproto.repo.createRecord")
+            .map(|request| serde_json::from_slice::<Value>(&request.body).unwrap()["record"].clone())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|record| grapheme_count(record["text"].as_str().unwrap()) <= BLUESKY_CHARACTER_LIMIT));
+        assert!(records[0]["reply"].is_null());
+        let root = json!({ "uri": "at://did:plc:alice/app.bsky.feed.post/1", "cid": "cid1" });
+        assert_eq!(records[1]["reply"], json!({ "root": root, "parent": root }));
+        assert_eq!(records[2]["reply"]["root"], root);
+        assert_eq!(records[2]["reply"]["parent"], json!({ "uri": "at://did:plc:alice/app.bsky.feed.post/2", "cid": "cid2" }));
+    }
+
+    #[tokio::test]
+    async fn test_bluesky_logs_in_again_when_turned_down() {
+        let server = MockServer::start().await;
+        for token in ["revoked", "fresh"] {
+            Mock::given(method("POST"))
+                .and(path("/xrpc/com.atproto.server.createSession"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "accessJwt": token, "refreshJwt": "refresh" })))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.repo.createRecord"))
+            .and(header("Authorization", "Bearer revoked"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.repo.createRecord"))
+            .and(header("Authorization", "Bearer fresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "uri": "at://did:plc:bob/app.bsky.feed.post/1", "cid": "cid1" })))
+            .mount(&server)
+            .await;
+        let config = BlueskyConfig {
+            enabled: true,
+            handle: "bob.test".to_string(),
+            service_url: server.uri(),
+            decrypted_password: "password".to_string().into(),
+            ..Default::default()
+        };
+        let details = post_to_bluesky(&reqwest::Client::new(), RetryPolicy::ONCE, &config, "Hello", &[], None, &UploadProgress::default(), None).await.unwrap();
+        assert_eq!(details.link.as_deref(), Some("https://bsky.app/profile/did:plc:bob/post/1"));
+
+        let logins = server.received_requests().await.unwrap().iter().filter(|request| request.url.path() == "/xrpc/com.atproto.server.createSession").count();
+        assert_eq!(logins, 2);
+        let cached = BLUESKY_SESSIONS.lock().unwrap().get(&bluesky_session_key(&server.uri(), &config)).map(|session| session.access_jwt.clone());
+        assert_eq!(cached.as_deref(), Some("fresh"));
+    }
+
+    #[tokio::test]
+    async fn test_matrix_post() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$abc" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let config = MatrixConfig {
+            enabled: true,
+            homeserver_url: format!("{}/", server.uri()),
+            room_id: "!room:example.org".to_string(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let details = post_to_matrix(&reqwest::Client::new(), RetryPolicy::ONCE, &config, "Hello", &[]).await.unwrap();
+        assert_eq!(details.link.as_deref(), Some("https://matrix.to/#/!room:example.org/$abc"));
+
+        let requests = server.received_requests().await.unwrap();
+        let path = requests[0].url.path();
+        assert!(path.starts_with("/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/"), "{}", path);
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body, json!({ "msgtype": "m.text", "body": "Hello" }));
+
+        let content = matrix_image_content("mxc://example.org/xyz", "cat.png", Some("A cat"), "image/png", 42, Some((4, 3)));
+        assert_eq!(content["body"], "A cat");
+        assert_eq!(content["info"], json!({ "mimetype": "image/png", "size": 42, "w": 4, "h": 3 }));
+    }
+
+    #[test]
+    fn test_lemmy_post() {
+        assert_eq!(lemmy_title("\n  First line\nSecond line", None), "First line");
+        assert_eq!(lemmy_title("First line", Some("  Given  ")), "Given");
+        let long = "word ".repeat(60);
+        let title = lemmy_title(&long, None);
+        assert!(grapheme_count(&title) <= LEMMY_TITLE_LIMIT);
+        assert!(title.ends_with("word…"));
+
+        let text = "New release\n\nNotes at https://example.com/notes and https://example.com/other";
+        let post = lemmy_post_body(text, "New release", 7);
+        assert_eq!(post["name"], "New release");
+        assert_eq!(post["community_id"], 7);
+        assert_eq!(post["body"], text);
+        assert_eq!(post["url"], "https://example.com/notes");
+
+        let post = lemmy_post_body("Just a title", "Just a title", 7);
+        assert!(post.get("body").is_none());
+        assert!(post.get("url").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lemmy_logs_in_again_for_expired_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/user/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jwt": "fresh" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/community"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "community_view": { "community": { "id": 7 } } })))
+            .mount(&server)
+            .await;
+        // Lemmy v3 answers an expired token with a 400, not a 401
+        Mock::given(method("POST"))
+            .and(path("/api/v3/post"))
+            .and(header("Authorization", "Bearer stale"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "not_logged_in" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/post"))
+            .and(header("Authorization", "Bearer fresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "post_view": { "post": { "id": 1 } } })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = LemmyConfig {
+            enabled: true,
+            instance_url: server.uri(),
+            username: "alice".to_string(),
+            community: "rust".to_string(),
+            decrypted_password: "password".to_string().into(),
+            ..Default::default()
+        };
+        let key = format!("{}/api/v3|alice", server.uri());
+        LEMMY_SESSIONS.lock().unwrap().insert(key.clone(), "stale".to_string());
+        post_to_lemmy(&reqwest::Client::new(), RetryPolicy::ONCE, &config, "Hello", &[], &LemmyOptions::default()).await.unwrap();
+        assert_eq!(LEMMY_SESSIONS.lock().unwrap().get(&key).map(String::as_str), Some("fresh"));
+    }
+
+    #[tokio::test]
+    async fn test_wordpress_post_link() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/wp-json/wp/v2/posts"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "id": 5, "link": "https://blog.example.com/2030/01/hello/" })))
+            .mount(&server)
+            .await;
+        let config = WordPressConfig {
+            enabled: true,
+            site_url: server.uri(),
+            username: "alice".to_string(),
+            decrypted_app_password: "app password".to_string().into(),
+            ..Default::default()
+        };
+        let options = WordPressOptions { title: Some("Hello".to_string()) };
+        let details = post_to_wordpress(&reqwest::Client::new(), RetryPolicy::ONCE, &config, "Hello", &[], &options).await.unwrap();
+        assert_eq!(details.link.as_deref(), Some("https://blog.example.com/2030/01/hello/"));
+        assert!(details.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_wordpress_content() {
+        let content = wordpress_content("Fish & chips\nfor tea\n\n<b>Yum</b>", &[("https://example.com/a.jpg".to_string(), "A \"plate\"".to_string())]);
+        assert_eq!(
+            content,
+            "<!-- wp:paragraph -->\n<p>Fish &amp; chips<br>for tea</p>\n<!-- /wp:paragraph -->\n\n\
+             <!-- wp:paragraph -->\n<p>&lt;b&gt;Yum&lt;/b&gt;</p>\n<!-- /wp:paragraph -->\n\n\
+             <!-- wp:image -->\n<figure class=\"wp-block-image\"><img src=\"https://example.com/a.jpg\" alt=\"A &quot;plate&quot;\"/></figure>\n<!-- /wp:image -->"
+        );
+    }
+
+    #[test]
+    fn test_plan_telegram_post() {
+        // Short text with an image becomes the caption
+        let plan = plan_telegram_post("Look at this", true);
+        assert_eq!(plan.caption.as_deref(), Some("Look at this"));
+        assert_eq!(plan.message, None);
+
+        // Too long for a caption: the photo goes out bare and the text follows as a message
+        let long_text = "a".repeat(TELEGRAM_CAPTION_LIMIT + 1);
+        let plan = plan_telegram_post(&long_text, true);
+        assert_eq!(plan.caption, None);
+        assert_eq!(plan.message.as_deref(), Some(long_text.as_str()));
+        assert!(!plan.truncated);
+
+        // Too long even for a message
+        let plan = plan_telegram_post(&"é".repeat(TELEGRAM_MESSAGE_LIMIT + 10), false);
+        assert_eq!(plan.message.unwrap().chars().count(), TELEGRAM_MESSAGE_LIMIT);
+        assert!(plan.truncated);
+
+        // An image with no text is sent without a caption
+        assert_eq!(plan_telegram_post("", true).caption, None);
+    }
+
+    #[tokio::test]
+    async fn test_upload_progress_counts_sent_bytes() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        let progress = UploadProgress::default();
+        assert_eq!(progress.bytes(), None);
+
+        let bytes = vec![7; UPLOAD_CHUNK_SIZE * 2 + 10];
+        let total = bytes.len() as u64;
+        progress.add_total(bytes.len());
+        let client = reqwest::Client::new();
+        client.post(server.uri()).header(reqwest::header::CONTENT_LENGTH, bytes.len()).body(progress.body(&bytes)).send().await.unwrap();
+        assert_eq!(progress.bytes(), Some((total, total)));
+
+        // Sending again, as a retry does, doesn't go past the total
+        client.post(server.uri()).header(reqwest::header::CONTENT_LENGTH, bytes.len()).body(progress.body(&bytes)).send().await.unwrap();
+        assert_eq!(progress.bytes(), Some((total, total)));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_sends_nothing() {
+        // Nothing listens on these hosts, so any request would fail the post
+        let mut config = Config::default();
+        config.mastodon.instance_url = "https://mastodon.invalid".to_string();
+        config.pixelfed.instance_url = "https://pixelfed.invalid".to_string();
+        let options = PostOptions { dry_run: true, ..Default::default() };
+        let report = post_to_platforms(reqwest::Client::new(), Arc::new(config), vec![Platform::Mastodon, Platform::Pixelfed], "Hello".to_string(), Vec::new(), options).await;
+
+        assert!(report.dry_run);
+        assert_eq!(report.succeeded(), vec![Platform::Mastodon]);
+        assert_eq!(report.failed().len(), 1);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].1.contains("https://mastodon.invalid/api/v1/statuses"));
+    }
+
+    #[tokio::test]
+    async fn test_pixelfed_requires_image() {
+        let config = PixelfedConfig {
+            enabled: true,
+            instance_url: "https://pixelfed.example".to_string(),
+            decrypted_access_token: "token".to_string().into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::new();
+        let result = post_to_pixelfed(&client, RetryPolicy::ONCE, &config, "Caption", &[], &UploadProgress::default()).await;
+        assert!(matches!(result, Err(PostError::Api(_))));
+    }
+
+    #[tokio::test]
+    async fn test_threads_response_id() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "17890"})))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "error": {"message": "Error validating access token", "code": 190}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let response = client.post(server.uri()).send().await.unwrap();
+        assert_eq!(threads_response_id(response).await.unwrap(), "17890");
+
+        // An expired token is an auth problem even though Graph API answers with a 400
+        let response = client.post(server.uri()).send().await.unwrap();
+        assert!(matches!(threads_response_id(response).await, Err(PostError::Auth(_))));
+    }
+
+    #[test]
+    fn test_post_report_partial_failure() {
+        let report = PostReport {
+            results: vec![
+                (Platform::Bluesky, Ok(())),
+                (Platform::Mastodon, Err(PostError::Network("timeout".to_string()))),
+                (Platform::Nostr, Ok(())),
+            ],
+            nostr_relays: vec![
+                ("wss://relay.damus.io".to_string(), Ok(())),
+                ("wss://nos.lol".to_string(), Err("blocked: rate-limited".to_string())),
+            ],
+            warnings: Vec::new(),
+            dry_run: false,
+            scheduled: Vec::new(),
+            links: Vec::new(),
+            shortened_text: None,
+        };
+
+        assert!(!report.all_succeeded());
+        assert_eq!(report.succeeded(), vec![Platform::Bluesky, Platform::Nostr]);
+        let failed = report.failed();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, Platform::Mastodon);
+        assert_eq!(report.rejected_relays(), vec![("wss://nos.lol", "blocked: rate-limited")]);
+    }
+
+    #[test]
+    fn test_upload_file_name_keeps_extension() {
+        assert_eq!(upload_file_name("/home/user/Pictures/screenshot.png"), "screenshot.png");
+        assert_eq!(mime_guess::from_path("/home/user/Pictures/screenshot.png").first_or_octet_stream(), "image/png");
+        assert_eq!(upload_file_name("/home/user/Pictures/cat.gif"), "cat.gif");
+        assert_eq!(upload_file_name(""), "image");
+    }
+
+    #[test]
+    fn test_micropub_fields() {
+        assert_eq!(
+            micropub_fields("Hello", &MicroBlogOptions::default()),
+            vec![("h", "entry".to_string()), ("content", "Hello".to_string())]
+        );
+        let options = MicroBlogOptions {
+            title: Some(" My Article ".to_string()),
+            categories: vec!["rust".to_string(), " ".to_string(), " cosmic".to_string()],
+        };
+        assert_eq!(
+            micropub_fields("Hello", &options)[2..],
+            [
+                ("name", "My Article".to_string()),
+                ("category[]", "rust".to_string()),
+                ("category[]", "cosmic".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mastodon_poll_form_fields() {
+        let poll = MastodonPoll {
+            options: vec!["Tea".to_string(), "  ".to_string(), " Coffee ".to_string()],
+            expires_in: 86400,
+            multiple: false,
+        };
+        assert_eq!(
+            poll.form_fields(),
+            vec![
+                ("poll[options][]", "Tea".to_string()),
+                ("poll[options][]", "Coffee".to_string()),
+                ("poll[expires_in]", "86400".to_string()),
+                ("poll[multiple]", "false".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_nostr_mentions() {
+        let public_key = Keys::generate().public_key();
+        let npub = public_key.to_bech32().unwrap();
+        let text = format!("Thanks @{}, and @Bob@Example.com. Mail me at me@example.com!", npub);
+
+        let mentions = find_nostr_mentions(&text);
+        assert_eq!(mentions.len(), 2);
+        assert_eq!(&text[mentions[0].0.clone()], format!("@{}", npub));
+        assert_eq!(mentions[0].1, NostrMention::Npub(public_key));
+        assert_eq!(&text[mentions[1].0.clone()], "@Bob@Example.com");
+        assert_eq!(mentions[1].1, NostrMention::Nip05 { name: "bob".to_string(), domain: "example.com".to_string() });
+
+        assert!(find_nostr_mentions("@everyone @npub1notakey").is_empty());
+    }
+
+    #[test]
+    fn test_parse_nip05_response() {
+        let public_key = Keys::generate().public_key();
+        let response = json!({ "names": { "bob": public_key.to_hex() } });
+        assert_eq!(parse_nip05_response(&response, "bob"), Some(public_key));
+        assert_eq!(parse_nip05_response(&response, "alice"), None);
+    }
+
+    #[test]
+    fn test_nostr_hashtags() {
+        let text = "Loving #Rust and #rust, see https://example.com/#anchor #Café #2024 #日本語!";
+        assert_eq!(nostr_hashtags(text), vec!["rust", "café", "日本語"]);
+    }
+
+    #[test]
+    fn test_shorten_links_selection() {
+        let endpoint = "https://is.gd/create.php?format=simple&url={url}";
+        let long = "https://example.com/articles/2024/a-rather-long-title";
+        let text = format!("Read {}. Also https://is.gd/create.php?url=elsewhere-but-on-the-shortener, https://ex.com/a and {}", long, long);
+        assert_eq!(links_to_shorten(&text, endpoint), vec![long.to_string()]);
+
+        let short_links = HashMap::from([(long.to_string(), "https://is.gd/abc".to_string())]);
+        assert_eq!(
+            replace_links(&text, &short_links),
+            "Read https://is.gd/abc. Also https://is.gd/create.php?url=elsewhere-but-on-the-shortener, https://ex.com/a and https://is.gd/abc"
+        );
+        assert_eq!(
+            shortener_request_url(endpoint, "https://example.com/?a=1&b=2"),
+            "https://is.gd/create.php?format=simple&url=https%3A%2F%2Fexample.com%2F%3Fa%3D1%26b%3D2"
+        );
+    }
+
+    #[test]
+    fn test_describe_error_response() {
+        let unprocessable = reqwest::StatusCode::UNPROCESSABLE_ENTITY;
+        assert_eq!(
+            describe_error_response(unprocessable, r#"{"error":"Validation failed: Text character limit of 500 exceeded"}"#),
+            "HTTP 422 Unprocessable Entity: Validation failed: Text character limit of 500 exceeded"
+        );
+        let bad_request = reqwest::StatusCode::BAD_REQUEST;
+        assert_eq!(
+            describe_error_response(bad_request, r#"{"error":"InvalidRequest","message":"Record/text must not be longer than 300 graphemes"}"#),
+            "HTTP 400 Bad Request: Record/text must not be longer than 300 graphemes"
+        );
+        assert_eq!(
+            describe_error_response(bad_request, r#"{"error":"invalid_request","error_description":"The category is not valid"}"#),
+            "HTTP 400 Bad Request: The category is not valid"
+        );
+        assert_eq!(
+            describe_error_response(bad_request, r#"{"errors":[{"message":"Title is required"},"Body is too short"]}"#),
+            "HTTP 400 Bad Request: Title is required; Body is too short"
+        );
+        assert_eq!(describe_error_response(reqwest::StatusCode::BAD_GATEWAY, "  "), "HTTP 502 Bad Gateway");
+
+        let page = format!("<html>{}</html>", "x".repeat(500));
+        let described = describe_error_response(reqwest::StatusCode::INTERNAL_SERVER_ERROR, &page);
+        assert!(described.starts_with("HTTP 500 Internal Server Error: <html>xxx"));
+        assert!(described.ends_with('…'));
+        assert!(described.chars().count() < 250);
+    }
+
+    #[test]
+    fn test_parse_caption() {
+        assert_eq!(parse_caption(r#"{"caption": " A cat on a windowsill "}"#).as_deref(), Some("A cat on a windowsill"));
+        assert_eq!(parse_caption(r#"[{"generated_text": "a dog running"}]"#).as_deref(), Some("a dog running"));
+        assert_eq!(parse_caption("A bowl of ramen\n").as_deref(), Some("A bowl of ramen"));
+        assert_eq!(parse_caption(r#""quoted""#).as_deref(), Some("quoted"));
+        assert_eq!(parse_caption(r#"{"error": "model loading"}"#), None);
+        assert_eq!(parse_caption("   "), None);
+    }
+
+    #[test]
+    fn test_normalize_relay_url() {
+        assert_eq!(normalize_relay_url("wss://Relay.Damus.io").unwrap(), "wss://relay.damus.io");
+        assert_eq!(normalize_relay_url(" wss://relay.damus.io:443/ ").unwrap(), "wss://relay.damus.io");
+        assert_eq!(normalize_relay_url("wss://nostr.example.com/inbox").unwrap(), "wss://nostr.example.com/inbox");
+        assert_eq!(normalize_relay_url("ws://localhost:7777").unwrap(), "ws://localhost:7777");
+        assert!(normalize_relay_url("wss://").is_err());
+        assert!(normalize_relay_url("ws://relay.damus.io").is_err());
+        assert!(normalize_relay_url("https://relay.damus.io").is_err());
+        assert!(normalize_relay_url("relay.damus.io").is_err());
+    }
+
+    #[test]
+    fn test_nip65_relay_tags() {
+        let tags = nip65_relay_tags(&["wss://relay.damus.io".to_string(), "wss://nos.lol".to_string()]);
+        let tags: Vec<Vec<String>> = tags.into_iter().map(|tag| tag.to_vec()).collect();
+        assert_eq!(tags, vec![vec!["r", "wss://relay.damus.io"], vec!["r", "wss://nos.lol"]]);
+    }
+
+    #[test]
+    fn test_nostr_article_tags() {
+        let text = "## Release notes\n\nWhat changed in 2.0";
+        let article = NostrArticle {
+            title: String::new(),
+            summary: " The short version ".to_string(),
+            identifier: "5b0c2e1a".to_string(),
+        };
+        let tags: Vec<Vec<String>> = nostr_article_tags(text, &article, Timestamp::from(1_700_000_000)).into_iter().map(|tag| tag.to_vec()).collect();
+        assert_eq!(
+            tags,
+            vec![
+                vec!["d", "5b0c2e1a"],
+                vec!["title", "Release notes"],
+                vec!["summary", "The short version"],
+                vec!["published_at", "1700000000"],
+            ]
+        );
+
+        assert_eq!(nostr_article_title(text, "  Version 2.0: What's new? "), "Version 2.0: What's new?");
+    }
+
+    #[test]
+    fn test_mine_nostr_pow() {
+        assert_eq!(leading_zero_bits(&[0, 0, 0b0001_0000, 0xFF]), 19);
+        assert_eq!(leading_zero_bits(&[0; 4]), 32);
+
+        // The ID nostr computes when signing must be the one that was mined
+        let keys = Keys::generate();
+        let content = "Proof of \"work\"\nwith a line break 🦀";
+        let tags = vec![Tag::hashtag("nostr")];
+        let progress = PowProgress::default();
+        let (tags, created_at) = mine_nostr_pow(keys.public_key(), Kind::TextNote, tags, content, 8, &progress).unwrap();
+        assert_eq!(tags.last().unwrap().as_slice()[0], "nonce");
+        assert_eq!(tags.last().unwrap().as_slice()[2], "8");
+        let event = EventBuilder::text_note(content)
+            .tags(tags)
+            .custom_created_at(created_at)
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert!(leading_zero_bits(event.id.as_bytes()) >= 8);
+        assert_eq!(progress.attempts(), None);
+
+        progress.cancel();
+        assert!(mine_nostr_pow(keys.public_key(), Kind::TextNote, Vec::new(), content, 200, &progress).is_err());
+    }
+
+    #[test]
+    fn test_nostr_relay_info_url() {
+        assert_eq!(nostr_relay_info_url("wss://relay.damus.io").as_deref(), Some("https://relay.damus.io"));
+        assert_eq!(nostr_relay_info_url("ws://localhost:7777").as_deref(), Some("http://localhost:7777"));
+        assert_eq!(nostr_relay_info_url("https://relay.damus.io"), None);
+    }
+
+    #[test]
+    fn test_bluesky_session_expiry() {
+        let encode = |claims: Value| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(claims.to_string());
+        let access_jwt = format!("{}.{}.signature", encode(json!({ "alg": "ES256K" })), encode(json!({ "exp": 1_700_000_000 })));
+        let session = BlueskySession::from_response(&json!({
+            "accessJwt": access_jwt,
+            "refreshJwt": "refresh"
+        }))
+        .unwrap();
+        assert_eq!(session.access_expires_at, Some(1_700_000_000));
+        assert!(session.is_fresh(1_700_000_000 - 3600));
+        // Within the margin it's treated as expired so it's refreshed in time
+        assert!(!session.is_fresh(1_700_000_000 - 30));
+
+        let opaque = BlueskySession::from_response(&json!({ "accessJwt": "opaque", "refreshJwt": "refresh" })).unwrap();
+        assert!(!opaque.is_fresh(0));
+        assert_eq!(opaque.did, None);
+    }
+
+    #[test]
+    fn test_bluesky_session_pds() {
+        let session = BlueskySession::from_response(&json!({
+            "accessJwt": "access",
+            "refreshJwt": "refresh",
+            "did": "did:plc:abc",
+            "didDoc": {
+                "service": [{
+                    "id": "#atproto_pds",
+                    "type": "AtprotoPersonalDataServer",
+                    "serviceEndpoint": "https://morel.us-east.host.bsky.network"
+                }]
+            }
+        }))
+        .unwrap();
+        assert_eq!(session.did.as_deref(), Some("did:plc:abc"));
+        assert_eq!(session.pds_url.as_deref(), Some("https://morel.us-east.host.bsky.network"));
+    }
+
+    #[test]
+    fn test_is_video() {
+        assert!(ImageAttachment::new("/tmp/clip.mp4".to_string()).is_video());
+        assert!(ImageAttachment::new("/tmp/clip.MOV".to_string()).is_video());
+        assert!(!ImageAttachment::new("/tmp/photo.jpg".to_string()).is_video());
+    }
+
+    #[test]
+    fn test_mp4_duration() {
+        let mp4_box = |kind: &[u8; 4], contents: &[u8]| {
+            let mut bytes = ((contents.len() + 8) as u32).to_be_bytes().to_vec();
+            bytes.extend_from_slice(kind);
+            bytes.extend_from_slice(contents);
+            bytes
+        };
+        // Version 0 mvhd: version and flags, creation and modification times, timescale, duration
+        let mut mvhd = vec![0; 12];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&90_500u32.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 80]);
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        file.extend(mp4_box(b"mdat", &[0; 32]));
+        file.extend(mp4_box(b"moov", &mp4_box(b"mvhd", &mvhd)));
+        assert_eq!(mp4_duration(&mut Cursor::new(&file)), Some(Duration::from_millis(90_500)));
+
+        let mut mvhd = vec![1, 0, 0, 0];
+        mvhd.extend_from_slice(&[0; 16]);
+        mvhd.extend_from_slice(&600u32.to_be_bytes());
+        mvhd.extend_from_slice(&(600u64 * 240).to_be_bytes());
+        let file = mp4_box(b"moov", &mp4_box(b"mvhd", &mvhd));
+        assert_eq!(mp4_duration(&mut Cursor::new(&file)), Some(Duration::from_secs(240)));
+
+        assert_eq!(mp4_duration(&mut Cursor::new(mp4_box(b"ftyp", b"isom"))), None);
+
+        // A box claiming to be as large as can be is given up on rather than overflowing
+        let mut file = 1u32.to_be_bytes().to_vec();
+        file.extend_from_slice(b"free");
+        file.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(mp4_duration(&mut Cursor::new(&file)), None);
+    }
+
+    #[test]
+    fn test_preview_posts() {
+        let text = "word ".repeat(80);
+        let mut config = Config::default();
+        assert_eq!(preview_posts(Platform::Bluesky, &text, true, &config).len(), 2);
+        assert_eq!(preview_posts(Platform::Mastodon, &text, true, &config), vec![text.trim().to_string()]);
+        assert_eq!(preview_posts(Platform::MicroBlog, &text, true, &config), vec![text.clone()]);
+
+        // Over-long Bluesky text becomes a thread by default, and blocked text is shown as typed
+        assert_eq!(preview_posts(Platform::Bluesky, &text, false, &config).len(), 2);
+        config.bluesky.truncate_behavior = BlueskyTruncateBehavior::BlockPost;
+        assert_eq!(preview_posts(Platform::Bluesky, &text, false, &config), vec![text.clone()]);
+        config.bluesky.truncate_behavior = BlueskyTruncateBehavior::Truncate;
+        assert_eq!(preview_posts(Platform::Bluesky, &text, false, &config), vec![text[..BLUESKY_CHARACTER_LIMIT].to_string()]);
+        config.bluesky.truncate_behavior = BlueskyTruncateBehavior::SkipBluesky;
+        assert!(preview_posts(Platform::Bluesky, &text, false, &config).is_empty());
+    }
+
+    #[test]
+    fn test_truncation_keeps_trailing_link() {
+        let url = "https://example.com/blog/2024/06/a-post-with-a-long-and-descriptive-slug";
+        let text = format!("{} {}", "word ".repeat(70).trim_end(), url);
+        let truncated = truncate_for_bluesky(&text);
+        assert!(post_length(Platform::Bluesky, &truncated) <= BLUESKY_CHARACTER_LIMIT);
+        assert!(truncated.ends_with(&format!("word… {}", url)));
+        assert!(!truncated.contains("wo…"));
+
+        // Trailing punctuation after the link doesn't stop it being kept
+        let truncated = truncate_for_bluesky(&format!("{}.", text));
+        assert!(truncated.ends_with(url));
+
+        // A link in the middle is counted as it's shown, so the text after it fills the post
+        let text = format!("{} {}", url, "word ".repeat(70));
+        let truncated = truncate_for_bluesky(&text);
+        assert!(truncated.starts_with(url));
+        assert_eq!(post_length(Platform::Bluesky, &truncated), BLUESKY_CHARACTER_LIMIT);
+
+        // A long link only takes up the room it's shown in
+        let long_url = format!("https://example.com/{}", "a".repeat(400));
+        let text = format!("{} {}", "word ".repeat(70).trim_end(), long_url);
+        let truncated = truncate_for_bluesky(&text);
+        assert!(truncated.ends_with(&long_url));
+        assert!(post_length(Platform::Bluesky, &truncated) <= BLUESKY_CHARACTER_LIMIT);
+        assert!(post_length(Platform::Bluesky, &truncated) > BLUESKY_CHARACTER_LIMIT - 10);
+
+        // A link the cut would go through is left out whole
+        let text = format!("{} {} tail", "w".repeat(290), url);
+        assert_eq!(truncate_for_bluesky(&text), format!("{} ", "w".repeat(290)));
+    }
+
+    #[test]
+    fn test_markdown_to_plain_text() {
+        assert_eq!(markdown_to_plain_text("Some **bold** and *italic* text"), "Some bold and italic text");
+        assert_eq!(markdown_to_plain_text("Read [the docs](https://example.com/docs)."), "Read the docs (https://example.com/docs).");
+        assert_eq!(markdown_to_plain_text("See <https://example.com>"), "See https://example.com");
+        assert_eq!(
+            markdown_to_plain_text("# Release\n\nNew:\n\n- `fast` mode\n- fixes\n\nThanks!\nBye"),
+            "Release\n\nNew:\n\n- fast mode\n- fixes\n\nThanks!\nBye"
+        );
+
+        let text = "**Hi** [there](https://example.com)";
+        let mut config = Config::default();
+        assert_eq!(text_for_platform(Platform::Mastodon, text, true, false, &config), "Hi there (https://example.com)");
+        assert_eq!(text_for_platform(Platform::Lemmy, text, true, false, &config), text);
+        assert_eq!(text_for_platform(Platform::Mastodon, text, false, false, &config), text);
+
+        // Pleroma and Akkoma format Markdown themselves
+        config.mastodon.pleroma_detected = true;
+        assert_eq!(text_for_platform(Platform::Mastodon, text, true, false, &config), text);
+        config.mastodon.compatibility = MastodonCompatibility::Mastodon;
+        assert_eq!(text_for_platform(Platform::Mastodon, text, true, false, &config), "Hi there (https://example.com)");
+    }
+
+    #[test]
+    fn test_text_for_platform_signature() {
+        let mut config = Config::default();
+        config.signatures.insert(Platform::Mastodon, "— via [yall](https://example.com) ".to_string());
+        config.signatures.insert(Platform::Nostr, "  ".to_string());
+
+        let text = "Hello\n";
+        assert_eq!(text_for_platform(Platform::Mastodon, text, false, true, &config), "Hello\n\n— via [yall](https://example.com)");
+        assert_eq!(text_for_platform(Platform::Mastodon, text, false, false, &config), "Hello");
+        // The signature is Markdown like the rest of the text
+        assert_eq!(text_for_platform(Platform::Mastodon, text, true, true, &config), "Hello\n\n— via yall (https://example.com)");
+        assert_eq!(text_for_platform(Platform::Nostr, text, false, true, &config), "Hello");
+        assert_eq!(text_for_platform(Platform::Bluesky, text, false, true, &config), "Hello");
+    }
+
+    #[test]
+    fn test_truncation_keeps_signature() {
+        let mut config = Config::default();
+        config.bluesky.truncate_behavior = BlueskyTruncateBehavior::Truncate;
+        config.signatures.insert(Platform::Bluesky, "— via [yall](https://example.com)".to_string());
+        let text = "word ".repeat(70);
+
+        let posts = preview_posts(Platform::Bluesky, &text_for_platform(Platform::Bluesky, &text, false, true, &config), false, &config);
+        assert_eq!(posts.len(), 1);
+        assert!(grapheme_count(&posts[0]) <= BLUESKY_CHARACTER_LIMIT);
+        assert!(posts[0].starts_with("word word"));
+        assert!(posts[0].ends_with("word\n\n— via [yall](https://example.com)"));
+
+        // Also once the signature's Markdown is turned into plain text
+        let posts = preview_posts(Platform::Bluesky, &text_for_platform(Platform::Bluesky, &text, true, true, &config), false, &config);
+        assert!(grapheme_count(&posts[0]) <= BLUESKY_CHARACTER_LIMIT);
+        assert!(posts[0].ends_with("\n\n— via yall (https://example.com)"));
+    }
+
+    #[test]
+    fn test_clean_up_whitespace() {
+        let text = "First line  \n\n\n\n\nSecond\t\n  indented\n\n";
+        assert_eq!(clean_up_whitespace(text, TextCleanup::default()), "First line\n\n\n\n\nSecond\n  indented");
+        let both = TextCleanup { trim_trailing_whitespace: true, collapse_blank_lines: true };
+        assert_eq!(clean_up_whitespace(text, both), "First line\n\n\nSecond\n  indented");
+        let collapse = TextCleanup { trim_trailing_whitespace: false, collapse_blank_lines: true };
+        assert_eq!(clean_up_whitespace(text, collapse), "First line  \n\n\nSecond\t\n  indented\n\n");
+        let neither = TextCleanup { trim_trailing_whitespace: false, collapse_blank_lines: false };
+        assert_eq!(clean_up_whitespace(text, neither), text);
+    }
+
+    #[test]
+    fn test_grapheme_counts() {
+        let family = "👨‍👩‍👧";
+        assert_eq!(family.chars().count(), 5);
+        assert_eq!(grapheme_count(family), 1);
+        assert_eq!(post_length(Platform::Bluesky, family), 1);
+        assert_eq!(post_length(Platform::Mastodon, family), 5);
+
+        // 300 family emoji fit on Bluesky and are never cut apart
+        let text = family.repeat(301);
+        assert_eq!(truncate_for_bluesky(&text), family.repeat(BLUESKY_CHARACTER_LIMIT));
+
+        assert_eq!(word_count("Hello, wide world 🇳🇴!"), 3);
+        assert_eq!(word_count("   "), 0);
+    }
+}